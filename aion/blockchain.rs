@@ -25,12 +25,14 @@ use std::io::{BufReader, BufRead};
 use std::time::{Instant, Duration};
 use std::thread::sleep;
 use std::sync::Arc;
+use std::path::Path;
 use rustc_hex::FromHex;
 use bytes::ToPretty;
 use rlp::PayloadInfo;
 use acore::service::ClientService;
 use acore::client::{DatabaseCompactionProfile, VMType, BlockImportError, BlockChainClient, BlockId};
 use acore::ImportError;
+use acore::snapshot::{PackedReader, PackedWriter, SnapshotReader};
 use acore::miner::Miner;
 use acore::verification::queue::VerifierSettings;
 use cache::CacheConfig;
@@ -88,6 +90,10 @@ pub enum BlockchainCmd {
     Export(ExportBlockchain),
     /// sub-command `revert`
     Revert(RevertBlockchain),
+    /// sub-command `snapshot`
+    Snapshot(SnapshotBlockchain),
+    /// sub-command `restore`
+    Restore(RestoreBlockchain),
 }
 
 /// Config for sub-command `db kill`
@@ -150,6 +156,37 @@ pub struct RevertBlockchain {
     pub to_block: BlockId,
 }
 
+/// Config for sub-command `snapshot`
+#[derive(Debug, PartialEq)]
+pub struct SnapshotBlockchain {
+    pub spec: SpecType,
+    pub cache_config: CacheConfig,
+    pub dirs: Directories,
+    pub file_path: Option<String>,
+    pub pruning: Pruning,
+    pub pruning_history: u64,
+    pub pruning_memory: usize,
+    pub compaction: DatabaseCompactionProfile,
+    pub wal: bool,
+    pub fat_db: Switch,
+    pub block_at: BlockId,
+}
+
+/// Config for sub-command `restore`
+#[derive(Debug, PartialEq)]
+pub struct RestoreBlockchain {
+    pub spec: SpecType,
+    pub cache_config: CacheConfig,
+    pub dirs: Directories,
+    pub file_path: Option<String>,
+    pub pruning: Pruning,
+    pub pruning_history: u64,
+    pub pruning_memory: usize,
+    pub compaction: DatabaseCompactionProfile,
+    pub wal: bool,
+    pub fat_db: Switch,
+}
+
 /// Execute the blockchain subcommand related code
 pub fn execute(cmd: BlockchainCmd) -> Result<(), String> {
     match cmd {
@@ -157,6 +194,8 @@ pub fn execute(cmd: BlockchainCmd) -> Result<(), String> {
         BlockchainCmd::Import(import_cmd) => execute_import(import_cmd),
         BlockchainCmd::Export(export_cmd) => execute_export(export_cmd),
         BlockchainCmd::Revert(revert_cmd) => execute_revert(revert_cmd),
+        BlockchainCmd::Snapshot(snapshot_cmd) => execute_snapshot(snapshot_cmd),
+        BlockchainCmd::Restore(restore_cmd) => execute_restore(restore_cmd),
     }
 }
 
@@ -473,6 +512,62 @@ fn execute_export(cmd: ExportBlockchain) -> Result<(), String> {
     Ok(())
 }
 
+fn execute_snapshot(cmd: SnapshotBlockchain) -> Result<(), String> {
+    let timer = Instant::now();
+    let file_path = cmd.file_path.ok_or("No file path provided.")?;
+    let service = start_client(
+        cmd.dirs,
+        cmd.spec,
+        cmd.pruning,
+        cmd.pruning_history,
+        cmd.pruning_memory,
+        cmd.fat_db,
+        cmd.compaction,
+        cmd.wal,
+        cmd.cache_config,
+        false,
+    )?;
+    let client = service.client();
+
+    let writer = PackedWriter::new(Path::new(&file_path))
+        .map_err(|e| format!("Failed to open snapshot writer: {}", e))?;
+    client
+        .take_snapshot(writer, cmd.block_at)
+        .map_err(|e| format!("Encountered fatal error while creating snapshot: {}", e))?;
+
+    let ms = timer.elapsed().as_milliseconds();
+    info!(target: "snapshot", "Snapshot written to {} in {} ms", file_path, ms);
+    Ok(())
+}
+
+fn execute_restore(cmd: RestoreBlockchain) -> Result<(), String> {
+    let timer = Instant::now();
+    let file_path = cmd.file_path.ok_or("No file path provided.")?;
+    let reader = PackedReader::new(Path::new(&file_path))
+        .map_err(|e| format!("Couldn't open snapshot at {}: {}", file_path, e))?;
+    let service = start_client(
+        cmd.dirs,
+        cmd.spec,
+        cmd.pruning,
+        cmd.pruning_history,
+        cmd.pruning_memory,
+        cmd.fat_db,
+        cmd.compaction,
+        cmd.wal,
+        cmd.cache_config,
+        false,
+    )?;
+    let client = service.client();
+
+    client
+        .restore_snapshot(&reader)
+        .map_err(|e| format!("Encountered fatal error while restoring snapshot: {}", e))?;
+
+    let ms = timer.elapsed().as_milliseconds();
+    info!(target: "snapshot", "Restored snapshot of #{} in {} ms", reader.manifest().block_number, ms);
+    Ok(())
+}
+
 /// remove specified db
 pub fn kill_db(cmd: KillBlockchain) -> Result<(), String> {
     let spec = cmd.spec.spec()?;
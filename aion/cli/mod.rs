@@ -114,6 +114,28 @@ usage! {
             "Revert Database to (including) block BLOCK, which may be an index, hash.",
         }
 
+        CMD cmd_snapshot
+        {
+            "Make a snapshot of the database",
+
+            ARG arg_snapshot_at: (String) = "latest",
+            "--at=[BLOCK]",
+            "Take a snapshot at the given block, which may be an index, hash, or latest. Note that taking snapshots at non-recent blocks will only work with --pruning archive",
+
+            ARG arg_snapshot_file: (Option<String>) = None,
+            "<FILE>",
+            "Path to the file to export to",
+        }
+
+        CMD cmd_restore
+        {
+            "Restore the database from the given snapshot",
+
+            ARG arg_restore_file: (Option<String>) = None,
+            "<FILE>",
+            "Path to the file to restore from",
+        }
+
         CMD cmd_db
         {
             "Manage the database representing the state of the blockchain on this system",
@@ -128,7 +150,7 @@ usage! {
         ["Operating Options"]
             ARG arg_chain: (String) = "mainnet", or |c: &Config| c.aion.as_ref()?.chain.clone(),
             "--chain=[CHAIN]",
-            "Specify the blockchain type. CHAIN may be a JSON chain specification file.",
+            "Specify the blockchain type. CHAIN may be either mainnet, dev (an instant-seal development chain) or a JSON chain specification file.",
 
             ARG arg_keys_path: (Option<String>) = None, or |c: &Config| c.aion.as_ref()?.keys_path.clone(),
             "--keys-path=[PATH]",
@@ -142,6 +164,22 @@ usage! {
             "--db-path=[PATH]",
             "Specify the database directory path",
 
+            FLAG flag_light: (bool) = false, or |c: &Config| c.aion.as_ref()?.light.clone(),
+            "--light",
+            "Run as a light client. Only block headers are synced, state is fetched from full nodes with proofs.",
+
+            ARG arg_fvm_backend: (String) = "native", or |c: &Config| c.aion.as_ref()?.fvm_backend.clone(),
+            "--fvm-backend=[BACKEND]",
+            "Specify the FastVM backend. BACKEND may be native (the JIT library) or interpreter (pure Rust).",
+
+            ARG arg_diff_fvm_backend: (Option<String>) = None, or |c: &Config| c.aion.as_ref()?.diff_fvm_backend.clone(),
+            "--diff-fvm-backend=[BACKEND]",
+            "Re-execute every imported block with another FastVM backend on a scratch state and report divergences. BACKEND may be native or interpreter.",
+
+            ARG arg_diff_report: (String) = "$BASE/divergence.jsonl", or |c: &Config| c.aion.as_ref()?.diff_report.clone(),
+            "--diff-report=[PATH]",
+            "Specify the file divergence reports of --diff-fvm-backend are appended to.",
+
         ["Miscellaneous Options"]
             FLAG flag_full_help: (bool) = false, or |_| None,
             "--full-help",
@@ -218,6 +256,42 @@ usage! {
             "--black_ip_list=[IPs]",
             "IP list whose connecting requests are to be rejected.",
 
+            ARG arg_node_key_file: (String) = "$BASE/network.key", or |c: &Config| c.network.as_ref()?.node_key_file.clone(),
+            "--node-key-file=[PATH]",
+            "Specify the file the node key is loaded from, a new key is generated there if it does not exist. The node id is derived from this key.",
+
+            FLAG flag_no_legacy_peers: (bool) = false, or |c: &Config| c.network.as_ref()?.no_legacy_peers.clone(),
+            "--no-legacy-peers",
+            "Only connect to peers which authenticate with their node key.",
+
+            ARG arg_peer_max_messages: (u32) = 500u32, or |c: &Config| c.network.as_ref()?.peer_max_messages.clone(),
+            "--peer-max-messages=[NUM]",
+            "Accept up to NUM messages per second from a peer, 0 for no limit.",
+
+            ARG arg_peer_max_bandwidth: (u32) = 8192u32, or |c: &Config| c.network.as_ref()?.peer_max_bandwidth.clone(),
+            "--peer-max-bandwidth=[KB]",
+            "Accept up to KB kilobytes per second from a peer, 0 for no limit.",
+
+            ARG arg_network_max_messages: (u32) = 5000u32, or |c: &Config| c.network.as_ref()?.network_max_messages.clone(),
+            "--network-max-messages=[NUM]",
+            "Accept up to NUM messages per second from all peers, 0 for no limit.",
+
+            ARG arg_network_max_bandwidth: (u32) = 65536u32, or |c: &Config| c.network.as_ref()?.network_max_bandwidth.clone(),
+            "--network-max-bandwidth=[KB]",
+            "Accept up to KB kilobytes per second from all peers, 0 for no limit.",
+
+            FLAG flag_disconnect_flooding_peers: (bool) = false, or |c: &Config| c.network.as_ref()?.disconnect_flooding_peers.clone(),
+            "--disconnect-flooding-peers",
+            "Disconnect peers going over the traffic limits instead of dropping their messages.",
+
+            ARG arg_compression: (Vec<String>) = vec!["snappy".into(), "zstd".into()], or |c: &Config| c.network.as_ref()?.compression.clone(),
+            "--compression=[ALGORITHMS]...",
+            "Compress messages to peers with one of ALGORITHMS, snappy and zstd are supported, none disables compression.",
+
+            ARG arg_light_serve_gas: (u64) = 10000000u64, or |c: &Config| c.network.as_ref()?.light_serve_gas.clone(),
+            "--light-serve-gas=[GAS]",
+            "Execute transactions for light clients with up to GAS gas, requests over it are served with GAS and penalised.",
+
         ["Rpc Options"]
             ARG arg_rpc_processing_threads: (Option<usize>) = None, or |c: &Config| c.rpc.as_ref()?.processing_threads,
             "--rpc--processing-threads=[NUM]",
@@ -238,7 +312,7 @@ usage! {
 
             ARG arg_http_apis: (Vec<String>) = vec!["all".into()], or |c: &Config| c.http.as_ref()?.apis.clone(),
             "--http-apis=[APIS]...",
-            "Specify the APIs available through the HTTP interface. APIS is a comma-delimited list of API name. Possible name are all, web3, eth, stratum, net, personal, rpc. You can also disable a specific API by putting '-' in the front: all,-personal.",
+            "Specify the APIs available through the HTTP interface. APIS is a comma-delimited list of API name. Possible name are all, web3, eth, stratum, net, personal, rpc, debug, traces, admin. Pubsub is only served over WebSockets and IPC. The debug, traces and admin APIs are not included in all and have to be listed explicitly. You can also disable a specific API by putting '-' in the front: all,-personal.",
 
             ARG arg_http_hosts: (Vec<String>) = vec!["none".into()], or |c: &Config| c.http.as_ref()?.hosts.clone(),
             "--http-hosts=[HOSTS]...",
@@ -267,7 +341,7 @@ usage! {
 
             ARG arg_ws_apis: (Vec<String>) = vec!["all".into()], or |c: &Config| c.websockets.as_ref()?.apis.clone(),
             "--ws-apis=[APIS]...",
-            "Specify the APIs available through the WebSockets interface. APIS is a comma-delimited list of API name. Possible name are web3, eth, stratum, net, personal, rpc.",
+            "Specify the APIs available through the WebSockets interface. APIS is a comma-delimited list of API name. Possible name are web3, eth, stratum, net, personal, rpc, pubsub, debug, traces, admin. The debug, traces and admin APIs are not included in all and have to be listed explicitly.",
 
             ARG arg_ws_origins: (Vec<String>) = vec!["none".into()], or |c: &Config| c.websockets.as_ref()?.origins.clone(),
             "--ws-origins=[URL]...",
@@ -292,7 +366,7 @@ usage! {
 
             ARG arg_ipc_apis: (Vec<String>) = vec!["all".into()], or |c: &Config| c.ipc.as_ref()?.apis.clone(),
             "--ipc-apis=[APIS]...",
-            "Specify custom API set available via JSON-RPC over IPC. Possible name are web3, eth, stratum, net, personal, rpc.",
+            "Specify custom API set available via JSON-RPC over IPC. Possible name are web3, eth, stratum, net, personal, rpc, pubsub, debug, traces, admin. The admin API is always enabled over IPC unless disabled with -admin.",
 
         ["Sealing/Mining Options"]
             FLAG flag_force_sealing: (bool) = false, or |c: &Config| c.mining.as_ref()?.force_sealing.clone(),
@@ -375,10 +449,17 @@ usage! {
             "--author=[ADDRESS]",
             "Specify the block author (aka \"coinbase\") address for sending block rewards from sealed blocks. NOTE: MINING WILL NOT WORK WITHOUT THIS OPTION.",
 
-            // TOREMOVE: Unity MS1 use only
-            ARG arg_staker_private_key: (Option<String>) = None, or |c: &Config| c.mining.as_ref()?.staker_private_key.clone(),
-            "--staker-private-key=[ADDRESS]",
-            "Specify the PoS block author's private key for sending block rewards from sealed blocks. NOTE: INTERNAL STAKING WILL NOT WORK WITHOUT THIS OPTION.",
+            ARG arg_staker: (Option<String>) = None, or |c: &Config| c.mining.as_ref()?.staker.clone(),
+            "--staker=[ADDRESS]",
+            "Specify the keystore account used by the internal staker to sign PoS blocks. Its key is decrypted with the passwords given by --password, the account is not unlocked for other uses. NOTE: INTERNAL STAKING WILL NOT WORK WITHOUT THIS OPTION OR --staker-signer.",
+
+            ARG arg_staker_signer: (Option<String>) = None, or |c: &Config| c.mining.as_ref()?.staker_signer.clone(),
+            "--staker-signer=[SOCKET]",
+            "Sign PoS blocks of the internal staker through the remote signer listening on the Unix domain socket SOCKET (see staker-signer) instead of a local account. Conflicts with --staker. Unix only.",
+
+            ARG arg_internal_miner_threads: (usize) = 0usize, or |c: &Config| c.mining.as_ref()?.internal_miner_threads.clone(),
+            "--internal-miner-threads=[NUM]",
+            "Number of threads used by the built-in CPU Equihash miner, 0 disables it. Each thread needs around 600 MB of memory. Meant for private and test networks, requires --author.",
 
             ARG arg_tx_gas_limit: (Option<String>) = None, or |c: &Config| c.mining.as_ref()?.tx_gas_limit.clone(),
             "--tx-gas-limit=[GAS]",
@@ -397,13 +478,17 @@ usage! {
             "--disable-wal",
             "Disables DB WAL, which gives a significant speed up but means an unclean exit is unrecoverable.",
 
+            FLAG flag_db_backup: (bool) = false, or |c: &Config| c.db.as_ref()?.db_backup.clone(),
+            "--db-backup",
+            "Back up the database before migrating it to a newer schema version. The backup is restored if the migration fails.",
+
             FLAG flag_scale_verifiers: (bool) = false, or |c: &Config| c.db.as_ref()?.scale_verifiers.clone(),
             "--scale-verifiers",
             "Automatically scale amount of verifier threads based on workload. Not guaranteed to be faster.",
 
             ARG arg_pruning: (String) = "archive", or |c: &Config| c.db.as_ref()?.pruning.clone(),
             "--pruning=[METHOD]",
-            "Configure pruning of the state/storage trie. METHOD may be one of auto, archive, fast: archive - keep all state trie data. No pruning. fast - maintain journal overlay. Fast but 50MB used. auto - use the method most recently synced or default to fast if none synced.",
+            "Configure pruning of the state/storage trie. METHOD may be one of auto, archive, fast, basic: archive - keep all state trie data. No pruning. fast - maintain journal overlay. Fast but 50MB used. basic - reference-counted journal on disk. Slower but low memory overhead. auto - use the method most recently synced or default to fast if none synced.",
 
             ARG arg_pruning_history: (u64) = 64u64, or |c: &Config| c.db.as_ref()?.pruning_history.clone(),
             "--pruning-history=[NUM]",
@@ -472,6 +557,10 @@ struct Operating {
     base_path: Option<String>,
     db_path: Option<String>,
     keys_path: Option<String>,
+    light: Option<bool>,
+    fvm_backend: Option<String>,
+    diff_fvm_backend: Option<String>,
+    diff_report: Option<String>,
 }
 
 /// Account options shape for config file
@@ -495,6 +584,15 @@ struct Network {
     local_node: Option<String>,
     boot_nodes: Option<Vec<String>>,
     ip_black_list: Option<Vec<String>>,
+    node_key_file: Option<String>,
+    no_legacy_peers: Option<bool>,
+    peer_max_messages: Option<u32>,
+    peer_max_bandwidth: Option<u32>,
+    network_max_messages: Option<u32>,
+    network_max_bandwidth: Option<u32>,
+    disconnect_flooding_peers: Option<bool>,
+    compression: Option<Vec<String>>,
+    light_serve_gas: Option<u64>,
 }
 
 /// Rpc options shape for config file
@@ -544,7 +642,9 @@ struct Ipc {
 #[serde(deny_unknown_fields)]
 struct Mining {
     author: Option<String>,
-    staker_private_key: Option<String>,
+    staker: Option<String>,
+    staker_signer: Option<String>,
+    internal_miner_threads: Option<usize>,
     force_sealing: Option<bool>,
     reseal_min_period: Option<u64>,
     work_queue_size: Option<usize>,
@@ -577,6 +677,7 @@ struct Database {
     pruning_history: Option<u64>,
     pruning_memory: Option<usize>,
     disable_wal: Option<bool>,
+    db_backup: Option<bool>,
     cache_size: Option<u32>,
     //    cache_size_db: Option<u32>,
     cache_size_blocks: Option<u32>,
@@ -739,6 +840,8 @@ mod tests {
             cmd_db: false,
             cmd_db_kill: false,
             cmd_revert: false,
+            cmd_snapshot: false,
+            cmd_restore: false,
 
             // Arguments
             arg_import_file: None,
@@ -751,12 +854,19 @@ mod tests {
             arg_account_private_key: None,
             arg_account_address: None,
             arg_revert_blocks_to: "0".into(),
+            arg_snapshot_at: "latest".into(),
+            arg_snapshot_file: None,
+            arg_restore_file: None,
 
             // -- Operating Options
             arg_chain: "xyz".into(),
             arg_base_path: Some("base".into()),
             arg_db_path: Some("db".into()),
             arg_keys_path: Some("keys".into()),
+            flag_light: false,
+            arg_fvm_backend: "native".into(),
+            arg_diff_fvm_backend: None,
+            arg_diff_report: "$BASE/divergence.jsonl".into(),
 
             // -- Account Options
             arg_unlock: vec!["0xdeadbeefcafe0000000000000000000000000000".into()],
@@ -776,6 +886,15 @@ mod tests {
             arg_net_id: 128u32,
             flag_sync_from_boot_nodes_only: true,
             arg_ip_black_list: vec!["ip1".into(), "ip2".into()],
+            arg_node_key_file: "$BASE/network.key".into(),
+            flag_no_legacy_peers: false,
+            arg_peer_max_messages: 500u32,
+            arg_peer_max_bandwidth: 8192u32,
+            arg_network_max_messages: 5000u32,
+            arg_network_max_bandwidth: 65536u32,
+            flag_disconnect_flooding_peers: false,
+            arg_compression: vec!["snappy".into(), "zstd".into()],
+            arg_light_serve_gas: 10000000u64,
 
             // -- API and Console Options
             // RPC
@@ -828,7 +947,9 @@ mod tests {
             arg_blk_price_window: 20usize,
             flag_dynamic_gas_price: true,
             arg_local_max_gas_price: 100000000000u64,
-            arg_staker_private_key: Some("staker_private_key".into()),
+            arg_staker: Some("0xa0733306c2ee0c60224b0e59efeae8eee558c0ca1b39e7e5a14a575124549417".into()),
+            arg_staker_signer: Some("/tmp/staker-signer.ipc".into()),
+            arg_internal_miner_threads: 2usize,
             // -- Database Options
             arg_pruning: "auto".into(),
             arg_pruning_history: 64u64,
@@ -838,6 +959,7 @@ mod tests {
             arg_cache_size_state: 25u32,
             arg_cache_size: Some(128),
             flag_disable_wal: true,
+            flag_db_backup: true,
             arg_db_compaction: "ssd".into(),
             arg_fat_db: "auto".into(),
             flag_scale_verifiers: true,
@@ -897,6 +1019,10 @@ mod tests {
                     base_path: None,
                     db_path: None,
                     keys_path: None,
+                    light: None,
+                    fvm_backend: None,
+                    diff_fvm_backend: None,
+                    diff_report: None,
                 }),
                 account: Some(Account {
                     unlock: Some(vec!["0x1".into(), "0x2".into(), "0x3".into()]),
@@ -912,6 +1038,15 @@ mod tests {
                     boot_nodes: None,
                     sync_from_boot_nodes_only: None,
                     ip_black_list: None,
+                    node_key_file: None,
+                    no_legacy_peers: None,
+                    peer_max_messages: None,
+                    peer_max_bandwidth: None,
+                    network_max_messages: None,
+                    network_max_bandwidth: None,
+                    disconnect_flooding_peers: None,
+                    compression: None,
+                    light_serve_gas: None,
                 }),
                 websockets: Some(Ws {
                     disable: Some(true),
@@ -961,13 +1096,16 @@ mod tests {
                     dynamic_gas_price: None,
                     max_blk_traverse: None,
                     local_max_gas_price: None,
-                    staker_private_key: None
+                    staker: None,
+                    staker_signer: None,
+                    internal_miner_threads: None,
                 }),
                 db: Some(Database {
                     pruning: Some("fast".into()),
                     pruning_history: Some(64),
                     pruning_memory: None,
                     disable_wal: None,
+                    db_backup: None,
                     cache_size: None,
                     //                    cache_size_db: Some(256),
                     cache_size_blocks: Some(16),
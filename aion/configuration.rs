@@ -23,8 +23,8 @@ use std::time::Duration;
 use cli::{Args, ArgsError};
 use aion_types::{U256, Address};
 use bytes::Bytes;
-use p2p::Config;
-use acore::client::{VMType};
+use p2p::{Compression, Config};
+use acore::client::{DifferentialConfig, VMType};
 use acore::miner::{MinerOptions, Banning};
 use acore::verification::queue::VerifierSettings;
 
@@ -41,7 +41,8 @@ use logger::{LogConfig};
 use dir::{self, Directories, default_local_path, default_data_path};
 use run::RunCmd;
 use blockchain::{
-    BlockchainCmd, ImportBlockchain, ExportBlockchain, KillBlockchain, RevertBlockchain, DataFormat,
+    BlockchainCmd, ImportBlockchain, ExportBlockchain, KillBlockchain, RevertBlockchain,
+    SnapshotBlockchain, RestoreBlockchain, DataFormat,
 };
 use account::{AccountCmd, NewAccount, ListAccounts, ImportAccounts, ImportAccount, ExportAccount};
 
@@ -86,6 +87,8 @@ impl Configuration {
         let pruning_history = self.args.arg_pruning_history;
         let pruning_memory = self.args.arg_pruning_memory;
         let vm_type = VMType::FastVM;
+        let fvm_backend = self.args.arg_fvm_backend.parse()?;
+        let differential = self.differential_config()?;
         let spec = self.chain()?;
         let logger_config = self.logger_config();
         let ws_conf = self.ws_config()?;
@@ -206,6 +209,35 @@ impl Configuration {
                 to_block: to_block_id(&self.args.arg_revert_blocks_to)?,
             };
             Cmd::Blockchain(BlockchainCmd::Revert(revert_cmd))
+        } else if self.args.cmd_snapshot {
+            let snapshot_cmd = SnapshotBlockchain {
+                spec,
+                cache_config,
+                dirs,
+                file_path: self.args.arg_snapshot_file.clone(),
+                pruning,
+                pruning_history,
+                pruning_memory,
+                compaction,
+                wal,
+                fat_db,
+                block_at: to_block_id(&self.args.arg_snapshot_at)?,
+            };
+            Cmd::Blockchain(BlockchainCmd::Snapshot(snapshot_cmd))
+        } else if self.args.cmd_restore {
+            let restore_cmd = RestoreBlockchain {
+                spec,
+                cache_config,
+                dirs,
+                file_path: self.args.arg_restore_file.clone(),
+                pruning,
+                pruning_history,
+                pruning_memory,
+                compaction,
+                wal,
+                fat_db,
+            };
+            Cmd::Blockchain(BlockchainCmd::Restore(restore_cmd))
         } else {
             let verifier_settings = self.verifier_settings();
 
@@ -222,14 +254,21 @@ impl Configuration {
                 http_conf,
                 ipc_conf,
                 net_conf,
+                node_key_path: replace_home(&self.directories().base, &self.args.arg_node_key_file)
+                    .into(),
                 acc_conf: self.accounts_config()?,
                 stake_conf: self.stake_config()?,
                 miner_extras: self.miner_extras()?,
                 fat_db,
                 compaction,
                 wal,
+                db_backup: self.args.flag_db_backup,
                 vm_type,
+                fvm_backend,
+                differential,
                 verifier_settings,
+                light: self.args.flag_light,
+                light_serve_gas: self.args.arg_light_serve_gas,
             };
             Cmd::Run(run_cmd)
         };
@@ -240,6 +279,20 @@ impl Configuration {
         })
     }
 
+    /// parse differential execution parameters
+    fn differential_config(&self) -> Result<Option<DifferentialConfig>, String> {
+        match self.args.arg_diff_fvm_backend {
+            Some(ref backend) => {
+                Ok(Some(DifferentialConfig {
+                    fvm_backend: backend.parse()?,
+                    report_path: replace_home(&self.directories().base, &self.args.arg_diff_report)
+                        .into(),
+                }))
+            }
+            None => Ok(None),
+        }
+    }
+
     /// parse miner startup parameters
     fn miner_extras(&self) -> Result<MinerExtras, String> {
         let extras = MinerExtras {
@@ -327,11 +380,24 @@ impl Configuration {
         let stake_contract = &self.args.arg_stake_contract;
         let cfg = StakeConfig {
             contract: string_to_address(stake_contract)?,
+            staker: self.staker()?,
+            signer: self.args.arg_staker_signer.clone(),
         };
+        if cfg.staker.is_some() && cfg.signer.is_some() {
+            return Err("--staker and --staker-signer cannot be used together".into());
+        }
 
         Ok(cfg)
     }
 
+    /// parse internal staker account
+    fn staker(&self) -> Result<Option<Address>, String> {
+        match self.args.arg_staker {
+            Some(ref staker) => Ok(Some(string_to_address(staker)?)),
+            None => Ok(None),
+        }
+    }
+
     /// parse account config
     fn accounts_config(&self) -> Result<AccountsConfig, String> {
         let cfg = AccountsConfig {
@@ -383,7 +449,7 @@ impl Configuration {
             minimal_gas_price: U256::from(self.args.arg_min_gas_price),
             maximal_gas_price: U256::from(self.args.arg_max_gas_price),
             local_max_gas_price: U256::from(self.args.arg_local_max_gas_price),
-            staker_private_key: self.args.arg_staker_private_key.to_owned(),
+            internal_miner_threads: self.args.arg_internal_miner_threads,
         };
 
         Ok(options)
@@ -421,6 +487,22 @@ impl Configuration {
         ret.sync_from_boot_nodes_only = self.args.flag_sync_from_boot_nodes_only;
         ret.net_id = self.args.arg_net_id.clone();
         ret.ip_black_list = self.args.arg_ip_black_list.clone();
+        ret.allow_legacy_peers = !self.args.flag_no_legacy_peers;
+        ret.bans_path = Some(replace_home(&self.directories().base, "$BASE/banned_peers").into());
+        ret.nodes_path = Some(replace_home(&self.directories().base, "$BASE/nodes").into());
+        ret.rate_limit.peer_messages = self.args.arg_peer_max_messages;
+        ret.rate_limit.peer_bytes = self.args.arg_peer_max_bandwidth.saturating_mul(1024);
+        ret.rate_limit.global_messages = self.args.arg_network_max_messages;
+        ret.rate_limit.global_bytes = self.args.arg_network_max_bandwidth.saturating_mul(1024);
+        ret.rate_limit.disconnect = self.args.flag_disconnect_flooding_peers;
+        ret.compression = Vec::new();
+        for name in &self.args.arg_compression {
+            match Compression::from_str(name) {
+                Some(compression) => ret.compression.push(compression),
+                None if name == "none" => {}
+                None => return Err(format!("Invalid compression algorithm: {}", name)),
+            }
+        }
         Ok(ret)
     }
 
@@ -484,10 +566,13 @@ impl Configuration {
 
     /// parse ipc config params
     fn ipc_config(&self) -> Result<IpcConfiguration, String> {
+        // admin api is served over ipc unless disabled explicitly
+        let mut apis = vec!["admin".to_owned()];
+        apis.extend(self.args.arg_ipc_apis.iter().cloned());
         let conf = IpcConfiguration {
             enabled: !self.args.flag_no_ipc,
             socket_addr: self.ipc_path(),
-            apis: self.args.arg_ipc_apis.join(",").parse()?,
+            apis: apis.join(",").parse()?,
         };
 
         Ok(conf)
@@ -639,11 +724,14 @@ mod tests {
     use acore::client::{BlockId};
     use acore::transaction::transaction_queue::PrioritizationStrategy;
     use account::{AccountCmd, NewAccount, ImportAccounts, ListAccounts};
-    use blockchain::{BlockchainCmd, ImportBlockchain, ExportBlockchain, DataFormat};
+    use blockchain::{
+        BlockchainCmd, ImportBlockchain, ExportBlockchain, SnapshotBlockchain, RestoreBlockchain,
+        DataFormat,
+    };
     use cli::Args;
     use dir::Directories;
     use run::RunCmd;
-    use p2p::Config;
+    use p2p::{Compression, Config, RateLimitConfig};
     use super::*;
 
     #[derive(Debug, PartialEq)]
@@ -671,6 +759,14 @@ mod tests {
             net_id: 256,
             sync_from_boot_nodes_only: false,
             ip_black_list: Vec::new(),
+            node_key: None,
+            allow_legacy_peers: true,
+            bans_path: Some(
+                replace_home(&Directories::default().base, "$BASE/banned_peers").into(),
+            ),
+            nodes_path: Some(replace_home(&Directories::default().base, "$BASE/nodes").into()),
+            rate_limit: RateLimitConfig::new(),
+            compression: vec![Compression::Snappy, Compression::Zstd],
         }
     }
 
@@ -796,6 +892,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_command_blockchain_snapshot() {
+        let args = vec!["aion", "snapshot", "--at", "100", "snapshot.bin"];
+        let conf = parse(&args);
+        assert_eq!(
+            conf.into_command().unwrap().cmd,
+            Cmd::Blockchain(BlockchainCmd::Snapshot(SnapshotBlockchain {
+                spec: Default::default(),
+                cache_config: Default::default(),
+                dirs: Default::default(),
+                file_path: Some("snapshot.bin".into()),
+                pruning: Default::default(),
+                pruning_history: 64,
+                pruning_memory: 32,
+                compaction: Default::default(),
+                wal: true,
+                fat_db: Default::default(),
+                block_at: BlockId::Number(100),
+            }))
+        );
+    }
+
+    #[test]
+    fn test_command_blockchain_restore() {
+        let args = vec!["aion", "restore", "snapshot.bin"];
+        let conf = parse(&args);
+        assert_eq!(
+            conf.into_command().unwrap().cmd,
+            Cmd::Blockchain(BlockchainCmd::Restore(RestoreBlockchain {
+                spec: Default::default(),
+                cache_config: Default::default(),
+                dirs: Default::default(),
+                file_path: Some("snapshot.bin".into()),
+                pruning: Default::default(),
+                pruning_history: 64,
+                pruning_memory: 32,
+                compaction: Default::default(),
+                wal: true,
+                fat_db: Default::default(),
+            }))
+        );
+    }
+
     #[test]
     fn test_run_cmd() {
         let args = vec!["aion"];
@@ -813,14 +952,20 @@ mod tests {
             http_conf: Default::default(),
             ipc_conf: Default::default(),
             net_conf: default_network_config(),
+            node_key_path: replace_home(&Directories::default().base, "$BASE/network.key").into(),
             acc_conf: Default::default(),
             stake_conf: Default::default(),
             miner_extras: Default::default(),
             compaction: Default::default(),
             wal: true,
+            db_backup: false,
             vm_type: Default::default(),
+            fvm_backend: Default::default(),
+            differential: None,
             fat_db: Default::default(),
             verifier_settings: Default::default(),
+            light: false,
+            light_serve_gas: 10000000,
         };
         assert_eq!(conf.into_command().unwrap().cmd, Cmd::Run(expected));
     }
@@ -846,6 +991,43 @@ mod tests {
         assert_eq!(conf3.miner_options().unwrap(), mining_options);
     }
 
+    #[test]
+    fn should_not_unlock_staker_account() {
+        let staker: Address =
+            "a0733306c2ee0c60224b0e59efeae8eee558c0ca1b39e7e5a14a575124549417".into();
+
+        let conf0 = parse(&["aion"]);
+        let conf1 = parse(&[
+            "aion",
+            "--staker",
+            "0xa0733306c2ee0c60224b0e59efeae8eee558c0ca1b39e7e5a14a575124549417",
+        ]);
+
+        assert_eq!(conf0.stake_config().unwrap().staker, None);
+        assert!(conf0.accounts_config().unwrap().unlocked_accounts.is_empty());
+        assert_eq!(conf1.stake_config().unwrap().staker, Some(staker));
+        // the staker signs with its password, it is not unlocked for rpc
+        assert!(conf1.accounts_config().unwrap().unlocked_accounts.is_empty());
+    }
+
+    #[test]
+    fn should_parse_staker_signer() {
+        let conf0 = parse(&["aion", "--staker-signer", "/tmp/signer.ipc"]);
+        let conf1 = parse(&[
+            "aion",
+            "--staker-signer",
+            "/tmp/signer.ipc",
+            "--staker",
+            "0xa0733306c2ee0c60224b0e59efeae8eee558c0ca1b39e7e5a14a575124549417",
+        ]);
+
+        assert_eq!(
+            conf0.stake_config().unwrap().signer,
+            Some("/tmp/signer.ipc".into())
+        );
+        assert!(conf1.stake_config().is_err());
+    }
+
     #[test]
     fn should_parse_rpc_hosts() {
         // given
@@ -866,6 +1048,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_parse_compression() {
+        // given
+
+        // when
+        let conf0 = parse(&["aion"]);
+        let conf1 = parse(&["aion", "--compression", "snappy"]);
+        let conf2 = parse(&["aion", "--compression", "none"]);
+        let conf3 = parse(&["aion", "--compression", "lz4"]);
+
+        // then
+        assert_eq!(
+            conf0.net_config().unwrap().compression,
+            vec![Compression::Snappy, Compression::Zstd]
+        );
+        assert_eq!(conf1.net_config().unwrap().compression, vec![Compression::Snappy]);
+        assert!(conf2.net_config().unwrap().compression.is_empty());
+        assert!(conf3.net_config().is_err());
+    }
+
     #[test]
     fn should_use_correct_cache_path_if_base_is_set() {
         let std = parse(&["aion"]);
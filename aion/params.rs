@@ -29,6 +29,7 @@ use user_defaults::UserDefaults;
 #[derive(Debug, PartialEq)]
 pub enum SpecType {
     Default,
+    Dev,
     Custom(String),
 }
 
@@ -42,6 +43,7 @@ impl str::FromStr for SpecType {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let spec = match s {
             "foundation" | "mainnet" => SpecType::Default,
+            "dev" => SpecType::Dev,
             other => SpecType::Custom(other.into()),
         };
         Ok(spec)
@@ -52,6 +54,7 @@ impl fmt::Display for SpecType {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.write_str(match *self {
             SpecType::Default => "mainnet",
+            SpecType::Dev => "dev",
             SpecType::Custom(ref custom) => custom,
         })
     }
@@ -65,6 +68,9 @@ impl SpecType {
             SpecType::Default => {
                 return Ok(Spec::new_foundation());
             }
+            SpecType::Dev => {
+                return Ok(Spec::new_dev());
+            }
             SpecType::Custom(ref filename) => {
                 file = fs::File::open(filename).map_err(|e| {
                     format!("Could not load specification file at {}: {}", filename, e)
@@ -134,6 +140,8 @@ impl Default for AccountsConfig {
 #[derive(Debug, PartialEq)]
 pub struct StakeConfig {
     pub contract: Address,
+    pub staker: Option<Address>,
+    pub signer: Option<String>,
 }
 
 impl Default for StakeConfig {
@@ -142,6 +150,8 @@ impl Default for StakeConfig {
             contract: Address::from(
                 "a0733306c2ee0c60224b0e59efeae8eee558c0ca1b39e7e5a14a575124549416",
             ),
+            staker: None,
+            signer: None,
         }
     }
 }
@@ -216,6 +226,7 @@ mod tests {
     #[test]
     fn test_spec_type_parsing() {
         assert_eq!(SpecType::Default, "mainnet".parse().unwrap());
+        assert_eq!(SpecType::Dev, "dev".parse().unwrap());
     }
 
     #[test]
@@ -226,6 +237,7 @@ mod tests {
     #[test]
     fn test_spec_type_display() {
         assert_eq!(format!("{}", SpecType::Default), "mainnet");
+        assert_eq!(format!("{}", SpecType::Dev), "dev");
         assert_eq!(format!("{}", SpecType::Custom("foo/bar".into())), "foo/bar");
     }
 
@@ -240,6 +252,10 @@ mod tests {
             Pruning::Specific(Algorithm::OverlayRecent),
             "fast".parse().unwrap()
         );
+        assert_eq!(
+            Pruning::Specific(Algorithm::RefCounted),
+            "basic".parse().unwrap()
+        );
     }
 
     #[test]
@@ -28,7 +28,7 @@ use helpers::aion_ipc_path;
 use jsonrpc_core::MetaIoHandler;
 use aion_rpc::informant::{RpcStats, Middleware};
 use aion_rpc::{self as rpc, Metadata, DomainsValidation};
-use rpc_apis::{self, ApiSet};
+use rpc_apis::{self, Api, ApiSet};
 use tokio::runtime::TaskExecutor;
 pub use aion_rpc::{IpcServer, HttpServer, WsServer, RequestMiddleware, WsError, WsErrorKind};
 
@@ -175,7 +175,10 @@ pub fn new_http<D: rpc_apis::Dependencies>(
     let addr = url
         .parse()
         .map_err(|_| format!("Invalid {} listen host/port given: {}", id, url))?;
-    let handler = setup_apis(conf.apis, deps);
+    // subscriptions need a persistent connection
+    let mut apis = conf.apis.list_apis();
+    apis.remove(&Api::Pubsub);
+    let handler = setup_apis(ApiSet::List(apis), deps);
 
     let cors_domains = into_domains(conf.cors);
     let allowed_hosts = into_domains(with_domain(conf.hosts));
@@ -26,11 +26,14 @@ use std::sync::Arc;
 
 use acore::account_provider::AccountProvider;
 use acore::client::Client;
+use acore::light::LightClient;
 use acore::miner::external::ExternalMiner;
 use acore::miner::Miner;
 use acore::sync::SyncProvider;
+use aion_types::U256;
 use aion_rpc::dispatch::{DynamicGasPrice, FullDispatcher};
-use aion_rpc::informant::{ActivityNotifier, ClientNotifier};
+use aion_rpc::impls::EthPubSubClient;
+use aion_rpc::informant::{ActivityNotifier, ClientNotifier, LightClientNotifier};
 use aion_rpc::Metadata;
 use jsonrpc_core::{self as core, MetaIoHandler};
 use parking_lot::Mutex;
@@ -53,6 +56,14 @@ pub enum Api {
     Rpc,
     /// Ping (Safe)
     Ping,
+    /// Eth Pub-Sub (Safe)
+    Pubsub,
+    /// Debug (Unsafe)
+    Debug,
+    /// Traces (Unsafe)
+    Traces,
+    /// Admin (Unsafe), only enabled over ipc or when listed explicitly
+    Admin,
 }
 
 impl FromStr for Api {
@@ -69,6 +80,10 @@ impl FromStr for Api {
             "personal" => Ok(Personal),
             "rpc" => Ok(Rpc),
             "ping" => Ok(Ping),
+            "pubsub" => Ok(Pubsub),
+            "debug" => Ok(Debug),
+            "traces" => Ok(Traces),
+            "admin" => Ok(Admin),
             api => Err(format!("Unknown api: {}", api)),
         }
     }
@@ -134,6 +149,10 @@ fn to_modules(apis: &HashSet<Api>) -> BTreeMap<String, String> {
             Api::Personal => ("personal", "1.0"),
             Api::Rpc => ("rpc", "1.0"),
             Api::Ping => ("ping", "1.0"),
+            Api::Pubsub => ("pubsub", "1.0"),
+            Api::Debug => ("debug", "1.0"),
+            Api::Traces => ("traces", "1.0"),
+            Api::Admin => ("admin", "1.0"),
         };
         modules.insert(name.into(), version.into());
     }
@@ -168,9 +187,38 @@ pub struct FullDependencies {
     pub dynamic_gas_price: Option<DynamicGasPrice>,
     /// task executor
     pub executor: TaskExecutor,
+    /// pub-sub client shared by the transports serving it
+    pub pubsub: Mutex<Option<EthPubSubClient<Client, SyncProvider>>>,
 }
 
 impl FullDependencies {
+    /// Get the pub-sub client, created on first use. Chain and transaction notifications are
+    /// registered once, whatever the number of transports serving pub-sub.
+    fn pubsub_client(&self) -> EthPubSubClient<Client, SyncProvider> {
+        let mut pubsub = self.pubsub.lock();
+        if let Some(ref client) = *pubsub {
+            return client.clone();
+        }
+
+        let client = EthPubSubClient::new(
+            self.client.clone(),
+            self.sync.clone(),
+            self.executor.clone(),
+        );
+        let h = client.handler();
+        self.miner
+            .add_transactions_listener(Box::new(move |hashes| {
+                if let Some(h) = h.upgrade() {
+                    h.notify_new_transactions(hashes);
+                }
+            }));
+        if let Some(h) = client.handler().upgrade() {
+            self.client.add_notify(h);
+        }
+        *pubsub = Some(client.clone());
+        client
+    }
+
     /// register api methods to handler
     fn extend_api<S>(
         &self,
@@ -260,6 +308,20 @@ impl FullDependencies {
                 Api::Ping => {
                     handler.extend_with(PingClient::new().to_delegate());
                 }
+                Api::Pubsub => {
+                    if !for_generic_pubsub {
+                        handler.extend_with(self.pubsub_client().to_delegate());
+                    }
+                }
+                Api::Debug => {
+                    handler.extend_with(DebugClient::new(self.client.clone()).to_delegate());
+                }
+                Api::Traces => {
+                    handler.extend_with(TracesClient::new(self.client.clone()).to_delegate());
+                }
+                Api::Admin => {
+                    handler.extend_with(AdminClient::new(&self.sync).to_delegate());
+                }
             }
         }
     }
@@ -280,9 +342,59 @@ impl Dependencies for FullDependencies {
     }
 }
 
+/// RPC dependencies for a light client.
+pub struct LightDependencies {
+    /// light client
+    pub client: Arc<LightClient>,
+    /// sync provider
+    pub sync: Arc<SyncProvider>,
+    /// gas price reported by eth_gasPrice
+    pub gas_price: U256,
+}
+
+impl Dependencies for LightDependencies {
+    type Notifier = LightClientNotifier;
+
+    fn activity_notifier(&self) -> LightClientNotifier { LightClientNotifier }
+
+    fn extend_with_set<S>(&self, handler: &mut MetaIoHandler<Metadata, S>, apis: &HashSet<Api>)
+    where S: core::Middleware<Metadata> {
+        use aion_rpc::impls::*;
+        use aion_rpc::traits::*;
+
+        for api in apis {
+            match *api {
+                Api::Web3 => {
+                    handler.extend_with(Web3Client::new().to_delegate());
+                }
+                Api::Net => {
+                    handler.extend_with(NetClient::new(&self.sync).to_delegate());
+                }
+                Api::Eth => {
+                    let client = LightEthClient::new(&self.client, &self.sync, self.gas_price);
+                    handler.extend_with(client.to_delegate());
+                }
+                Api::Rpc => {
+                    let modules = to_modules(&apis);
+                    handler.extend_with(RpcClient::new(modules).to_delegate());
+                }
+                Api::Ping => {
+                    handler.extend_with(PingClient::new().to_delegate());
+                }
+                Api::Admin => {
+                    handler.extend_with(AdminClient::new(&self.sync).to_delegate());
+                }
+                // the other apis need the full chain
+                _ => {}
+            }
+        }
+    }
+}
+
 impl ApiSet {
     /// list all enabled apis
     pub fn list_apis(&self) -> HashSet<Api> {
+        // admin, debug and traces are left out, they have to be listed explicitly
         let all = [
             Api::Web3,
             Api::Net,
@@ -291,12 +403,13 @@ impl ApiSet {
             Api::Rpc,
             Api::Personal,
             Api::Ping,
+            Api::Pubsub,
         ]
             .into_iter()
             .cloned()
             .collect();
 
-        let public_list = [
+        let public_list: HashSet<Api> = [
             Api::Web3,
             Api::Net,
             Api::Eth,
@@ -304,6 +417,7 @@ impl ApiSet {
             Api::Rpc,
             Api::Personal,
             Api::Ping,
+            Api::Pubsub,
         ]
             .into_iter()
             .cloned()
@@ -312,7 +426,11 @@ impl ApiSet {
         match *self {
             ApiSet::List(ref apis) => apis.clone(),
             ApiSet::PublicContext => public_list,
-            ApiSet::IpcContext => public_list,
+            ApiSet::IpcContext => {
+                let mut ipc_list = public_list;
+                ipc_list.insert(Api::Admin);
+                ipc_list
+            }
             ApiSet::All => all,
         }
     }
@@ -330,6 +448,10 @@ mod test {
         assert_eq!(Api::Stratum, "stratum".parse().unwrap());
         assert_eq!(Api::Personal, "personal".parse().unwrap());
         assert_eq!(Api::Rpc, "rpc".parse().unwrap());
+        assert_eq!(Api::Pubsub, "pubsub".parse().unwrap());
+        assert_eq!(Api::Debug, "debug".parse().unwrap());
+        assert_eq!(Api::Traces, "traces".parse().unwrap());
+        assert_eq!(Api::Admin, "admin".parse().unwrap());
         assert!("rp".parse::<Api>().is_err());
     }
 
@@ -352,6 +474,9 @@ mod test {
             Api::Rpc,
             Api::Personal,
             Api::Ping,
+            Api::Pubsub,
+            // unsafe
+            Api::Admin,
         ]
         .into_iter()
         .collect();
@@ -371,6 +496,7 @@ mod test {
                     Api::Rpc,
                     Api::Personal,
                     Api::Ping,
+                    Api::Pubsub,
                 ]
                 .into_iter()
                 .collect()
@@ -378,6 +504,28 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_unsafe_apis_listed_explicitly() {
+        let list = |apis: &str| apis.parse::<ApiSet>().unwrap().list_apis();
+        for &(name, ref api) in &[
+            ("admin", Api::Admin),
+            ("debug", Api::Debug),
+            ("traces", Api::Traces),
+        ] {
+            assert!(!list("all").contains(api), "{} enabled by all", name);
+            assert!(
+                list(&format!("all,{}", name)).contains(api),
+                "{} not enabled when listed",
+                name
+            );
+            assert!(
+                !list(&format!("{0},all,-{0}", name)).contains(api),
+                "{} enabled when removed",
+                name
+            );
+        }
+    }
+
     #[test]
     fn test_all_without_personal_apis() {
         assert_eq!(
@@ -390,6 +538,7 @@ mod test {
                     Api::Stratum,
                     Api::Rpc,
                     Api::Ping,
+                    Api::Pubsub,
                 ]
                 .into_iter()
                 .collect()
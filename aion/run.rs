@@ -19,19 +19,25 @@
  *
  ******************************************************************************/
 
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Weak};
 use std::thread;
 use std::time::{Duration, Instant};
 
 use acore::account_provider::{AccountProvider, AccountProviderSettings};
-use acore::client::{Client, DatabaseCompactionProfile, VMType , ChainNotify
-};
+use acore::client::{Client, DatabaseCompactionProfile, DifferentialConfig, FastVMBackend, VMType,
+ChainNotify};
 use acore::miner::external::ExternalMiner;
-use acore::miner::{Miner, MinerOptions, MinerService};
-use acore::service::{ClientService, run_miner, run_staker, pos_sealing, run_transaction_pool};
+use acore::miner::{AccountStaker, InternalMiner, Miner, MinerOptions, MinerService, StakerSigner};
+use acore::light::{Fetcher, LightClient};
+use acore::service::{
+    ClientService, LightClientService, run_miner, run_staker, pos_sealing, run_transaction_pool,
+    run_instant_seal,
+};
 use acore::verification::queue::VerifierSettings;
-use acore::sync::Sync;
+use acore::sync::{LightSync, Sync};
 use aion_rpc::{dispatch::DynamicGasPrice, informant};
+use aion_types::Address;
 use aion_version::version;
 use ansi_term::Colour;
 use cache::CacheConfig;
@@ -49,6 +55,8 @@ use parking_lot::{Condvar, Mutex};
 use rpc;
 use rpc_apis;
 use p2p::Config;
+use key::{generate_keypair, Ed25519Secret};
+use rustc_hex::ToHex;
 
 use user_defaults::UserDefaults;
 
@@ -71,18 +79,24 @@ pub struct RunCmd {
     pub http_conf: rpc::HttpConfiguration,
     pub ipc_conf: rpc::IpcConfiguration,
     pub net_conf: Config,
+    pub node_key_path: PathBuf,
     pub acc_conf: AccountsConfig,
     pub stake_conf: StakeConfig,
     pub miner_extras: MinerExtras,
     pub fat_db: Switch,
     pub compaction: DatabaseCompactionProfile,
     pub wal: bool,
+    pub db_backup: bool,
     pub vm_type: VMType,
+    pub fvm_backend: FastVMBackend,
+    pub differential: Option<DifferentialConfig>,
     pub verifier_settings: VerifierSettings,
+    pub light: bool,
+    pub light_serve_gas: u64,
 }
 
 /// run kernel
-pub fn execute_impl(cmd: RunCmd) -> Result<(Weak<Client>), String> {
+pub fn execute_impl(mut cmd: RunCmd) -> Result<(Weak<Client>), String> {
     // load spec
     let spec = cmd.spec.spec()?;
 
@@ -130,6 +144,7 @@ pub fn execute_impl(cmd: RunCmd) -> Result<(Weak<Client>), String> {
     let tx_status_channel = IoChannel::disconnected();
 
     // create miner
+    let internal_miner_threads = cmd.miner_options.internal_miner_threads;
     let miner = Miner::new(
         cmd.miner_options,
         &spec,
@@ -140,6 +155,14 @@ pub fn execute_impl(cmd: RunCmd) -> Result<(Weak<Client>), String> {
     miner.set_gas_floor_target(cmd.miner_extras.gas_floor_target);
     miner.set_gas_ceil_target(cmd.miner_extras.gas_ceil_target);
     miner.set_extra_data(cmd.miner_extras.extra_data);
+    if let Some(staker) = cmd.stake_conf.staker {
+        miner.set_staker(Arc::new(account_staker(&account_provider, staker, &passwords)?));
+        info!(target: "run", "Internal staker: {}", staker);
+    }
+    if let Some(ref signer) = cmd.stake_conf.signer {
+        miner.set_staker(remote_staker(signer)?);
+        info!(target: "run", "Internal staker signing through {}", signer);
+    }
 
     // create client config
     let mut client_config = to_client_config(
@@ -156,7 +179,11 @@ pub fn execute_impl(cmd: RunCmd) -> Result<(Weak<Client>), String> {
 
     client_config.queue.verifier_settings = cmd.verifier_settings;
     client_config.stake_contract = cmd.stake_conf.contract;
+    client_config.db_backup = cmd.db_backup;
+    client_config.fvm_backend = cmd.fvm_backend;
+    client_config.differential = cmd.differential;
 
+    cmd.net_conf.set_node_key(load_node_key(&cmd.node_key_path)?)?;
     let (id, binding) = &cmd.net_conf.get_id_and_binding();
 
     info!(target: "run","          id: {}", &id);
@@ -170,7 +197,7 @@ pub fn execute_impl(cmd: RunCmd) -> Result<(Weak<Client>), String> {
         &cmd.dirs.ipc_path(),
         miner.clone(),
     )
-    .map_err(|e| format!("Client service error: {:?}", e))?;
+    .map_err(|e| format!("Client service error: {}", e))?;
 
     info!(target: "run","     genesis: {:?}",genesis_hash);
     info!(
@@ -192,6 +219,8 @@ pub fn execute_impl(cmd: RunCmd) -> Result<(Weak<Client>), String> {
 
     let client = service.client();
 
+    let fork_blocks = spec.engine.params().fork_blocks();
+
     // drop the spec to free up genesis state.
     drop(spec);
 
@@ -205,7 +234,13 @@ pub fn execute_impl(cmd: RunCmd) -> Result<(Weak<Client>), String> {
           if cmd.ipc_conf.enabled { "y" } else { "n" },
     );
 
-    let sync = Arc::new(Sync::new(cmd.net_conf.clone(), client.clone()));
+    let sync = Arc::new(Sync::new(
+        cmd.net_conf.clone(),
+        client.clone(),
+        client.clone(),
+        fork_blocks,
+        cmd.light_serve_gas.into(),
+    ));
     let weak_sync = Arc::downgrade(&sync);
     sync.register_callback(weak_sync);
     let sync_notify = sync.clone() as Arc<ChainNotify>;
@@ -239,6 +274,7 @@ pub fn execute_impl(cmd: RunCmd) -> Result<(Weak<Client>), String> {
         external_miner: external_miner.clone(),
         dynamic_gas_price: cmd.dynamic_gas_price.clone(),
         executor: runtime_rpc.executor(),
+        pubsub: Default::default(),
     });
 
     let dependencies = rpc::Dependencies {
@@ -297,6 +333,17 @@ pub fn execute_impl(cmd: RunCmd) -> Result<(Weak<Client>), String> {
         .expect("seal block runtime loop init failed");
     let executor_miner = runtime_miner.executor();
     let close_miner = run_miner(executor_miner.clone(), client.clone());
+    let close_instant_seal = match client.engine().seals_internally() {
+        Some(_) => Some(run_instant_seal(executor_miner.clone(), client.clone())),
+        None => None,
+    };
+
+    // start internal CPU miner
+    let internal_miner = if internal_miner_threads > 0 {
+        Some(InternalMiner::start(client.clone(), internal_miner_threads))
+    } else {
+        None
+    };
 
     // start internal staker module
     let runtime_staker = tokio::runtime::Builder::new()
@@ -327,6 +374,12 @@ pub fn execute_impl(cmd: RunCmd) -> Result<(Weak<Client>), String> {
     // close pool
     let _ = close_transaction_pool.send(());
     let _ = close_miner.send(());
+    if let Some(close_instant_seal) = close_instant_seal {
+        let _ = close_instant_seal.send(());
+    }
+    if let Some(mut internal_miner) = internal_miner {
+        internal_miner.stop();
+    }
     let _ = close_staker.send(());
     let _ = close_pos_invoker.send(());
 
@@ -386,7 +439,156 @@ pub fn execute(cmd: RunCmd) -> Result<(), String> {
             wait_for_drop(weak_client);
         })
     }
-    wait(execute_impl(cmd))
+    match cmd.light {
+        true => wait(execute_light(cmd)),
+        false => wait(execute_impl(cmd)),
+    }
+}
+
+/// run kernel as a light client
+pub fn execute_light(mut cmd: RunCmd) -> Result<(Weak<LightClient>), String> {
+    // load spec
+    let spec = cmd.spec.spec()?;
+
+    // load genesis hash
+    let genesis_hash = spec.genesis_header().hash();
+
+    // database paths
+    let db_dirs = cmd
+        .dirs
+        .database(genesis_hash.clone(), spec.data_dir.clone());
+
+    // load user defaults
+    let user_defaults = UserDefaults::load(&db_dirs.user_defaults_path())?;
+    let algorithm = cmd.pruning.to_algorithm(&user_defaults);
+
+    // create dirs used by aion
+    cmd.dirs.create_dirs()?;
+
+    print_logo();
+
+    print_running_environment(&cmd.spec, &spec.data_dir, &cmd.dirs, &db_dirs);
+
+    // create client config
+    let client_config = to_client_config(
+        &cmd.cache_config,
+        spec.name.to_lowercase(),
+        false,
+        cmd.compaction,
+        cmd.wal,
+        cmd.vm_type,
+        algorithm,
+        cmd.pruning_history,
+        cmd.pruning_memory,
+    );
+
+    cmd.net_conf.set_node_key(load_node_key(&cmd.node_key_path)?)?;
+    let (id, binding) = &cmd.net_conf.get_id_and_binding();
+
+    info!(target: "run","          id: {}", &id);
+    info!(target: "run","     binding: {}", &binding);
+
+    // create light client service.
+    let service = LightClientService::start(client_config, &spec, &db_dirs.light_client_path())
+        .map_err(|e| format!("Light client service error: {}", e))?;
+
+    info!(target: "run","     genesis: {:?}",genesis_hash);
+
+    let client = service.client();
+
+    let fork_blocks = spec.engine.params().fork_blocks();
+
+    // drop the spec to free up genesis state.
+    drop(spec);
+
+    // log apis
+    info!(target: "run", "        apis: rpc-http({}) rpc-ws({}) rpc-ipc({})",
+          if cmd.http_conf.enabled { "y" } else { "n" },
+          if cmd.ws_conf.enabled { "y" } else { "n" },
+          if cmd.ipc_conf.enabled { "y" } else { "n" },
+    );
+
+    let sync = Arc::new(LightSync::new(cmd.net_conf.clone(), client.clone(), fork_blocks));
+    let weak_sync = Arc::downgrade(&sync);
+    sync.register_callback(weak_sync.clone());
+    client.set_fetcher(weak_sync as Weak<Fetcher>);
+
+    let runtime_sync = tokio::runtime::Builder::new()
+        .name_prefix("p2p-loop #")
+        .build()
+        .expect("p2p runtime loop init failed");
+
+    if let Some(config_path) = cmd.dirs.config {
+        fill_back_local_node(config_path, sync.get_local_node_info());
+    }
+    sync.run(runtime_sync.executor());
+
+    // start rpc servers
+    let rpc_stats = Arc::new(informant::RpcStats::default());
+    let deps_for_rpc_apis = Arc::new(rpc_apis::LightDependencies {
+        client: client.clone(),
+        sync: sync.clone(),
+        gas_price: cmd.miner_options.minimal_gas_price,
+    });
+
+    let dependencies = rpc::Dependencies {
+        apis: deps_for_rpc_apis.clone(),
+        stats: rpc_stats.clone(),
+    };
+
+    let runtime_jsonrpc = tokio::runtime::Builder::new()
+        .core_threads(cmd.http_conf.processing_threads)
+        .name_prefix("jsonrpc_eventloop-")
+        .build()
+        .map_err(|_| format!("can't spawn jsonrpc eventloop"))?;
+    let executor_jsonrpc = runtime_jsonrpc.executor();
+
+    let ws_server = rpc::new_ws(cmd.ws_conf.clone(), &dependencies, executor_jsonrpc.clone())?;
+    let ipc_server = rpc::new_ipc(
+        cmd.ipc_conf.clone(),
+        &dependencies,
+        executor_jsonrpc.clone(),
+    )?;
+    let http_server = rpc::new_http(
+        "HTTP JSON-RPC",
+        "jsonrpc",
+        cmd.http_conf.clone(),
+        &dependencies,
+        executor_jsonrpc.clone(),
+    )?;
+
+    // Create a weak reference to the client so that we can wait on shutdown until it is dropped
+    let weak_client = Arc::downgrade(&client);
+
+    // Handle exit
+    wait_for_exit();
+
+    info!(target: "run","AION is shutting down. Please wait for remaining tasks to finish.");
+
+    // close rpc
+    if let Some(ws_server) = ws_server {
+        ws_server.close();
+    }
+    if let Some(http_server) = http_server {
+        http_server.close();
+    }
+    if let Some(ipc_server) = ipc_server {
+        ipc_server.close();
+    }
+
+    sync.shutdown();
+
+    runtime_sync
+        .shutdown_now()
+        .wait()
+        .expect("Failed to shutdown p2p&sync runtime instance!");
+    runtime_jsonrpc
+        .shutdown_now()
+        .wait()
+        .expect("Failed to shutdown json rpc runtime instance!");
+
+    info!(target: "run","shutdown completed");
+    Ok(weak_client)
 }
 
 /// env printer
@@ -410,6 +612,9 @@ fn print_running_environment(
         SpecType::Default => {
             info!(target: "run", "Load built-in Mainnet Genesis Spec.");
         }
+        SpecType::Dev => {
+            info!(target: "run", "Load built-in Development Genesis Spec.");
+        }
         SpecType::Custom(ref filename) => {
             info!(
                 target: "run",
@@ -511,6 +716,40 @@ fn prepare_account_provider(
     Ok(account_provider)
 }
 
+/// Staker signing with the keystore account `staker`, with the first of `passwords` which
+/// decrypts it. The account is not unlocked for other users of the account provider.
+fn account_staker(
+    account_provider: &Arc<AccountProvider>,
+    staker: Address,
+    passwords: &[String],
+) -> Result<AccountStaker, String>
+{
+    if !account_provider.has_account(&staker).unwrap_or(false) {
+        return Err(format!("Staker account {} not found for the current chain.", staker));
+    }
+    passwords
+        .iter()
+        .filter_map(|p| AccountStaker::new(account_provider.clone(), staker, p.clone()).ok())
+        .next()
+        .ok_or_else(|| {
+            format!(
+                "No valid password for staker account {}. {}",
+                staker, VERIFY_PASSWORD_HINT
+            )
+        })
+}
+
+#[cfg(unix)]
+fn remote_staker(signer: &str) -> Result<Arc<StakerSigner>, String> {
+    use acore::miner::RemoteStaker;
+    Ok(Arc::new(RemoteStaker::new(signer)?))
+}
+
+#[cfg(not(unix))]
+fn remote_staker(_signer: &str) -> Result<Arc<StakerSigner>, String> {
+    Err("--staker-signer needs Unix domain sockets and is not supported on this platform".into())
+}
+
 /// Construct an error `String` with an adaptive hint on how to create an account.
 fn build_create_account_hint(spec: &SpecType, keys: &str) -> String {
     format!(
@@ -519,7 +758,47 @@ fn build_create_account_hint(spec: &SpecType, keys: &str) -> String {
     )
 }
 
-/// Fill back local node if local node id is empty
+/// Load node key, a new one is generated and stored if the file does not exist
+fn load_node_key(path: &Path) -> Result<Ed25519Secret, String> {
+    use std::fs;
+    use std::io::Write;
+    if path.exists() {
+        let hex = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read node key {}: {}", path.display(), e))?;
+        hex.trim()
+            .parse::<Ed25519Secret>()
+            .map_err(|e| format!("Invalid node key {}: {}", path.display(), e))
+    } else {
+        let keypair = generate_keypair();
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        }
+        create_node_key_file(path)
+            .and_then(|mut file| file.write_all(keypair.secret().to_hex().as_bytes()))
+            .map_err(|e| format!("Failed to write node key {}: {}", path.display(), e))?;
+        info!(target: "run", "Node key generated at {}", path.display());
+        Ok(keypair.secret().clone())
+    }
+}
+
+/// node key file is readable and writable by the owner only, like the keystore key files
+#[cfg(unix)]
+fn create_node_key_file(path: &Path) -> ::std::io::Result<::std::fs::File> {
+    use std::os::unix::fs::OpenOptionsExt;
+    ::std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)
+}
+
+#[cfg(not(unix))]
+fn create_node_key_file(path: &Path) -> ::std::io::Result<::std::fs::File> {
+    ::std::fs::OpenOptions::new().write(true).create_new(true).open(path)
+}
+
+/// Fill back local node if local node id is empty or not derived from the node key
 fn fill_back_local_node(path: String, local_node_info: &String) {
     use std::fs;
     use std::io::BufRead;
@@ -533,9 +812,8 @@ fn fill_back_local_node(path: String, local_node_info: &String) {
         .map(|config| {
             let config_ = config.clone().to_owned();
             let option: Vec<&str> = config_.split("=").collect();
-            if option[0].trim() == "local_node" && option[1]
-                .find("00000000-0000-0000-0000-000000000000")
-                .is_some()
+            if option[0].trim() == "local_node"
+                && option[1].trim().trim_matches('"') != local_node_info.as_str()
             {
                 no_change = false;
                 format!("local_node = {:?}", local_node_info)
@@ -28,7 +28,9 @@ use keychain::{
     StoreAccountRef, OpaqueSecretEd25519,
 };
 use keychain::accounts_dir::MemoryDirectory;
-use keychain::ethkey::{Address, Ed25519Secret, generate_keypair, Message, Ed25519Signature};
+use keychain::ethkey::{
+    Address, Ed25519Public, Ed25519Secret, generate_keypair, Message, Ed25519Signature,
+};
 pub use keychain::{Derivation, IndexDerivation, KeyFile};
 
 #[cfg(test)]
@@ -426,6 +428,47 @@ impl AccountProvider {
         }
     }
 
+    /// Signs data of any length. If password is not provided the account must be unlocked.
+    pub fn sign_data(
+        &self,
+        address: Address,
+        password: Option<String>,
+        data: &[u8],
+    ) -> Result<Ed25519Signature, SignError>
+    {
+        let account = self.sstore.account_ref(&address)?;
+        match self.unlocked_secrets.read().get(&account) {
+            Some(secret) => Ok(self.sstore.sign_data_with_secret(&secret, data)?),
+            None => {
+                let password = password
+                    .map(Ok)
+                    .unwrap_or_else(|| self.password(&account))?;
+                let secret = self.sstore.raw_secret(&account, &password)?;
+                Ok(self.sstore.sign_data_with_secret(&secret, data)?)
+            }
+        }
+    }
+
+    /// Public key of the account, decrypted with `password`.
+    pub fn account_public(&self, address: Address, password: &str) -> Result<Ed25519Public, Error> {
+        let account = self.sstore.account_ref(&address)?;
+        let secret = self.sstore.raw_secret(&account, password)?;
+        Ok(self.sstore.public_with_secret(&secret)?)
+    }
+
+    /// Signs data of any length with the account decrypted by `password`.
+    pub fn sign_data_with_password(
+        &self,
+        address: Address,
+        password: &str,
+        data: &[u8],
+    ) -> Result<Ed25519Signature, SignError>
+    {
+        let account = self.sstore.account_ref(&address)?;
+        let secret = self.sstore.raw_secret(&account, password)?;
+        Ok(self.sstore.sign_data_with_secret(&secret, data)?)
+    }
+
     /// Signs given message with supplied token. Returns a token to use in next signing within this session.
     pub fn sign_with_token(
         &self,
@@ -452,6 +495,7 @@ mod tests {
     use std::time::Instant;
     use keychain::ethkey::{generate_keypair, Address};
     use keychain::StoreAccountRef;
+    use rcrypto::ed25519;
 
     #[test]
     fn unlock_account_temp() {
@@ -524,6 +568,25 @@ mod tests {
         assert!(ap.sign(kp.address(), None, Default::default()).is_err());
     }
 
+    #[test]
+    fn sign_data_of_any_length() {
+        let kp = generate_keypair();
+        let ap = AccountProvider::transient_provider();
+        assert!(
+            ap.insert_account_ed25519(kp.secret().clone(), "test")
+                .is_ok()
+        );
+        let seed = [7u8; 64];
+        assert!(ap.sign_data(kp.address(), None, &seed).is_err());
+        assert!(
+            ap.unlock_account_permanently(&kp.address(), "test".into())
+                .is_ok()
+        );
+        let signature = ap.sign_data(kp.address(), None, &seed).unwrap();
+        assert_eq!(signature.get_public(), *kp.public());
+        assert!(ed25519::verify(&seed, kp.public(), &signature[32..]));
+    }
+
     #[test]
     fn should_not_return_blacklisted_account() {
         // given
@@ -817,6 +817,25 @@ impl BlockChain {
         })
     }
 
+    /// Inserts a block restored from a snapshot. The block must extend a block already in
+    /// the chain, its total difficulty is taken from there. `is_best` forces the best block to
+    /// be updated to this block.
+    pub fn insert_snapshot_block(
+        &self,
+        batch: &mut DBTransaction,
+        bytes: &[u8],
+        receipts: Vec<Receipt>,
+        is_best: bool,
+    )
+    {
+        let parent_hash = BlockView::new(bytes).header_view().parent_hash();
+        assert!(
+            self.is_known(&parent_hash),
+            "snapshot blocks are checked to extend the chain before insertion; qed"
+        );
+        self.insert_unordered(batch, bytes, receipts, None, is_best, false);
+    }
+
     #[cfg(test)]
     /// Inserts a verified, known block from the canonical chain.
     ///
@@ -835,6 +854,19 @@ impl BlockChain {
         is_best: bool,
         is_ancient: bool,
     ) -> bool
+    {
+        self.insert_unordered(batch, bytes, receipts, parent_td, is_best, is_ancient)
+    }
+
+    fn insert_unordered(
+        &self,
+        batch: &mut DBTransaction,
+        bytes: &[u8],
+        receipts: Vec<Receipt>,
+        parent_td: Option<U256>,
+        is_best: bool,
+        is_ancient: bool,
+    ) -> bool
     {
         let block = BlockView::new(bytes);
         let header = block.header_view();
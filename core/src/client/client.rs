@@ -31,7 +31,7 @@ use time::precise_time_ns;
 use blake2b::blake2b;
 use acore_bytes::Bytes;
 use journaldb;
-use kvdb::{DBTransaction, KeyValueDB};
+use kvdb::{DBTransaction, DBValue, KeyValueDB};
 use trie::{Trie, TrieFactory, TrieSpec};
 
 // other
@@ -42,6 +42,7 @@ use blockchain::{BlockChain, BlockProvider, TreeRoute};
 use types::blockchain::import_route::ImportRoute;
 use types::blockchain::extra::TransactionAddress;
 use client::Error as ClientError;
+use super::differential::DifferentialExecutor;
 use client::{
     BlockChainClient, BlockId, BlockImportError, CallAnalytics, ChainNotify, ClientConfig,
     MiningBlockChainClient, ProvingBlockChainClient, PruningInfo, TransactionId,
@@ -61,8 +62,9 @@ use parking_lot::{Mutex, RwLock, Condvar};
 use receipt::{LocalizedReceipt, Receipt};
 use rlp::*;
 use service::ClientIoMessage;
+use snapshot::{self, SnapshotReader, SnapshotWriter, Error as SnapshotError};
 use spec::Spec;
-use state::{State};
+use state::{self, State};
 use db::StateDB;
 use transaction::{
     Transaction,
@@ -157,6 +159,7 @@ pub struct Client {
     notify: RwLock<Vec<Weak<ChainNotify>>>,
     last_hashes: RwLock<VecDeque<H256>>,
     factories: Factories,
+    differential: Option<DifferentialExecutor>,
     history: u64,
 }
 
@@ -178,7 +181,7 @@ impl Client {
 
         let trie_factory = TrieFactory::new(trie_spec);
         let factories = Factories {
-            vm: VmFactory::new(),
+            vm: VmFactory::with_fvm_backend(config.fvm_backend),
             trie: trie_factory,
             accountdb: Default::default(),
         };
@@ -225,6 +228,11 @@ impl Client {
             );
         }
 
+        let differential = config
+            .differential
+            .clone()
+            .map(|c| DifferentialExecutor::new(c, &factories));
+
         let block_queue = BlockQueue::new(
             config.queue.clone(),
             engine.clone(),
@@ -246,6 +254,7 @@ impl Client {
             notify: RwLock::new(Vec::new()),
             last_hashes: RwLock::new(VecDeque::new()),
             factories,
+            differential,
             history,
         });
 
@@ -430,14 +439,16 @@ impl Client {
             }
         }
 
+        let grand_parent = grand_parent.map(|header| header.decode());
+        let great_grand_parent = great_grand_parent.map(|header| header.decode());
         let enact_result = enact_verified(
             block,
             engine,
             db,
             &parent,
-            grand_parent.map(|header| header.decode()).as_ref(),
-            great_grand_parent.map(|header| header.decode()).as_ref(),
-            last_hashes,
+            grand_parent.as_ref(),
+            great_grand_parent.as_ref(),
+            last_hashes.clone(),
             self.factories.clone(),
             self.db.read().clone(),
             self,
@@ -452,6 +463,23 @@ impl Client {
             return Err(());
         }
 
+        // Differential execution on a scratch state, never affects the import
+        if let Some(ref differential) = self.differential {
+            let scratch_result = enact_verified(
+                block,
+                engine,
+                self.state_db.read().boxed_clone(),
+                &parent,
+                grand_parent.as_ref(),
+                great_grand_parent.as_ref(),
+                last_hashes,
+                differential.factories(),
+                self.db.read().clone(),
+                self,
+            );
+            differential.check(header, &locked_block, scratch_result);
+        }
+
         Ok(locked_block)
     }
 
@@ -784,6 +812,44 @@ impl Client {
         .expect("State root of best block header always valid.")
     }
 
+    /// Take a snapshot of the state at the given block, along with the blocks leading up to it.
+    pub fn take_snapshot<W: SnapshotWriter>(&self, writer: W, at: BlockId) -> Result<(), SnapshotError> {
+        let chain = self.chain.read().clone();
+        let hash = Self::block_hash(&chain, &self.miner, at.clone())
+            .ok_or_else(|| SnapshotError::UnknownBlock(at))?;
+        let header = chain
+            .block_header(&hash)
+            .ok_or(SnapshotError::BlockNotFound(hash))?;
+
+        let state_db = self.state_db.read().boxed_clone();
+        if !state_db.journal_db().contains(header.state_root()) {
+            return Err(SnapshotError::StatePruned(header.number()));
+        }
+
+        snapshot::take_snapshot(
+            &chain,
+            hash,
+            state_db.as_hashstore(),
+            &**self.db.read(),
+            writer,
+        )
+    }
+
+    /// Restore a snapshot. The database must not contain any blocks beyond genesis,
+    /// and the client should be restarted once the restoration is done.
+    pub fn restore_snapshot<R: SnapshotReader>(&self, reader: &R) -> Result<(), SnapshotError> {
+        let _import_lock = self.import_lock.lock();
+        let chain = self.chain.read().clone();
+        if chain.best_block_number() != 0 {
+            return Err(SnapshotError::DatabaseNotEmpty(chain.best_block_number()));
+        }
+
+        let journal_db = self.state_db.read().journal_db().boxed_clone();
+        snapshot::restore(&chain, &*self.engine, journal_db, reader)?;
+        self.db.read().flush()?;
+        Ok(())
+    }
+
     #[cfg(test)]
     /// Get info on the cache.
     pub fn blockchain_cache_info(&self) -> BlockChainCacheSize { self.chain.read().cache_size() }
@@ -848,6 +914,7 @@ impl Client {
             env_info: &EnvInfo,
             machine: &::machine::EthereumMachine,
             state_diff: bool,
+            tracing: bool,
             transaction: &SignedTransaction,
         ) -> Result<Executed, CallError>
         {
@@ -864,6 +931,7 @@ impl Client {
                 .map_or(false, |v| env_info.number >= v);
             if aion040fork && for_local_avm(state, transaction) {
                 let avm_result = AvmExecutor::new(state, env_info, machine)
+                    .with_tracing(tracing)
                     .transact_virtual(&[transaction.clone()], false);
                 match avm_result[0].clone() {
                     Err(x) => return Err(x.into()),
@@ -871,6 +939,7 @@ impl Client {
                 }
             } else {
                 ret = FvmExecutor::new(state, env_info, machine)
+                    .with_tracing(tracing)
                     .transact_virtual(transaction, false)?;
             }
 
@@ -883,8 +952,9 @@ impl Client {
         }
 
         let state_diff = analytics.state_diffing;
+        let tracing = analytics.transaction_tracing;
 
-        call(state, env_info, machine, state_diff, t)
+        call(state, env_info, machine, state_diff, tracing, t)
     }
 
     fn block_number_ref(&self, id: &BlockId) -> Option<BlockNumber> {
@@ -1259,17 +1329,18 @@ impl BlockChainClient for Client {
 
         const PROOF: &'static str =
             "The transaction address contains a valid index within block; qed";
-        Ok(self
+        let mut replayed = self
             .replay_block_transactions(block, analytics)?
-            .nth(address.index)
-            .expect(PROOF))
+            .take(address.index + 1)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(replayed.pop().expect(PROOF))
     }
 
     fn replay_block_transactions(
         &self,
         block: BlockId,
         analytics: CallAnalytics,
-    ) -> Result<Box<Iterator<Item = Executed>>, CallError>
+    ) -> Result<Box<Iterator<Item = Result<Executed, CallError>>>, CallError>
     {
         let mut env_info = self.env_info(block).ok_or(CallError::StatePruned)?;
         let body = self.block_body(block).ok_or(CallError::StatePruned)?;
@@ -1281,16 +1352,16 @@ impl BlockChainClient for Client {
 
         const PROOF: &'static str =
             "Transactions fetched from blockchain; blockchain transactions are valid; qed";
-        const EXECUTE_PROOF: &'static str = "Transaction replayed; qed";
 
-        Ok(Box::new(txs.into_iter().map(move |t| {
-            let t = SignedTransaction::new(t).expect(PROOF);
-            let machine = engine.machine();
-            let x = Self::do_virtual_call(machine, &env_info, &mut state, &t, analytics)
-                .expect(EXECUTE_PROOF);
-            env_info.gas_used = env_info.gas_used + x.gas_used;
-            x
-        })))
+        Ok(Box::new(txs.into_iter().map(
+            move |t| -> Result<Executed, CallError> {
+                let t = SignedTransaction::new(t).expect(PROOF);
+                let machine = engine.machine();
+                let x = Self::do_virtual_call(machine, &env_info, &mut state, &t, analytics)?;
+                env_info.gas_used = env_info.gas_used + x.gas_used;
+                Ok(x)
+            },
+        )))
     }
 
     fn disable(&self) {
@@ -1904,6 +1975,47 @@ impl ProvingBlockChainClient for Client {
         self.state_at(id)
             .and_then(move |state| state.prove_account(key1).ok())
     }
+
+    fn prove_transaction(
+        &self,
+        transaction: SignedTransaction,
+        id: BlockId,
+    ) -> Option<(Bytes, Vec<DBValue>)>
+    {
+        let (header, mut env_info) = match (self.block_header(id), self.env_info(id)) {
+            (Some(header), Some(env_info)) => (header, env_info),
+            _ => return None,
+        };
+        // a transaction can't use more gas than the block allows
+        if transaction.gas > header.gas_limit() {
+            return None;
+        }
+        env_info.gas_limit = transaction.gas.clone();
+
+        // avm storage lives outside of the state trie and can't be proven.
+        let machine = self.engine.machine();
+        let aion040fork = machine
+            .params()
+            .monetary_policy_update
+            .map_or(false, |v| env_info.number >= v);
+        if aion040fork {
+            let mut state = self.state_at(id)?;
+            if for_local_avm(&mut state, &transaction) {
+                return None;
+            }
+        }
+
+        let mut jdb = self.state_db.read().journal_db().boxed_clone();
+        state::prove_transaction(
+            jdb.as_hashstore_mut(),
+            header.state_root(),
+            &transaction,
+            machine,
+            &env_info,
+            self.factories.clone(),
+            self.db.read().clone(),
+        )
+    }
 }
 
 /// Returns `LocalizedReceipt` given `LocalizedTransaction`
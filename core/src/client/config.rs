@@ -30,7 +30,8 @@ use aion_types::Address;
 
 pub use std::time::Duration;
 pub use types::blockchain::config::Config as BlockChainConfig;
-pub use vms::VMType;
+pub use vms::{FastVMBackend, VMType};
+use super::differential::DifferentialConfig;
 
 /// Client state db compaction profile
 #[derive(Debug, PartialEq, Clone)]
@@ -80,6 +81,10 @@ pub struct ClientConfig {
     pub blockchain: BlockChainConfig,
     /// VM type.
     pub vm_type: VMType,
+    /// Backend executing FastVM code.
+    pub fvm_backend: FastVMBackend,
+    /// Re-execute imported blocks with another VM configuration and report divergences.
+    pub differential: Option<DifferentialConfig>,
     /// Fat DB enabled?
     pub fat_db: bool,
     /// The JournalDB ("pruning") algorithm to use.
@@ -88,6 +93,8 @@ pub struct ClientConfig {
     pub db_compaction: DatabaseCompactionProfile,
     /// Should db have WAL enabled?
     pub db_wal: bool,
+    /// Back up the db before migrating it to a newer schema version?
+    pub db_backup: bool,
     /// The chain spec name
     pub spec_name: String,
     /// State db cache-size.
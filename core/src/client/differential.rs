@@ -0,0 +1,248 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Differential execution.
+//!
+//! Re-executes every imported block with a second VM configuration on a scratch copy of
+//! the parent state and compares the receipts, logs, gas used and state roots with the
+//! ones of the canonical execution. Divergences are appended to a report file, one JSON
+//! object per line; they never fail the import.
+//!
+//! The scratch state is never committed. AVM object graphs are written by content hash,
+//! so anything the second execution stores there can't shadow canonical data.
+
+use std::fmt::Debug;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+use aion_types::H256;
+use block::{IsBlock, LockedBlock};
+use factory::{Factories, VmFactory};
+use header::{BlockNumber, Header};
+use parking_lot::Mutex;
+use rustc_hex::ToHex;
+use serde_json;
+use vms::FastVMBackend;
+
+/// Differential execution configuration.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DifferentialConfig {
+    /// FastVM backend blocks are re-executed with.
+    pub fvm_backend: FastVMBackend,
+    /// File divergence reports are appended to.
+    pub report_path: PathBuf,
+}
+
+/// A single value that differs between the two executions.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Mismatch {
+    /// Index of the transaction in the block, `None` for block level values.
+    pub transaction_index: Option<usize>,
+    /// Hash of the transaction, `None` for block level values.
+    pub transaction_hash: Option<H256>,
+    /// Name of the value.
+    pub field: &'static str,
+    /// Value produced by the canonical execution.
+    pub expected: String,
+    /// Value produced by the differential execution.
+    pub actual: String,
+}
+
+/// Everything that diverged while executing one block.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct DivergenceReport {
+    /// Block number.
+    pub block_number: BlockNumber,
+    /// Hash of the imported block.
+    pub block_hash: H256,
+    /// FastVM backend of the differential execution.
+    pub fvm_backend: String,
+    /// Values that differ.
+    pub mismatches: Vec<Mismatch>,
+}
+
+/// Re-executes blocks with an alternative VM configuration and reports divergences.
+pub struct DifferentialExecutor {
+    config: DifferentialConfig,
+    factories: Factories,
+    report_lock: Mutex<()>,
+}
+
+impl DifferentialExecutor {
+    /// Create the executor. `factories` are the ones of the canonical execution; only
+    /// the VM factory is replaced.
+    pub fn new(config: DifferentialConfig, factories: &Factories) -> Self {
+        let factories = Factories {
+            vm: VmFactory::with_fvm_backend(config.fvm_backend),
+            ..factories.clone()
+        };
+        DifferentialExecutor {
+            config,
+            factories,
+            report_lock: Mutex::new(()),
+        }
+    }
+
+    /// Factories the differential execution must use.
+    pub fn factories(&self) -> Factories { self.factories.clone() }
+
+    /// Compare the differential execution of the block `header` with the canonical one
+    /// and write a report if they diverge.
+    pub fn check<E: Debug>(
+        &self,
+        header: &Header,
+        expected: &LockedBlock,
+        actual: Result<LockedBlock, E>,
+    ) -> Option<DivergenceReport>
+    {
+        let mismatches = match actual {
+            Ok(ref actual) => compare(expected, actual),
+            Err(ref e) => {
+                vec![Mismatch {
+                    transaction_index: None,
+                    transaction_hash: None,
+                    field: "execution",
+                    expected: "success".into(),
+                    actual: format!("{:?}", e),
+                }]
+            }
+        };
+        if mismatches.is_empty() {
+            trace!(target: "differential", "Block #{} executed identically", header.number());
+            return None;
+        }
+
+        let report = DivergenceReport {
+            block_number: header.number(),
+            block_hash: header.hash(),
+            fvm_backend: self.config.fvm_backend.to_string(),
+            mismatches,
+        };
+        warn!(
+            target: "differential",
+            "Block #{} ({:x}) diverged with fastvm backend {}: {} mismatches",
+            report.block_number,
+            report.block_hash,
+            report.fvm_backend,
+            report.mismatches.len()
+        );
+        if let Err(e) = self.write_report(&report) {
+            warn!(
+                target: "differential",
+                "Failed to write divergence report to {}: {}",
+                self.config.report_path.display(),
+                e
+            );
+        }
+        Some(report)
+    }
+
+    fn write_report(&self, report: &DivergenceReport) -> Result<(), String> {
+        let line = serde_json::to_string(report).map_err(|e| e.to_string())?;
+        let _lock = self.report_lock.lock();
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.config.report_path)
+            .map_err(|e| e.to_string())?;
+        writeln!(file, "{}", line).map_err(|e| e.to_string())
+    }
+}
+
+/// Values of `actual` that differ from `expected`.
+pub fn compare(expected: &LockedBlock, actual: &LockedBlock) -> Vec<Mismatch> {
+    let mut mismatches = Vec::new();
+    {
+        let mut check = |index: Option<usize>, field: &'static str, e: String, a: String| {
+            if e != a {
+                mismatches.push(Mismatch {
+                    transaction_index: index,
+                    transaction_hash: index.map(|i| expected.transactions()[i].hash().clone()),
+                    field,
+                    expected: e,
+                    actual: a,
+                });
+            }
+        };
+
+        let (expected_receipts, actual_receipts) = (expected.receipts(), actual.receipts());
+        check(
+            None,
+            "receipts",
+            expected_receipts.len().to_string(),
+            actual_receipts.len().to_string(),
+        );
+        for (i, (e, a)) in expected_receipts
+            .iter()
+            .zip(actual_receipts.iter())
+            .enumerate()
+        {
+            let index = Some(i);
+            check(
+                index,
+                "gas_used",
+                format!("{}", e.gas_used),
+                format!("{}", a.gas_used),
+            );
+            check(
+                index,
+                "state_root",
+                format!("{:x}", e.simple_receipt.state_root),
+                format!("{:x}", a.simple_receipt.state_root),
+            );
+            check(
+                index,
+                "logs",
+                format!("{:?}", e.simple_receipt.logs),
+                format!("{:?}", a.simple_receipt.logs),
+            );
+            check(index, "output", e.output.to_hex(), a.output.to_hex());
+            check(
+                index,
+                "error_message",
+                e.error_message.clone(),
+                a.error_message.clone(),
+            );
+        }
+
+        let (e, a) = (expected.header(), actual.header());
+        check(
+            None,
+            "gas_used",
+            format!("{}", e.gas_used()),
+            format!("{}", a.gas_used()),
+        );
+        check(
+            None,
+            "state_root",
+            format!("{:x}", e.state_root()),
+            format!("{:x}", a.state_root()),
+        );
+        check(
+            None,
+            "receipts_root",
+            format!("{:x}", e.receipts_root()),
+            format!("{:x}", a.receipts_root()),
+        );
+    }
+    mismatches
+}
@@ -26,11 +26,13 @@ mod error;
 mod client;
 mod traits;
 mod chain_notify;
+mod differential;
 
 pub use self::client::*;
-pub use self::config::{ClientConfig, DatabaseCompactionProfile, BlockChainConfig, VMType};
+pub use self::config::{ClientConfig, DatabaseCompactionProfile, BlockChainConfig, FastVMBackend, VMType};
 pub use self::error::Error;
 pub use self::chain_notify::ChainNotify;
+pub use self::differential::{DifferentialConfig, DifferentialExecutor, DivergenceReport, Mismatch};
 pub use self::traits::{BlockChainClient, MiningBlockChainClient, EngineClient};
 pub use self::traits::ProvingBlockChainClient;
 
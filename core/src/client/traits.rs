@@ -38,6 +38,7 @@ use verification::queue::QueueInfo as BlockQueueInfo;
 use aion_types::{H256, H128, U256, Address};
 use vms::LastHashes;
 use acore_bytes::Bytes;
+use kvdb::DBValue;
 use num_bigint::BigUint;
 
 use types::ids::*;
@@ -226,12 +227,13 @@ pub trait BlockChainClient: Sync + Send {
     /// Replays a given transaction for inspection.
     fn replay(&self, t: TransactionId, analytics: CallAnalytics) -> Result<Executed, CallError>;
 
-    /// Replays all the transactions in a given block for inspection.
+    /// Replays all the transactions in a given block for inspection. Each transaction is only
+    /// executed once the iterator gets to it.
     fn replay_block_transactions(
         &self,
         block: BlockId,
         analytics: CallAnalytics,
-    ) -> Result<Box<Iterator<Item = Executed>>, CallError>;
+    ) -> Result<Box<Iterator<Item = Result<Executed, CallError>>>, CallError>;
 
     /// Get last hashes starting from best block.
     fn last_hashes(&self) -> LastHashes;
@@ -379,4 +381,13 @@ pub trait ProvingBlockChainClient: BlockChainClient {
     /// The key is the blake2b hash of the account's address.
     /// Returns a vector of raw trie nodes (in order from the root) proving the query.
     fn prove_account(&self, key1: H256, id: BlockId) -> Option<(Vec<Bytes>, BasicAccount)>;
+
+    /// Prove execution of a fastvm transaction at the given block.
+    /// Returns the output of the call and a vector of database items necessary
+    /// to reproduce it.
+    fn prove_transaction(
+        &self,
+        transaction: SignedTransaction,
+        id: BlockId,
+    ) -> Option<(Bytes, Vec<DBValue>)>;
 }
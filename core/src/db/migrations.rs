@@ -0,0 +1,43 @@
+/*******************************************************************************
+ * Copyright (c) 2015-2018 Parity Technologies (UK) Ltd.
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Client database schema version and migrations.
+
+use kvdb::migration::{Config, Manager};
+
+use super::COL_NODE_INFO;
+
+/// Schema version of the current database layout. Databases created before schema versioning
+/// was introduced have this layout.
+const BASE_VERSION: u32 = 1;
+/// Number of entries migrated between two progress checkpoints.
+const MIGRATION_BATCH_SIZE: usize = 10_000;
+
+/// Migrations of the client database. Whenever the layout of a db changes, bump the schema
+/// version by registering the migration converting existing entries here.
+pub fn migration_manager() -> Manager {
+    Manager::new(Config {
+        meta_db: COL_NODE_INFO,
+        base_version: BASE_VERSION,
+        batch_size: MIGRATION_BATCH_SIZE,
+    })
+}
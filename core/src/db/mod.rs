@@ -22,10 +22,9 @@
 mod db;
 mod state_db;
 mod account_db;
+mod migrations;
 
 pub use self::db::*;
 pub use self::state_db::StateDB;
-pub use self::account_db::{Factory};
-
-#[cfg(test)]
-pub use self::account_db::{AccountDBMut};
+pub use self::account_db::{Factory, AccountDB, AccountDBMut};
+pub use self::migrations::migration_manager;
@@ -0,0 +1,166 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Instant seal engine for development chains.
+//!
+//! Seals blocks itself as soon as transactions are pending, or at most once per configured
+//! interval. Seals are not verified, so the engine must never be used on a public network.
+
+use std::time::Duration;
+use acore_bytes::Bytes;
+use aion_machine::{LiveBlock, Machine, WithBalances};
+use aion_types::U256;
+use ajson;
+use client::BlockChainClient;
+use header::Header;
+use machine::EthereumMachine;
+use num_bigint::BigUint;
+
+use super::Engine;
+
+/// nonce and solution of the placeholder PoW seal
+const NONCE_LENGTH: usize = 32;
+const SOLUTION_LENGTH: usize = 1408;
+
+/// Params for an instant seal engine.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InstantSealParams {
+    /// base reward for a block.
+    pub block_reward: U256,
+    /// min period between sealed blocks.
+    pub seal_interval: Duration,
+}
+
+impl From<ajson::spec::InstantSealParams> for InstantSealParams {
+    fn from(p: ajson::spec::InstantSealParams) -> Self {
+        InstantSealParams {
+            block_reward: p.block_reward.map_or_else(Default::default, Into::into),
+            seal_interval: Duration::from_secs(p.seal_interval.unwrap_or(0)),
+        }
+    }
+}
+
+/// An engine which seals blocks itself without any proof of work or stake.
+pub struct InstantSeal {
+    params: InstantSealParams,
+    machine: EthereumMachine,
+}
+
+impl InstantSeal {
+    /// Returns new instance of InstantSeal
+    pub fn new(params: InstantSealParams, machine: EthereumMachine) -> Self {
+        InstantSeal {
+            params,
+            machine,
+        }
+    }
+}
+
+impl Engine for InstantSeal {
+    fn name(&self) -> &str { "InstantSeal" }
+
+    fn machine(&self) -> &EthereumMachine { &self.machine }
+
+    fn seal_fields(&self, _header: &Header) -> usize { 2 }
+
+    fn seals_internally(&self) -> Option<Duration> { Some(self.params.seal_interval) }
+
+    fn generate_seal(&self, _header: &Header) -> Vec<Bytes> {
+        vec![vec![0u8; NONCE_LENGTH], vec![0u8; SOLUTION_LENGTH]]
+    }
+
+    fn calculate_difficulty(
+        &self,
+        parent: &Header,
+        _grand_parent: Option<&Header>,
+        _great_grand_parent: Option<&Header>,
+        _client: &BlockChainClient,
+    ) -> U256
+    {
+        *parent.difficulty()
+    }
+
+    fn set_difficulty_from_parent(
+        &self,
+        header: &mut Header,
+        parent: &Header,
+        _grand_parent: Option<&Header>,
+        _great_grand_parent: Option<&Header>,
+        _client: &BlockChainClient,
+    )
+    {
+        header.set_difficulty(*parent.difficulty());
+    }
+
+    fn block_reward(&self, _header: &<EthereumMachine as Machine>::Header) -> U256 {
+        self.params.block_reward
+    }
+
+    fn on_close_block(
+        &self,
+        block: &mut <EthereumMachine as Machine>::LiveBlock,
+    ) -> Result<(), <EthereumMachine as Machine>::Error>
+    {
+        let author = *LiveBlock::header(&*block).author();
+
+        let reward = self.params.block_reward;
+        if reward == U256::zero() {
+            return Ok(());
+        }
+
+        self.machine.add_balance(block, &author, &reward)?;
+        self.machine.note_rewards(block, &[(author, reward)])
+    }
+
+    fn verify_local_seal_pow(
+        &self,
+        _header: &<EthereumMachine as Machine>::Header,
+    ) -> Result<(), <EthereumMachine as Machine>::Error>
+    {
+        Ok(())
+    }
+
+    fn verify_seal_pos(
+        &self,
+        _header: &<EthereumMachine as Machine>::Header,
+        _parent: &<EthereumMachine as Machine>::Header,
+        _grand_parent: Option<&<EthereumMachine as Machine>::Header>,
+        _stake: Option<BigUint>,
+    ) -> Result<(), <EthereumMachine as Machine>::Error>
+    {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use spec::Spec;
+
+    #[test]
+    fn dev_spec_seals_internally() {
+        let spec = Spec::new_dev();
+        assert_eq!(spec.engine.name(), "InstantSeal");
+        assert_eq!(spec.engine.seals_internally(), Some(Duration::from_secs(0)));
+        let seal = spec.engine.generate_seal(&spec.genesis_header());
+        assert_eq!(seal.len(), spec.engine.seal_fields(&spec.genesis_header()));
+    }
+}
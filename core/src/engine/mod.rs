@@ -22,10 +22,14 @@
 
 //! Consensus engine specification and basic implementations.
 pub mod unity_engine;
+pub mod instant_seal;
 pub use self::unity_engine::UnityEngine;
+pub use self::instant_seal::InstantSeal;
 
 use std::fmt;
 use std::sync::Arc;
+use std::time::Duration;
+use acore_bytes::Bytes;
 use types::error::Error;
 use spec::CommonParams;
 use header::{Header, BlockNumber};
@@ -131,6 +135,18 @@ pub trait Engine: Sync + Send {
     /// The number of additional header fields required for this engine.
     fn seal_fields(&self, _header: &<EthereumMachine as Machine>::Header) -> usize { 0 }
 
+    /// Min period between blocks if the engine seals blocks itself, without external miners or
+    /// stakers. None for engines sealed externally.
+    fn seals_internally(&self) -> Option<Duration> { None }
+
+    /// Generate the seal of a block sealed by the engine itself.
+    fn generate_seal(&self, _header: &<EthereumMachine as Machine>::Header) -> Vec<Bytes> {
+        Vec::new()
+    }
+
+    /// Block reward paid to the author of the block with the given header.
+    fn block_reward(&self, _header: &<EthereumMachine as Machine>::Header) -> U256 { U256::zero() }
+
     /// Block transformation functions, after the transactions.
     fn on_close_block(
         &self,
@@ -194,6 +210,19 @@ pub trait Engine: Sync + Send {
         Ok(())
     }
 
+    /// Phase 3 verification for light clients, which hold headers only. Checks the header
+    /// against its ancestors, recomputing the difficulty where it doesn't depend on state.
+    fn verify_header_family(
+        &self,
+        _header: &<EthereumMachine as Machine>::Header,
+        _parent: &<EthereumMachine as Machine>::Header,
+        _grand_parent: Option<&<EthereumMachine as Machine>::Header>,
+        _great_grand_parent: Option<&<EthereumMachine as Machine>::Header>,
+    ) -> Result<(), Error>
+    {
+        Ok(())
+    }
+
     /// Populate a header's difficulty based on its parent's header.
     /// Usually implements the chain scoring rule based on weight.
     fn set_difficulty_from_parent(
@@ -41,6 +41,7 @@ use block::ExecutedBlock;
 use transaction::UnverifiedTransaction;
 use types::error::{BlockError, Error};
 use types::BlockNumber;
+use unexpected::Mismatch;
 use equihash::EquihashValidator;
 use fixed_point::{FixedPoint};
 use client::{BlockChainClient, BlockId};
@@ -170,19 +171,33 @@ impl DifficultyCalc {
         great_grand_parent: Option<&Header>,
         client: &BlockChainClient,
     ) -> U256
+    {
+        self.calculate_difficulty_from_headers(parent, grand_parent, great_grand_parent)
+            .unwrap_or_else(|| self.initial_pos_difficulty(parent, client))
+    }
+
+    /// Calculate the difficulty from the ancestor headers only. Returns None for the blocks
+    /// whose difficulty is reset from the total stake: the first PoS block and the first block
+    /// after the unity hybrid seed update.
+    pub fn calculate_difficulty_from_headers(
+        &self,
+        parent: &Header,
+        grand_parent: Option<&Header>,
+        great_grand_parent: Option<&Header>,
+    ) -> Option<U256>
     {
         match self.unity_update {
             // AION 2.0
             Some(fork_number) if parent.number() >= fork_number => {
                 // 1. First PoS block
                 if parent.number() == fork_number {
-                    return self.initial_pos_difficulty(parent, client);
+                    return None;
                 }
 
                 // 1.1 If first block after the unity hybrid seed update, reset pos difficulty
                 if let Some(fork_number) = self.unity_hybrid_seed_update {
                     if parent.number() == fork_number {
-                        return self.initial_pos_difficulty(parent, client);
+                        return None;
                     }
                 }
 
@@ -194,12 +209,12 @@ impl DifficultyCalc {
                 let great_grand_parent = match great_grand_parent {
                     Some(header) => header,
                     None => {
-                        return grand_parent.difficulty().to_owned();
+                        return Some(grand_parent.difficulty().to_owned());
                     }
                 };
 
                 // 3. Normal case
-                self.calculate_difficulty_v2(grand_parent, great_grand_parent)
+                Some(self.calculate_difficulty_v2(grand_parent, great_grand_parent))
             }
             // AION 1.x
             _ => {
@@ -207,14 +222,22 @@ impl DifficultyCalc {
                 let grand_parent = match grand_parent {
                     Some(header) => header,
                     None => {
-                        return parent.difficulty().to_owned();
+                        return Some(parent.difficulty().to_owned());
                     }
                 };
-                self.calculate_difficulty_v1(parent, grand_parent)
+                Some(self.calculate_difficulty_v1(parent, grand_parent))
             }
         }
     }
 
+    /// Lower bound of the difficulty of a block sealed with the given seal type.
+    pub fn minimum_difficulty(&self, seal_type: &Option<SealType>) -> U256 {
+        match *seal_type {
+            Some(SealType::PoS) => self.minimum_pos_difficulty,
+            _ => self.minimum_pow_difficulty,
+        }
+    }
+
     // Dynamic initial pos difficulty: ARK-71
     fn initial_pos_difficulty(&self, parent: &Header, client: &BlockChainClient) -> U256 {
         let initial_pos_difficulty = client
@@ -283,10 +306,7 @@ impl DifficultyCalc {
                 temp
             }
         };
-        let minimum_difficulty = match grand_parent.seal_type() {
-            Some(SealType::PoS) => self.minimum_pos_difficulty,
-            _ => self.minimum_pow_difficulty,
-        };
+        let minimum_difficulty = self.minimum_difficulty(grand_parent.seal_type());
 
         cmp::max(minimum_difficulty, diff)
     }
@@ -621,6 +641,75 @@ impl Engine for Arc<UnityEngine> {
         Ok(())
     }
 
+    fn verify_header_family(
+        &self,
+        header: &Header,
+        parent: &Header,
+        grand_parent: Option<&Header>,
+        great_grand_parent: Option<&Header>,
+    ) -> Result<(), Error>
+    {
+        let unity_update = self
+            .machine
+            .params()
+            .unity_update
+            .map_or(false, |fork_number| header.number() > fork_number);
+
+        let mut parent_validators: Vec<Box<DependentHeaderValidator>> = Vec::with_capacity(3);
+        parent_validators.push(Box::new(NumberValidator {}));
+        parent_validators.push(Box::new(TimestampValidator {}));
+        if unity_update {
+            parent_validators.push(Box::new(SealTypeValidator {}));
+        }
+        for v in parent_validators.iter() {
+            v.validate(header, parent)?;
+        }
+
+        // The difficulty of the first PoS blocks is derived from the total stake, which light
+        // clients don't hold. Only the lower bound can be checked for those.
+        let difficulty = header.difficulty().to_owned();
+        match self.difficulty_calc.calculate_difficulty_from_headers(
+            parent,
+            grand_parent,
+            great_grand_parent,
+        ) {
+            Some(expected) if expected != difficulty => {
+                return Err(BlockError::InvalidDifficulty(Mismatch {
+                    expected: expected,
+                    found: difficulty,
+                })
+                .into());
+            }
+            Some(_) => {}
+            None => {
+                let minimum = self.difficulty_calc.minimum_difficulty(header.seal_type());
+                if difficulty < minimum {
+                    return Err(BlockError::InvalidDifficulty(Mismatch {
+                        expected: minimum,
+                        found: difficulty,
+                    })
+                    .into());
+                }
+            }
+        }
+
+        // The stake needed to check the PoS timestamp isn't available, the seed and signature
+        // are.
+        if header.seal_type() == &Some(SealType::PoS) {
+            if !unity_update {
+                return Err(BlockError::InvalidPoSBlockNumber.into());
+            }
+            let unity_hybrid_seed_update = self
+                .machine
+                .params()
+                .unity_hybrid_seed_update
+                .map_or(false, |fork_number| header.number() > fork_number);
+            PoSValidator::validate_seal(header, parent, grand_parent, unity_hybrid_seed_update)?;
+        }
+
+        Ok(())
+    }
+
     fn set_difficulty_from_parent(
         &self,
         header: &mut Header,
@@ -639,6 +728,8 @@ impl Engine for Arc<UnityEngine> {
         header.set_difficulty(difficulty);
     }
 
+    fn block_reward(&self, header: &Header) -> U256 { self.calculate_reward(header) }
+
     fn on_close_block(&self, block: &mut ExecutedBlock) -> Result<(), Error> {
         let result_block_reward;
         let author;
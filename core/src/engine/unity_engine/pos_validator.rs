@@ -53,6 +53,43 @@ impl PoSValidator {
             return Err(BlockError::NullStake.into());
         }
 
+        Self::validate_seal(
+            header,
+            parent_header,
+            grand_parent_header,
+            unity_hybrid_seed_update,
+        )?;
+
+        // Verify timestamp
+        let seed = &header.seal()[0];
+        let difficulty = header.difficulty().clone();
+        let timestamp = header.timestamp();
+        let parent_timestamp = parent_header.timestamp();
+
+        let delta_uint = calculate_delta(difficulty, &seed, stake.clone());
+
+        if timestamp - parent_timestamp != delta_uint {
+            Err(BlockError::InvalidPoSTimestamp(timestamp, parent_timestamp, delta_uint).into())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Validate the seed and the signature of a pos seal. Unlike `validate`, this doesn't need
+    /// the stake of the block producer, so light clients can run it.
+    pub fn validate_seal(
+        header: &Header,
+        parent_header: &Header,
+        grand_parent_header: Option<&Header>,
+        unity_hybrid_seed_update: bool,
+    ) -> Result<(), Error>
+    {
+        // Return error if seal type is not PoS
+        if header.seal_type() != &Some(SealType::PoS) {
+            error!(target: "pos", "block seal type is not PoS");
+            return Err(BlockError::InvalidPoSSealType.into());
+        }
+
         // Get seal, check seal length
         let seal = header.seal();
         if seal.len() != 3 {
@@ -102,18 +139,7 @@ impl PoSValidator {
         //     return Err(BlockError::InvalidPoSAuthor.into());
         // }
 
-        // Verify timestamp
-        let difficulty = header.difficulty().clone();
-        let timestamp = header.timestamp();
-        let parent_timestamp = parent_header.timestamp();
-
-        let delta_uint = calculate_delta(difficulty, &seed, stake.clone());
-
-        if timestamp - parent_timestamp != delta_uint {
-            Err(BlockError::InvalidPoSTimestamp(timestamp, parent_timestamp, delta_uint).into())
-        } else {
-            Ok(())
-        }
+        Ok(())
     }
 
     // Validate the seed of the pos seal
@@ -454,3 +454,63 @@ fn test_calculate_difficulty4() {
     );
     assert_eq!(difficulty, U256::from(17));
 }
+
+#[test]
+fn test_calculate_difficulty_from_headers() {
+    let params = UnityEngineParams {
+        rampup_upper_bound: U256::zero(),
+        rampup_lower_bound: U256::zero(),
+        rampup_start_value: U256::zero(),
+        rampup_end_value: U256::zero(),
+        lower_block_reward: U256::zero(),
+        upper_block_reward: U256::zero(),
+        difficulty_bound_divisor: U256::from(2048u64),
+        difficulty_bound_divisor_unity: 20u64,
+        minimum_pow_difficulty: U256::from(16),
+        minimum_pos_difficulty: U256::from(2345),
+        block_time_lower_bound: 5u64,
+        block_time_upper_bound: 15u64,
+        block_time_unity: 10u64,
+    };
+    let calculator = DifficultyCalc::new(&params, Some(3u64), Some(5u64));
+    let mut parent_header = Header::default();
+    parent_header.set_timestamp(1524538000u64);
+    parent_header.set_difficulty(U256::from(4096));
+    parent_header.set_number(2);
+    let mut grand_parent_header = Header::default();
+    grand_parent_header.set_timestamp(1524537990u64);
+    grand_parent_header.set_number(1);
+
+    // pre-unity blocks only depend on their ancestors
+    let difficulty = calculator.calculate_difficulty_from_headers(
+        &parent_header,
+        Some(&grand_parent_header),
+        None,
+    );
+    assert_eq!(difficulty, Some(U256::from(4096)));
+
+    // the first PoS block and the first block after the hybrid seed update depend on the
+    // total stake
+    parent_header.set_number(3);
+    assert_eq!(
+        calculator.calculate_difficulty_from_headers(
+            &parent_header,
+            Some(&grand_parent_header),
+            None,
+        ),
+        None
+    );
+    parent_header.set_number(5);
+    assert_eq!(
+        calculator.calculate_difficulty_from_headers(
+            &parent_header,
+            Some(&grand_parent_header),
+            None,
+        ),
+        None
+    );
+    assert_eq!(
+        calculator.minimum_difficulty(&Some(SealType::PoS)),
+        U256::from(2345)
+    );
+}
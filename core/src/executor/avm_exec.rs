@@ -28,7 +28,7 @@ use std::collections::{HashSet, HashMap};
 use std::time::SystemTime;
 
 use aion_types::{H256, U256, Address};
-use vms::{ActionParams, ActionValue, CallType, EnvInfo, AvmExecutionResult as ExecutionResult, ParamsType, AvmStatusCode};
+use vms::{ActionParams, ActionValue, CallType, EnvInfo, AvmExecutionResult as ExecutionResult, ParamsType, AvmStatusCode, ExecStatus};
 use state::{Backend as StateBackend, State, Substate, CleanupMode};
 use machine::EthereumMachine as Machine;
 use types::error::ExecutionError;
@@ -37,6 +37,7 @@ use executor::avm_externality::*;
 use transaction::{Action, SignedTransaction};
 use crossbeam;
 use types::executed::Executed;
+use types::trace::{CallTrace, TraceCallType};
 use kvdb::{DBTransaction};
 use super::params::*;
 
@@ -53,6 +54,7 @@ pub struct Executive<'a, B: 'a + StateBackend> {
     info: &'a EnvInfo,
     machine: &'a Machine,
     depth: usize,
+    tracing: bool,
 }
 
 /// avm executive
@@ -64,9 +66,17 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
             info: info,
             machine: machine,
             depth: 0,
+            tracing: false,
         }
     }
 
+    /// Enables tracing of executed transactions. Calls made inside the avm are not reported
+    /// back to the kernel, so only the outermost call frame is traced.
+    pub fn with_tracing(mut self, tracing: bool) -> Self {
+        self.tracing = tracing;
+        self
+    }
+
     /// generate an avm externality with substates
     /// what is externality?
     /// AVMExternalities acts as bridge between kernel and avm, and
@@ -331,6 +341,11 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
                 }));
             } else {
                 total_gas_used = total_gas_used + gas_used;
+                let trace = if self.tracing {
+                    Some(Self::trace(&t, &result, &substate.contracts_created))
+                } else {
+                    None
+                };
                 final_results.push(Ok(Executed {
                     exception: result.exception,
                     gas: t.gas,
@@ -341,6 +356,7 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
                     contracts_created: substate.contracts_created,
                     output: result.return_data.to_vec(),
                     state_diff: None,
+                    trace,
                     transaction_fee: fees_value,
                     touched: touched,
                     state_root: result.state_root,
@@ -396,6 +412,45 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
         return final_results;
     }
 
+    /// Trace of the outermost call frame of transaction `t`.
+    fn trace(
+        t: &SignedTransaction,
+        result: &ExecutionResult,
+        contracts_created: &[Address],
+    ) -> CallTrace
+    {
+        let (call_type, to) = match t.action {
+            Action::Create => {
+                (
+                    TraceCallType::Create,
+                    contracts_created.first().cloned().unwrap_or_default(),
+                )
+            }
+            Action::Call(ref address) => (TraceCallType::Call, *address),
+        };
+        let mut trace = CallTrace {
+            call_type,
+            from: t.sender(),
+            to,
+            value: t.value,
+            gas: t.gas,
+            gas_used: U256::zero(),
+            input: t.data.clone(),
+            output: Vec::new(),
+            builtin: false,
+            error: None,
+            revert_reason: None,
+            calls: Vec::new(),
+        };
+        trace.set_result(
+            &ExecStatus::from(result.status_code.clone()),
+            result.gas_left,
+            result.return_data.to_vec(),
+            &result.exception,
+        );
+        trace
+    }
+
     fn decode_alias_and_set(raw_set: &[u8], set: &mut HashSet<H256>) {
         assert!(raw_set.len() >= 5);
         let mut index = 5;
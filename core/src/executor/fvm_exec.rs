@@ -21,6 +21,7 @@
  ******************************************************************************/
 
 //! Transaction Execution environment.
+use std::mem;
 use std::sync::{Arc, Mutex};
 use std::collections::HashSet;
 
@@ -35,6 +36,7 @@ use executor::fvm_externality::*;
 use transaction::{Action, SignedTransaction};
 use crossbeam;
 use types::executed::Executed;
+use types::trace::CallTrace;
 use precompiled::builtin::{BuiltinExtImpl, BuiltinContext};
 use super::params::*;
 
@@ -61,6 +63,7 @@ pub struct Executive<'a, B: 'a + StateBackend> {
     info: &'a EnvInfo,
     machine: &'a Machine,
     depth: usize,
+    tracing: bool,
 }
 
 impl<'a, B: 'a + StateBackend> Executive<'a, B> {
@@ -71,9 +74,16 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
             info: info,
             machine: machine,
             depth: 0,
+            tracing: false,
         }
     }
 
+    /// Enables call tree tracing of executed transactions.
+    pub fn with_tracing(mut self, tracing: bool) -> Self {
+        self.tracing = tracing;
+        self
+    }
+
     /// Populates executive from parent properties. Increments executive depth.
     pub fn from_parent(
         state: &'a mut State<B>,
@@ -87,6 +97,7 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
             info: info,
             machine: machine,
             depth: parent_depth + 1,
+            tracing: false,
         }
     }
 
@@ -212,7 +223,7 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
         } else {
             t.gas - base_gas_required
         };
-        let mut substate = Substate::new();
+        let mut substate = Substate::with_tracing(self.tracing);
 
         // NOTE: there can be no invalid transactions from this point.
         // Transactions filtered above are rejected and not included in the current block.
@@ -333,6 +344,18 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
     /// Modifies the substate.
     /// Returns either gas_left or `vm::Error`.
     pub fn call(&mut self, params: ActionParams, substate: &mut Substate) -> ExecutionResult {
+        if !substate.tracing {
+            return self.do_call(params, substate);
+        }
+
+        let trace = self.new_trace(&params);
+        let siblings = mem::replace(&mut substate.traces, Vec::new());
+        let result = self.do_call(params, substate);
+        Self::push_trace(trace, siblings, &result, substate);
+        result
+    }
+
+    fn do_call(&mut self, params: ActionParams, substate: &mut Substate) -> ExecutionResult {
         trace!(
             target: "executive",
             "Executive::call(params={:?}) self.env_info={:?}",
@@ -398,7 +421,7 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
             let cost = builtin.cost(data);
             debug!(target: "vm", "builtin gas cost = {:?}", cost);
             if cost <= params.gas {
-                let mut unconfirmed_substate = Substate::new();
+                let mut unconfirmed_substate = Substate::with_tracing(substate.tracing);
                 let mut result = {
                     let builtin_context = BuiltinContext {
                         sender: params.sender.clone(),
@@ -436,7 +459,7 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
         } else {
             if params.code.is_some() {
                 // part of substate that may be reverted
-                let mut unconfirmed_substate = Substate::new();
+                let mut unconfirmed_substate = Substate::with_tracing(substate.tracing);
 
                 let result = self.exec_vm(params.clone(), &mut unconfirmed_substate);
 
@@ -492,6 +515,18 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
     /// NOTE. It does not finalize the transaction (doesn't do refunds, nor suicides).
     /// Modifies the substate.
     pub fn create(&mut self, params: ActionParams, substate: &mut Substate) -> ExecutionResult {
+        if !substate.tracing {
+            return self.do_create(params, substate);
+        }
+
+        let trace = self.new_trace(&params);
+        let siblings = mem::replace(&mut substate.traces, Vec::new());
+        let result = self.do_create(params, substate);
+        Self::push_trace(trace, siblings, &result, substate);
+        result
+    }
+
+    fn do_create(&mut self, params: ActionParams, substate: &mut Substate) -> ExecutionResult {
         // EIP-684: If a contract creation is attempted, due to either a creation transaction or the
         // CREATE (or future CREATE2) opcode, and the destination address already has either
         // nonzero nonce, or nonempty code, then the creation throws immediately, with exactly
@@ -538,7 +573,7 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
         self.state.checkpoint();
 
         // part of substate that may be reverted
-        let mut unconfirmed_substate = Substate::new();
+        let mut unconfirmed_substate = Substate::with_tracing(substate.tracing);
         // Normally there won't be any address collision. Set new account's nonce and balance
         // to 0.
         // the nonce of a new contract account starts at 0 (according to java version implementation)
@@ -662,6 +697,7 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
                     result.return_data.to_vec()
                 },
                 state_diff: None,
+                trace: substate.traces.pop(),
                 transaction_fee: fees_value,
                 touched: HashSet::new(),
                 state_root: H256::default(),
@@ -688,7 +724,57 @@ impl<'a, B: 'a + StateBackend> Executive<'a, B> {
             _ => {
                 // Rollback state changes by reverting to checkpoint
                 self.state.revert_to_checkpoint();
+                // Calls made by a failed frame are still part of its trace
+                substate.traces.extend(un_substate.traces);
             }
         }
     }
+
+    /// Starts the trace of a call frame described by `params`.
+    fn new_trace(&self, params: &ActionParams) -> CallTrace {
+        let value = match params.value {
+            ActionValue::Transfer(value) => value,
+            ActionValue::Apparent(_) => U256::zero(),
+        };
+        let input = match params.call_type {
+            CallType::None => params.code.as_ref().map_or_else(Vec::new, |code| code.to_vec()),
+            _ => params.data.clone().unwrap_or_default(),
+        };
+        CallTrace {
+            call_type: params.call_type.clone().into(),
+            from: params.sender,
+            to: params.address,
+            value,
+            gas: params.gas,
+            gas_used: U256::zero(),
+            input,
+            output: Vec::new(),
+            builtin: self
+                .machine
+                .builtin(&params.code_address, self.info.number)
+                .is_some(),
+            error: None,
+            revert_reason: None,
+            calls: Vec::new(),
+        }
+    }
+
+    /// Completes `trace` with the result of the call and the calls it made (currently held in
+    /// `substate.traces`), and appends it after its `siblings`.
+    fn push_trace(
+        mut trace: CallTrace,
+        siblings: Vec<CallTrace>,
+        result: &ExecutionResult,
+        substate: &mut Substate,
+    )
+    {
+        trace.calls = mem::replace(&mut substate.traces, siblings);
+        trace.set_result(
+            &result.status_code,
+            result.gas_left,
+            result.return_data.to_vec(),
+            &result.exception,
+        );
+        substate.traces.push(trace);
+    }
 }
@@ -22,7 +22,7 @@
 
 use trie::TrieFactory;
 use db::Factory as AccountFactory;
-use vms::{FastVMFactory, AVMFactory};
+use vms::{FastVMFactory, FastVMBackend, AVMFactory};
 
 /// Virtual machine factory
 #[derive(Clone)]
@@ -48,10 +48,13 @@ impl VmFactory {
         self.fastvm.clone().unwrap()
     }
 
-    pub fn new() -> Self {
+    pub fn new() -> Self { VmFactory::with_fvm_backend(FastVMBackend::default()) }
+
+    /// Create factories executing FastVM code with the given backend.
+    pub fn with_fvm_backend(backend: FastVMBackend) -> Self {
         VmFactory {
             avm: Some(AVMFactory::new()),
-            fastvm: Some(FastVMFactory::new()),
+            fastvm: Some(FastVMFactory::with_backend(backend)),
         }
     }
 }
@@ -88,6 +88,9 @@ extern crate p2p;
 extern crate rand;
 extern crate ctrlc;
 extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
 
 /// pub mod is used here to avoid name collision when used in other module
 pub mod account_provider;
@@ -97,6 +100,7 @@ pub mod blockchain;
 pub mod miner;
 pub mod block;
 pub mod client;
+pub mod light;
 // unverified transaction
 pub mod transaction;
 // PoW Engine
@@ -107,6 +111,7 @@ pub mod sync;
 
 // boot
 pub mod service;
+pub mod snapshot;
 pub mod spec;
 pub mod verification;
 
@@ -129,6 +134,9 @@ pub use types::{
     state::log_entry,
     state::receipt,
     state::state_diff,
+    account::account_diff,
+    executed,
+    trace,
     block::status as block_status,
     error::Error,
     error::CallError,
@@ -0,0 +1,281 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Header-only chain of the light client.
+//!
+//! Headers are kept in the headers db, the canonical number to hash index, total
+//! difficulties and the best header in the extra db. The best chain is the one with
+//! the highest total difficulty.
+
+use std::sync::Arc;
+
+use aion_types::{H256, U256};
+use byteorder::{BigEndian, ByteOrder};
+use client::BlockId;
+use db::{COL_EXTRA, COL_HEADERS};
+use encoded;
+use engine::{Engine, UnityEngine};
+use header::{BlockNumber, Header};
+use kvdb::{DBTransaction, KeyValueDB};
+use parking_lot::RwLock;
+use rlp::{decode, encode};
+use types::blockchain::info::BlockChainInfo;
+use types::error::{BlockError, Error, ImportError};
+
+const BEST_KEY: &'static [u8] = b"light_best";
+const CANONICAL_PREFIX: &'static [u8] = b"light_num";
+const TD_PREFIX: &'static [u8] = b"light_td";
+
+fn canonical_key(number: BlockNumber) -> Vec<u8> {
+    let mut number_buf = [0u8; 8];
+    BigEndian::write_u64(&mut number_buf, number);
+    let mut key = CANONICAL_PREFIX.to_vec();
+    key.extend_from_slice(&number_buf);
+    key
+}
+
+fn td_key(hash: &H256) -> Vec<u8> {
+    let mut key = TD_PREFIX.to_vec();
+    key.extend_from_slice(hash);
+    key
+}
+
+#[derive(Debug, Clone)]
+struct BestHeader {
+    hash: H256,
+    number: BlockNumber,
+    timestamp: u64,
+    total_difficulty: U256,
+}
+
+/// Chain of verified block headers.
+pub struct HeaderChain {
+    db: Arc<KeyValueDB>,
+    engine: Arc<Engine>,
+    genesis_hash: H256,
+    best: RwLock<BestHeader>,
+}
+
+impl HeaderChain {
+    /// Open the header chain stored in `db`, starting a new chain from `genesis` if the
+    /// db is empty.
+    pub fn new(db: Arc<KeyValueDB>, genesis: &Header, engine: Arc<Engine>) -> Result<Self, Error> {
+        let genesis_hash = genesis.hash();
+        let best_hash = match db.get(COL_EXTRA, BEST_KEY)? {
+            Some(hash) => H256::from_slice(&hash),
+            None => {
+                let mut batch = DBTransaction::new();
+                batch.put_vec(COL_HEADERS, &genesis_hash, encode(genesis).into_vec());
+                batch.put_vec(
+                    COL_EXTRA,
+                    &td_key(&genesis_hash),
+                    encode(genesis.difficulty()).into_vec(),
+                );
+                batch.put(COL_EXTRA, &canonical_key(0), &genesis_hash);
+                batch.put(COL_EXTRA, BEST_KEY, &genesis_hash);
+                db.write(batch)?;
+                db.flush()?;
+                genesis_hash
+            }
+        };
+
+        let best_header = db
+            .get(COL_HEADERS, &best_hash)?
+            .map(|h| encoded::Header::new(h.into_vec()))
+            .ok_or_else(|| Error::Other(format!("best header {:x} missing", best_hash)))?;
+        let total_difficulty = db
+            .get(COL_EXTRA, &td_key(&best_hash))?
+            .map(|td| decode(&td))
+            .ok_or_else(|| Error::Other(format!("total difficulty of {:x} missing", best_hash)))?;
+
+        Ok(HeaderChain {
+            db: db,
+            engine: engine,
+            genesis_hash: genesis_hash,
+            best: RwLock::new(BestHeader {
+                hash: best_hash,
+                number: best_header.number(),
+                timestamp: best_header.timestamp(),
+                total_difficulty: total_difficulty,
+            }),
+        })
+    }
+
+    /// Verify a header and insert it into the chain. Returns true if it became the best
+    /// header.
+    ///
+    /// The seal and the difficulty are checked against the ancestors. The timestamp of PoS
+    /// blocks isn't verified since that requires the stake of the sealer.
+    pub fn insert(&self, header: Header) -> Result<bool, Error> {
+        let hash = header.hash();
+        if self.header(&hash).is_some() {
+            return Err(ImportError::AlreadyInChain.into());
+        }
+        let parent = self
+            .header(header.parent_hash())
+            .ok_or_else(|| BlockError::UnknownParent(*header.parent_hash()))?
+            .decode();
+        let grand_parent = self.header(parent.parent_hash()).map(|h| h.decode());
+        let great_grand_parent = grand_parent
+            .as_ref()
+            .and_then(|h| self.header(h.parent_hash()))
+            .map(|h| h.decode());
+
+        UnityEngine::validate_block_header(&header)?;
+        self.engine.verify_block_basic(&header)?;
+        self.engine.verify_block_unordered(&header)?;
+        self.engine.verify_header_family(
+            &header,
+            &parent,
+            grand_parent.as_ref(),
+            great_grand_parent.as_ref(),
+        )?;
+
+        let parent_td = self
+            .total_difficulty(header.parent_hash())
+            .ok_or_else(|| BlockError::UnknownParent(*header.parent_hash()))?;
+        let total_difficulty = parent_td + *header.difficulty();
+
+        let mut batch = DBTransaction::new();
+        batch.put_vec(COL_HEADERS, &hash, encode(&header).into_vec());
+        batch.put_vec(COL_EXTRA, &td_key(&hash), encode(&total_difficulty).into_vec());
+
+        let mut best = self.best.write();
+        let is_best = total_difficulty > best.total_difficulty;
+        if is_best {
+            // make the new branch canonical down to the common ancestor.
+            batch.put(COL_EXTRA, &canonical_key(header.number()), &hash);
+            let mut number = header.number();
+            let mut ancestor = parent;
+            while number > 0 {
+                number -= 1;
+                let ancestor_hash = ancestor.hash();
+                if self.canonical_hash(number) == Some(ancestor_hash) {
+                    break;
+                }
+                batch.put(COL_EXTRA, &canonical_key(number), &ancestor_hash);
+                ancestor = match self.header(ancestor.parent_hash()) {
+                    Some(h) => h.decode(),
+                    None => break,
+                };
+            }
+            // drop the retracted branch above the new best header.
+            for number in (header.number() + 1)..(best.number + 1) {
+                batch.delete(COL_EXTRA, &canonical_key(number));
+            }
+            batch.put(COL_EXTRA, BEST_KEY, &hash);
+        }
+        self.db.write(batch)?;
+
+        if is_best {
+            *best = BestHeader {
+                hash: hash,
+                number: header.number(),
+                timestamp: header.timestamp(),
+                total_difficulty: total_difficulty,
+            };
+        }
+        Ok(is_best)
+    }
+
+    /// Get a header by hash.
+    pub fn header(&self, hash: &H256) -> Option<encoded::Header> {
+        self.db
+            .get(COL_HEADERS, hash)
+            .ok()
+            .and_then(|h| h)
+            .map(|h| encoded::Header::new(h.into_vec()))
+    }
+
+    /// Get the hash of the canonical header with the given number.
+    pub fn canonical_hash(&self, number: BlockNumber) -> Option<H256> {
+        self.db
+            .get(COL_EXTRA, &canonical_key(number))
+            .ok()
+            .and_then(|h| h)
+            .map(|h| H256::from_slice(&h))
+    }
+
+    /// Get the total difficulty of the chain ending at the given header.
+    pub fn total_difficulty(&self, hash: &H256) -> Option<U256> {
+        self.db
+            .get(COL_EXTRA, &td_key(hash))
+            .ok()
+            .and_then(|td| td)
+            .map(|td| decode(&td))
+    }
+
+    /// Get the hash of a block. Pending resolves to the best header.
+    pub fn block_hash(&self, id: BlockId) -> Option<H256> {
+        match id {
+            BlockId::Hash(hash) => self.header(&hash).map(|_| hash),
+            BlockId::Number(number) => self.canonical_hash(number),
+            BlockId::Earliest => Some(self.genesis_hash),
+            BlockId::Latest | BlockId::Pending => Some(self.best.read().hash),
+        }
+    }
+
+    /// Get a header by block id.
+    pub fn block_header(&self, id: BlockId) -> Option<encoded::Header> {
+        self.block_hash(id).and_then(|hash| self.header(&hash))
+    }
+
+    /// Get the best header.
+    pub fn best_header(&self) -> encoded::Header {
+        let hash = self.best.read().hash;
+        self.header(&hash).expect("best header is always stored; qed")
+    }
+
+    /// Get the hashes of up to `count` ancestors of a header, starting with its parent.
+    pub fn ancestor_hashes(&self, header: &encoded::Header, count: usize) -> Vec<H256> {
+        let mut hashes = Vec::with_capacity(count);
+        let mut hash = header.parent_hash();
+        while hashes.len() < count {
+            let parent = match self.header(&hash) {
+                Some(parent) => parent,
+                None => break,
+            };
+            hashes.push(hash);
+            if parent.number() == 0 {
+                break;
+            }
+            hash = parent.parent_hash();
+        }
+        hashes
+    }
+
+    /// Get the chain info.
+    pub fn chain_info(&self) -> BlockChainInfo {
+        let best = self.best.read();
+        BlockChainInfo {
+            total_difficulty: best.total_difficulty,
+            pending_total_difficulty: best.total_difficulty,
+            genesis_hash: self.genesis_hash,
+            best_block_hash: best.hash,
+            best_block_number: best.number,
+            best_block_timestamp: best.timestamp,
+            ancient_block_hash: None,
+            ancient_block_number: None,
+            first_block_hash: None,
+            first_block_number: None,
+        }
+    }
+}
@@ -0,0 +1,257 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Light client. Chain queries are answered from the header chain, state queries with
+//! proofs fetched from full nodes and verified against the header state roots.
+
+use std::sync::{Arc, Weak};
+
+use aion_types::{Address, H128, H256, U256};
+use client::BlockId;
+use encoded;
+use engine::Engine;
+use header::{BlockNumber, Header};
+use parking_lot::RwLock;
+use receipt::Receipt;
+use state::BasicAccount;
+use transaction::SignedTransaction;
+use types::blockchain::info::BlockChainInfo;
+use types::error::Error as CoreError;
+use types::executed::Executed;
+use vms::EnvInfo;
+
+use super::chain::HeaderChain;
+use super::request::{self, Error, Request, Response};
+
+/// Number of peers a request is sent to before giving up.
+const MAX_ATTEMPTS: usize = 3;
+
+/// Sends requests to full nodes.
+pub trait Fetcher: Send + Sync {
+    /// Send a request to a peer and wait for its response.
+    fn fetch(&self, request: Request) -> Result<Response, Error>;
+}
+
+/// Read-only chain and state queries served by a light client.
+pub trait LightChainClient: Send + Sync {
+    /// Get blockchain information.
+    fn chain_info(&self) -> BlockChainInfo;
+
+    /// Get raw block header data by block id.
+    fn block_header(&self, id: BlockId) -> Option<encoded::Header>;
+
+    /// Get block hash.
+    fn block_hash(&self, id: BlockId) -> Option<H256>;
+
+    /// Get a block number by ID.
+    fn block_number(&self, id: BlockId) -> Option<BlockNumber> {
+        self.block_header(id).map(|header| header.number())
+    }
+
+    /// Get block total difficulty.
+    fn block_total_difficulty(&self, id: BlockId) -> Option<U256>;
+
+    /// Get the best block header.
+    fn best_block_header(&self) -> encoded::Header;
+
+    /// Verify and import a header. Returns true if it became the best header.
+    fn import_header(&self, header: Header) -> Result<bool, CoreError>;
+
+    /// Get an account at the state of the given block.
+    fn account(&self, address: &Address, id: BlockId) -> Result<BasicAccount, Error>;
+
+    /// Get address balance at the given block's state.
+    fn balance(&self, address: &Address, id: BlockId) -> Result<U256, Error> {
+        self.account(address, id).map(|account| account.balance)
+    }
+
+    /// Get address nonce at the given block's state.
+    fn nonce(&self, address: &Address, id: BlockId) -> Result<U256, Error> {
+        self.account(address, id).map(|account| account.nonce)
+    }
+
+    /// Get value of the storage at given position at the given block's state.
+    fn storage_at(&self, address: &Address, position: &H128, id: BlockId) -> Result<H128, Error>;
+
+    /// Get the receipts of a block.
+    fn block_receipts(&self, id: BlockId) -> Result<Vec<Receipt>, Error>;
+
+    /// Make a virtual call of a fastvm transaction at the given block's state.
+    fn call(&self, transaction: &SignedTransaction, id: BlockId) -> Result<Executed, Error>;
+}
+
+/// Header-only client fetching state from full nodes.
+pub struct LightClient {
+    chain: HeaderChain,
+    engine: Arc<Engine>,
+    fetcher: RwLock<Option<Weak<Fetcher>>>,
+}
+
+impl LightClient {
+    /// Create a light client over the given header chain.
+    pub fn new(chain: HeaderChain, engine: Arc<Engine>) -> Self {
+        LightClient {
+            chain: chain,
+            engine: engine,
+            fetcher: RwLock::new(None),
+        }
+    }
+
+    /// Set the source of requested data.
+    pub fn set_fetcher(&self, fetcher: Weak<Fetcher>) { *self.fetcher.write() = Some(fetcher); }
+
+    /// Get the header chain.
+    pub fn chain(&self) -> &HeaderChain { &self.chain }
+
+    /// Fetch a response and check it, retrying with another peer if the response can't be
+    /// used.
+    fn fetch<T, F>(&self, request: Request, check: F) -> Result<T, Error>
+    where F: Fn(Response) -> Result<T, Error> {
+        let fetcher = match self.fetcher.read().as_ref().and_then(|f| f.upgrade()) {
+            Some(fetcher) => fetcher,
+            None => return Err(Error::NoPeers),
+        };
+
+        let mut result = Err(Error::NoPeers);
+        for _ in 0..MAX_ATTEMPTS {
+            result = fetcher.fetch(request.clone()).and_then(&check);
+            match result {
+                Ok(_) | Err(Error::NoPeers) | Err(Error::Execution(_)) => break,
+                Err(ref e) => debug!(target: "light", "Request {:?} failed: {}", request, e),
+            }
+        }
+        result
+    }
+
+    fn header(&self, id: BlockId) -> Result<encoded::Header, Error> {
+        self.chain.block_header(id).ok_or(Error::UnknownBlock)
+    }
+
+    fn env_info(&self, header: &encoded::Header, gas_limit: U256) -> EnvInfo {
+        let mut last_hashes = self.chain.ancestor_hashes(header, 256);
+        last_hashes.resize(256, H256::default());
+        EnvInfo {
+            number: header.number(),
+            author: header.author(),
+            timestamp: header.timestamp(),
+            difficulty: header.difficulty(),
+            last_hashes: Arc::new(last_hashes),
+            gas_used: U256::default(),
+            gas_limit: gas_limit,
+        }
+    }
+}
+
+impl LightChainClient for LightClient {
+    fn chain_info(&self) -> BlockChainInfo { self.chain.chain_info() }
+
+    fn block_header(&self, id: BlockId) -> Option<encoded::Header> { self.chain.block_header(id) }
+
+    fn block_hash(&self, id: BlockId) -> Option<H256> { self.chain.block_hash(id) }
+
+    fn block_total_difficulty(&self, id: BlockId) -> Option<U256> {
+        self.chain
+            .block_hash(id)
+            .and_then(|hash| self.chain.total_difficulty(&hash))
+    }
+
+    fn best_block_header(&self) -> encoded::Header { self.chain.best_header() }
+
+    fn import_header(&self, header: Header) -> Result<bool, CoreError> { self.chain.insert(header) }
+
+    fn account(&self, address: &Address, id: BlockId) -> Result<BasicAccount, Error> {
+        let header = self.header(id)?;
+        let start_nonce = self.engine.account_start_nonce(header.number());
+        let request = Request::Account {
+            block_hash: header.hash(),
+            address: *address,
+        };
+        self.fetch(request, |response| {
+            match response {
+                Response::Account(proof) => {
+                    request::check_account(&header, address, &proof, start_nonce)
+                }
+                _ => Err(Error::WrongResponse),
+            }
+        })
+    }
+
+    fn storage_at(&self, address: &Address, position: &H128, id: BlockId) -> Result<H128, Error> {
+        let header = self.header(id)?;
+        let request = Request::Storage {
+            block_hash: header.hash(),
+            address: *address,
+            key: *position,
+        };
+        self.fetch(request, |response| {
+            match response {
+                Response::Storage(account_proof, storage_proof) => {
+                    request::check_storage(
+                        &header,
+                        address,
+                        position,
+                        &account_proof,
+                        &storage_proof,
+                    )
+                }
+                _ => Err(Error::WrongResponse),
+            }
+        })
+    }
+
+    fn block_receipts(&self, id: BlockId) -> Result<Vec<Receipt>, Error> {
+        let header = self.header(id)?;
+        let request = Request::Receipts {
+            block_hash: header.hash(),
+        };
+        self.fetch(request, |response| {
+            match response {
+                Response::Receipts(receipts) => {
+                    request::check_receipts(&header, &receipts).map(|_| receipts)
+                }
+                _ => Err(Error::WrongResponse),
+            }
+        })
+    }
+
+    fn call(&self, transaction: &SignedTransaction, id: BlockId) -> Result<Executed, Error> {
+        let header = self.header(id)?;
+        let env_info = self.env_info(&header, transaction.gas);
+        let request = Request::Execution {
+            block_hash: header.hash(),
+            transaction: transaction.clone(),
+        };
+        self.fetch(request, |response| {
+            match response {
+                Response::Execution(proof) => {
+                    request::check_execution(
+                        &header,
+                        transaction,
+                        &proof,
+                        self.engine.machine(),
+                        &env_info,
+                    )
+                }
+                _ => Err(Error::WrongResponse),
+            }
+        })
+    }
+}
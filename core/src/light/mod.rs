@@ -0,0 +1,37 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Light client mode.
+//!
+//! A light client only syncs and verifies block headers. State and receipts are requested
+//! from full nodes on demand together with merkle proofs, which are checked against the
+//! state and receipts roots of the verified headers.
+
+mod chain;
+mod client;
+pub mod request;
+
+#[cfg(test)]
+mod tests;
+
+pub use self::chain::HeaderChain;
+pub use self::client::{Fetcher, LightChainClient, LightClient};
+pub use self::request::{Error, Request, Response};
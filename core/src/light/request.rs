@@ -0,0 +1,215 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Requests served by full nodes to light clients and verification of their responses
+//! against block headers.
+
+use std::fmt;
+
+use acore_bytes::Bytes;
+use aion_types::{Address, H128, H256, U256};
+use blake2b::{BLAKE2B_EMPTY, BLAKE2B_NULL_RLP};
+use encoded;
+use kvdb::DBValue;
+use machine::EthereumMachine;
+use receipt::Receipt;
+use rlp::{DecoderError, Encodable, UntrustedRlp};
+use state::{self, BasicAccount, ProvedExecution};
+use transaction::SignedTransaction;
+use trie;
+use triehash::ordered_trie_root;
+use types::executed::{Executed, ExecutionError};
+use vms::EnvInfo;
+
+/// A request for data which can be verified against the header of the given block.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Request {
+    /// Account at the state of a block.
+    Account {
+        /// Hash of the block.
+        block_hash: H256,
+        /// Address of the account.
+        address: Address,
+    },
+    /// Storage value of an account at the state of a block.
+    Storage {
+        /// Hash of the block.
+        block_hash: H256,
+        /// Address of the account.
+        address: Address,
+        /// Storage key.
+        key: H128,
+    },
+    /// Receipts of a block.
+    Receipts {
+        /// Hash of the block.
+        block_hash: H256,
+    },
+    /// Virtual execution of a fastvm transaction on the state of a block.
+    Execution {
+        /// Hash of the block.
+        block_hash: H256,
+        /// Transaction to execute.
+        transaction: SignedTransaction,
+    },
+}
+
+impl Request {
+    /// Hash of the block the request refers to.
+    pub fn block_hash(&self) -> &H256 {
+        match *self {
+            Request::Account { ref block_hash, .. } => block_hash,
+            Request::Storage { ref block_hash, .. } => block_hash,
+            Request::Receipts { ref block_hash } => block_hash,
+            Request::Execution { ref block_hash, .. } => block_hash,
+        }
+    }
+}
+
+/// Unverified response to a `Request`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Response {
+    /// Trie nodes proving the account, in order from the state root.
+    Account(Vec<Bytes>),
+    /// Trie nodes proving the account and trie nodes proving the storage value.
+    Storage(Vec<Bytes>, Vec<Bytes>),
+    /// Receipts of the block.
+    Receipts(Vec<Receipt>),
+    /// State items needed to execute the transaction.
+    Execution(Vec<DBValue>),
+}
+
+/// Light client request errors.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// No peer to send the request to.
+    NoPeers,
+    /// No response received in time.
+    Timeout,
+    /// Peer couldn't serve the request.
+    Unavailable,
+    /// Response doesn't answer the request.
+    WrongResponse,
+    /// Response failed verification against the block header.
+    BadProof,
+    /// The block isn't known to the header chain.
+    UnknownBlock,
+    /// Transaction execution failed.
+    Execution(ExecutionError),
+    /// Response could not be decoded.
+    Decoder(DecoderError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::NoPeers => write!(f, "No peers to serve the request"),
+            Error::Timeout => write!(f, "Request timed out"),
+            Error::Unavailable => write!(f, "Request could not be served"),
+            Error::WrongResponse => write!(f, "Response doesn't match the request"),
+            Error::BadProof => write!(f, "Response failed proof verification"),
+            Error::UnknownBlock => write!(f, "Unknown block"),
+            Error::Execution(ref err) => write!(f, "{}", err),
+            Error::Decoder(ref err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl From<DecoderError> for Error {
+    fn from(err: DecoderError) -> Self { Error::Decoder(err) }
+}
+
+/// Check an account proof against the state root of `header`. Accounts proven not to
+/// exist are returned with default values.
+pub fn check_account(
+    header: &encoded::Header,
+    address: &Address,
+    proof: &[Bytes],
+    account_start_nonce: U256,
+) -> Result<BasicAccount, Error>
+{
+    match trie::verify_secure_proof(&header.state_root(), proof, address) {
+        Ok(Some(rlp)) => Ok(UntrustedRlp::new(&rlp).as_val()?),
+        Ok(None) => {
+            Ok(BasicAccount {
+                nonce: account_start_nonce,
+                balance: U256::zero(),
+                storage_root: BLAKE2B_NULL_RLP,
+                code_hash: BLAKE2B_EMPTY,
+            })
+        }
+        Err(_) => Err(Error::BadProof),
+    }
+}
+
+/// Check a storage proof against the state root of `header`. Returns the storage value,
+/// zero if it doesn't exist.
+pub fn check_storage(
+    header: &encoded::Header,
+    address: &Address,
+    key: &H128,
+    account_proof: &[Bytes],
+    storage_proof: &[Bytes],
+) -> Result<H128, Error>
+{
+    let account = check_account(header, address, account_proof, U256::zero())?;
+    if account.storage_root == BLAKE2B_NULL_RLP {
+        return Ok(H128::zero());
+    }
+
+    match trie::verify_secure_proof(&account.storage_root, storage_proof, key) {
+        Ok(Some(rlp)) => {
+            let value: Bytes = UntrustedRlp::new(&rlp).as_val()?;
+            let mut word = H128::zero();
+            let len = ::std::cmp::min(value.len(), 16);
+            word[16 - len..].copy_from_slice(&value[value.len() - len..]);
+            Ok(word)
+        }
+        Ok(None) => Ok(H128::zero()),
+        Err(_) => Err(Error::BadProof),
+    }
+}
+
+/// Check block receipts against the receipts root of `header`.
+pub fn check_receipts(header: &encoded::Header, receipts: &[Receipt]) -> Result<(), Error> {
+    let receipts_root =
+        ordered_trie_root(receipts.iter().map(|r| r.simple_receipt().rlp_bytes()));
+    match receipts_root == header.receipts_root() {
+        true => Ok(()),
+        false => Err(Error::BadProof),
+    }
+}
+
+/// Execute a transaction on the state items proven by a full node.
+pub fn check_execution(
+    header: &encoded::Header,
+    transaction: &SignedTransaction,
+    proof: &[DBValue],
+    machine: &EthereumMachine,
+    env_info: &EnvInfo,
+) -> Result<Executed, Error>
+{
+    match state::check_proof(proof, header.state_root(), transaction, machine, env_info) {
+        ProvedExecution::Complete(executed) => Ok(executed),
+        ProvedExecution::Failed(err) => Err(Error::Execution(err)),
+        ProvedExecution::BadProof => Err(Error::BadProof),
+    }
+}
@@ -0,0 +1,170 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+use std::sync::Arc;
+
+use acore_bytes::Bytes;
+use aion_types::{Address, H128, H256, U256};
+use blake2b::blake2b;
+use client::BlockId;
+use encoded;
+use header::{Header, SealType};
+use helpers::{get_temp_state, new_db};
+use light::{Error, HeaderChain};
+use light::request::{check_account, check_receipts, check_storage};
+use receipt::Receipt;
+use rlp::encode;
+use spec::Spec;
+use state::CleanupMode;
+use triehash::ordered_trie_root;
+
+fn header_with(state_root: H256, receipts_root: H256) -> encoded::Header {
+    let mut header = Header::new();
+    header.set_state_root(state_root);
+    header.set_receipts_root(receipts_root);
+    encoded::Header::new(encode(&header).into_vec())
+}
+
+fn child_of(parent: &Header, difficulty: u64) -> Header {
+    let mut header = Header::new();
+    header.set_seal_type(SealType::PoS);
+    header.set_parent_hash(parent.hash());
+    header.set_number(parent.number() + 1);
+    header.set_timestamp(parent.timestamp() + 10);
+    header.set_difficulty(difficulty.into());
+    header.set_gas_limit(parent.gas_limit().clone());
+    header
+}
+
+#[test]
+fn account_proof() {
+    let address = Address::from(1);
+    let mut state = get_temp_state();
+    state.add_balance(&address, &69.into(), CleanupMode::NoEmpty).unwrap();
+    state.commit().unwrap();
+
+    let (proof, account) = state.prove_account(blake2b(&address)).unwrap();
+    assert_eq!(account.balance, 69.into());
+
+    let header = header_with(*state.root(), H256::zero());
+    let checked = check_account(&header, &address, &proof, U256::zero()).unwrap();
+    assert_eq!(checked.balance, 69.into());
+
+    // proof of an absent account yields defaults.
+    let missing = Address::from(2);
+    let (proof, _) = state.prove_account(blake2b(&missing)).unwrap();
+    let checked = check_account(&header, &missing, &proof, U256::zero()).unwrap();
+    assert_eq!(checked.balance, U256::zero());
+
+    // a proof doesn't verify against another root.
+    let header = header_with(H256::from(3), H256::zero());
+    assert!(check_account(&header, &address, &proof, U256::zero()).is_err());
+}
+
+#[test]
+fn storage_proof() {
+    let address = Address::from(1);
+    let key = H128::from(5);
+    let mut state = get_temp_state();
+    state.new_contract(&address, 0.into(), 0.into());
+    state.set_storage(&address, key.to_vec(), vec![0x12, 0x34]).unwrap();
+    state.commit().unwrap();
+
+    let (account_proof, _) = state.prove_account(blake2b(&address)).unwrap();
+    let (storage_proof, value) = state.prove_storage(blake2b(&address), blake2b(&key)).unwrap();
+    assert_eq!(value, H256::from(0x1234));
+
+    let header = header_with(*state.root(), H256::zero());
+    let checked = check_storage(&header, &address, &key, &account_proof, &storage_proof);
+    assert_eq!(checked.unwrap(), H128::from(0x1234));
+
+    // a storage proof without its nodes doesn't verify.
+    let checked = check_storage(&header, &address, &key, &account_proof, &[]);
+    assert!(checked.is_err());
+}
+
+#[test]
+fn receipts_proof() {
+    let receipts = vec![Receipt::new(
+        H256::zero(),
+        21000.into(),
+        U256::zero(),
+        Vec::new(),
+        Bytes::new(),
+        String::default(),
+    )];
+    let receipts_root =
+        ordered_trie_root(receipts.iter().map(|r| encode(r.simple_receipt()).into_vec()));
+
+    let header = header_with(H256::zero(), receipts_root);
+    assert!(check_receipts(&header, &receipts).is_ok());
+    match check_receipts(&header, &[]) {
+        Err(Error::BadProof) => {}
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[test]
+fn header_chain_reorg() {
+    let spec = Spec::new_test();
+    let genesis = spec.genesis_header();
+    let chain = HeaderChain::new(new_db(), &genesis, spec.engine.clone()).unwrap();
+    assert_eq!(chain.best_header().hash(), genesis.hash());
+
+    let a1 = child_of(&genesis, 10);
+    let a2 = child_of(&a1, 10);
+    assert!(chain.insert(a1.clone()).unwrap());
+    assert!(chain.insert(a2.clone()).unwrap());
+    assert!(chain.insert(a2.clone()).is_err());
+    assert_eq!(chain.block_hash(BlockId::Latest), Some(a2.hash()));
+    assert_eq!(chain.block_hash(BlockId::Number(1)), Some(a1.hash()));
+
+    // a heavier single block fork becomes canonical and retracts the old branch.
+    let mut b1 = child_of(&genesis, 100);
+    b1.set_timestamp(genesis.timestamp() + 20);
+    assert!(chain.insert(b1.clone()).unwrap());
+    assert_eq!(chain.block_hash(BlockId::Latest), Some(b1.hash()));
+    assert_eq!(chain.block_hash(BlockId::Number(1)), Some(b1.hash()));
+    assert_eq!(chain.block_hash(BlockId::Number(2)), None);
+    assert_eq!(
+        chain.total_difficulty(&b1.hash()),
+        Some(*genesis.difficulty() + U256::from(100))
+    );
+
+    // headers with unknown parents are rejected.
+    let orphan = child_of(&child_of(&b1, 1), 1);
+    assert!(chain.insert(orphan).is_err());
+}
+
+#[test]
+fn header_chain_reopen() {
+    let spec = Spec::new_test();
+    let genesis = spec.genesis_header();
+    let db = new_db();
+    let a1 = child_of(&genesis, 10);
+    {
+        let chain = HeaderChain::new(db.clone(), &genesis, spec.engine.clone()).unwrap();
+        chain.insert(a1.clone()).unwrap();
+    }
+    let chain = HeaderChain::new(db, &genesis, spec.engine.clone()).unwrap();
+    assert_eq!(chain.best_header().hash(), a1.hash());
+    assert_eq!(chain.chain_info().best_block_number, 1);
+}
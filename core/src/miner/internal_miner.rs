@@ -0,0 +1,129 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Internal CPU miner.
+//!
+//! Solves Equihash for the miner's current PoW sealing work and submits the solutions back
+//! through `MinerService::submit_seal`, so that private and test networks can produce PoW
+//! blocks without external mining software.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use aion_types::{H256, U256};
+use blake2b::Blake2b;
+use block::IsBlock;
+use client::{BlockChainClient, Client};
+use equihash::EquihashSolver;
+use miner::MinerService;
+use rand::{thread_rng, Rng};
+
+/// Equihash parameters used by the unity engine.
+const EQUIHASH_N: i32 = 210;
+const EQUIHASH_K: i32 = 9;
+/// Time to wait before asking again when there is no work to mine on.
+const IDLE_INTERVAL_MS: u64 = 500;
+
+/// Mining threads working on the sealing work of a client's miner.
+pub struct InternalMiner {
+    running: Arc<AtomicBool>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl InternalMiner {
+    /// Start `threads` mining threads for the given client.
+    pub fn start(client: Arc<Client>, threads: usize) -> InternalMiner {
+        let running = Arc::new(AtomicBool::new(true));
+        let threads = (0..threads)
+            .map(|i| {
+                let client = client.clone();
+                let running = running.clone();
+                thread::Builder::new()
+                    .name(format!("internal-miner #{}", i))
+                    .spawn(move || mine(&client, &running))
+                    .expect("Failed to spawn internal miner thread")
+            })
+            .collect();
+        info!(target: "miner", "Internal miner started");
+        InternalMiner {
+            running,
+            threads,
+        }
+    }
+
+    /// Stop all the mining threads and wait for them to finish.
+    pub fn stop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        for thread in self.threads.drain(..) {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for InternalMiner {
+    fn drop(&mut self) { self.stop(); }
+}
+
+fn mine(client: &Arc<Client>, running: &AtomicBool) {
+    let solver = EquihashSolver::new(EQUIHASH_N, EQUIHASH_K);
+    let mut rng = thread_rng();
+    while running.load(Ordering::SeqCst) {
+        let work = client.miner().map_sealing_work(&**client, |b| {
+            let header = b.header();
+            (header.mine_hash(), *header.parent_hash(), header.boundary())
+        });
+        let (mine_hash, parent_hash, boundary) = match work {
+            Some(work) => work,
+            None => {
+                thread::sleep(Duration::from_millis(IDLE_INTERVAL_MS));
+                continue;
+            }
+        };
+
+        let nonce: [u8; 32] = rng.gen();
+        // give up on stale work as soon as a new block is imported
+        let cancelled = || {
+            !running.load(Ordering::SeqCst) || client.chain_info().best_block_hash != parent_hash
+        };
+        for solution in solver.solve(&mine_hash, &nonce, cancelled) {
+            if !meets_boundary(&mine_hash, &nonce, &solution, &boundary) {
+                continue;
+            }
+            let seal = vec![nonce.to_vec(), solution];
+            match client.miner().submit_seal(&**client, mine_hash, seal) {
+                Ok(_) => info!(target: "miner", "Internal miner sealed block {}", mine_hash),
+                Err(e) => warn!(target: "miner", "Internal miner solution rejected: {}", e),
+            }
+            break;
+        }
+    }
+}
+
+/// Same check as the PoW header validator: blake2b(mine hash, nonce, solution) < boundary.
+fn meets_boundary(mine_hash: &H256, nonce: &[u8], solution: &[u8], boundary: &H256) -> bool {
+    let mut input: Vec<u8> = Vec::with_capacity(32 + 32 + solution.len());
+    input.extend_from_slice(mine_hash.as_ref());
+    input.extend_from_slice(nonce);
+    input.extend_from_slice(solution);
+    U256::from(Blake2b::hash_256(input.as_slice())) < U256::from(*boundary)
+}
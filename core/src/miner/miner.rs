@@ -27,7 +27,6 @@ use std::thread;
 use std::ops::Deref;
 use std::hash::{Hash, Hasher};
 
-use rustc_hex::FromHex;
 use account_provider::AccountProvider;
 use acore_bytes::Bytes;
 use aion_types::{Address, H256, U256};
@@ -37,7 +36,7 @@ use engine::Engine;
 use header::{BlockNumber, Header, SealType};
 use types::error::*;
 use io::IoChannel;
-use miner::{MinerService, MinerStatus};
+use miner::{MinerService, MinerStatus, StakerSigner, StakerStats};
 use parking_lot::{Mutex, RwLock};
 use receipt::Receipt;
 use spec::Spec;
@@ -56,15 +55,16 @@ use transaction::transaction_queue::{
     AccountDetails, PrioritizationStrategy, RemovalReason, TransactionOrigin, TransactionQueue,
 };
 use using_queue::{GetAction, UsingQueue};
-use rcrypto::ed25519;
-use key::Ed25519KeyPair;
+use lru_cache::LruCache;
 use num::Zero;
 use num_bigint::BigUint;
-use delta_calc::calculate_delta;
+use delta_calc::{calculate_delta, expected_delta};
 use key::public_to_address_ed25519;
 use blake2b::blake2b;
 
 const POW_UPDATE_COOLDOWN: Duration = Duration::from_secs(1);
+/// Number of past stakes kept for the staker stats.
+const STAKE_CACHE_SIZE: usize = 1024;
 
 struct Seed([u8; 64]);
 
@@ -129,8 +129,8 @@ pub struct MinerOptions {
     pub maximal_gas_price: U256,
     /// maximal gas price of a new local transaction to be accepted by the miner/transaction queue when using dynamic gas price
     pub local_max_gas_price: U256,
-    /// Staker private key
-    pub staker_private_key: Option<String>,
+    /// Number of internal CPU miner threads, 0 disables the internal miner
+    pub internal_miner_threads: usize,
 }
 
 impl Default for MinerOptions {
@@ -150,7 +150,7 @@ impl Default for MinerOptions {
             minimal_gas_price: 10_000_000_000u64.into(),
             maximal_gas_price: 9_000_000_000_000_000_000u64.into(),
             local_max_gas_price: 100_000_000_000u64.into(),
-            staker_private_key: None,
+            internal_miner_threads: 0,
         }
     }
 }
@@ -208,18 +208,23 @@ pub struct Miner {
     // the current PoS block with minimum timestamp
     best_pos: Mutex<Option<SealedBlock>>,
     next_allowed_reseal: Mutex<Instant>,
+    // earliest time the next block may be sealed by an internally sealing engine
+    next_internal_seal: Mutex<Instant>,
     sealing_block_last_request: Mutex<u64>,
     // for sealing...
     options: MinerOptions,
     gas_range_target: RwLock<(U256, U256)>,
     author: RwLock<Address>,
-    // TOREMOVE: Unity MS1 use only.
-    staker: Option<Ed25519KeyPair>,
+    staker: RwLock<Option<Arc<StakerSigner>>>,
     extra_data: RwLock<Bytes>,
     engine: Arc<Engine>,
     accounts: Option<Arc<AccountProvider>>,
     tx_message: Mutex<IoChannel<TxIoMessage>>,
     transaction_pool_update_lock: Mutex<bool>,
+    transaction_listener: RwLock<Vec<Box<Fn(&[H256]) + Send + Sync>>>,
+    // stakes of stakers at past blocks by (signing key, coinbase, block hash), each one costs a
+    // call to the staking contract
+    stake_cache: Mutex<LruCache<(H256, Address, H256), BigUint>>,
 }
 
 impl Miner {
@@ -245,6 +250,11 @@ impl Miner {
     /// Clear all pending block states
     pub fn clear(&self) { self.sealing_work_pow.lock().queue.reset(); }
 
+    /// Set a callback to be notified about transactions imported to the queue.
+    pub fn add_transactions_listener(&self, f: Box<Fn(&[H256]) + Send + Sync>) {
+        self.transaction_listener.write().push(f);
+    }
+
     /// Get `Some` `clone()` of the current pending block's state or `None` if we're not sealing.
     pub fn pending_state(&self, latest_block_number: BlockNumber) -> Option<State<::db::StateDB>> {
         self.map_pending_block(|b| b.state().clone(), latest_block_number)
@@ -282,6 +292,44 @@ impl Miner {
         }
     }
 
+    /// Seal and import a block straight away if the engine seals internally and there are
+    /// pending transactions. Respects the engine's seal interval between two blocks.
+    pub fn try_seal_internally(&self, client: &MiningBlockChainClient) {
+        let interval = match self.engine.seals_internally() {
+            Some(interval) => interval,
+            None => return,
+        };
+        if Instant::now() < *self.next_internal_seal.lock() {
+            return;
+        }
+
+        self.update_transaction_pool(client, false);
+        if self.transaction_pool.status().pending == 0 {
+            return;
+        }
+
+        let block = match self.prepare_block(client, &Some(SealType::PoW), None, None, None) {
+            Ok((block, _)) => block,
+            Err(_) => return,
+        };
+        if block.transactions().is_empty() {
+            return;
+        }
+
+        let seal = self.engine.generate_seal(block.header());
+        match block.lock().try_seal_pow(&*self.engine, seal) {
+            Ok(sealed) => {
+                *self.next_internal_seal.lock() = Instant::now() + interval;
+                if let Some(error) = client.import_sealed_block(sealed).err() {
+                    warn!(target: "miner", "Internally sealed block import error: {}", error);
+                }
+            }
+            Err((error, _)) => {
+                warn!(target: "miner", "Internal seal rejected: {}", error);
+            }
+        }
+    }
+
     pub fn invoke_pos_interval(&self, client: &MiningBlockChainClient) {
         // compete with import_lock, if another is imported, block will be None, or else try importing pending_best
         let block = {
@@ -323,19 +371,15 @@ impl Miner {
         }
 
         // Return if no internal staker
-        if self.staker.is_none() {
-            return;
-        }
+        let staker = match self.staker() {
+            Some(staker) => staker,
+            None => return,
+        };
 
         // Staker
-        let staker: Ed25519KeyPair = self
-            .staker()
-            .to_owned()
-            .expect("Internal staker is null. Should have checked before.");
-        let sk: [u8; 64] = staker.secret().0;
         let pk: [u8; 32] = staker.public().0;
         let coinbase: Address = client
-            .get_coinbase(staker.address())
+            .get_coinbase(public_to_address_ed25519(&staker.public()))
             .unwrap_or(Address::default());
 
         // 1. Get the stake. Stop proceeding if stake is 0.
@@ -384,7 +428,13 @@ impl Miner {
         }
         // Normal unity seed
         else {
-            ed25519::signature(&seed, &sk)
+            match staker.sign_seed(best_block_header.number() + 1, &seed) {
+                Ok(signature) => signature,
+                Err(error) => {
+                    debug!(target: "staker", "Failed to sign the seed: {}", error);
+                    return;
+                }
+            }
         };
 
         let delta_uint = calculate_delta(difficulty, &new_seed, stake.clone());
@@ -403,8 +453,7 @@ impl Miner {
                     client,
                     new_timestamp,
                     new_seed,
-                    &sk,
-                    &pk,
+                    &*staker,
                     &best_block_header.decode(),
                     grand_parant.map(|header| header.decode()).as_ref(),
                     stake,
@@ -417,16 +466,14 @@ impl Miner {
         }
     }
 
-    // TOREMOVE-Unity: Unity MS1 use only
     /// Generate PoS block
-    /// sk/pk is public/private key of signer
+    /// staker signs the seal of the new block
     pub fn produce_pos_block_internal(
         &self,
         client: &MiningBlockChainClient,
         timestamp: u64,
         seed: [u8; 64],
-        sk: &[u8; 64],
-        pk: &[u8; 32],
+        staker: &StakerSigner,
         parent: &Header,
         grand_parant: Option<&Header>,
         stake: BigUint,
@@ -450,13 +497,16 @@ impl Miner {
             })?;
 
         // 2. Generate signature
+        let pk: H256 = staker.public();
         let mut preseal = Vec::with_capacity(3);
         preseal.push(seed.to_vec());
         preseal.push(vec![0u8; 64]);
         preseal.push(pk.to_vec());
         let presealed_block = raw_block.pre_seal(preseal);
         let mine_hash: H256 = presealed_block.header().mine_hash();
-        let signature = ed25519::signature(&mine_hash.0, sk);
+        let signature = staker
+            .sign_seal(parent.number() + 1, &parent.hash(), &mine_hash)
+            .map_err(Error::Other)?;
 
         // 3. Seal the block
         let mut seal: Vec<Bytes> = Vec::with_capacity(3);
@@ -533,15 +583,10 @@ impl Miner {
         let transaction_pool: TransactionPool =
             TransactionPool::new(RwLock::new(transaction_queue));
 
-        // TOREMOVE: Unity MS1 use only
-        let staker: Option<Ed25519KeyPair> = match options.staker_private_key.to_owned() {
-            Some(key) => parse_staker(key).ok(),
-            None => None,
-        };
-
         Miner {
             transaction_pool,
             next_allowed_reseal: Mutex::new(Instant::now()),
+            next_internal_seal: Mutex::new(Instant::now()),
             sealing_block_last_request: Mutex::new(0),
             sealing_work_pow: Mutex::new(SealingWork {
                 queue: UsingQueue::new(options.work_queue_size),
@@ -552,13 +597,15 @@ impl Miner {
             best_pos: Mutex::new(None),
             gas_range_target: RwLock::new((U256::zero(), U256::zero())),
             author: RwLock::new(Address::default()),
-            staker: staker,
+            staker: RwLock::new(None),
             extra_data: RwLock::new(Vec::new()),
             options,
             accounts,
             engine: spec.engine.clone(),
             tx_message: Mutex::new(message_channel),
             transaction_pool_update_lock: Mutex::new(true),
+            transaction_listener: RwLock::new(vec![]),
+            stake_cache: Mutex::new(LruCache::new(STAKE_CACHE_SIZE)),
         }
     }
 
@@ -1007,6 +1054,7 @@ impl Miner {
             })
             .unwrap_or(default_origin);
 
+        let hash = transaction.hash().clone();
         let result = self.transaction_pool.add_transaction(
             transaction,
             origin,
@@ -1015,6 +1063,10 @@ impl Miner {
         )?;
         debug!(target: "rpc_tx", "{:?} tx finished importing [{:?}]", thread::current().id(), time::Instant::now());
 
+        for listener in &*self.transaction_listener.read() {
+            listener(&[hash]);
+        }
+
         Ok(result)
     }
 
@@ -1063,6 +1115,51 @@ impl Miner {
         self.sealing_work_pow.lock().queue.reset();
     }
 
+    /// Stake of a staker at the block `hash`, `None` if the state of the block is not available.
+    fn past_stake(
+        &self,
+        client: &MiningBlockChainClient,
+        public: &H256,
+        coinbase: Address,
+        hash: H256,
+    ) -> Option<BigUint>
+    {
+        let key = (*public, coinbase, hash);
+        if let Some(stake) = self.stake_cache.lock().get_mut(&key) {
+            return Some(stake.clone());
+        }
+        let stake = client.get_stake(public, coinbase, BlockId::Hash(hash))?;
+        self.stake_cache.lock().insert(key, stake.clone());
+        Some(stake)
+    }
+
+    /// Whether the staker was eligible to produce a PoS block at the height of `header` before
+    /// it, `None` if the parent block or its state is not available. Requires the hybrid seed of
+    /// the staker, so only valid after the hybrid seed update.
+    fn eligible_before(
+        &self,
+        client: &MiningBlockChainClient,
+        public: &H256,
+        coinbase: Address,
+        header: &Header,
+    ) -> Option<bool>
+    {
+        let parent = client.block_header_data(header.parent_hash())?.decode();
+        let grand_parent_seed = match client.block_header_data(parent.parent_hash()) {
+            Some(ref grand_parent) if grand_parent.seal_type() == Some(SealType::PoS) => {
+                grand_parent.seal()[0].clone()
+            }
+            _ => vec![0u8; 64],
+        };
+        let stake = self.past_stake(client, public, coinbase, parent.hash())?;
+        if stake.is_zero() {
+            return Some(false);
+        }
+        let seed = Self::generate_hybrid_seed(&grand_parent_seed, public, &parent);
+        let delta = calculate_delta(*header.difficulty(), &seed, stake);
+        Some(parent.timestamp() + delta < header.timestamp())
+    }
+
     // U30-22
     // Generate hybrid seed for pos block
     fn generate_hybrid_seed(
@@ -1147,7 +1244,7 @@ impl MinerService for Miner {
 
     fn set_author(&self, author: Address) { *self.author.write() = author; }
 
-    fn set_staker(&mut self, staker: Ed25519KeyPair) { self.staker = Some(staker); }
+    fn set_staker(&self, staker: Arc<StakerSigner>) { *self.staker.write() = Some(staker); }
 
     fn set_extra_data(&self, extra_data: Bytes) { *self.extra_data.write() = extra_data; }
 
@@ -1178,7 +1275,7 @@ impl MinerService for Miner {
     fn author(&self) -> Address { *self.author.read() }
 
     /// Get the PoS staker that we will seal PoS blocks.
-    fn staker(&self) -> &Option<Ed25519KeyPair> { &self.staker }
+    fn staker(&self) -> Option<Arc<StakerSigner>> { self.staker.read().clone() }
 
     /// Get the extra_data that we will seal blocks with.
     fn extra_data(&self) -> Bytes { self.extra_data.read().clone() }
@@ -1788,6 +1885,88 @@ impl MinerService for Miner {
             })
     }
 
+    fn staker_stats(
+        &self,
+        client: &MiningBlockChainClient,
+        staker: &H256,
+        from: BlockNumber,
+        to: BlockNumber,
+    ) -> StakerStats
+    {
+        let headers: Vec<Header> = (from..to + 1)
+            .filter_map(|number| client.block_header(BlockId::Number(number)))
+            .filter(|header| header.seal_type() == Some(SealType::PoS))
+            .map(|header| header.decode())
+            .collect();
+
+        // The staker is known by its signing public key if it is registered, otherwise take it
+        // as a coinbase and find its public key in the blocks it produced.
+        let (public, coinbase) = match client.get_coinbase(public_to_address_ed25519(staker)) {
+            Some(coinbase) if !coinbase.is_zero() => (Some(*staker), coinbase),
+            _ => {
+                let public = headers
+                    .iter()
+                    .rev()
+                    .find(|header| header.author() == staker)
+                    .map(|header| H256::from(header.seal()[2].as_slice()));
+                (public, *staker)
+            }
+        };
+        let produced_by_staker = |header: &Header| {
+            match public {
+                Some(ref public) => header.seal()[2].as_slice() == &public[..],
+                None => header.author() == &coinbase,
+            }
+        };
+
+        let stake: BigUint = public
+            .and_then(|public| client.get_stake(&public, coinbase, BlockId::Latest))
+            .unwrap_or(BigUint::zero());
+        let best_header = client.best_block_header();
+        let pos_difficulty = if best_header.seal_type() == Some(SealType::PoS) {
+            Some(best_header.difficulty())
+        } else {
+            client
+                .block_header_data(&best_header.parent_hash())
+                .filter(|header| header.seal_type() == Some(SealType::PoS))
+                .map(|header| header.difficulty())
+        };
+        let expected_block_time = if stake.is_zero() {
+            None
+        } else {
+            pos_difficulty.map(|difficulty| expected_delta(difficulty, stake.clone()))
+        };
+
+        let hybrid_seed_update = self.engine.machine().params().unity_hybrid_seed_update;
+        let mut stats = StakerStats {
+            stake: stake.into(),
+            total_stake: client
+                .get_total_stake(BlockId::Latest)
+                .unwrap_or(U256::zero()),
+            expected_block_time,
+            blocks_produced: 0,
+            rewards: U256::zero(),
+            missed_opportunities: 0,
+            unchecked_blocks: 0,
+        };
+        for header in &headers {
+            if produced_by_staker(header) {
+                stats.blocks_produced += 1;
+                stats.rewards = stats.rewards + self.engine.block_reward(header);
+            } else if let Some(ref public) = public {
+                if !hybrid_seed_update.map_or(false, |fork_number| header.number() > fork_number) {
+                    continue;
+                }
+                match self.eligible_before(client, public, coinbase, header) {
+                    Some(true) => stats.missed_opportunities += 1,
+                    Some(false) => {}
+                    None => stats.unchecked_blocks += 1,
+                }
+            }
+        }
+        stats
+    }
+
     // AION 2.0
     // Check if it's allowed to produce a new block with given seal type.
     // A block's seal type must be different than its parent's seal type.
@@ -1810,29 +1989,6 @@ impl MinerService for Miner {
     }
 }
 
-// TOREMOVE-Unity: Unity MS1 use only
-fn parse_staker(key: String) -> Result<Ed25519KeyPair, String> {
-    let bytes: Vec<u8>;
-    if key.starts_with("0x") {
-        bytes = String::from(&key[2..])
-            .from_hex()
-            .map_err(|_| "Private key is not hex string.")?;
-    } else {
-        bytes = key
-            .from_hex()
-            .map_err(|_| "Private key is not hex string.")?;
-    }
-    if bytes.len() != 32 {
-        return Err("Private key length is not 32.".to_owned());
-    }
-    let mut sk = [0; 32];
-    sk.copy_from_slice(&bytes[..]);
-    let (secret, public): ([u8; 64], [u8; 32]) = ed25519::keypair(&sk);
-    let mut keypair: Vec<u8> = secret.to_vec();
-    keypair.extend(public.to_vec());
-    Ok(keypair.into())
-}
-
 #[cfg(test)]
 mod tests {
     use aion_types::U256;
@@ -1902,7 +2058,7 @@ mod tests {
                 minimal_gas_price: 0u64.into(),
                 maximal_gas_price: 9_000_000_000_000_000_000u64.into(),
                 local_max_gas_price: 100_000_000_000u64.into(),
-                staker_private_key: None,
+                internal_miner_threads: 0,
             },
             &Spec::new_test(),
             None, // accounts provider
@@ -1929,7 +2085,7 @@ mod tests {
                 minimal_gas_price: 0u64.into(),
                 maximal_gas_price: 9_000_000_000_000_000_000u64.into(),
                 local_max_gas_price: 100_000_000_000u64.into(),
-                staker_private_key: None,
+                internal_miner_threads: 0,
             },
             spec,
             None, // accounts provider
@@ -2374,4 +2530,120 @@ mod tests {
         let result = miner.try_seal_pos(&client, seal, block);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn staker_stats_over_pos_chain() {
+        use engine::Engine;
+        use header::Header;
+        use key::public_to_address_ed25519;
+        use machine::EthereumMachine;
+        use miner::StakerStats;
+        use rlp::{RlpStream, EMPTY_LIST_RLP};
+        use std::collections::BTreeMap;
+        use std::sync::atomic::Ordering as AtomicOrdering;
+        use tests::common::null_engine::{NullEngine, NullEngineParams};
+
+        // PoS blocks after #3 are on the hybrid seed update and count as missed opportunities
+        let mut spec = Spec::new_unity(Some(0));
+        let mut params = spec.engine.machine().params().clone();
+        params.unity_hybrid_seed_update = Some(3);
+        spec.engine = Arc::new(NullEngine::new(
+            NullEngineParams {
+                block_reward: U256::from(13),
+            },
+            EthereumMachine::regular(params, BTreeMap::new(), U256::zero()),
+        ));
+        let miner = miner_with_spec(&spec);
+        let client = TestBlockChainClient::new_with_spec(spec);
+
+        let (public, coinbase) = (H256::from(7), H256::from(9));
+        let (other_public, other_coinbase) = (H256::from(8), H256::from(10));
+        // (author, signing key, seconds after the parent) of the PoS blocks #2, #4, ..., #10.
+        // With a PoS difficulty of 1 every staker may produce a block 1 second after its parent.
+        let pos_blocks = vec![
+            (other_coinbase, other_public, 10), // before the update, not counted
+            (coinbase, public, 10),
+            (other_coinbase, other_public, 10), // staker was eligible first, missed
+            (other_coinbase, other_public, 1), // staker was not eligible first
+            (coinbase, public, 10),
+        ];
+        for (i, (author, signer, delay)) in pos_blocks.into_iter().enumerate() {
+            let number = 2 * i as u64 + 1;
+            let mut pow = Header::new();
+            pow.set_number(number);
+            pow.set_timestamp(10 * number);
+            pow.set_seal_type(SealType::PoW);
+            pow.set_seal(vec![vec![number as u8; 32], vec![0u8; 1408]]);
+            let mut pos = Header::new();
+            pos.set_number(number + 1);
+            pos.set_timestamp(10 * number + delay);
+            pos.set_difficulty(U256::from(1));
+            pos.set_author(author);
+            pos.set_seal_type(SealType::PoS);
+            pos.set_seal(vec![vec![number as u8; 64], vec![0u8; 64], signer.to_vec()]);
+            for mut header in vec![pow, pos] {
+                header.set_parent_hash(client.chain_info().best_block_hash);
+                let mut rlp = RlpStream::new_list(2);
+                rlp.append(&header);
+                rlp.append_raw(&EMPTY_LIST_RLP, 1);
+                client.import_block(rlp.as_raw().to_vec()).unwrap();
+            }
+        }
+        assert_eq!(client.chain_info().best_block_number, 10);
+
+        let stats = StakerStats {
+            stake: U256::from(10000),
+            total_stake: U256::from(10000),
+            expected_block_time: Some(1),
+            blocks_produced: 2,
+            rewards: U256::from(26),
+            missed_opportunities: 1,
+            unchecked_blocks: 0,
+        };
+        // An unregistered staker is found by its coinbase, its key is taken from its blocks
+        assert_eq!(miner.staker_stats(&client, &coinbase, 1, 10), stats);
+        assert_eq!(
+            miner.staker_stats(&client, &public, 1, 10),
+            StakerStats {
+                stake: U256::zero(),
+                expected_block_time: None,
+                blocks_produced: 0,
+                rewards: U256::zero(),
+                missed_opportunities: 0,
+                ..stats
+            }
+        );
+
+        // A registered staker is found by its signing key
+        client
+            .coinbases
+            .write()
+            .insert(public_to_address_ed25519(&public), coinbase);
+        assert_eq!(miner.staker_stats(&client, &public, 1, 10), stats);
+        assert_eq!(
+            miner.staker_stats(&client, &public, 5, 9),
+            StakerStats {
+                blocks_produced: 0,
+                rewards: U256::zero(),
+                ..stats
+            }
+        );
+
+        // Past stakes are only looked up once
+        let stake_calls = client.stake_calls.load(AtomicOrdering::SeqCst);
+        assert_eq!(miner.staker_stats(&client, &public, 1, 10), stats);
+        assert_eq!(client.stake_calls.load(AtomicOrdering::SeqCst), stake_calls + 1);
+
+        // Blocks whose parent state is pruned are reported instead of counted as not eligible
+        *client.history.write() = Some(1);
+        let miner = miner_with_spec(&client.spec);
+        assert_eq!(
+            miner.staker_stats(&client, &public, 1, 10),
+            StakerStats {
+                missed_opportunities: 0,
+                unchecked_blocks: 2,
+                ..stats
+            }
+        );
+    }
 }
@@ -21,12 +21,21 @@
  ******************************************************************************/
 
 mod miner;
+mod internal_miner;
+#[cfg(unix)]
+mod remote_staker;
+mod staker;
 pub mod external;
 
 pub use self::miner::{Miner, MinerOptions, Banning, PendingSet};
+pub use self::internal_miner::InternalMiner;
+#[cfg(unix)]
+pub use self::remote_staker::RemoteStaker;
+pub use self::staker::{AccountStaker, StakerSigner};
 pub use transaction::local_transactions::Status as LocalTransactionStatus;
 
 use std::collections::BTreeMap;
+use std::sync::Arc;
 
 use aion_types::{H256, U256, Address};
 use acore_bytes::Bytes;
@@ -36,7 +45,6 @@ use types::error::{Error};
 use header::{BlockNumber, SealType};
 use receipt::Receipt;
 use transaction::{UnverifiedTransaction, PendingTransaction};
-use key::Ed25519KeyPair;
 
 /// Miner client API, this trait is somewhat related to multiple kinds of miner
 /// however, only one kind of miner now
@@ -48,13 +56,13 @@ pub trait MinerService: Send + Sync {
     fn author(&self) -> Address;
 
     /// Get the PoS staker that will seal PoS blocks.
-    fn staker(&self) -> &Option<Ed25519KeyPair>;
+    fn staker(&self) -> Option<Arc<StakerSigner>>;
 
     /// Set the author that we will seal blocks as.
     fn set_author(&self, author: Address);
 
-    /// Set the PoS author that will seal PoS blocks.
-    fn set_staker(&mut self, staker: Ed25519KeyPair);
+    /// Set the PoS staker that will seal PoS blocks.
+    fn set_staker(&self, staker: Arc<StakerSigner>);
 
     /// Get the extra_data that we will seal blocks with.
     fn extra_data(&self) -> Bytes;
@@ -172,6 +180,16 @@ pub trait MinerService: Send + Sync {
     // Check if the next block is on the unity hybrid seed hard fork
     fn unity_hybrid_seed_update(&self, client: &MiningBlockChainClient) -> bool;
 
+    /// Staking statistics of a staker, given by its signing public key or its coinbase, over
+    /// the blocks `from` to `to` included.
+    fn staker_stats(
+        &self,
+        client: &MiningBlockChainClient,
+        staker: &H256,
+        from: BlockNumber,
+        to: BlockNumber,
+    ) -> StakerStats;
+
     // AION 2.0
     // Check if it's allowed to produce a new block with given seal type.
     // A block's seal type must be different than its parent's seal type.
@@ -226,6 +244,28 @@ pub trait MinerService: Send + Sync {
     fn default_gas_limit(&self) -> U256;
 }
 
+/// Staking statistics of a staker
+#[derive(Debug, PartialEq)]
+pub struct StakerStats {
+    /// Effective stake of the staker at the best block
+    pub stake: U256,
+    /// Total stake of the staking contract at the best block
+    pub total_stake: U256,
+    /// Mean time in seconds for the staker to produce a PoS block, `None` without stake
+    pub expected_block_time: Option<u64>,
+    /// Number of PoS blocks produced by the staker in the block range
+    pub blocks_produced: u64,
+    /// Block rewards earned with these blocks
+    pub rewards: U256,
+    /// Number of PoS blocks of other stakers in the block range that the staker was eligible
+    /// to produce first. Only counted after the unity hybrid seed update, the seeds of older
+    /// blocks cannot be derived without the staker's key.
+    pub missed_opportunities: u64,
+    /// Number of PoS blocks of other stakers that could not be checked for missed
+    /// opportunities, because the state of their parent is not available (pruned)
+    pub unchecked_blocks: u64,
+}
+
 /// Mining status
 #[derive(Debug)]
 pub struct MinerStatus {
@@ -0,0 +1,251 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Staker signing through a remote signer.
+//!
+//! The staker key can be kept by a separate signer process, such as the `staker-signer`
+//! reference binary, listening on a Unix domain socket. Requests and responses are JSON
+//! objects, one per line. Binary data is hex encoded with a `0x` prefix:
+//!
+//! ```text
+//! > {"method":"public"}
+//! < {"result":"0x<32 bytes public key>"}
+//! > {"method":"sign_seed","number":1024,"data":"0x<64 bytes parent seed>"}
+//! < {"result":"0x<64 bytes signature>"}
+//! > {"method":"sign_seal","number":1024,"parent":"0x<32 bytes parent hash>","data":"0x<32 bytes mine hash>"}
+//! < {"error":"<reason>"}
+//! ```
+//!
+//! The signer is expected to refuse sealing a block on another parent at the same block number
+//! as a block it sealed before, or at a lower one, and likewise for seeds. Seals of other mine
+//! hashes on the same parent have to be signed, the node stakes again with a new timestamp when
+//! its block at that height was not imported.
+
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use aion_types::H256;
+use header::BlockNumber;
+use parking_lot::Mutex;
+use rcrypto::ed25519;
+use rustc_hex::{FromHex, ToHex};
+use serde_json;
+
+use super::StakerSigner;
+
+/// Time to wait for the signer to answer a request.
+const SIGNER_TIMEOUT_MS: u64 = 5000;
+
+#[derive(Serialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+enum SignerRequest {
+    Public,
+    SignSeed { number: BlockNumber, data: String },
+    SignSeal { number: BlockNumber, parent: String, data: String },
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum SignerResponse {
+    Result(String),
+    Error(String),
+}
+
+/// Staker signing through a signer listening on a local socket.
+pub struct RemoteStaker {
+    path: PathBuf,
+    public: H256,
+    /// Connection to the signer, reopened on the next request after an I/O failure.
+    connection: Mutex<Option<BufReader<UnixStream>>>,
+}
+
+impl RemoteStaker {
+    /// Connect to the signer listening on `path` and fetch the staker's public key.
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<RemoteStaker, String> {
+        let mut staker = RemoteStaker {
+            path: path.as_ref().to_path_buf(),
+            public: H256::zero(),
+            connection: Mutex::new(None),
+        };
+        let public = staker.request(&SignerRequest::Public)?;
+        if public.len() != 32 {
+            return Err(format!(
+                "Signer {} returned an invalid public key",
+                staker.path.display()
+            ));
+        }
+        staker.public = H256::from_slice(&public);
+        Ok(staker)
+    }
+
+    fn connect(&self) -> Result<BufReader<UnixStream>, String> {
+        let stream = UnixStream::connect(&self.path)
+            .map_err(|e| format!("Cannot connect to signer {}: {}", self.path.display(), e))?;
+        let timeout = Some(Duration::from_millis(SIGNER_TIMEOUT_MS));
+        stream
+            .set_read_timeout(timeout)
+            .and_then(|_| stream.set_write_timeout(timeout))
+            .map_err(|e| format!("Cannot configure signer connection: {}", e))?;
+        Ok(BufReader::new(stream))
+    }
+
+    fn exchange(
+        connection: &mut BufReader<UnixStream>,
+        request: &SignerRequest,
+    ) -> Result<SignerResponse, String>
+    {
+        let mut line = serde_json::to_string(request).map_err(|e| e.to_string())?;
+        line.push('\n');
+        connection
+            .get_mut()
+            .write_all(line.as_bytes())
+            .map_err(|e| format!("Signer write failed: {}", e))?;
+        line.clear();
+        match connection.read_line(&mut line) {
+            Ok(0) => Err("Signer closed the connection".into()),
+            Ok(_) => {
+                serde_json::from_str(&line).map_err(|e| format!("Invalid signer response: {}", e))
+            }
+            Err(e) => Err(format!("Signer read failed: {}", e)),
+        }
+    }
+
+    fn request(&self, request: &SignerRequest) -> Result<Vec<u8>, String> {
+        let mut connection = self.connection.lock();
+        if connection.is_none() {
+            *connection = Some(self.connect()?);
+        }
+        let response = {
+            let stream = connection.as_mut().expect("connection opened above; qed");
+            Self::exchange(stream, request)
+        };
+        match response {
+            Ok(SignerResponse::Result(result)) => {
+                let result = result.trim_left_matches("0x");
+                result
+                    .from_hex()
+                    .map_err(|e| format!("Invalid signer response: {}", e))
+            }
+            Ok(SignerResponse::Error(error)) => Err(format!("Signer refused: {}", error)),
+            Err(error) => {
+                // The stream may be left in the middle of a response, start over next time
+                *connection = None;
+                Err(error)
+            }
+        }
+    }
+
+    fn sign(&self, data: &[u8], request: SignerRequest) -> Result<[u8; 64], String> {
+        let signature = self.request(&request)?;
+        if signature.len() != 64 || !ed25519::verify(data, &self.public, &signature) {
+            return Err("Signer returned an invalid signature".into());
+        }
+        let mut result = [0u8; 64];
+        result.copy_from_slice(&signature);
+        Ok(result)
+    }
+}
+
+impl StakerSigner for RemoteStaker {
+    fn public(&self) -> H256 { self.public }
+
+    fn sign_seed(&self, number: BlockNumber, seed: &[u8]) -> Result<[u8; 64], String> {
+        let request = SignerRequest::SignSeed {
+            number,
+            data: format!("0x{}", seed.to_hex()),
+        };
+        self.sign(seed, request)
+    }
+
+    fn sign_seal(
+        &self,
+        number: BlockNumber,
+        parent: &H256,
+        mine_hash: &H256,
+    ) -> Result<[u8; 64], String>
+    {
+        let request = SignerRequest::SignSeal {
+            number,
+            parent: format!("0x{}", parent.to_hex()),
+            data: format!("0x{}", mine_hash.to_hex()),
+        };
+        self.sign(mine_hash, request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixListener;
+    use std::thread;
+
+    use aion_types::H256;
+    use key::{generate_keypair, sign_data_ed25519};
+    use rustc_hex::{FromHex, ToHex};
+    use serde_json::{self, Value};
+    use tempdir::TempDir;
+    use super::{RemoteStaker, StakerSigner};
+
+    #[test]
+    fn remote_staker_signs_through_socket() {
+        let dir = TempDir::new("remote_staker").unwrap();
+        let path = dir.path().join("signer.ipc");
+        let listener = UnixListener::bind(&path).unwrap();
+        let kp = generate_keypair();
+        let public = *kp.public();
+        let secret = kp.secret().clone();
+
+        // Signs seeds at #1 only and refuses everything else
+        let signer = thread::spawn(move || {
+            let stream = listener.accept().unwrap().0;
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut writer = stream;
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 0 {
+                let request: Value = serde_json::from_str(&line).unwrap();
+                let response = match (request["method"].as_str(), request["number"].as_u64()) {
+                    (Some("public"), _) => format!(r#"{{"result":"0x{}"}}"#, public.to_hex()),
+                    (Some("sign_seed"), Some(1)) => {
+                        let data: Vec<u8> = request["data"].as_str().unwrap()[2..]
+                            .from_hex()
+                            .unwrap();
+                        let signature = sign_data_ed25519(&secret, &data).unwrap();
+                        format!(r#"{{"result":"0x{}"}}"#, signature[32..96].to_hex())
+                    }
+                    _ => r#"{"error":"refused"}"#.to_string(),
+                };
+                writer.write_all(format!("{}\n", response).as_bytes()).unwrap();
+                line.clear();
+            }
+        });
+
+        let staker = RemoteStaker::new(&path).unwrap();
+        assert_eq!(staker.public(), public);
+        assert!(staker.sign_seed(1, &[3u8; 64]).is_ok());
+        assert!(staker.sign_seed(2, &[3u8; 64]).is_err());
+        assert!(staker.sign_seal(1, &H256::from(1), &H256::from(7)).is_err());
+
+        drop(staker);
+        signer.join().unwrap();
+    }
+}
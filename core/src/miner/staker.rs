@@ -0,0 +1,223 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Signing for the internal staker.
+//!
+//! The miner never holds the staker's secret key. PoS seeds and seals are signed through a
+//! `StakerSigner`, either an account of the local keystore with its password from a password
+//! file or a remote signer reached over a local socket (see `RemoteStaker`).
+
+use std::ptr;
+use std::sync::Arc;
+
+use account_provider::AccountProvider;
+use aion_types::{Address, H256};
+use header::BlockNumber;
+use parking_lot::Mutex;
+
+/// Signs the PoS seeds and seals produced by the internal staker.
+///
+/// Both requests carry the number of the block being produced and seals carry its parent, so
+/// that a signer can refuse to sign conflicting data at the same height.
+pub trait StakerSigner: Send + Sync {
+    /// Ed25519 public key of the staker.
+    fn public(&self) -> H256;
+
+    /// Sign the parent `seed`, giving the seed of the new PoS block `number`.
+    fn sign_seed(&self, number: BlockNumber, seed: &[u8]) -> Result<[u8; 64], String>;
+
+    /// Sign the mine hash of the new PoS block `number` on top of `parent`.
+    fn sign_seal(
+        &self,
+        number: BlockNumber,
+        parent: &H256,
+        mine_hash: &H256,
+    ) -> Result<[u8; 64], String>;
+}
+
+/// Password kept in memory, overwritten with zeros when dropped.
+struct Password(String);
+
+impl Drop for Password {
+    fn drop(&mut self) {
+        unsafe {
+            for byte in self.0.as_bytes_mut() {
+                ptr::write_volatile(byte, 0);
+            }
+        }
+    }
+}
+
+/// Staker signing with an `AccountProvider` account.
+///
+/// The account is not unlocked in the provider, which would let RPC callers sign with it too.
+/// The staker keeps the password and passes it on each signature instead.
+///
+/// Like the remote signer, the staker refuses another seed, or a seal on another parent, at the
+/// same or a lower block number than the last one it signed. The record is kept in memory only
+/// and starts over when the node restarts.
+pub struct AccountStaker {
+    accounts: Arc<AccountProvider>,
+    address: Address,
+    password: Password,
+    public: H256,
+    /// Last signed seed with its block number and signature. Each signature decrypts the key
+    /// file and the seed signature is requested on every staking attempt, so it is reused while
+    /// the parent block stays the same.
+    last_seed: Mutex<Option<(BlockNumber, Vec<u8>, [u8; 64])>>,
+    /// Block number and parent of the last signed seal.
+    last_seal: Mutex<Option<(BlockNumber, H256)>>,
+}
+
+impl AccountStaker {
+    /// Create a staker signing with `address`, whose key file is decrypted with `password`.
+    pub fn new(
+        accounts: Arc<AccountProvider>,
+        address: Address,
+        password: String,
+    ) -> Result<AccountStaker, String>
+    {
+        let public = accounts
+            .account_public(address, &password)
+            .map_err(|e| format!("Cannot use staker account {}: {}", address, e))?;
+        Ok(AccountStaker {
+            accounts,
+            address,
+            password: Password(password),
+            public,
+            last_seed: Mutex::new(None),
+            last_seal: Mutex::new(None),
+        })
+    }
+
+    fn sign(&self, data: &[u8]) -> Result<[u8; 64], String> {
+        let signature = self
+            .accounts
+            .sign_data_with_password(self.address, &self.password.0, data)
+            .map_err(|e| format!("Staker account {} failed to sign: {}", self.address, e))?;
+        let mut result = [0u8; 64];
+        result.copy_from_slice(&signature[32..96]);
+        Ok(result)
+    }
+}
+
+impl StakerSigner for AccountStaker {
+    fn public(&self) -> H256 { self.public }
+
+    fn sign_seed(&self, number: BlockNumber, seed: &[u8]) -> Result<[u8; 64], String> {
+        let mut last_seed = self.last_seed.lock();
+        if let Some((last_number, ref signed, signature)) = *last_seed {
+            if number == last_number && signed.as_slice() == seed {
+                return Ok(signature);
+            }
+            if number <= last_number {
+                return Err(format!(
+                    "Staker already signed another seed at #{}, refusing #{}",
+                    last_number, number
+                ));
+            }
+        }
+        let signature = self.sign(seed)?;
+        *last_seed = Some((number, seed.to_vec(), signature));
+        Ok(signature)
+    }
+
+    fn sign_seal(
+        &self,
+        number: BlockNumber,
+        parent: &H256,
+        mine_hash: &H256,
+    ) -> Result<[u8; 64], String>
+    {
+        let mut last_seal = self.last_seal.lock();
+        if let Some((last_number, last_parent)) = *last_seal {
+            // another mine hash on the same parent is a new attempt with another timestamp
+            let same_parent = number == last_number && *parent == last_parent;
+            if !same_parent && number <= last_number {
+                return Err(format!(
+                    "Staker already sealed a block on another parent at #{}, refusing #{}",
+                    last_number, number
+                ));
+            }
+        }
+        let signature = self.sign(&mine_hash.0)?;
+        *last_seal = Some((number, *parent));
+        Ok(signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use account_provider::AccountProvider;
+    use aion_types::H256;
+    use key::generate_keypair;
+    use rcrypto::ed25519;
+    use super::{AccountStaker, StakerSigner};
+
+    #[test]
+    fn account_staker_signs_seed() {
+        let kp = generate_keypair();
+        let accounts = Arc::new(AccountProvider::transient_provider());
+        accounts
+            .insert_account_ed25519(kp.secret().clone(), "test")
+            .unwrap();
+        assert!(AccountStaker::new(accounts.clone(), kp.address(), "wrong".into()).is_err());
+
+        let staker = AccountStaker::new(accounts.clone(), kp.address(), "test".into()).unwrap();
+        assert_eq!(staker.public(), *kp.public());
+        // the account stays locked for everyone else
+        assert!(!accounts.is_unlocked_generic(&kp.address()));
+
+        let seed = [3u8; 64];
+        let signature = staker.sign_seed(1, &seed).unwrap();
+        assert!(ed25519::verify(&seed, kp.public(), &signature));
+        assert_eq!(staker.sign_seed(1, &seed).unwrap()[..], signature[..]);
+
+        let mine_hash = H256::from(7);
+        let signature = staker.sign_seal(1, &H256::from(1), &mine_hash).unwrap();
+        assert!(ed25519::verify(&mine_hash, kp.public(), &signature));
+    }
+
+    #[test]
+    fn account_staker_refuses_double_signing() {
+        let kp = generate_keypair();
+        let accounts = Arc::new(AccountProvider::transient_provider());
+        accounts
+            .insert_account_ed25519(kp.secret().clone(), "test")
+            .unwrap();
+        let staker = AccountStaker::new(accounts, kp.address(), "test".into()).unwrap();
+
+        assert!(staker.sign_seed(10, &[1u8; 64]).is_ok());
+        assert!(staker.sign_seed(10, &[1u8; 64]).is_ok());
+        assert!(staker.sign_seed(10, &[2u8; 64]).is_err());
+        assert!(staker.sign_seed(9, &[3u8; 64]).is_err());
+
+        // staking again on the same parent gives another mine hash
+        assert!(staker.sign_seal(10, &H256::from(1), &H256::from(4)).is_ok());
+        assert!(staker.sign_seal(10, &H256::from(1), &H256::from(5)).is_ok());
+        assert!(staker.sign_seal(10, &H256::from(2), &H256::from(6)).is_err());
+        assert!(staker.sign_seal(9, &H256::from(3), &H256::from(6)).is_err());
+
+        assert!(staker.sign_seed(11, &[2u8; 64]).is_ok());
+        assert!(staker.sign_seal(11, &H256::from(2), &H256::from(6)).is_ok());
+    }
+}
@@ -22,6 +22,7 @@
 
 //! Creates and registers client and network services.
 
+use std::fs;
 use std::time::{Instant, Duration};
 use std::path::Path;
 use std::sync::Arc;
@@ -33,10 +34,12 @@ use tokio::prelude::{Future, Stream};
 use acore_bytes::Bytes;
 use client::{ChainNotify, Client, ClientConfig};
 use db;
+use light::{HeaderChain, LightClient};
 use types::error::*;
 use io::*;
 use kvdb::KeyValueDB;
 use kvdb::{DatabaseConfig, RepositoryConfig, DbRepository, DBTransaction, Error as DbError};
+use kvdb::migration::Manager;
 use miner::Miner;
 use spec::Spec;
 use aion_types::{H256};
@@ -105,6 +108,23 @@ pub fn run_staker(executor: TaskExecutor, client: Arc<Client>) -> oneshot::Sende
     close
 }
 
+/// Run the sealing loop of an internally sealing engine (e.g. instant seal)
+pub fn run_instant_seal(executor: TaskExecutor, client: Arc<Client>) -> oneshot::Sender<()> {
+    let (close, shutdown_signal) = oneshot::channel();
+    let seal_block_task = Interval::new(Instant::now(), Duration::from_millis(100))
+        .for_each(move |_| {
+            let client: Arc<Client> = client.clone();
+            client.miner().try_seal_internally(&*client);
+            Ok(())
+        })
+        .map_err(|e| panic!("interval err: {:?}", e))
+        .select(shutdown_signal.map_err(|_| {}))
+        .map(|_| ())
+        .map_err(|_| ());
+    executor.spawn(seal_block_task);
+    close
+}
+
 /// Run the transaction pool
 pub fn run_transaction_pool(executor: TaskExecutor, client: Arc<Client>) -> oneshot::Sender<()> {
     let (close, shutdown_signal) = oneshot::channel();
@@ -159,7 +179,12 @@ impl ClientService {
                 db_path: db_path.to_string_lossy().into(),
             });
         }
-        let dbs = DbRepository::init(db_configs)?;
+        let dbs = ClientService::open_dbs(
+            &db::migration_manager(),
+            db_configs,
+            client_path,
+            config.db_backup,
+        )?;
         let dbs = Arc::new(dbs);
 
         // correct dbs
@@ -201,6 +226,62 @@ impl ClientService {
     /// Get a handle to the database.
     pub fn db(&self) -> Arc<KeyValueDB> { self.database.clone() }
 
+    /// Open the dbs and migrate them with `manager`. With `backup` set, the dbs are copied
+    /// before migrating and the copy is restored if the migration fails.
+    fn open_dbs(
+        manager: &Manager,
+        db_configs: Vec<RepositoryConfig>,
+        client_path: &Path,
+        backup: bool,
+    ) -> Result<DbRepository, Error>
+    {
+        let dbs = DbRepository::init(db_configs.clone())?;
+        if !manager.is_needed(&dbs)? {
+            // records the schema version of new dbs
+            manager.execute(&dbs)?;
+            return Ok(dbs);
+        }
+
+        let backup_path = client_path.with_extension("backup");
+        let dbs = match backup {
+            true => {
+                drop(dbs);
+                info!(target: "migration", "Backing up database to {}", backup_path.display());
+                if backup_path.exists() {
+                    fs::remove_dir_all(&backup_path)?;
+                }
+                copy_dir(client_path, &backup_path)?;
+                DbRepository::init(db_configs)?
+            }
+            false => dbs,
+        };
+
+        match manager.execute(&dbs) {
+            Ok(_) => {
+                if backup {
+                    info!(
+                        target: "migration",
+                        "Database backup kept at {}",
+                        backup_path.display()
+                    );
+                }
+                Ok(dbs)
+            }
+            Err(e) => {
+                if backup {
+                    drop(dbs);
+                    warn!(
+                        target: "migration",
+                        "Migration failed: {}, restoring database backup",
+                        e
+                    );
+                    restore_backup(client_path, &backup_path)?;
+                }
+                Err(e.into())
+            }
+        }
+    }
+
     fn check_db(
         dbs: Arc<KeyValueDB>,
         best_block_number: u64,
@@ -301,6 +382,93 @@ impl ClientService {
     }
 }
 
+/// Light client service setup. Opens the header chain dbs and creates the light client.
+pub struct LightClientService {
+    client: Arc<LightClient>,
+    database: Arc<DbRepository>,
+}
+
+impl LightClientService {
+    /// Start the `LightClientService`.
+    pub fn start(
+        config: ClientConfig,
+        spec: &Spec,
+        client_path: &Path,
+    ) -> Result<LightClientService, Error>
+    {
+        info!(
+            target:"run",
+            "     network: {} (light)",
+            spec.name,
+        );
+
+        let mut db_config = DatabaseConfig::default();
+        db_config.wal = config.db_wal;
+        let mut db_configs = Vec::new();
+        for db_name in vec![db::COL_HEADERS, db::COL_EXTRA] {
+            let db_path = client_path.join(db_name);
+            db_config.compact_options = config.db_compaction.compaction_profile(&db_path);
+            db_configs.push(RepositoryConfig {
+                db_name: db_name.into(),
+                db_config: db_config.clone(),
+                db_path: db_path.to_string_lossy().into(),
+            });
+        }
+        let dbs = Arc::new(DbRepository::init(db_configs)?);
+
+        let chain = HeaderChain::new(dbs.clone(), &spec.genesis_header(), spec.engine.clone())?;
+        let client = Arc::new(LightClient::new(chain, spec.engine.clone()));
+
+        Ok(LightClientService {
+            client,
+            database: dbs,
+        })
+    }
+
+    /// Get light client interface
+    pub fn client(&self) -> Arc<LightClient> { self.client.clone() }
+
+    /// Get a handle to the database.
+    pub fn db(&self) -> Arc<KeyValueDB> { self.database.clone() }
+}
+
+/// Replace the directory `path` with `backup`. The directory is moved aside and only deleted
+/// once the backup is in place, or moved back if the backup cannot be.
+fn restore_backup(path: &Path, backup: &Path) -> ::std::io::Result<()> {
+    let aside = path.with_extension("failed");
+    if aside.exists() {
+        fs::remove_dir_all(&aside)?;
+    }
+    fs::rename(path, &aside)?;
+    if let Err(e) = fs::rename(backup, path) {
+        if let Err(e) = fs::rename(&aside, path) {
+            error!(
+                target: "migration",
+                "Cannot move the database back from {}: {}",
+                aside.display(),
+                e
+            );
+        }
+        return Err(e);
+    }
+    fs::remove_dir_all(&aside)
+}
+
+/// Recursively copy the directory `from` to `to`.
+fn copy_dir(from: &Path, to: &Path) -> ::std::io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
 /// IO interface for the Client handler
 struct ClientIoHandler {
     client: Arc<Client>,
@@ -344,6 +512,64 @@ mod tests {
     use super::*;
     use blockchain::{BlockChain,BlockProvider};
     use helpers::{new_db,generate_dummy_blockchain_with_db};
+    use kvdb::migration::{Config, Migration};
+    use kvdb::Result as DbResult;
+    use tempdir::TempDir;
+
+    struct Failing;
+
+    impl Migration for Failing {
+        fn version(&self) -> u32 { 2 }
+
+        fn description(&self) -> &'static str { "always fails" }
+
+        fn db_name(&self) -> &'static str { db::COL_HEADERS }
+
+        fn migrate(&self, _key: &[u8], _value: &[u8], _batch: &mut DBTransaction) -> DbResult<()> {
+            Err(DbError::Migration("broken".into()))
+        }
+    }
+
+    #[test]
+    fn test_failed_migration_restores_backup() {
+        let tempdir = TempDir::new("").unwrap();
+        let client_path = tempdir.path().join("db");
+        let db_configs: Vec<RepositoryConfig> = db::DB_NAMES
+            .iter()
+            .map(|db_name| {
+                RepositoryConfig {
+                    db_name: db_name.to_string(),
+                    db_config: DatabaseConfig::default(),
+                    db_path: client_path.join(db_name).to_str().unwrap().to_string(),
+                }
+            })
+            .collect();
+        {
+            let dbs = DbRepository::init(db_configs.clone()).unwrap();
+            let mut batch = DBTransaction::new();
+            batch.put(db::COL_HEADERS, b"key", b"value");
+            dbs.write(batch).unwrap();
+        }
+
+        let mut manager = Manager::new(Config {
+            meta_db: db::COL_NODE_INFO,
+            base_version: 1,
+            batch_size: 10,
+        });
+        manager.add_migration(Failing).unwrap();
+        assert!(ClientService::open_dbs(&manager, db_configs.clone(), &client_path, true).is_err());
+
+        // the backup is back in place and nothing is left aside
+        assert!(!client_path.with_extension("backup").exists());
+        assert!(!client_path.with_extension("failed").exists());
+        let dbs = DbRepository::init(db_configs).unwrap();
+        assert_eq!(manager.version(&dbs).unwrap(), 1);
+        assert_eq!(
+            &*dbs.get(db::COL_HEADERS, b"key").unwrap().unwrap(),
+            &b"value"[..]
+        );
+    }
+
     #[test]
     fn test_correct_db() {
         let db = new_db();
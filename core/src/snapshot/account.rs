@@ -0,0 +1,252 @@
+/*******************************************************************************
+ * Copyright (c) 2015-2018 Parity Technologies (UK) Ltd.
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Account state encoding and decoding.
+//!
+//! An account entry is the list
+//! `[address_hash, account, code, transformed_code, storage, object_graph]`
+//! where `account` is the compressed account rlp, `storage` is the list of raw
+//! `[key, value]` pairs of the storage trie and `object_graph` is empty for
+//! FastVM accounts and `[storage_root, graph_hash, graph]` for AVM accounts,
+//! whose account rlp holds the delta root in place of the storage root.
+
+use acore_bytes::Bytes;
+use aion_types::H256;
+use blake2b::{blake2b, BLAKE2B_EMPTY, BLAKE2B_NULL_RLP};
+use db::{AccountDB, AccountDBMut};
+use kvdb::{DBTransaction, DBValue, HashStore, KeyValueDB};
+use rlp::{RlpStream, UntrustedRlp};
+use rlp_compress::{compress, decompress, snapshot_swapper};
+use state::BasicAccount;
+use trie::{Trie, TrieDB, TrieDBMut, TrieMut};
+
+use super::Error;
+
+/// Encode the account stored under `address_hash`, together with its code,
+/// storage and object graph.
+pub fn to_fat_rlp(
+    db: &HashStore,
+    graph_db: &KeyValueDB,
+    address_hash: &H256,
+    account_rlp: &[u8],
+) -> Result<Bytes, Error>
+{
+    let account: BasicAccount = UntrustedRlp::new(account_rlp).as_val()?;
+    let account_db = AccountDB::from_hash(db, address_hash.clone());
+
+    // avm accounts keep the delta root in the account rlp.
+    let avm_roots = match graph_db.get(::db::COL_AVM_GRAPH, &account.storage_root)? {
+        Some(roots) => {
+            let rlp = UntrustedRlp::new(&roots);
+            let storage_root: H256 = rlp.val_at(0)?;
+            let graph_hash: H256 = rlp.val_at(1)?;
+            let graph = graph_db
+                .get(::db::COL_AVM_GRAPH, &graph_hash)?
+                .map_or_else(Vec::new, |g| g.into_vec());
+            Some((storage_root, graph_hash, graph))
+        }
+        None => None,
+    };
+    let storage_root = avm_roots
+        .as_ref()
+        .map_or(account.storage_root.clone(), |r| r.0.clone());
+
+    let code = match account.code_hash == BLAKE2B_EMPTY {
+        true => Vec::new(),
+        false => {
+            account_db
+                .get(&account.code_hash)
+                .ok_or_else(|| Error::MissingCode(account.code_hash.clone()))?
+                .into_vec()
+        }
+    };
+    let transformed_code = account_db
+        .get(&blake2b(address_hash))
+        .map_or_else(Vec::new, |c| c.into_vec());
+
+    let storage_trie = TrieDB::new(&account_db, &storage_root)?;
+    let mut storage = Vec::new();
+    for item in storage_trie.iter()? {
+        let (key, value) = item?;
+        storage.push((key, value.into_vec()));
+    }
+
+    let mut stream = RlpStream::new_list(6);
+    stream.append(address_hash);
+    stream.append(&compress(account_rlp, snapshot_swapper()).into_vec());
+    stream.append(&code);
+    stream.append(&transformed_code);
+    stream.begin_list(storage.len());
+    for (key, value) in storage {
+        stream.begin_list(2).append(&key).append(&value);
+    }
+    match avm_roots {
+        Some((storage_root, graph_hash, graph)) => {
+            stream
+                .begin_list(3)
+                .append(&storage_root)
+                .append(&graph_hash)
+                .append(&graph);
+        }
+        None => {
+            stream.begin_list(0);
+        }
+    }
+    Ok(stream.out())
+}
+
+/// Decode an account entry, writing its code and storage into `db` and its
+/// object graph into `graph_batch`. Returns the address hash and account rlp
+/// to be inserted into the state trie.
+pub fn from_fat_rlp(
+    db: &mut HashStore,
+    graph_batch: &mut DBTransaction,
+    rlp: UntrustedRlp,
+) -> Result<(H256, Bytes), Error>
+{
+    let address_hash: H256 = rlp.val_at(0)?;
+    let compressed: Bytes = rlp.val_at(1)?;
+    let account_rlp = decompress(&compressed, snapshot_swapper()).into_vec();
+    let account: BasicAccount = UntrustedRlp::new(&account_rlp).as_val()?;
+    let code: Bytes = rlp.val_at(2)?;
+    let transformed_code: Bytes = rlp.val_at(3)?;
+    let graph = rlp.at(5)?;
+
+    let storage_root = match graph.item_count()? {
+        0 => account.storage_root.clone(),
+        _ => {
+            let storage_root: H256 = graph.val_at(0)?;
+            let graph_hash: H256 = graph.val_at(1)?;
+            let graph_data: Bytes = graph.val_at(2)?;
+
+            let mut concatenated_root = Vec::with_capacity(64);
+            concatenated_root.extend_from_slice(&storage_root[..]);
+            concatenated_root.extend_from_slice(&graph_hash[..]);
+            if blake2b(&concatenated_root) != account.storage_root {
+                return Err(Error::StorageRootMismatch(address_hash));
+            }
+
+            let mut roots = RlpStream::new_list(2);
+            roots.append(&storage_root).append(&graph_hash);
+            graph_batch.put(::db::COL_AVM_GRAPH, &account.storage_root, &roots.out());
+            graph_batch.put(::db::COL_AVM_GRAPH, &graph_hash, &graph_data);
+            storage_root
+        }
+    };
+
+    let mut account_db = AccountDBMut::from_hash(db, address_hash.clone());
+
+    if code.is_empty() {
+        if account.code_hash != BLAKE2B_EMPTY {
+            return Err(Error::MissingCode(account.code_hash));
+        }
+    } else {
+        if blake2b(&code) != account.code_hash {
+            return Err(Error::MissingCode(account.code_hash));
+        }
+        account_db.emplace(account.code_hash.clone(), DBValue::from_slice(&code));
+    }
+    if !transformed_code.is_empty() {
+        account_db.emplace(blake2b(&address_hash), DBValue::from_slice(&transformed_code));
+    }
+
+    let mut root = BLAKE2B_NULL_RLP;
+    {
+        let mut storage_trie = TrieDBMut::new(&mut account_db, &mut root);
+        for pair in rlp.at(4)?.iter() {
+            let key: Bytes = pair.val_at(0)?;
+            let value: Bytes = pair.val_at(1)?;
+            storage_trie.insert(&key, &value)?;
+        }
+    }
+    if root != storage_root {
+        return Err(Error::StorageRootMismatch(address_hash));
+    }
+
+    Ok((address_hash, account_rlp))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use aion_types::{H256, U256};
+    use blake2b::{blake2b, BLAKE2B_EMPTY, BLAKE2B_NULL_RLP};
+    use db::AccountDBMut;
+    use kvdb::{DBTransaction, HashStore, KeyValueDB, MemoryDB};
+    use rlp::{self, UntrustedRlp};
+    use state::BasicAccount;
+    use tests::common::helpers::new_db;
+    use trie::{TrieDBMut, TrieMut};
+
+    use super::{from_fat_rlp, to_fat_rlp};
+
+    fn roundtrip(db: &MemoryDB, graph_db: &Arc<KeyValueDB>, address_hash: &H256, account: &BasicAccount) {
+        let account_rlp = rlp::encode(account).into_vec();
+        let fat_rlp = to_fat_rlp(db, &**graph_db, address_hash, &account_rlp).unwrap();
+
+        let mut restored = MemoryDB::new();
+        let mut batch = DBTransaction::new();
+        let (hash, restored_rlp) =
+            from_fat_rlp(&mut restored, &mut batch, UntrustedRlp::new(&fat_rlp)).unwrap();
+        assert_eq!(&hash, address_hash);
+        assert_eq!(restored_rlp, account_rlp);
+        assert_eq!(restored.keys(), db.keys());
+    }
+
+    #[test]
+    fn encoding_basic() {
+        let address_hash = blake2b(b"basic");
+        let account = BasicAccount {
+            nonce: 50.into(),
+            balance: 123456789.into(),
+            storage_root: BLAKE2B_NULL_RLP,
+            code_hash: BLAKE2B_EMPTY,
+        };
+        roundtrip(&MemoryDB::new(), &new_db(), &address_hash, &account);
+    }
+
+    #[test]
+    fn encoding_code_and_storage() {
+        let address_hash = blake2b(b"contract");
+        let code = vec![0x60u8, 0x00, 0x60, 0x00, 0xf3];
+        let mut db = MemoryDB::new();
+        let mut root = BLAKE2B_NULL_RLP;
+        let code_hash = {
+            let mut account_db = AccountDBMut::from_hash(&mut db, address_hash.clone());
+            {
+                let mut trie = TrieDBMut::new(&mut account_db, &mut root);
+                trie.insert(&blake2b(b"key1"), &[0x01]).unwrap();
+                trie.insert(&blake2b(b"key2"), &[0x02, 0x03]).unwrap();
+            }
+            account_db.insert(&code)
+        };
+
+        let account = BasicAccount {
+            nonce: 1.into(),
+            balance: U256::zero(),
+            storage_root: root,
+            code_hash: code_hash,
+        };
+        roundtrip(&db, &new_db(), &address_hash, &account);
+    }
+}
@@ -0,0 +1,171 @@
+/*******************************************************************************
+ * Copyright (c) 2015-2018 Parity Technologies (UK) Ltd.
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Snapshot-related errors.
+
+use std::fmt;
+use std::io;
+
+use aion_types::{H256, U256};
+use client::BlockId;
+use kvdb;
+use rlp::DecoderError;
+use trie::TrieError;
+use util_error::UtilError;
+
+/// Snapshot-related errors.
+#[derive(Debug)]
+pub enum Error {
+    /// Requested block id doesn't resolve to a known block.
+    UnknownBlock(BlockId),
+    /// Requested block is not in the chain.
+    BlockNotFound(H256),
+    /// State of the requested block has been pruned.
+    StatePruned(u64),
+    /// Restoration target already contains blocks beyond genesis.
+    DatabaseNotEmpty(u64),
+    /// Snapshot was written in an unsupported format version.
+    UnsupportedVersion(u64),
+    /// File is not a packed snapshot.
+    InvalidFormat(String),
+    /// Chunk listed in the manifest is missing from the snapshot.
+    MissingChunk(H256),
+    /// Chunk contents don't hash to the value listed in the manifest.
+    ChunkHashMismatch(H256, H256),
+    /// Code of an account could not be found.
+    MissingCode(H256),
+    /// Restored storage of an account doesn't match its storage root.
+    StorageRootMismatch(H256),
+    /// Restored state doesn't match the manifest state root: (expected, found).
+    StateRootMismatch(H256, H256),
+    /// Restored chain head doesn't match the manifest: (expected, found).
+    BestBlockMismatch(H256, H256),
+    /// Block doesn't extend the restored chain.
+    BrokenChain(H256),
+    /// Total difficulty of a block chunk doesn't match the restored chain: (expected, found).
+    TotalDifficultyMismatch(U256, U256),
+    /// Block fails verification.
+    InvalidBlock(H256, String),
+    /// Trie error.
+    Trie(TrieError),
+    /// Decoder error.
+    Decoder(DecoderError),
+    /// Database error.
+    Database(kvdb::Error),
+    /// Util error.
+    Util(UtilError),
+    /// Standard io error.
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::UnknownBlock(ref id) => write!(f, "Unknown block {:?}", id),
+            Error::BlockNotFound(ref hash) => write!(f, "Block {:x} not found in the chain", hash),
+            Error::StatePruned(number) => {
+                write!(f, "State of block #{} has been pruned, try an archive node", number)
+            }
+            Error::DatabaseNotEmpty(number) => {
+                write!(
+                    f,
+                    "Database already contains blocks up to #{}, restore requires a fresh database",
+                    number
+                )
+            }
+            Error::UnsupportedVersion(version) => {
+                write!(f, "Unsupported snapshot version {}", version)
+            }
+            Error::InvalidFormat(ref msg) => write!(f, "Invalid snapshot file: {}", msg),
+            Error::MissingChunk(ref hash) => write!(f, "Missing snapshot chunk {:x}", hash),
+            Error::ChunkHashMismatch(ref expected, ref found) => {
+                write!(
+                    f,
+                    "Snapshot chunk hash mismatch: expected {:x}, found {:x}",
+                    expected, found
+                )
+            }
+            Error::MissingCode(ref hash) => write!(f, "Missing code {:x}", hash),
+            Error::StorageRootMismatch(ref address_hash) => {
+                write!(f, "Storage root mismatch for account {:x}", address_hash)
+            }
+            Error::StateRootMismatch(ref expected, ref found) => {
+                write!(
+                    f,
+                    "State root mismatch: expected {:x}, found {:x}",
+                    expected, found
+                )
+            }
+            Error::BestBlockMismatch(ref expected, ref found) => {
+                write!(
+                    f,
+                    "Best block mismatch: expected {:x}, found {:x}",
+                    expected, found
+                )
+            }
+            Error::BrokenChain(ref hash) => {
+                write!(f, "Block {:x} does not extend the restored chain", hash)
+            }
+            Error::TotalDifficultyMismatch(ref expected, ref found) => {
+                write!(
+                    f,
+                    "Total difficulty mismatch: expected {}, found {}",
+                    expected, found
+                )
+            }
+            Error::InvalidBlock(ref hash, ref err) => {
+                write!(f, "Invalid block {:x}: {}", hash, err)
+            }
+            Error::Trie(ref err) => write!(f, "{}", err),
+            Error::Decoder(ref err) => write!(f, "{}", err),
+            Error::Database(ref err) => write!(f, "Database error: {}", err),
+            Error::Util(ref err) => write!(f, "{}", err),
+            Error::Io(ref err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl From<TrieError> for Error {
+    fn from(err: TrieError) -> Self { Error::Trie(err) }
+}
+
+impl From<DecoderError> for Error {
+    fn from(err: DecoderError) -> Self { Error::Decoder(err) }
+}
+
+impl From<kvdb::Error> for Error {
+    fn from(err: kvdb::Error) -> Self { Error::Database(err) }
+}
+
+impl From<UtilError> for Error {
+    fn from(err: UtilError) -> Self { Error::Util(err) }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self { Error::Io(err) }
+}
+
+impl<E> From<Box<E>> for Error
+where Error: From<E>
+{
+    fn from(err: Box<E>) -> Self { Error::from(*err) }
+}
@@ -0,0 +1,313 @@
+/*******************************************************************************
+ * Copyright (c) 2015-2018 Parity Technologies (UK) Ltd.
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Snapshot i/o.
+//!
+//! A packed snapshot is a single file: all chunks back to back, followed by
+//! the manifest (with the offset and length of every chunk) and finally the
+//! offset of the manifest as an 8-byte little-endian integer.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use acore_bytes::Bytes;
+use aion_types::H256;
+use byteorder::{ByteOrder, LittleEndian};
+use rlp::{RlpStream, UntrustedRlp};
+
+use super::{Error, ManifestData};
+
+/// Something which can write snapshots.
+pub trait SnapshotWriter {
+    /// Write a compressed state chunk.
+    fn write_state_chunk(&mut self, hash: H256, chunk: &[u8]) -> io::Result<()>;
+
+    /// Write a compressed block chunk.
+    fn write_block_chunk(&mut self, hash: H256, chunk: &[u8]) -> io::Result<()>;
+
+    /// Complete writing. The manifest's chunk lists must match the chunks written.
+    fn finish(self, manifest: ManifestData) -> io::Result<()>
+    where Self: Sized;
+}
+
+/// Something which can read snapshots.
+pub trait SnapshotReader {
+    /// Get the manifest data for this snapshot.
+    fn manifest(&self) -> &ManifestData;
+
+    /// Get raw chunk data by hash.
+    fn chunk(&self, hash: H256) -> Result<Bytes, Error>;
+}
+
+// (hash, length, offset) of a chunk within a packed snapshot.
+#[derive(Debug, Clone, PartialEq)]
+struct ChunkInfo(H256, u64, u64);
+
+impl ChunkInfo {
+    fn rlp_append(&self, s: &mut RlpStream) {
+        s.begin_list(3);
+        s.append(&self.0).append(&self.1).append(&self.2);
+    }
+
+    fn decode(rlp: UntrustedRlp) -> Result<Self, Error> {
+        Ok(ChunkInfo(rlp.val_at(0)?, rlp.val_at(1)?, rlp.val_at(2)?))
+    }
+}
+
+/// A packed snapshot writer.
+pub struct PackedWriter {
+    file: File,
+    state_chunks: Vec<ChunkInfo>,
+    block_chunks: Vec<ChunkInfo>,
+    cur_len: u64,
+}
+
+impl PackedWriter {
+    /// Create a new "PackedWriter", to write into the file at the given path.
+    pub fn new(path: &Path) -> io::Result<Self> {
+        Ok(PackedWriter {
+            file: File::create(path)?,
+            state_chunks: Vec::new(),
+            block_chunks: Vec::new(),
+            cur_len: 0,
+        })
+    }
+
+    fn write_chunk(&mut self, hash: H256, chunk: &[u8]) -> io::Result<ChunkInfo> {
+        self.file.write_all(chunk)?;
+        let info = ChunkInfo(hash, chunk.len() as u64, self.cur_len);
+        self.cur_len += chunk.len() as u64;
+        Ok(info)
+    }
+}
+
+impl SnapshotWriter for PackedWriter {
+    fn write_state_chunk(&mut self, hash: H256, chunk: &[u8]) -> io::Result<()> {
+        let info = self.write_chunk(hash, chunk)?;
+        self.state_chunks.push(info);
+        Ok(())
+    }
+
+    fn write_block_chunk(&mut self, hash: H256, chunk: &[u8]) -> io::Result<()> {
+        let info = self.write_chunk(hash, chunk)?;
+        self.block_chunks.push(info);
+        Ok(())
+    }
+
+    fn finish(mut self, manifest: ManifestData) -> io::Result<()> {
+        let written_state: Vec<_> = self.state_chunks.iter().map(|c| c.0).collect();
+        let written_blocks: Vec<_> = self.block_chunks.iter().map(|c| c.0).collect();
+        if written_state != manifest.state_hashes || written_blocks != manifest.block_hashes {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "manifest does not match the written chunks",
+            ));
+        }
+
+        let mut stream = RlpStream::new_list(6);
+        stream.append(&manifest.version);
+        stream.begin_list(self.state_chunks.len());
+        for info in &self.state_chunks {
+            info.rlp_append(&mut stream);
+        }
+        stream.begin_list(self.block_chunks.len());
+        for info in &self.block_chunks {
+            info.rlp_append(&mut stream);
+        }
+        stream.append(&manifest.state_root);
+        stream.append(&manifest.block_number);
+        stream.append(&manifest.block_hash);
+
+        let mut offset = [0u8; 8];
+        LittleEndian::write_u64(&mut offset, self.cur_len);
+
+        self.file.write_all(&stream.out())?;
+        self.file.write_all(&offset)?;
+        self.file.flush()
+    }
+}
+
+/// A packed snapshot reader.
+pub struct PackedReader {
+    file: File,
+    chunks: HashMap<H256, (u64, u64)>,
+    manifest: ManifestData,
+}
+
+impl PackedReader {
+    /// Create a new `PackedReader` for the file at the given path.
+    pub fn new(path: &Path) -> Result<Self, Error> {
+        let mut file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        if file_len < 8 {
+            return Err(Error::InvalidFormat("file too short".into()));
+        }
+
+        file.seek(SeekFrom::End(-8))?;
+        let mut offset = [0u8; 8];
+        file.read_exact(&mut offset)?;
+        let manifest_off = LittleEndian::read_u64(&offset);
+        if manifest_off > file_len - 8 {
+            return Err(Error::InvalidFormat("manifest offset out of bounds".into()));
+        }
+
+        let manifest_len = file_len - 8 - manifest_off;
+        let mut manifest_buf = vec![0u8; manifest_len as usize];
+        file.seek(SeekFrom::Start(manifest_off))?;
+        file.read_exact(&mut manifest_buf)?;
+
+        let rlp = UntrustedRlp::new(&manifest_buf);
+        let state_chunks = rlp
+            .at(1)?
+            .iter()
+            .map(ChunkInfo::decode)
+            .collect::<Result<Vec<_>, _>>()?;
+        let block_chunks = rlp
+            .at(2)?
+            .iter()
+            .map(ChunkInfo::decode)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let manifest = ManifestData {
+            version: rlp.val_at(0)?,
+            state_hashes: state_chunks.iter().map(|c| c.0).collect(),
+            block_hashes: block_chunks.iter().map(|c| c.0).collect(),
+            state_root: rlp.val_at(3)?,
+            block_number: rlp.val_at(4)?,
+            block_hash: rlp.val_at(5)?,
+        };
+
+        let mut chunks = HashMap::new();
+        for info in state_chunks.into_iter().chain(block_chunks) {
+            if info.2 + info.1 > manifest_off {
+                return Err(Error::InvalidFormat("chunk out of bounds".into()));
+            }
+            chunks.insert(info.0, (info.1, info.2));
+        }
+
+        Ok(PackedReader {
+            file,
+            chunks,
+            manifest,
+        })
+    }
+}
+
+impl SnapshotReader for PackedReader {
+    fn manifest(&self) -> &ManifestData { &self.manifest }
+
+    fn chunk(&self, hash: H256) -> Result<Bytes, Error> {
+        let &(len, off) = self.chunks.get(&hash).ok_or(Error::MissingChunk(hash))?;
+        let mut file = &self.file;
+        let mut buf = vec![0u8; len as usize];
+        file.seek(SeekFrom::Start(off))?;
+        file.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempdir::TempDir;
+    use blake2b::blake2b;
+
+    use super::{SnapshotWriter, SnapshotReader, PackedWriter, PackedReader};
+    use snapshot::{ManifestData, SNAPSHOT_VERSION};
+
+    const STATE_CHUNKS: &'static [&'static [u8]] = &[b"dogs", b"cats", b"hello world", b"hi", b"notarealchunk"];
+    const BLOCK_CHUNKS: &'static [&'static [u8]] = &[b"hello!", b"goodbye!", b"abcdefg", b"hijklmnop", b"qrstuvwxy", b"and", b"z"];
+
+    #[test]
+    fn packed_write_and_read() {
+        let tempdir = TempDir::new("").unwrap();
+        let path = tempdir.path().join("packed");
+        let mut writer = PackedWriter::new(&path).unwrap();
+
+        let mut state_hashes = Vec::new();
+        let mut block_hashes = Vec::new();
+
+        for chunk in STATE_CHUNKS {
+            let hash = blake2b(chunk);
+            state_hashes.push(hash.clone());
+            writer.write_state_chunk(hash, chunk).unwrap();
+        }
+
+        for chunk in BLOCK_CHUNKS {
+            let hash = blake2b(chunk);
+            block_hashes.push(hash.clone());
+            writer.write_block_chunk(blake2b(chunk), chunk).unwrap();
+        }
+
+        let manifest = ManifestData {
+            version: SNAPSHOT_VERSION,
+            state_hashes: state_hashes,
+            block_hashes: block_hashes,
+            state_root: blake2b(b"notarealroot"),
+            block_number: 12345678987654321,
+            block_hash: blake2b(b"notarealblock"),
+        };
+
+        writer.finish(manifest.clone()).unwrap();
+
+        let reader = PackedReader::new(&path).unwrap();
+        assert_eq!(reader.manifest(), &manifest);
+
+        for hash in manifest
+            .state_hashes
+            .iter()
+            .chain(&manifest.block_hashes)
+        {
+            let chunk = reader.chunk(hash.clone()).unwrap();
+            assert_eq!(&blake2b(&chunk), hash);
+        }
+    }
+
+    #[test]
+    fn rejects_mismatched_manifest() {
+        let tempdir = TempDir::new("").unwrap();
+        let path = tempdir.path().join("packed");
+        let mut writer = PackedWriter::new(&path).unwrap();
+        writer
+            .write_state_chunk(blake2b(STATE_CHUNKS[0]), STATE_CHUNKS[0])
+            .unwrap();
+
+        let manifest = ManifestData {
+            version: SNAPSHOT_VERSION,
+            state_hashes: vec![],
+            block_hashes: vec![],
+            state_root: Default::default(),
+            block_number: 0,
+            block_hash: Default::default(),
+        };
+        assert!(writer.finish(manifest).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_file() {
+        let tempdir = TempDir::new("").unwrap();
+        let path = tempdir.path().join("packed");
+        ::std::fs::write(&path, b"abc").unwrap();
+        assert!(PackedReader::new(&path).is_err());
+    }
+}
@@ -0,0 +1,470 @@
+/*******************************************************************************
+ * Copyright (c) 2015-2018 Parity Technologies (UK) Ltd.
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Snapshot creation and restoration.
+//!
+//! A snapshot holds the full state at some block together with the blocks and
+//! receipts from genesis up to it, split into hash-addressed chunks which are
+//! listed by a manifest. Restoring a snapshot into a fresh database lets a node
+//! start syncing from the snapshot block without executing the blocks before it.
+//! The restored blocks must link up to the genesis of the chain and pass the
+//! engine's basic checks, and the snapshot block must commit to the restored
+//! state.
+
+use acore_bytes::Bytes;
+use aion_types::{H256, U256};
+use blake2b::{blake2b, BLAKE2B_NULL_RLP};
+use blockchain::{BlockChain, BlockProvider};
+use engine::Engine;
+use header::Header;
+use journaldb::JournalDB;
+use kvdb::{DBTransaction, HashStore, KeyValueDB};
+use receipt::Receipt;
+use rlp::{RlpStream, UntrustedRlp};
+use rlp_compress::{compress, decompress, blocks_swapper};
+use trie::{Trie, TrieDB, TrieDBMut, TrieMut};
+use verification;
+
+pub mod io;
+mod account;
+mod error;
+
+pub use self::error::Error;
+pub use self::io::{SnapshotReader, SnapshotWriter, PackedReader, PackedWriter};
+
+/// Current snapshot format version.
+pub const SNAPSHOT_VERSION: u64 = 1;
+
+/// Preferred size of a chunk, in bytes. Accounts are never split, so a chunk
+/// holding an account with a large storage may exceed it.
+pub const PREFERRED_CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+/// Manifest data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestData {
+    /// Snapshot format version.
+    pub version: u64,
+    /// List of state chunk hashes.
+    pub state_hashes: Vec<H256>,
+    /// List of block chunk hashes.
+    pub block_hashes: Vec<H256>,
+    /// The final, expected state root.
+    pub state_root: H256,
+    /// Block number this snapshot was taken at.
+    pub block_number: u64,
+    /// Block hash this snapshot was taken at.
+    pub block_hash: H256,
+}
+
+/// Take a snapshot of the state at `block_hash` along with the blocks before it.
+/// The state must be available in `state_db`.
+pub fn take_snapshot<W: SnapshotWriter>(
+    chain: &BlockChain,
+    block_hash: H256,
+    state_db: &HashStore,
+    graph_db: &KeyValueDB,
+    mut writer: W,
+) -> Result<(), Error>
+{
+    let header = chain
+        .block_header(&block_hash)
+        .ok_or_else(|| Error::BlockNotFound(block_hash.clone()))?;
+    let state_root = header.state_root().clone();
+
+    info!(target: "snapshot", "Taking snapshot at #{} ({:x})", header.number(), block_hash);
+
+    let state_hashes = chunk_state(state_db, graph_db, &state_root, &mut writer)?;
+    let block_hashes = chunk_blocks(chain, block_hash.clone(), &mut writer)?;
+
+    info!(
+        target: "snapshot",
+        "Wrote {} state chunks and {} block chunks",
+        state_hashes.len(),
+        block_hashes.len()
+    );
+
+    writer.finish(ManifestData {
+        version: SNAPSHOT_VERSION,
+        state_hashes,
+        block_hashes,
+        state_root,
+        block_number: header.number(),
+        block_hash,
+    })?;
+
+    Ok(())
+}
+
+/// Restore a snapshot into a fresh database. `db` is the state journal of the
+/// chain database.
+pub fn restore<R: SnapshotReader>(
+    chain: &BlockChain,
+    engine: &Engine,
+    db: Box<JournalDB>,
+    reader: &R,
+) -> Result<(), Error>
+{
+    let manifest = reader.manifest().clone();
+    if manifest.version != SNAPSHOT_VERSION {
+        return Err(Error::UnsupportedVersion(manifest.version));
+    }
+
+    info!(
+        target: "snapshot",
+        "Restoring snapshot of #{} ({:x}): {} state chunks, {} block chunks",
+        manifest.block_number,
+        manifest.block_hash,
+        manifest.state_hashes.len(),
+        manifest.block_hashes.len()
+    );
+
+    let backing = db.backing().clone();
+    let mut rebuilder = StateRebuilder::new(db);
+    for (i, hash) in manifest.state_hashes.iter().enumerate() {
+        rebuilder.feed(&read_chunk(reader, hash)?)?;
+        debug!(target: "snapshot", "Restored state chunk {}/{}", i + 1, manifest.state_hashes.len());
+    }
+    if rebuilder.state_root() != manifest.state_root {
+        return Err(Error::StateRootMismatch(
+            manifest.state_root,
+            rebuilder.state_root(),
+        ));
+    }
+    rebuilder.finalize(manifest.block_number, &manifest.block_hash)?;
+
+    let mut blocks = BlockRebuilder::new(chain, engine, &*backing, &manifest);
+    for (i, hash) in manifest.block_hashes.iter().enumerate() {
+        blocks.feed(&read_chunk(reader, hash)?)?;
+        debug!(target: "snapshot", "Restored block chunk {}/{}", i + 1, manifest.block_hashes.len());
+    }
+    blocks.finalize()?;
+
+    info!(target: "snapshot", "Snapshot restored, best block #{}", chain.best_block_number());
+    Ok(())
+}
+
+fn read_chunk<R: SnapshotReader>(reader: &R, hash: &H256) -> Result<Bytes, Error> {
+    let chunk = reader.chunk(hash.clone())?;
+    let found = blake2b(&chunk);
+    if &found != hash {
+        return Err(Error::ChunkHashMismatch(hash.clone(), found));
+    }
+    Ok(chunk)
+}
+
+// Collects rlp items into chunks of roughly `PREFERRED_CHUNK_SIZE`.
+struct Chunker {
+    items: Vec<Bytes>,
+    size: usize,
+}
+
+impl Chunker {
+    fn new() -> Self {
+        Chunker {
+            items: Vec::new(),
+            size: 0,
+        }
+    }
+
+    fn push(&mut self, item: Bytes) {
+        self.size += item.len();
+        self.items.push(item);
+    }
+
+    fn is_full(&self) -> bool { self.size >= PREFERRED_CHUNK_SIZE }
+
+    fn is_empty(&self) -> bool { self.items.is_empty() }
+
+    fn take(&mut self, header: Option<&U256>) -> (H256, Bytes) {
+        let mut stream = RlpStream::new();
+        match header {
+            Some(parent_td) => {
+                stream.begin_list(2).append(parent_td);
+                stream.begin_list(self.items.len());
+            }
+            None => {
+                stream.begin_list(self.items.len());
+            }
+        }
+        for item in self.items.drain(..) {
+            stream.append_raw(&item, 1);
+        }
+        self.size = 0;
+
+        let chunk = stream.out();
+        (blake2b(&chunk), chunk)
+    }
+}
+
+/// Write the state trie under `root` into state chunks.
+/// Returns the hashes of the written chunks.
+pub fn chunk_state<W: SnapshotWriter + ?Sized>(
+    db: &HashStore,
+    graph_db: &KeyValueDB,
+    root: &H256,
+    writer: &mut W,
+) -> Result<Vec<H256>, Error>
+{
+    let account_trie = TrieDB::new(db, root)?;
+    let mut chunker = Chunker::new();
+    let mut hashes = Vec::new();
+
+    for item in account_trie.iter()? {
+        let (account_key, account_data) = item?;
+        let address_hash = H256::from_slice(&account_key);
+        chunker.push(account::to_fat_rlp(db, graph_db, &address_hash, &account_data)?);
+
+        if chunker.is_full() {
+            let (hash, chunk) = chunker.take(None);
+            writer.write_state_chunk(hash.clone(), &chunk)?;
+            hashes.push(hash);
+        }
+    }
+    if !chunker.is_empty() || hashes.is_empty() {
+        let (hash, chunk) = chunker.take(None);
+        writer.write_state_chunk(hash.clone(), &chunk)?;
+        hashes.push(hash);
+    }
+
+    Ok(hashes)
+}
+
+/// Write the blocks from genesis, excluded, to `block_hash`, oldest first, into
+/// block chunks. Each chunk starts with the total difficulty of the parent of
+/// its first block. Returns the hashes of the written chunks.
+pub fn chunk_blocks<W: SnapshotWriter + ?Sized>(
+    chain: &BlockChain,
+    block_hash: H256,
+    writer: &mut W,
+) -> Result<Vec<H256>, Error>
+{
+    let mut blocks = Vec::new();
+    let mut current = block_hash;
+    loop {
+        let details = chain
+            .block_details(&current)
+            .ok_or_else(|| Error::BlockNotFound(current.clone()))?;
+        if details.number == 0 {
+            break;
+        }
+        blocks.push(current);
+        current = details.parent;
+    }
+    blocks.reverse();
+
+    let mut chunker = Chunker::new();
+    let mut hashes = Vec::new();
+    let mut parent_td = None;
+
+    for hash in blocks {
+        if parent_td.is_none() {
+            let parent = chain
+                .block_details(&hash)
+                .and_then(|d| chain.block_details(&d.parent))
+                .ok_or_else(|| Error::BlockNotFound(hash.clone()))?;
+            parent_td = Some(parent.total_difficulty);
+        }
+
+        let block = chain
+            .block(&hash)
+            .ok_or_else(|| Error::BlockNotFound(hash.clone()))?;
+        let receipts = chain
+            .block_receipts(&hash)
+            .ok_or_else(|| Error::BlockNotFound(hash.clone()))?;
+
+        let mut entry = RlpStream::new_list(2);
+        entry.append(&compress(&block.into_inner(), blocks_swapper()).into_vec());
+        entry.append_list(&receipts.receipts);
+        chunker.push(entry.out());
+
+        if chunker.is_full() {
+            let (chunk_hash, chunk) = chunker.take(parent_td.as_ref());
+            writer.write_block_chunk(chunk_hash.clone(), &chunk)?;
+            hashes.push(chunk_hash);
+            parent_td = None;
+        }
+    }
+    if !chunker.is_empty() {
+        let (chunk_hash, chunk) = chunker.take(parent_td.as_ref());
+        writer.write_block_chunk(chunk_hash.clone(), &chunk)?;
+        hashes.push(chunk_hash);
+    }
+
+    Ok(hashes)
+}
+
+/// Rebuilds the state trie from state chunks, fed in manifest order.
+pub struct StateRebuilder {
+    db: Box<JournalDB>,
+    state_root: H256,
+}
+
+impl StateRebuilder {
+    /// Create a new state rebuilder writing into the given database.
+    pub fn new(db: Box<JournalDB>) -> Self {
+        StateRebuilder {
+            db,
+            state_root: BLAKE2B_NULL_RLP,
+        }
+    }
+
+    /// Feed a state chunk, committing the accounts it contains to the database.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<(), Error> {
+        let rlp = UntrustedRlp::new(chunk);
+        let mut batch = DBTransaction::new();
+        let mut accounts = Vec::with_capacity(rlp.item_count()?);
+
+        for entry in rlp.iter() {
+            accounts.push(account::from_fat_rlp(
+                self.db.as_hashstore_mut(),
+                &mut batch,
+                entry,
+            )?);
+        }
+
+        {
+            let mut account_trie = match self.state_root == BLAKE2B_NULL_RLP {
+                true => TrieDBMut::new(self.db.as_hashstore_mut(), &mut self.state_root),
+                false => TrieDBMut::from_existing(self.db.as_hashstore_mut(), &mut self.state_root)?,
+            };
+            for (address_hash, account_rlp) in accounts {
+                account_trie.insert(&address_hash, &account_rlp)?;
+            }
+        }
+
+        self.db.inject(&mut batch)?;
+        self.db.backing().write(batch)?;
+        Ok(())
+    }
+
+    /// Journal the restored state under the snapshot block. The state root must
+    /// have been checked beforehand.
+    pub fn finalize(mut self, era: u64, id: &H256) -> Result<(), Error> {
+        let mut batch = DBTransaction::new();
+        self.db.journal_under(&mut batch, era, id)?;
+        self.db.backing().write(batch)?;
+        Ok(())
+    }
+
+    /// Get the state root of the rebuilder.
+    pub fn state_root(&self) -> H256 { self.state_root.clone() }
+}
+
+/// Rebuilds the chain from block chunks, fed in manifest order, on top of the
+/// best block of the chain, which is the genesis of a fresh database.
+pub struct BlockRebuilder<'a> {
+    chain: &'a BlockChain,
+    engine: &'a Engine,
+    db: &'a KeyValueDB,
+    best_hash: H256,
+    state_root: H256,
+    // hash, number and total difficulty of the last restored block
+    last: (H256, u64, U256),
+}
+
+impl<'a> BlockRebuilder<'a> {
+    /// Create a new block rebuilder restoring the chain up to the manifest block.
+    pub fn new(
+        chain: &'a BlockChain,
+        engine: &'a Engine,
+        db: &'a KeyValueDB,
+        manifest: &ManifestData,
+    ) -> Self
+    {
+        BlockRebuilder {
+            chain,
+            engine,
+            db,
+            best_hash: manifest.block_hash.clone(),
+            state_root: manifest.state_root.clone(),
+            last: (
+                chain.best_block_hash(),
+                chain.best_block_number(),
+                chain.best_block_total_difficulty(),
+            ),
+        }
+    }
+
+    /// Feed a block chunk, inserting its blocks into the chain once they are
+    /// checked to extend the last restored block.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<(), Error> {
+        let rlp = UntrustedRlp::new(chunk);
+        let parent_td: U256 = rlp.val_at(0)?;
+        if parent_td != self.last.2 {
+            return Err(Error::TotalDifficultyMismatch(self.last.2, parent_td));
+        }
+
+        for entry in rlp.at(1)?.iter() {
+            let compressed: Bytes = entry.val_at(0)?;
+            let block = decompress(&compressed, blocks_swapper()).into_vec();
+            let receipts: Vec<Receipt> = entry.list_at(1)?;
+
+            let header: Header = UntrustedRlp::new(&block).val_at(0)?;
+            let hash = header.hash();
+            let (last_hash, last_number, last_td) = self.last.clone();
+            if last_hash == self.best_hash
+                || header.parent_hash() != &last_hash
+                || header.number() != last_number + 1
+            {
+                return Err(Error::BrokenChain(hash));
+            }
+            verification::verify_block_basic(&header, &block, self.engine)
+                .and_then(|_| self.engine.verify_block_unordered(&header))
+                .map_err(|e| Error::InvalidBlock(hash.clone(), e.to_string()))?;
+            let is_best = hash == self.best_hash;
+            if is_best {
+                self.check_state_root(&header)?;
+            }
+
+            let mut batch = DBTransaction::new();
+            self.chain.insert_snapshot_block(&mut batch, &block, receipts, is_best);
+            self.db.write(batch)?;
+            self.chain.commit();
+
+            self.last = (hash, header.number(), last_td + *header.difficulty());
+        }
+
+        Ok(())
+    }
+
+    /// Check that the chain was restored up to the manifest block.
+    pub fn finalize(self) -> Result<(), Error> {
+        if self.last.0 != self.best_hash {
+            return Err(Error::BestBlockMismatch(self.best_hash, self.last.0));
+        }
+        let header = self
+            .chain
+            .block_header(&self.best_hash)
+            .ok_or_else(|| Error::BlockNotFound(self.best_hash.clone()))?;
+        self.check_state_root(&header)
+    }
+
+    fn check_state_root(&self, header: &Header) -> Result<(), Error> {
+        if header.state_root() != &self.state_root {
+            return Err(Error::StateRootMismatch(
+                header.state_root().clone(),
+                self.state_root.clone(),
+            ));
+        }
+        Ok(())
+    }
+}
@@ -36,7 +36,7 @@ use parking_lot::RwLock;
 use rlp::{Rlp, RlpStream};
 use types::BlockNumber;
 use vms::{ActionParams, ActionValue, CallType, EnvInfo, ParamsType};
-use engine::{Engine, UnityEngine};
+use engine::{Engine, InstantSeal, UnityEngine};
 use types::error::Error;
 use executor::fvm_exec::Executive;
 use factory::Factories;
@@ -75,6 +75,20 @@ pub struct CommonParams {
     pub unity_hybrid_seed_update: Option<BlockNumber>,
 }
 
+impl CommonParams {
+    /// Block numbers of the hard forks configured for the chain.
+    pub fn fork_blocks(&self) -> Vec<BlockNumber> {
+        vec![
+            self.monetary_policy_update,
+            self.unity_update,
+            self.unity_hybrid_seed_update,
+        ]
+        .into_iter()
+        .filter_map(|number| number)
+        .collect()
+    }
+}
+
 impl From<ajson::spec::Params> for CommonParams {
     fn from(p: ajson::spec::Params) -> Self {
         let data_size = p.maximum_extra_data_size.into();
@@ -299,6 +313,10 @@ impl Spec {
         }
     }
 
+    /// Create a new development chain spec, blocks are sealed instantly and the address whose
+    /// secret is blake2b('') is prefunded.
+    pub fn new_dev() -> Spec { load_bundled!("dev") }
+
     /// Create a new Foundation Mainnet chain spec.
     pub fn new_foundation() -> Spec {
         Spec::load(include_bytes!("../../../resources/mainnet.json") as &[u8]).unwrap()
@@ -339,6 +357,9 @@ impl Spec {
                     panic!("NullEngine Should not be used in normal builds");
                 }
             }
+            ajson::spec::Engine::InstantSeal(instant_seal) => {
+                Arc::new(InstantSeal::new(instant_seal.params.into(), machine))
+            }
         }
     }
 
@@ -768,15 +768,19 @@ impl VMAccount for AionVMAccount {
         let mut recorder = Recorder::new();
 
         let trie = TrieDB::new(db, &self.storage_root)?;
-        let item: U256 = {
+        let item: Bytes = {
             let query = (&mut recorder, ::rlp::decode);
-            trie.get_with(&storage_key, query)?
-                .unwrap_or_else(U256::zero)
+            trie.get_with(&storage_key, query)?.unwrap_or_else(Vec::new)
         };
 
+        // values are stored as raw bytes of up to 32 bytes, left-pad them into the word.
+        let mut value = H256::zero();
+        let len = ::std::cmp::min(item.len(), 32);
+        value[32 - len..].copy_from_slice(&item[item.len() - len..]);
+
         Ok((
             recorder.drain().into_iter().map(|r| r.data).collect(),
-            item.into(),
+            value,
         ))
     }
 }
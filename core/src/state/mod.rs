@@ -49,7 +49,7 @@ use vms::EnvInfo;
 
 use aion_types::{Address, H256, U256};
 use acore_bytes::Bytes;
-use kvdb::{KeyValueDB};
+use kvdb::{DBValue, HashStore, KeyValueDB, MockDbRepository};
 
 use trie;
 use trie::recorder::Recorder;
@@ -86,6 +86,90 @@ pub struct ApplyOutcome {
 /// Result type for the execution ("application") of a transaction.
 pub type ApplyResult = Result<ApplyOutcome, Error>;
 
+/// Return type of proof validity check.
+#[derive(Debug, Clone)]
+pub enum ProvedExecution {
+    /// Proof wasn't enough to complete execution.
+    BadProof,
+    /// The transaction failed, but not due to a bad proof.
+    Failed(ExecutionError),
+    /// The transaction successfully completed with the given proof.
+    Complete(Executed),
+}
+
+/// Check the given proof of execution against a state root.
+///
+/// Only fastvm transactions can be proven: avm storage lives outside of the state trie.
+pub fn check_proof(
+    proof: &[DBValue],
+    root: H256,
+    transaction: &SignedTransaction,
+    machine: &Machine,
+    env_info: &EnvInfo,
+) -> ProvedExecution
+{
+    let backend = self::backend::ProofCheck::new(proof);
+    // proof items are keyed by their plain hashes.
+    let mut factories = Factories::default();
+    factories.accountdb = ::db::Factory::Plain;
+
+    let res = State::from_existing(
+        backend,
+        root,
+        machine.account_start_nonce(env_info.number),
+        factories,
+        Arc::new(MockDbRepository::init(vec![String::new()])),
+    );
+
+    let mut state = match res {
+        Ok(state) => state,
+        Err(_) => return ProvedExecution::BadProof,
+    };
+
+    match FvmExecutor::new(&mut state, env_info, machine).transact_virtual(transaction, false) {
+        Ok(executed) => ProvedExecution::Complete(executed),
+        Err(ExecutionError::Internal(_)) => ProvedExecution::BadProof,
+        Err(e) => ProvedExecution::Failed(e),
+    }
+}
+
+/// Prove a fastvm transaction on the given state.
+/// Returns `None` when the transaction could not be proved,
+/// and a proof otherwise.
+pub fn prove_transaction(
+    db: &mut HashStore,
+    root: H256,
+    transaction: &SignedTransaction,
+    machine: &Machine,
+    env_info: &EnvInfo,
+    factories: Factories,
+    kvdb: Arc<KeyValueDB>,
+) -> Option<(Bytes, Vec<DBValue>)>
+{
+    let backend = self::backend::Proving::new(db);
+    let res = State::from_existing(
+        backend,
+        root,
+        machine.account_start_nonce(env_info.number),
+        factories,
+        kvdb,
+    );
+
+    let mut state = match res {
+        Ok(state) => state,
+        Err(_) => return None,
+    };
+
+    match FvmExecutor::new(&mut state, env_info, machine).transact_virtual(transaction, false) {
+        Err(ExecutionError::Internal(_)) => None,
+        Err(e) => {
+            trace!(target: "state", "Proved call failed: {}", e);
+            Some((Vec::new(), state.drop().1.extract_proof()))
+        }
+        Ok(res) => Some((res.output, state.drop().1.extract_proof())),
+    }
+}
+
 /// Representation of the entire state of all accounts in the system.
 ///
 /// `State` can work together with `StateDB` to share account cache.
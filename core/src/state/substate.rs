@@ -24,6 +24,7 @@
 use std::collections::HashSet;
 use aion_types::{U256, Address};
 use log_entry::LogEntry;
+use types::trace::CallTrace;
 use super::CleanupMode;
 
 /// State changes which should be applied in finalize,
@@ -46,12 +47,26 @@ pub struct Substate {
 
     /// Created contracts.
     pub contracts_created: Vec<Address>,
+
+    /// Whether calls made within this substate should be traced.
+    pub tracing: bool,
+
+    /// Traces of the calls made so far, if tracing is enabled.
+    pub traces: Vec<CallTrace>,
 }
 
 impl Substate {
     /// Creates new substate.
     pub fn new() -> Self { Substate::default() }
 
+    /// Creates new substate, tracing calls if `tracing` is set.
+    pub fn with_tracing(tracing: bool) -> Self {
+        Substate {
+            tracing,
+            ..Substate::default()
+        }
+    }
+
     /// Merge secondary substate `s` into self, accruing each element correspondingly.
     pub fn accrue(&mut self, s: Substate) {
         self.suicides.extend(s.suicides);
@@ -59,6 +74,7 @@ impl Substate {
         self.logs.extend(s.logs);
         self.sstore_clears_count = self.sstore_clears_count + s.sstore_clears_count;
         self.contracts_created.extend(s.contracts_created);
+        self.traces.extend(s.traces);
     }
 
     /// Get the cleanup mode object from this.
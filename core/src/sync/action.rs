@@ -31,6 +31,17 @@ pub enum Action {
     BODIESRES = 5,
     BROADCASTTX = 6,
     BROADCASTBLOCK = 7,
+    ACCOUNTPROOFREQ = 8,
+    ACCOUNTPROOFRES = 9,
+    STORAGEPROOFREQ = 10,
+    STORAGEPROOFRES = 11,
+    RECEIPTSREQ = 12,
+    RECEIPTSRES = 13,
+    EXECUTIONPROOFREQ = 14,
+    EXECUTIONPROOFRES = 15,
+    COMPACTBLOCK = 16,
+    BLOCKTXSREQ = 17,
+    BLOCKTXSRES = 18,
     UNKNOWN = 0xFF,
 }
 
@@ -45,6 +56,17 @@ impl Action {
             Action::BODIESRES => 5 as u8,
             Action::BROADCASTTX => 6 as u8,
             Action::BROADCASTBLOCK => 7 as u8,
+            Action::ACCOUNTPROOFREQ => 8 as u8,
+            Action::ACCOUNTPROOFRES => 9 as u8,
+            Action::STORAGEPROOFREQ => 10 as u8,
+            Action::STORAGEPROOFRES => 11 as u8,
+            Action::RECEIPTSREQ => 12 as u8,
+            Action::RECEIPTSRES => 13 as u8,
+            Action::EXECUTIONPROOFREQ => 14 as u8,
+            Action::EXECUTIONPROOFRES => 15 as u8,
+            Action::COMPACTBLOCK => 16 as u8,
+            Action::BLOCKTXSREQ => 17 as u8,
+            Action::BLOCKTXSRES => 18 as u8,
             Action::UNKNOWN => 0xFF as u8,
         }
     }
@@ -59,6 +81,17 @@ impl Action {
             5 => Action::BODIESRES,
             6 => Action::BROADCASTTX,
             7 => Action::BROADCASTBLOCK,
+            8 => Action::ACCOUNTPROOFREQ,
+            9 => Action::ACCOUNTPROOFRES,
+            10 => Action::STORAGEPROOFREQ,
+            11 => Action::STORAGEPROOFRES,
+            12 => Action::RECEIPTSREQ,
+            13 => Action::RECEIPTSRES,
+            14 => Action::EXECUTIONPROOFREQ,
+            15 => Action::EXECUTIONPROOFRES,
+            16 => Action::COMPACTBLOCK,
+            17 => Action::BLOCKTXSREQ,
+            18 => Action::BLOCKTXSRES,
             _ => Action::UNKNOWN,
         }
     }
@@ -0,0 +1,237 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Fork identifier
+//!
+//! Nodes announce a fork id in status: a checksum of the genesis hash and the fork block numbers
+//! already passed, plus the number of the next fork they know about. A peer is kept only if both
+//! fork lists can describe the same chain, otherwise it would feed headers failing verification.
+
+use std::fmt;
+
+use aion_types::H256;
+use blake2b::blake2b;
+use byteorder::{BigEndian, ByteOrder};
+
+pub const FORK_HASH_LENGTH: usize = 4;
+pub const FORK_ID_LENGTH: usize = FORK_HASH_LENGTH + 8;
+
+/// fork identifier announced in status
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ForkId {
+    /// checksum of genesis hash and passed forks
+    pub hash: [u8; FORK_HASH_LENGTH],
+    /// next fork block number, 0 if none is known
+    pub next: u64,
+}
+
+impl ForkId {
+    /// encode as hash followed by big endian next
+    pub fn to_bytes(&self) -> [u8; FORK_ID_LENGTH] {
+        let mut bytes = [0u8; FORK_ID_LENGTH];
+        bytes[..FORK_HASH_LENGTH].copy_from_slice(&self.hash);
+        BigEndian::write_u64(&mut bytes[FORK_HASH_LENGTH..], self.next);
+        bytes
+    }
+
+    /// decode, none if length does not match
+    pub fn from_bytes(bytes: &[u8]) -> Option<ForkId> {
+        if bytes.len() != FORK_ID_LENGTH {
+            return None;
+        }
+        let mut hash = [0u8; FORK_HASH_LENGTH];
+        hash.copy_from_slice(&bytes[..FORK_HASH_LENGTH]);
+        Some(ForkId {
+            hash,
+            next: BigEndian::read_u64(&bytes[FORK_HASH_LENGTH..]),
+        })
+    }
+}
+
+/// reason a remote fork id is rejected
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ForkError {
+    /// remote announces a fork we have passed without applying it
+    LocalIncompatibleOrStale,
+    /// remote is behind and misses a fork we applied
+    RemoteStale,
+    /// fork lists diverge
+    Incompatible,
+}
+
+impl fmt::Display for ForkError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let msg = match *self {
+            ForkError::LocalIncompatibleOrStale => "local chain is incompatible or stale",
+            ForkError::RemoteStale => "remote chain is stale",
+            ForkError::Incompatible => "incompatible fork",
+        };
+        f.write_str(msg)
+    }
+}
+
+/// fork ids of the local chain
+#[derive(Clone, Debug)]
+pub struct ForkFilter {
+    genesis_hash: H256,
+    /// fork block numbers, ascending without genesis and duplicates
+    forks: Vec<u64>,
+    /// checksums after passing none, one, .. all forks
+    hashes: Vec<[u8; FORK_HASH_LENGTH]>,
+}
+
+impl ForkFilter {
+    /// build from genesis hash and fork block numbers
+    pub fn new(genesis_hash: H256, mut forks: Vec<u64>) -> ForkFilter {
+        forks.retain(|number| *number > 0);
+        forks.sort();
+        forks.dedup();
+
+        let mut preimage = genesis_hash.to_vec();
+        let mut hashes = vec![checksum(&preimage)];
+        for number in &forks {
+            let mut bytes = [0u8; 8];
+            BigEndian::write_u64(&mut bytes, *number);
+            preimage.extend_from_slice(&bytes);
+            hashes.push(checksum(&preimage));
+        }
+
+        ForkFilter {
+            genesis_hash,
+            forks,
+            hashes,
+        }
+    }
+
+    /// genesis hash of the local chain
+    pub fn genesis_hash(&self) -> H256 { self.genesis_hash }
+
+    /// fork id at local best block number
+    pub fn current(&self, head: u64) -> ForkId {
+        let passed = self.passed(head);
+        ForkId {
+            hash: self.hashes[passed],
+            next: self.forks.get(passed).cloned().unwrap_or(0),
+        }
+    }
+
+    /// check remote fork id against local chain at best block number
+    pub fn validate(&self, head: u64, remote: &ForkId) -> Result<(), ForkError> {
+        let passed = self.passed(head);
+        match self.hashes.iter().position(|hash| *hash == remote.hash) {
+            // same forks passed, remote must not have scheduled a fork we are already past
+            Some(index) if index == passed => {
+                if remote.next > 0 && head >= remote.next {
+                    Err(ForkError::LocalIncompatibleOrStale)
+                } else {
+                    Ok(())
+                }
+            }
+            // remote is syncing, its next fork has to be our next one from there
+            Some(index) if index < passed => {
+                if remote.next == self.forks[index] {
+                    Ok(())
+                } else {
+                    Err(ForkError::RemoteStale)
+                }
+            }
+            // remote passed forks we know about but have not reached yet
+            Some(_) => Ok(()),
+            None => Err(ForkError::Incompatible),
+        }
+    }
+
+    /// number of forks passed at block number
+    fn passed(&self, head: u64) -> usize {
+        self.forks.iter().take_while(|number| **number <= head).count()
+    }
+}
+
+fn checksum(preimage: &[u8]) -> [u8; FORK_HASH_LENGTH] {
+    let mut hash = [0u8; FORK_HASH_LENGTH];
+    hash.copy_from_slice(&blake2b(preimage)[..FORK_HASH_LENGTH]);
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter() -> ForkFilter { ForkFilter::new(H256::from(1), vec![0, 300, 100, 300]) }
+
+    #[test]
+    fn test_fork_list() {
+        let filter = filter();
+        assert_eq!(filter.forks, vec![100, 300]);
+        assert_eq!(filter.hashes.len(), 3);
+        assert_eq!(filter.current(0).next, 100);
+        assert_eq!(filter.current(99).hash, filter.hashes[0]);
+        assert_eq!(filter.current(100).hash, filter.hashes[1]);
+        assert_eq!(filter.current(100).next, 300);
+        assert_eq!(filter.current(1000), ForkId {
+            hash: filter.hashes[2],
+            next: 0,
+        });
+
+        // genesis is part of the checksum
+        let other = ForkFilter::new(H256::from(2), vec![100, 300]);
+        assert!(other.hashes[0] != filter.hashes[0]);
+    }
+
+    #[test]
+    fn test_encoding() {
+        let id = filter().current(150);
+        assert_eq!(ForkId::from_bytes(&id.to_bytes()), Some(id));
+        assert_eq!(ForkId::from_bytes(&id.to_bytes()[1..]), None);
+    }
+
+    #[test]
+    fn test_validate() {
+        let filter = filter();
+
+        // same state
+        assert_eq!(filter.validate(150, &filter.current(150)), Ok(()));
+        assert_eq!(filter.validate(1000, &filter.current(1000)), Ok(()));
+        // remote behind on the same fork list
+        assert_eq!(filter.validate(1000, &filter.current(50)), Ok(()));
+        // remote ahead on the same fork list
+        assert_eq!(filter.validate(50, &filter.current(1000)), Ok(()));
+
+        // remote does not know about fork 300 we passed
+        let stale = ForkId {
+            hash: filter.hashes[1],
+            next: 0,
+        };
+        assert_eq!(filter.validate(1000, &stale), Err(ForkError::RemoteStale));
+
+        // remote schedules a fork at 200 we passed without applying
+        let unknown = ForkId {
+            hash: filter.hashes[1],
+            next: 200,
+        };
+        assert_eq!(filter.validate(250, &unknown), Err(ForkError::LocalIncompatibleOrStale));
+        assert_eq!(filter.validate(150, &unknown), Ok(()));
+
+        // different fork list
+        let other = ForkFilter::new(H256::from(1), vec![100, 200]);
+        assert_eq!(filter.validate(1000, &other.current(1000)), Err(ForkError::Incompatible));
+    }
+}
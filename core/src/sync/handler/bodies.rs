@@ -19,16 +19,20 @@
  *
  ******************************************************************************/
 
+use std::collections::HashMap;
 use std::sync::Arc;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
+
+use parking_lot::RwLock;
 
 use block::Block;
 use client::{BlockId, BlockChainClient};
 use aion_types::H256;
 use bytes::BufMut;
 use rlp::{RlpStream, UntrustedRlp};
-use p2p::{ChannelBuffer, Mgr};
+use p2p::{Behaviour, ChannelBuffer, Mgr};
 use sync::action::Action;
+use sync::node_info::NodeInfo;
 use sync::storage::SyncStorage;
 use sync::wrappers::{HeadersWrapper, BlocksWrapper};
 use header::Header;
@@ -37,18 +41,77 @@ use engine::UnityEngine;
 use super::{channel_buffer_template,channel_buffer_template_with_version};
 
 const HASH_LEN: usize = 32;
+const BODIES_TIMEOUT: u64 = 10000;
+const HEADERS_EXPIRY: u64 = 60000;
 
-pub fn sync_bodies(p2p: Mgr, storage: Arc<SyncStorage>) {
-    // Get all downloaded headers
+pub fn sync_bodies(
+    p2p: Mgr,
+    nodes_info: Arc<RwLock<HashMap<u64, RwLock<NodeInfo>>>>,
+    storage: Arc<SyncStorage>,
+)
+{
+    let now = SystemTime::now();
     let mut headers_wrappers: Vec<HeadersWrapper> = Vec::new();
+
+    // Request bodies of timed out requests again
+    let mut headers_with_bodies_requested = storage.headers_with_bodies_requested().lock();
+    let expired: Vec<u64> = headers_with_bodies_requested
+        .iter()
+        .filter(|(_, headers_wrapper)| {
+            headers_wrapper.timestamp + Duration::from_millis(BODIES_TIMEOUT) <= now
+        })
+        .map(|(node_hash, _)| *node_hash)
+        .collect();
+    for node_hash in expired {
+        if let Some(mut headers_wrapper) = headers_with_bodies_requested.remove(&node_hash) {
+            debug!(target: "sync", "bodies request to node {} timed out", node_hash);
+            // Prefer other nodes for the retry
+            headers_wrapper.node_hash = 0;
+            headers_wrappers.push(headers_wrapper);
+        }
+    }
+    drop(headers_with_bodies_requested);
+
+    // Get all downloaded headers
     let mut downloaded_headers = storage.downloaded_headers().lock();
     while let Some(headers_wrapper) = downloaded_headers.pop_front() {
         headers_wrappers.push(headers_wrapper);
     }
     drop(downloaded_headers);
 
-    // For each batch of downloaded headers, try to get bodies from the corresponding node
+    if headers_wrappers.is_empty() {
+        return;
+    }
+
+    // Nodes able to serve bodies, fastest first
+    let mut nodes: Vec<u64> = p2p
+        .get_active_nodes()
+        .into_iter()
+        .map(|node| node.hash)
+        .collect();
+    storage.scheduler().rank(&mut nodes);
+    let mut best_numbers: HashMap<u64, u64> = HashMap::new();
+    {
+        let nodes_info_read = nodes_info.read();
+        for node_hash in &nodes {
+            if let Some(node_info_lock) = nodes_info_read.get(node_hash) {
+                best_numbers.insert(*node_hash, node_info_lock.read().best_block_number);
+            }
+        }
+    }
+
+    // For each batch of downloaded headers, try to get bodies from the node which sent the
+    // headers, or from any other idle node which has the blocks
+    let mut headers_retained: Vec<HeadersWrapper> = Vec::new();
     for headers_wrapper in headers_wrappers {
+        let last_number = match headers_wrapper.headers.last() {
+            Some(header) => header.number(),
+            None => continue,
+        };
+        if headers_wrapper.timestamp + Duration::from_millis(HEADERS_EXPIRY) <= now {
+            continue;
+        }
+
         let mut hashes: Vec<u8> = Vec::new(); // headers' hashes to request bodies
         let mut headers_requested: Vec<Header> = Vec::new(); // headers request record
         for header in &headers_wrapper.headers {
@@ -57,25 +120,40 @@ pub fn sync_bodies(p2p: Mgr, storage: Arc<SyncStorage>) {
             headers_requested.push(header.clone());
         }
 
-        if hashes.len() == 0 {
-            continue;
-        }
+        let headers_with_bodies_requested = storage.headers_with_bodies_requested().lock();
+        let source = headers_wrapper.node_hash;
+        let node_hash = ::std::iter::once(source)
+            .filter(|node_hash| best_numbers.contains_key(node_hash))
+            .chain(nodes.iter().cloned().filter(|node_hash| {
+                *node_hash != source
+                    && best_numbers.get(node_hash).map_or(false, |best| *best >= last_number)
+            }))
+            .find(|node_hash| !headers_with_bodies_requested.contains_key(node_hash));
+        drop(headers_with_bodies_requested);
 
-        let node_hash = headers_wrapper.node_hash;
-        let mut headers_with_bodies_requested = storage.headers_with_bodies_requested().lock();
-        if !headers_with_bodies_requested.contains_key(&node_hash) {
-            drop(headers_with_bodies_requested);
-            if send(p2p.clone(), node_hash.clone(), hashes) {
-                let mut headers_wrapper_record = headers_wrapper.clone();
-                headers_wrapper_record.timestamp = SystemTime::now();
-                headers_wrapper_record.headers.clear();
-                headers_wrapper_record.headers.extend(headers_requested);
-                let mut headers_with_bodies_requested =
-                    storage.headers_with_bodies_requested().lock();
-                headers_with_bodies_requested.insert(node_hash, headers_wrapper_record);
-            }
+        let sent = match node_hash {
+            Some(node_hash) => send(p2p.clone(), node_hash, hashes),
+            None => false,
+        };
+        if let (true, Some(node_hash)) = (sent, node_hash) {
+            let mut headers_wrapper_record = headers_wrapper.clone();
+            headers_wrapper_record.node_hash = node_hash;
+            headers_wrapper_record.timestamp = SystemTime::now();
+            headers_wrapper_record.headers.clear();
+            headers_wrapper_record.headers.extend(headers_requested);
+            let mut headers_with_bodies_requested =
+                storage.headers_with_bodies_requested().lock();
+            headers_with_bodies_requested.insert(node_hash, headers_wrapper_record);
+        } else {
+            // Keep the headers until a node is idle
+            headers_retained.push(headers_wrapper);
         }
     }
+
+    let mut downloaded_headers = storage.downloaded_headers().lock();
+    for headers_wrapper in headers_retained.into_iter().rev() {
+        downloaded_headers.push_front(headers_wrapper);
+    }
 }
 
 pub fn send(p2p: Mgr, hash: u64, hashes: Vec<u8>) -> bool {
@@ -184,6 +262,7 @@ pub fn receive_res(p2p: Mgr, node_hash: u64, cb_in: ChannelBuffer, storage: Arc<
         );
         // also do body validation to import right blocks
     }
+    let mut invalid = false;
     {
         // Get the lock before iteration to keep the operation atomic, so that the downloaded blocks in the batch will be consecutive
         let mut recorded_blocks_hashes = storage.recorded_blocks_hashes().lock();
@@ -204,12 +283,22 @@ pub fn receive_res(p2p: Mgr, node_hash: u64, cb_in: ChannelBuffer, storage: Arc<
                 Err(_e) => {
                     // if body does not match header, break the loop, only download the right blocks before this bad one.
                     debug!(target: "sync_res", "Incomplete block body #{} from node {}.", headers[i].number(), node_hash);
+                    p2p.report(node_hash, Behaviour::InvalidBlock);
+                    invalid = true;
                     break;
                 }
             }
         }
     }
 
+    // request bodies missing from the response again
+    if !invalid && bodies.len() < headers.len() {
+        let mut headers_wrapper = HeadersWrapper::new();
+        headers_wrapper.node_hash = node_hash;
+        headers_wrapper.headers.extend_from_slice(&headers[bodies.len()..]);
+        storage.downloaded_headers().lock().push_back(headers_wrapper);
+    }
+
     // end if no block to download
     if blocks.is_empty() {
         return;
@@ -221,9 +310,10 @@ pub fn receive_res(p2p: Mgr, node_hash: u64, cb_in: ChannelBuffer, storage: Arc<
     let mut blocks_wrapper = BlocksWrapper::new();
     blocks_wrapper.node_hash = node_hash;
     blocks_wrapper.blocks.extend(blocks);
-    storage.insert_downloaded_blocks(blocks_wrapper);
+    storage.scheduler().blocks_downloaded(blocks_wrapper);
 
     // TODO: maybe we should consider reset the header request cooldown here
 
     p2p.update_node(&node_hash);
+    p2p.report(node_hash, Behaviour::UsefulResponse);
 }
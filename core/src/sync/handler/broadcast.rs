@@ -24,23 +24,24 @@ use parking_lot::RwLock;
 use std::thread;
 use std::time::{Duration,SystemTime};
 // use std::sync::RwLock;
-use std::collections::{HashMap};
+use std::collections::{HashMap, HashSet};
 // use lru_cache::LruCache;
+use block::Block;
 use client::{BlockChainClient, BlockId, BlockImportError};
 use types::error::{BlockError, ImportError};
-use header::Header;
+use header::{Header, Seal};
 use transaction::UnverifiedTransaction;
 use aion_types::H256;
 use bytes::BufMut;
 use rlp::{RlpStream, UntrustedRlp};
-use p2p::{ChannelBuffer,Mgr};
+use p2p::{Behaviour,ChannelBuffer,Mgr};
 use sync::action::Action;
 use sync::node_info::NodeInfo;
 use sync::storage::SyncStorage;
 use engine::unity_engine::UnityEngine;
 use acore_bytes::to_hex;
 
-use super::channel_buffer_template;
+use super::{channel_buffer_template,compact,is_invalid_block};
 
 const MAX_NEW_BLOCK_AGE: u64 = 20;
 // const MAX_RE_BROADCAST: usize = 10;
@@ -89,29 +90,167 @@ pub fn broad_new_transactions(p2p: Mgr, storage: Arc<SyncStorage>) {
 }
 
 /// Broadcast new blocks
-pub fn propagate_new_blocks(p2p: Mgr, block_hashes: Vec<H256>, client: Arc<BlockChainClient>) {
+pub fn propagate_new_blocks(
+    p2p: Mgr,
+    block_hashes: Vec<H256>,
+    client: Arc<BlockChainClient>,
+    node_info: Arc<RwLock<HashMap<u64, RwLock<NodeInfo>>>>,
+)
+{
     let active_nodes = p2p.get_active_nodes();
     if active_nodes.len() > 0 {
         for block_hash in block_hashes {
-            if let Some(block_rlp) = client.block(BlockId::Hash(block_hash)) {
-                let mut req = channel_buffer_template(Action::BROADCASTBLOCK.value());
-                req.body.put_slice(&block_rlp.into_inner());
-                req.head.len = req.body.len() as u32;
-                for node in active_nodes.iter() {
-                    p2p.send(node.hash, req.clone());
-                    trace!(target: "sync_broadcast", "Sync broadcast new block sent...");
-                }
+            if let Some(block) = client.block(BlockId::Hash(block_hash)) {
+                let header = block.decode_header();
+                let transactions = block.transactions();
+                relay_block(
+                    &p2p,
+                    &node_info,
+                    &header,
+                    &transactions,
+                    &block.into_inner(),
+                    None,
+                );
             }
         }
     }
 }
 
+/// Send block to active nodes, compact to nodes accepting compact blocks and whole to others
+pub fn relay_block(
+    p2p: &Mgr,
+    node_info: &RwLock<HashMap<u64, RwLock<NodeInfo>>>,
+    header: &Header,
+    transactions: &[UnverifiedTransaction],
+    block_rlp: &[u8],
+    exclude: Option<u64>,
+)
+{
+    let compact_nodes: HashSet<u64> = node_info
+        .read()
+        .iter()
+        .filter(|&(_, node_info_lock)| node_info_lock.read().compact_blocks)
+        .map(|(node_hash, _)| *node_hash)
+        .collect();
+
+    let mut block_req = None;
+    let mut compact_req = None;
+    for node_hash in p2p.get_active_nodes_hashes() {
+        if Some(node_hash) == exclude {
+            continue;
+        }
+        let req = if compact_nodes.contains(&node_hash) {
+            compact_req
+                .get_or_insert_with(|| compact::compact_block(header, transactions))
+                .clone()
+        } else {
+            block_req
+                .get_or_insert_with(|| {
+                    let mut req = channel_buffer_template(Action::BROADCASTBLOCK.value());
+                    req.body.put_slice(block_rlp);
+                    req.head.len = req.body.len() as u32;
+                    req
+                })
+                .clone()
+        };
+        p2p.send(node_hash, req);
+        trace!(target: "sync_broadcast", "Sync broadcast new block sent...");
+    }
+}
+
+/// Return true if a broadcast block with the header is to be imported: it is recent, its parent
+/// is in chain, it is not seen before and its header is valid
+pub fn is_new_block_wanted(
+    p2p: &Mgr,
+    node_hash: u64,
+    header: &Header,
+    client: &Arc<BlockChainClient>,
+    storage: &Arc<SyncStorage>,
+) -> bool
+{
+    let hash = header.hash();
+    let number = header.number();
+    let best_block_number = client.chain_info().best_block_number;
+
+    // Only accept side chain blocks within MAX_NEW_BLOCK_AGE
+    if best_block_number > number && best_block_number - number > MAX_NEW_BLOCK_AGE {
+        trace!(target: "sync_broadcast", "Ignored ancient new block {:?}", hash);
+        return false;
+    }
+
+    // Proceed only when the parent block is in chain
+    if client
+        .block_header(BlockId::Hash(*header.parent_hash()))
+        .is_none()
+    {
+        return false;
+    }
+
+    if storage.is_block_hash_recorded(&hash) {
+        return false;
+    }
+
+    // Do basic header validation before proceed
+    if let Err(e) = UnityEngine::validate_block_header(header) {
+        debug!(target: "sync_broadcast", "Invalid header: {:?}, header: {}", e, to_hex(&header.rlp(Seal::With)));
+        p2p.report(node_hash, Behaviour::InvalidHeader);
+        return false;
+    }
+    true
+}
+
+/// Import broadcast block with validated body and relay it to the other nodes
+pub fn import_new_block(
+    p2p: &Mgr,
+    node_hash: u64,
+    block: Block,
+    client: &Arc<BlockChainClient>,
+    storage: &Arc<SyncStorage>,
+    node_info: &RwLock<HashMap<u64, RwLock<NodeInfo>>>,
+)
+{
+    let hash = block.header.hash();
+    let number = block.header.number();
+    let block_rlp = block.rlp_bytes(Seal::With);
+    match client.import_block(block_rlp.clone()) {
+        Ok(_) => {
+            trace!(target: "sync_broadcast", "New broadcast block imported {:?} ({})", hash, number);
+            storage.insert_recorded_blocks_hashes(vec![hash]);
+            // Re-broadcast this block
+            relay_block(
+                p2p,
+                node_info,
+                &block.header,
+                &block.transactions,
+                &block_rlp,
+                Some(node_hash),
+            );
+        }
+        Err(BlockImportError::Import(ImportError::AlreadyInChain)) => {
+            trace!(target: "sync_broadcast", "New block already in chain {:?}", hash);
+        }
+        Err(BlockImportError::Import(ImportError::AlreadyQueued)) => {
+            trace!(target: "sync_broadcast", "New block already queued {:?}", hash);
+        }
+        Err(BlockImportError::Block(BlockError::UnknownParent(p))) => {
+            info!(target: "sync_broadcast", "New block with unknown parent ({:?}) {:?}", p, hash);
+        }
+        Err(e) => {
+            error!(target: "sync_broadcast", "Bad new block {:?} : {:?}", hash, e);
+            if is_invalid_block(&e) {
+                p2p.report(node_hash, Behaviour::InvalidBlock);
+            }
+        }
+    };
+}
+
 /// Handle block received from broadcast
 pub fn handle_broadcast_block(
     p2p: Mgr,
     node_hash: u64,
     req: ChannelBuffer,
     client: Arc<BlockChainClient>,
+    node_info: Arc<RwLock<HashMap<u64, RwLock<NodeInfo>>>>,
     storage: Arc<SyncStorage>,
     network_best_block_number: Arc<RwLock<u64>>,
 )
@@ -128,94 +267,38 @@ pub fn handle_broadcast_block(
     drop(network_best_block_number);
 
     let block_rlp = UntrustedRlp::new(req.body.as_slice());
-    if let Ok(header_rlp) = block_rlp.at(0) {
-        if let Ok(h) = header_rlp.as_val() {
-            let header: Header = h;
-            let hash = header.hash();
-            let number = header.number();
-
-            // Only accept side chain blocks within MAX_NEW_BLOCK_AGE
-            if best_block_number > number && best_block_number - number > MAX_NEW_BLOCK_AGE {
-                trace!(target: "sync_broadcast", "Ignored ancient new block {:?}", hash);
-                return;
-            }
-
-            let parent_hash = header.parent_hash();
-            // Proceed only when the parent block is in chain
-            if client.block_header(BlockId::Hash(*parent_hash)).is_some() {
-                let mut recorded_blocks_hashes = storage.recorded_blocks_hashes().lock();
-                if !recorded_blocks_hashes.contains_key(&hash) {
-                    // Do basic header validation before proceed
-                    let result = UnityEngine::validate_block_header(&header);
-                    match result {
-                        Ok(()) => {
-                            if let Ok(body_rlp) = block_rlp.at(1) {
-                                // parse body
-                                let mut transactions = Vec::new();
-                                if !body_rlp.is_empty() {
-                                    for transaction_rlp in body_rlp.iter() {
-                                        if !transaction_rlp.is_empty() {
-                                            if let Ok(transaction) = transaction_rlp.as_val() {
-                                                transactions.push(transaction);
-                                            }
-                                        }
-                                    }
-                                }
-                                // Do block integrity validation before proceed
-                                let result =
-                                    UnityEngine::validate_block_body(&header, &transactions);
-                                match result {
-                                    Ok(_) => {
-                                        let result =
-                                            client.import_block(block_rlp.as_raw().to_vec());
-
-                                        match result {
-                                            Ok(_) => {
-                                                trace!(target: "sync_broadcast", "New broadcast block imported {:?} ({})", hash, number);
-                                                recorded_blocks_hashes.insert(hash, 0);
-                                                let active_nodes = p2p.get_active_nodes();
-                                                for n in active_nodes.iter() {
-                                                    // Re-broadcast this block
-                                                    trace!(target: "sync_broadcast", "Sync broadcast new block sent...");
-                                                    p2p.send(n.hash, req.clone());
-                                                }
-                                            }
-                                            Err(BlockImportError::Import(
-                                                ImportError::AlreadyInChain,
-                                            )) => {
-                                                trace!(target: "sync_broadcast", "New block already in chain {:?}", hash);
-                                            }
-                                            Err(BlockImportError::Import(
-                                                ImportError::AlreadyQueued,
-                                            )) => {
-                                                trace!(target: "sync_broadcast", "New block already queued {:?}", hash);
-                                            }
-                                            Err(BlockImportError::Block(
-                                                BlockError::UnknownParent(p),
-                                            )) => {
-                                                info!(target: "sync_broadcast", "New block with unknown parent ({:?}) {:?}", p, hash);
-                                            }
-                                            Err(e) => {
-                                                error!(target: "sync_broadcast", "Bad new block {:?} : {:?}", hash, e);
-                                            }
-                                        };
-                                    }
-                                    Err(_e) => {
-                                        debug!(target: "sync_broadcast", "Incomplete block body #{} from node {}.", number, node_hash);
-                                    }
-                                }
+    if let Ok(header) = block_rlp.val_at::<Header>(0) {
+        if is_new_block_wanted(&p2p, node_hash, &header, &client, &storage) {
+            if let Ok(body_rlp) = block_rlp.at(1) {
+                // parse body
+                let mut transactions = Vec::new();
+                if !body_rlp.is_empty() {
+                    for transaction_rlp in body_rlp.iter() {
+                        if !transaction_rlp.is_empty() {
+                            if let Ok(transaction) = transaction_rlp.as_val() {
+                                transactions.push(transaction);
                             }
                         }
-                        Err(e) => {
-                            // ignore this batch if any invalidated header
-                            debug!(target: "sync_broadcast", "Invalid header: {:?}, header: {}", e, to_hex(header_rlp.as_raw()));
-                        }
+                    }
+                }
+                // Do block integrity validation before proceed
+                match UnityEngine::validate_block_body(&header, &transactions) {
+                    Ok(_) => {
+                        let block = Block {
+                            header,
+                            transactions,
+                        };
+                        import_new_block(&p2p, node_hash, block, &client, &storage, &node_info);
+                    }
+                    Err(_e) => {
+                        debug!(target: "sync_broadcast", "Incomplete block body #{} from node {}.", header.number(), node_hash);
+                        p2p.report(node_hash, Behaviour::InvalidBlock);
                     }
                 }
             }
-
-            p2p.update_node(&node_hash);
         }
+
+        p2p.update_node(&node_hash);
     }
 }
 
@@ -0,0 +1,357 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Compact block relay.
+//!
+//! A compact block carries the block header and a short id for each transaction, most of which
+//! the receiver already has in its transaction queue from BROADCASTTX. Transactions which can't
+//! be found locally are requested by index with BLOCKTXSREQ. Short ids are salted with the block
+//! hash so a collision in one block doesn't repeat in the next one.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use aion_types::H256;
+use blake2b::blake2b;
+use block::Block;
+use byteorder::{BigEndian, ByteOrder};
+use bytes::BufMut;
+use client::{BlockChainClient, BlockId};
+use engine::unity_engine::UnityEngine;
+use header::Header;
+use p2p::{Behaviour, ChannelBuffer, Mgr};
+use parking_lot::RwLock;
+use rlp::{RlpStream, UntrustedRlp};
+use sync::action::Action;
+use sync::node_info::NodeInfo;
+use sync::storage::SyncStorage;
+use sync::wrappers::CompactBlockWrapper;
+use transaction::UnverifiedTransaction;
+
+use super::broadcast::{import_new_block, is_new_block_wanted};
+use super::{channel_buffer_template, channel_buffer_template_with_version};
+
+/// Status capability flag of nodes accepting compact blocks
+pub const CAPABILITY_COMPACT_BLOCKS: u8 = 0x01;
+/// Max number of blocks waiting for missing transactions
+const MAX_PENDING_BLOCKS: usize = 32;
+/// Blocks waiting longer for missing transactions are dropped
+const PENDING_BLOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Short id of a transaction in the block
+pub fn short_id(block_hash: &H256, transaction_hash: &H256) -> u64 {
+    let mut salted = block_hash.to_vec();
+    salted.extend_from_slice(transaction_hash);
+    BigEndian::read_u64(&blake2b(salted)[..8])
+}
+
+/// Compact block message of the block
+pub fn compact_block(header: &Header, transactions: &[UnverifiedTransaction]) -> ChannelBuffer {
+    let block_hash = header.hash();
+    let mut rlp = RlpStream::new_list(2);
+    rlp.append(header);
+    rlp.begin_list(transactions.len());
+    for transaction in transactions {
+        rlp.append(&short_id(&block_hash, transaction.hash()));
+    }
+
+    let mut cb = channel_buffer_template(Action::COMPACTBLOCK.value());
+    cb.body.put_slice(rlp.as_raw());
+    cb.head.len = cb.body.len() as u32;
+    cb
+}
+
+/// Handle compact block received from broadcast
+pub fn handle_compact_block(
+    p2p: Mgr,
+    node_hash: u64,
+    req: ChannelBuffer,
+    client: Arc<BlockChainClient>,
+    node_info: Arc<RwLock<HashMap<u64, RwLock<NodeInfo>>>>,
+    storage: Arc<SyncStorage>,
+    network_best_block_number: Arc<RwLock<u64>>,
+)
+{
+    trace!(target: "sync_broadcast", "COMPACTBLOCK received.");
+    let network_best_number = *network_best_block_number.read();
+    let best_block_number = client.chain_info().best_block_number;
+
+    if best_block_number + 4 < network_best_number {
+        // Ignore COMPACTBLOCK message until full synced
+        trace!(target: "sync_broadcast", "Syncing..., ignore COMPACTBLOCK message.");
+        return;
+    }
+
+    let rlp = UntrustedRlp::new(req.body.as_slice());
+    let (header, short_ids) = match (rlp.val_at::<Header>(0), rlp.list_at::<u64>(1)) {
+        (Ok(header), Ok(short_ids)) => (header, short_ids),
+        _ => {
+            debug!(target: "sync_broadcast", "Invalid compact block from node {}.", node_hash);
+            p2p.report(node_hash, Behaviour::MalformedMessage);
+            return;
+        }
+    };
+    let block_hash = header.hash();
+
+    if storage.compact_blocks().lock().contains_key(&block_hash) {
+        // Missing transactions of this block already requested
+        return;
+    }
+    if !is_new_block_wanted(&p2p, node_hash, &header, &client, &storage) {
+        p2p.update_node(&node_hash);
+        return;
+    }
+
+    // Rebuild the block from the transaction queue
+    let mut queued: HashMap<u64, UnverifiedTransaction> = client
+        .ready_transactions()
+        .into_iter()
+        .map(|pending| {
+            let transaction: UnverifiedTransaction = pending.transaction.into();
+            (short_id(&block_hash, transaction.hash()), transaction)
+        })
+        .collect();
+    let mut wrapper = CompactBlockWrapper::new(node_hash, header);
+    wrapper.transactions = short_ids.iter().map(|id| queued.remove(id)).collect();
+
+    if wrapper.transactions.iter().all(|transaction| transaction.is_some()) {
+        let transactions: Vec<UnverifiedTransaction> = wrapper
+            .transactions
+            .iter()
+            .filter_map(|transaction| transaction.clone())
+            .collect();
+        if UnityEngine::validate_block_body(&wrapper.header, &transactions).is_ok() {
+            let block = Block {
+                header: wrapper.header,
+                transactions,
+            };
+            import_new_block(&p2p, node_hash, block, &client, &storage, &node_info);
+            p2p.update_node(&node_hash);
+            return;
+        }
+        // Short id collision, get all the transactions from the node
+        for slot in wrapper.transactions.iter_mut() {
+            *slot = None;
+        }
+    }
+    request_missing(&p2p, wrapper, &storage);
+    p2p.update_node(&node_hash);
+}
+
+/// Request the missing transactions of the compact block and keep it until they arrive
+fn request_missing(p2p: &Mgr, mut wrapper: CompactBlockWrapper, storage: &Arc<SyncStorage>) {
+    let block_hash = wrapper.header.hash();
+    let node_hash = wrapper.node_hash;
+    wrapper.requested_all = wrapper.transactions.iter().all(|transaction| transaction.is_none());
+    let indexes: Vec<u64> = wrapper
+        .transactions
+        .iter()
+        .enumerate()
+        .filter(|&(_, transaction)| transaction.is_none())
+        .map(|(index, _)| index as u64)
+        .collect();
+    debug!(target: "sync_broadcast", "Compact block #{} from node {}, {} of {} transactions missing.", wrapper.header.number(), node_hash, indexes.len(), wrapper.transactions.len());
+
+    {
+        let mut compact_blocks = storage.compact_blocks().lock();
+        compact_blocks.retain(|_, pending| {
+            pending
+                .timestamp
+                .elapsed()
+                .map(|elapsed| elapsed < PENDING_BLOCK_TIMEOUT)
+                .unwrap_or(false)
+        });
+        if compact_blocks.len() >= MAX_PENDING_BLOCKS {
+            trace!(target: "sync_broadcast", "Too many compact blocks pending, ignore {:?}", block_hash);
+            return;
+        }
+        compact_blocks.insert(block_hash, wrapper);
+    }
+
+    let mut rlp = RlpStream::new_list(2);
+    rlp.append(&block_hash);
+    rlp.begin_list(indexes.len());
+    for index in indexes {
+        rlp.append(&index);
+    }
+
+    let mut cb = channel_buffer_template(Action::BLOCKTXSREQ.value());
+    cb.body.put_slice(rlp.as_raw());
+    cb.head.len = cb.body.len() as u32;
+    if !p2p.send(node_hash, cb) {
+        storage.compact_blocks().lock().remove(&block_hash);
+    }
+}
+
+/// Serve the requested transactions of a block
+pub fn receive_req(p2p: Mgr, node_hash: u64, client: Arc<BlockChainClient>, req: ChannelBuffer) {
+    trace!(target: "sync_req", "compact/receive_req");
+
+    let rlp = UntrustedRlp::new(req.body.as_slice());
+    let (block_hash, indexes) = match (rlp.val_at::<H256>(0), rlp.list_at::<u64>(1)) {
+        (Ok(block_hash), Ok(indexes)) => (block_hash, indexes),
+        _ => {
+            debug!(target: "sync_req", "Invalid block transactions request from node {}.", node_hash);
+            p2p.report(node_hash, Behaviour::MalformedMessage);
+            return;
+        }
+    };
+
+    let transactions = match client.block_body(BlockId::Hash(block_hash)) {
+        Some(body) => body.transactions(),
+        None => {
+            trace!(target: "sync_req", "Unknown block {:?} requested by node {}.", block_hash, node_hash);
+            return;
+        }
+    };
+    let mut requested = Vec::new();
+    for index in indexes {
+        match transactions.get(index as usize) {
+            Some(transaction) => requested.push(transaction),
+            None => {
+                p2p.report(node_hash, Behaviour::MalformedMessage);
+                return;
+            }
+        }
+    }
+
+    let mut rlp = RlpStream::new_list(2);
+    rlp.append(&block_hash);
+    rlp.begin_list(requested.len());
+    for transaction in requested {
+        rlp.append(transaction);
+    }
+
+    let mut cb = channel_buffer_template_with_version(req.head.ver, Action::BLOCKTXSRES.value());
+    cb.body.put_slice(rlp.as_raw());
+    cb.head.len = cb.body.len() as u32;
+    p2p.send(node_hash, cb);
+}
+
+/// Complete the pending compact block with the received transactions and import it
+pub fn receive_res(
+    p2p: Mgr,
+    node_hash: u64,
+    res: ChannelBuffer,
+    client: Arc<BlockChainClient>,
+    node_info: Arc<RwLock<HashMap<u64, RwLock<NodeInfo>>>>,
+    storage: Arc<SyncStorage>,
+)
+{
+    trace!(target: "sync_res", "compact/receive_res");
+
+    let rlp = UntrustedRlp::new(res.body.as_slice());
+    let (block_hash, received) = match (
+        rlp.val_at::<H256>(0),
+        rlp.list_at::<UnverifiedTransaction>(1),
+    ) {
+        (Ok(block_hash), Ok(received)) => (block_hash, received),
+        _ => {
+            debug!(target: "sync_res", "Invalid block transactions from node {}.", node_hash);
+            p2p.report(node_hash, Behaviour::MalformedMessage);
+            return;
+        }
+    };
+
+    let mut wrapper = {
+        let mut compact_blocks = storage.compact_blocks().lock();
+        match compact_blocks.get(&block_hash) {
+            Some(wrapper) if wrapper.node_hash == node_hash => {}
+            _ => return,
+        }
+        compact_blocks
+            .remove(&block_hash)
+            .expect("checked the block is pending")
+    };
+
+    let missing = wrapper
+        .transactions
+        .iter()
+        .filter(|transaction| transaction.is_none())
+        .count();
+    if received.len() != missing {
+        debug!(target: "sync_res", "Node {} sent {} of {} missing transactions.", node_hash, received.len(), missing);
+        p2p.report(node_hash, Behaviour::MalformedMessage);
+        return;
+    }
+    let mut received = received.into_iter();
+    for slot in wrapper.transactions.iter_mut() {
+        if slot.is_none() {
+            *slot = received.next();
+        }
+    }
+
+    let transactions: Vec<UnverifiedTransaction> = wrapper
+        .transactions
+        .iter()
+        .filter_map(|transaction| transaction.clone())
+        .collect();
+    match UnityEngine::validate_block_body(&wrapper.header, &transactions) {
+        Ok(_) => {
+            let block = Block {
+                header: wrapper.header,
+                transactions,
+            };
+            import_new_block(&p2p, node_hash, block, &client, &storage, &node_info);
+        }
+        Err(_) if !wrapper.requested_all => {
+            // Short id collision, get all the transactions from the node
+            let count = wrapper.transactions.len();
+            wrapper.transactions = vec![None; count];
+            wrapper.timestamp = SystemTime::now();
+            request_missing(&p2p, wrapper, &storage);
+        }
+        Err(_e) => {
+            debug!(target: "sync_res", "Incomplete compact block #{} from node {}.", wrapper.header.number(), node_hash);
+            p2p.report(node_hash, Behaviour::InvalidBlock);
+        }
+    }
+    p2p.update_node(&node_hash);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_id() {
+        let tx_hash = H256::from(7);
+        let id = short_id(&H256::from(1), &tx_hash);
+        assert_eq!(id, short_id(&H256::from(1), &tx_hash));
+        // salted with the block hash
+        assert!(id != short_id(&H256::from(2), &tx_hash));
+        assert!(id != short_id(&H256::from(1), &H256::from(8)));
+    }
+
+    #[test]
+    fn test_compact_block_encoding() {
+        let mut header = Header::default();
+        header.set_number(10);
+        let cb = compact_block(&header, &[]);
+        assert_eq!(cb.head.action, Action::COMPACTBLOCK.value());
+        assert_eq!(cb.head.len as usize, cb.body.len());
+
+        let rlp = UntrustedRlp::new(cb.body.as_slice());
+        assert_eq!(rlp.val_at::<Header>(0).unwrap(), header);
+        assert!(rlp.list_at::<u64>(1).unwrap().is_empty());
+    }
+}
@@ -34,7 +34,7 @@ use client::{BlockChainClient, BlockId};
 use byteorder::{BigEndian, ByteOrder, ReadBytesExt};
 use bytes::BufMut;
 use rlp::{RlpStream, UntrustedRlp};
-use p2p::{ChannelBuffer, Mgr, Node};
+use p2p::{Behaviour, ChannelBuffer, Mgr, Node};
 use sync::action::Action;
 use sync::wrappers::{HeadersWrapper};
 use sync::node_info::{NodeInfo, Mode};
@@ -51,6 +51,7 @@ const DISTANCE_FLAG: u64 = NORMAL_REQUEST_SIZE as u64 * 6 - 1;
 const FAR_OVERLAPPING_BLOCKS: u64 = 3;
 const CLOSE_OVERLAPPING_BLOCKS: u64 = 15;
 const JUMP_SIZE: u64 = 200;
+const SCHEDULE_DISTANCE: u64 = 128;
 
 pub fn sync_headers(
     p2p: Mgr,
@@ -60,10 +61,28 @@ pub fn sync_headers(
     storage: Arc<SyncStorage>,
 )
 {
+    // Requeue ranges of timed out requests
+    for hash in storage.scheduler().expire() {
+        debug!(target: "sync", "headers request to node {} timed out", hash);
+    }
+
     let active_nodes = p2p.get_active_nodes();
+    // Spread header ranges over all nodes far enough ahead
+    let scheduled = schedule_nodes(
+        p2p.clone(),
+        &active_nodes,
+        nodes_info.clone(),
+        local_total_diff,
+        local_best_block_number,
+        storage.clone(),
+    );
+
     // Filter nodes. Only sync from nodes with higher total difficulty and with a cooldown restriction.
     let candidates: Vec<Node> =
-        filter_nodes_to_sync_headers(active_nodes, nodes_info.clone(), local_total_diff);
+        filter_nodes_to_sync_headers(active_nodes, nodes_info.clone(), local_total_diff)
+            .into_iter()
+            .filter(|node| !scheduled.contains(&node.hash))
+            .collect();
     // Pick a random node among all candidates
     if let Some(candidate) = pick_random_node(&candidates) {
         let candidate_hash = candidate.hash;
@@ -92,6 +111,50 @@ pub fn sync_headers(
     }
 }
 
+/// Request scheduled header ranges from every idle node far ahead of the local chain. Nodes
+/// resolving a fork are left to their own mode. Return the nodes handled by the scheduler.
+fn schedule_nodes(
+    p2p: Mgr,
+    active_nodes: &Vec<Node>,
+    nodes_info: Arc<RwLock<HashMap<u64, RwLock<NodeInfo>>>>,
+    local_total_diff: &U256,
+    local_best_block_number: u64,
+    storage: Arc<SyncStorage>,
+) -> Vec<u64>
+{
+    let mut scheduled: Vec<u64> = Vec::new();
+    let mut best_numbers: HashMap<u64, u64> = HashMap::new();
+    {
+        let nodes_info_read = nodes_info.read();
+        for node in active_nodes {
+            if let Some(node_info_lock) = nodes_info_read.get(&node.hash) {
+                let node_info = node_info_lock.read();
+                let far_ahead = &node_info.total_difficulty > local_total_diff
+                    && node_info.best_block_number > local_best_block_number + SCHEDULE_DISTANCE;
+                let forking = node_info.mode == Mode::Backward || node_info.mode == Mode::Forward;
+                if far_ahead && !forking {
+                    scheduled.push(node.hash);
+                    best_numbers.insert(node.hash, node_info.best_block_number);
+                }
+            }
+        }
+    }
+
+    let scheduler = storage.scheduler();
+    scheduler.rank(&mut scheduled);
+    for hash in &scheduled {
+        let node_best_number = best_numbers.get(hash).cloned().unwrap_or(0);
+        if let Some((from, size)) =
+            scheduler.assign(*hash, node_best_number, local_best_block_number)
+        {
+            if !send(p2p.clone(), *hash, from, size) {
+                scheduler.cancel(*hash);
+            }
+        }
+    }
+    scheduled
+}
+
 fn prepare_send(
     p2p: Mgr,
     node_hash: u64,
@@ -154,7 +217,7 @@ fn prepare_send(
     send(p2p.clone(), node_hash, from, size)
 }
 
-fn send(p2p: Mgr, hash: u64, from: u64, size: u32) -> bool {
+pub fn send(p2p: Mgr, hash: u64, from: u64, size: u32) -> bool {
     debug!(target:"sync_send", "headers.rs/send: from {}, size: {}, node hash: {}", from, size, hash);
     let mut cb = channel_buffer_template(Action::HEADERSREQ.value());
 
@@ -232,6 +295,8 @@ pub fn receive_res(p2p: Mgr, hash: u64, cb_in: ChannelBuffer, storage: Arc<SyncS
     let mut prev_header = Header::new();
     let mut header_wrapper = HeadersWrapper::new();
     let mut headers = Vec::new();
+    let mut first_number = 0;
+    let mut received = 0;
 
     for header_rlp in rlp.iter() {
         if let Ok(header) = header_rlp.as_val() {
@@ -251,8 +316,13 @@ pub fn receive_res(p2p: Mgr, hash: u64, cb_in: ChannelBuffer, storage: Arc<SyncS
                             prev_header.hash(),
                             header.hash(),
                         );
+                        p2p.report(hash, Behaviour::InvalidHeader);
                         break;
                     } else {
+                        if received == 0 {
+                            first_number = header.number();
+                        }
+                        received += 1;
                         let block_hash = header.hash();
 
                         // ignore the block if it is already downloaded or imported
@@ -268,14 +338,17 @@ pub fn receive_res(p2p: Mgr, hash: u64, cb_in: ChannelBuffer, storage: Arc<SyncS
                 Err(e) => {
                     // ignore this batch if any invalidated header
                     debug!(target: "sync_res", "Invalid header: {:?}, header: {}", e, to_hex(header_rlp.as_raw()));
+                    p2p.report(hash, Behaviour::InvalidHeader);
                     break;
                 }
             }
         } else {
             debug!(target: "sync_res", "Invalid header: {}", to_hex(header_rlp.as_raw()));
+            p2p.report(hash, Behaviour::MalformedMessage);
             break;
         }
     }
+    storage.scheduler().headers_received(hash, first_number, received);
 
     if !headers.is_empty() {
         debug!(target: "sync_res", "Node: {}, saved headers from {} to {}", hash, headers.first().expect("headers empty checked").number(), headers.last().expect("headers empty checked").number());
@@ -283,6 +356,7 @@ pub fn receive_res(p2p: Mgr, hash: u64, cb_in: ChannelBuffer, storage: Arc<SyncS
         header_wrapper.headers = headers;
         header_wrapper.timestamp = SystemTime::now();
         p2p.update_node(&hash);
+        p2p.report(hash, Behaviour::UsefulResponse);
         let mut downloaded_headers = downloaded_headers.lock();
         downloaded_headers.push_back(header_wrapper);
     } else {
@@ -22,6 +22,7 @@ use std::sync::Arc;
 use std::collections::HashMap;
 
 use parking_lot::RwLock;
+use p2p::{Behaviour, Mgr};
 
 use client::{BlockId, BlockChainClient, BlockStatus, BlockImportError};
 use types::error::{BlockError, ImportError};
@@ -31,6 +32,8 @@ use sync::storage::SyncStorage;
 use sync::node_info::{NodeInfo, Mode};
 use aion_types::H256;
 
+use super::is_invalid_block;
+
 pub fn import_staged_blocks(hash: &H256, client: Arc<BlockChainClient>, storage: Arc<SyncStorage>) {
     let mut blocks_to_import = Vec::new();
     let mut staged_blocks = storage.staged_blocks().lock();
@@ -72,11 +75,18 @@ pub fn import_staged_blocks(hash: &H256, client: Arc<BlockChainClient>, storage:
 }
 
 pub fn import_blocks(
+    p2p: Mgr,
     client: Arc<BlockChainClient>,
     storage: Arc<SyncStorage>,
     nodes_info: Arc<RwLock<HashMap<u64, RwLock<NodeInfo>>>>,
 )
 {
+    // Move scheduled downloads which are ready into the import queue
+    let local_best_block_number = client.chain_info().best_block_number;
+    for blocks_wrapper in storage.scheduler().release(local_best_block_number) {
+        storage.insert_downloaded_blocks(blocks_wrapper);
+    }
+
     // Get downloaded blocks
     let mut downloaded_blocks = Vec::new();
     let mut blocks_wrappers = storage.downloaded_blocks().lock();
@@ -135,8 +145,12 @@ pub fn import_blocks(
                     unknown_parent_hash = block_view.header_view().parent_hash();
                     break;
                 }
-                Err(_e) => {
+                Err(e) => {
                     // TODO add repeat threshold
+                    if is_invalid_block(&e) {
+                        debug!(target: "sync_import", "Node: {}, invalid block #{}: {:?}", &blocks_wrapper.node_hash, block_number, e);
+                        p2p.report(blocks_wrapper.node_hash, Behaviour::InvalidBlock);
+                    }
                     break;
                 }
             }
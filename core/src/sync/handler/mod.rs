@@ -23,8 +23,12 @@ pub mod status;
 pub mod headers;
 pub mod bodies;
 pub mod broadcast;
+pub mod compact;
 pub mod import;
+pub mod proofs;
 
+use client::BlockImportError;
+use types::error::ImportError;
 use p2p::{Module, PROTOCAL_VERSION, ChannelBuffer};
 
 fn channel_buffer_template(action: u8) -> ChannelBuffer {
@@ -34,3 +38,11 @@ fn channel_buffer_template(action: u8) -> ChannelBuffer {
 fn channel_buffer_template_with_version(version: u16, action: u8) -> ChannelBuffer {
     ChannelBuffer::new1(version, Module::SYNC.value(), action, 0u32)
 }
+
+/// Return true if the import error proves the block itself is invalid
+fn is_invalid_block(error: &BlockImportError) -> bool {
+    match *error {
+        BlockImportError::Block(_) | BlockImportError::Import(ImportError::KnownBad) => true,
+        _ => false,
+    }
+}
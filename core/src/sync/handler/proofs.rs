@@ -0,0 +1,352 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Light client requests for state proofs and receipts.
+//!
+//! Request bodies are rlp lists starting with a request id which is echoed back in the
+//! response. A response holding only the request id means the request couldn't be served.
+//!
+//! Execution proofs run a transaction, so they are served on a dedicated thread with a bounded
+//! queue and their gas is capped.
+
+use std::cmp;
+use std::sync::mpsc::{sync_channel, SyncSender, TrySendError};
+use std::sync::Arc;
+use std::thread;
+
+use acore_bytes::Bytes;
+use aion_types::{Address, H128, H256, U256};
+use blake2b::blake2b;
+use bytes::BufMut;
+use client::{BlockId, ProvingBlockChainClient};
+use kvdb::DBValue;
+use light::{Request, Response};
+use p2p::{Behaviour, ChannelBuffer, Mgr};
+use parking_lot::Mutex;
+use receipt::Receipt;
+use rlp::{DecoderError, RlpStream, UntrustedRlp};
+use sync::action::Action;
+use transaction::{Action as TransactionAction, Transaction};
+
+use super::{channel_buffer_template, channel_buffer_template_with_version};
+
+/// Execution proof requests waiting to be served, requests beyond it are refused
+const EXECUTION_QUEUE_SIZE: usize = 16;
+
+/// Serves execution proof requests off the network threads.
+pub struct ExecutionServer {
+    sender: Mutex<SyncSender<(u64, ChannelBuffer)>>,
+}
+
+impl ExecutionServer {
+    /// Start the thread serving execution proofs, transactions are run with up to `max_gas`.
+    pub fn new(p2p: Mgr, client: Arc<ProvingBlockChainClient>, max_gas: U256) -> ExecutionServer {
+        let (sender, receiver) = sync_channel::<(u64, ChannelBuffer)>(EXECUTION_QUEUE_SIZE);
+        thread::Builder::new()
+            .name("sync-execution".into())
+            .spawn(move || {
+                for (hash, cb) in receiver {
+                    receive_execution_req(p2p.clone(), hash, &*client, &max_gas, cb);
+                }
+            })
+            .expect("failed to start execution proof thread");
+        ExecutionServer {
+            sender: Mutex::new(sender),
+        }
+    }
+
+    /// Queue an execution proof request, refuse it if the queue is full.
+    pub fn queue(&self, p2p: Mgr, hash: u64, cb: ChannelBuffer) {
+        let result = self.sender.lock().try_send((hash, cb));
+        match result {
+            Ok(()) => {}
+            Err(TrySendError::Full((hash, cb))) => {
+                debug!(target: "sync_req", "execution proof queue full, refusing request from {}", hash);
+                if let Ok(req_id) = UntrustedRlp::new(cb.body.as_slice()).val_at(0) {
+                    respond(p2p, hash, &cb, req_id, None);
+                }
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                error!(target: "sync_req", "execution proof thread stopped");
+            }
+        }
+    }
+}
+
+/// Send a light client request to the node.
+pub fn send_req(p2p: Mgr, hash: u64, req_id: u64, request: &Request) -> bool {
+    let (action, body) = match *request {
+        Request::Account {
+            ref block_hash,
+            ref address,
+        } => {
+            let mut s = RlpStream::new_list(3);
+            s.append(&req_id).append(block_hash).append(address);
+            (Action::ACCOUNTPROOFREQ, s.out())
+        }
+        Request::Storage {
+            ref block_hash,
+            ref address,
+            ref key,
+        } => {
+            let mut s = RlpStream::new_list(4);
+            s.append(&req_id).append(block_hash).append(address).append(key);
+            (Action::STORAGEPROOFREQ, s.out())
+        }
+        Request::Receipts { ref block_hash } => {
+            let mut s = RlpStream::new_list(2);
+            s.append(&req_id).append(block_hash);
+            (Action::RECEIPTSREQ, s.out())
+        }
+        Request::Execution {
+            ref block_hash,
+            ref transaction,
+        } => {
+            let mut s = RlpStream::new_list(10);
+            s.append(&req_id)
+                .append(block_hash)
+                .append(&transaction.nonce)
+                .append(&transaction.gas_price)
+                .append(&transaction.gas)
+                .append(&transaction.action)
+                .append(&transaction.value)
+                .append(&transaction.data)
+                .append(&transaction.transaction_type)
+                .append(transaction.sender());
+            (Action::EXECUTIONPROOFREQ, s.out())
+        }
+    };
+    debug!(target: "sync_send", "proofs.rs/send_req: id {}, action {}, node hash: {}", req_id, action.value(), hash);
+
+    let mut cb = channel_buffer_template(action.value());
+    cb.body.put_slice(&body);
+    cb.head.len = cb.body.len() as u32;
+    p2p.send(hash, cb)
+}
+
+/// Serve a light client request with proofs from the local chain. Execution proofs are
+/// served by `ExecutionServer`.
+pub fn receive_req(
+    p2p: Mgr,
+    hash: u64,
+    client: Arc<ProvingBlockChainClient>,
+    cb_in: ChannelBuffer,
+)
+{
+    trace!(target: "sync_req", "proofs/receive_req");
+
+    let rlp = UntrustedRlp::new(cb_in.body.as_slice());
+    let req_id: u64 = match rlp.val_at(0) {
+        Ok(req_id) => req_id,
+        Err(e) => {
+            debug!(target: "sync_req", "invalid proof request: {:?}", e);
+            return;
+        }
+    };
+
+    let response = match Action::from(cb_in.head.action) {
+        Action::ACCOUNTPROOFREQ => account_proof(&rlp, &*client),
+        Action::STORAGEPROOFREQ => storage_proof(&rlp, &*client),
+        Action::RECEIPTSREQ => receipts(&rlp, &*client),
+        _ => return,
+    };
+    match response {
+        Ok(response) => respond(p2p, hash, &cb_in, req_id, response),
+        Err(e) => debug!(target: "sync_req", "invalid proof request: {:?}", e),
+    }
+}
+
+fn receive_execution_req(
+    p2p: Mgr,
+    hash: u64,
+    client: &ProvingBlockChainClient,
+    max_gas: &U256,
+    cb_in: ChannelBuffer,
+)
+{
+    trace!(target: "sync_req", "proofs/receive_execution_req");
+
+    let rlp = UntrustedRlp::new(cb_in.body.as_slice());
+    let req_id: u64 = match rlp.val_at(0) {
+        Ok(req_id) => req_id,
+        Err(e) => {
+            debug!(target: "sync_req", "invalid proof request: {:?}", e);
+            return;
+        }
+    };
+
+    match execution_proof(&p2p, hash, &rlp, client, max_gas) {
+        Ok(response) => respond(p2p, hash, &cb_in, req_id, response),
+        Err(e) => debug!(target: "sync_req", "invalid proof request: {:?}", e),
+    }
+}
+
+fn respond(p2p: Mgr, hash: u64, cb_in: &ChannelBuffer, req_id: u64, response: Option<Response>) {
+    let action = Action::from(cb_in.head.action);
+    let res_action = match action {
+        Action::ACCOUNTPROOFREQ => Action::ACCOUNTPROOFRES,
+        Action::STORAGEPROOFREQ => Action::STORAGEPROOFRES,
+        Action::RECEIPTSREQ => Action::RECEIPTSRES,
+        _ => Action::EXECUTIONPROOFRES,
+    };
+    let mut res = channel_buffer_template_with_version(cb_in.head.ver, res_action.value());
+    let body = match response {
+        Some(Response::Account(proof)) => {
+            let mut s = RlpStream::new_list(2);
+            s.append(&req_id).append_list::<Bytes, Bytes>(&proof);
+            s.out()
+        }
+        Some(Response::Storage(account_proof, storage_proof)) => {
+            let mut s = RlpStream::new_list(3);
+            s.append(&req_id)
+                .append_list::<Bytes, Bytes>(&account_proof)
+                .append_list::<Bytes, Bytes>(&storage_proof);
+            s.out()
+        }
+        Some(Response::Receipts(receipts)) => {
+            let mut s = RlpStream::new_list(2);
+            s.append(&req_id).append_list(&receipts);
+            s.out()
+        }
+        Some(Response::Execution(items)) => {
+            let mut s = RlpStream::new_list(2);
+            s.append(&req_id).begin_list(items.len());
+            for item in &items {
+                s.append(&item.to_vec());
+            }
+            s.out()
+        }
+        None => {
+            let mut s = RlpStream::new_list(1);
+            s.append(&req_id);
+            s.out()
+        }
+    };
+    res.body.put_slice(&body);
+    res.head.len = res.body.len() as u32;
+
+    p2p.update_node(&hash);
+    p2p.send(hash, res);
+}
+
+/// Decode a response to a light client request. Returns the request id and the response,
+/// `None` if the node couldn't serve the request.
+pub fn receive_res(cb_in: &ChannelBuffer) -> Result<(u64, Option<Response>), DecoderError> {
+    trace!(target: "sync_res", "proofs/receive_res");
+
+    let rlp = UntrustedRlp::new(cb_in.body.as_slice());
+    let req_id: u64 = rlp.val_at(0)?;
+    if rlp.item_count()? == 1 {
+        return Ok((req_id, None));
+    }
+    let response = match Action::from(cb_in.head.action) {
+        Action::ACCOUNTPROOFRES => Response::Account(rlp.list_at(1)?),
+        Action::STORAGEPROOFRES => Response::Storage(rlp.list_at(1)?, rlp.list_at(2)?),
+        Action::RECEIPTSRES => Response::Receipts(rlp.list_at(1)?),
+        Action::EXECUTIONPROOFRES => {
+            let items: Vec<Bytes> = rlp.list_at(1)?;
+            Response::Execution(items.iter().map(|item| DBValue::from_slice(item)).collect())
+        }
+        _ => return Err(DecoderError::Custom("unexpected action")),
+    };
+    Ok((req_id, Some(response)))
+}
+
+fn account_proof(
+    rlp: &UntrustedRlp,
+    client: &ProvingBlockChainClient,
+) -> Result<Option<Response>, DecoderError>
+{
+    let block_hash: H256 = rlp.val_at(1)?;
+    let address: Address = rlp.val_at(2)?;
+    Ok(client
+        .prove_account(blake2b(&address), BlockId::Hash(block_hash))
+        .map(|(proof, _)| Response::Account(proof)))
+}
+
+fn storage_proof(
+    rlp: &UntrustedRlp,
+    client: &ProvingBlockChainClient,
+) -> Result<Option<Response>, DecoderError>
+{
+    let block_hash: H256 = rlp.val_at(1)?;
+    let address: Address = rlp.val_at(2)?;
+    let key: H128 = rlp.val_at(3)?;
+    let id = BlockId::Hash(block_hash);
+    let account_proof = match client.prove_account(blake2b(&address), id) {
+        Some((proof, _)) => proof,
+        None => return Ok(None),
+    };
+    Ok(client
+        .prove_storage(blake2b(&address), blake2b(&key), id)
+        .map(|(storage_proof, _)| Response::Storage(account_proof, storage_proof)))
+}
+
+fn receipts(
+    rlp: &UntrustedRlp,
+    client: &ProvingBlockChainClient,
+) -> Result<Option<Response>, DecoderError>
+{
+    let block_hash: H256 = rlp.val_at(1)?;
+    match client.block_receipts(&block_hash) {
+        Some(receipts) => {
+            let receipts: Vec<Receipt> = UntrustedRlp::new(&receipts).as_list()?;
+            Ok(Some(Response::Receipts(receipts)))
+        }
+        None => Ok(None),
+    }
+}
+
+fn execution_proof(
+    p2p: &Mgr,
+    hash: u64,
+    rlp: &UntrustedRlp,
+    client: &ProvingBlockChainClient,
+    max_gas: &U256,
+) -> Result<Option<Response>, DecoderError>
+{
+    let block_hash: H256 = rlp.val_at(1)?;
+    let block_gas_limit = match client.block_header(BlockId::Hash(block_hash)) {
+        Some(header) => header.gas_limit(),
+        None => return Ok(None),
+    };
+    let gas: U256 = rlp.val_at(4)?;
+    if gas > *max_gas {
+        p2p.report(hash, Behaviour::ExpensiveRequest);
+    }
+    let gas = cmp::min(gas, cmp::min(block_gas_limit, *max_gas));
+    let action: TransactionAction = rlp.val_at(5)?;
+    let sender: Address = rlp.val_at(9)?;
+    let transaction = Transaction::new(
+        rlp.val_at(2)?,
+        rlp.val_at(3)?,
+        gas,
+        action,
+        rlp.val_at(6)?,
+        rlp.val_at(7)?,
+        rlp.val_at::<U256>(8)?,
+        None,
+    )
+    .fake_sign(sender);
+    Ok(client
+        .prove_transaction(transaction, BlockId::Hash(block_hash))
+        .map(|(_, items)| Response::Execution(items)))
+}
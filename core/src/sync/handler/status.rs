@@ -31,10 +31,12 @@ use byteorder::{BigEndian, ByteOrder, ReadBytesExt};
 use bytes::BufMut;
 use sync::node_info::NodeInfo;
 use sync::action::Action;
-use p2p::{ChannelBuffer, Mgr};
+use sync::fork_id::{ForkFilter, ForkId, FORK_ID_LENGTH};
+use p2p::{Behaviour, ChannelBuffer, Mgr};
 use std::time::Duration;
 use std::thread::sleep;
 
+use super::compact::CAPABILITY_COMPACT_BLOCKS;
 use super::{channel_buffer_template_with_version,channel_buffer_template};
 use super::super::INTERVAL_STATUS;
 
@@ -63,7 +65,15 @@ pub fn send(p2p: Mgr, hash: u64) {
     p2p.send(hash, cb);
 }
 
-pub fn receive_req(p2p: Mgr, chain_info: &BlockChainInfo, hash: u64, version: u16) {
+pub fn receive_req(
+    p2p: Mgr,
+    chain_info: &BlockChainInfo,
+    fork_filter: &ForkFilter,
+    hash: u64,
+    version: u16,
+    capabilities: u8,
+)
+{
     trace!(target: "sync_req", "status/receive_req");
 
     let mut cb = channel_buffer_template_with_version(version, Action::STATUSRES.value());
@@ -86,6 +96,8 @@ pub fn receive_req(p2p: Mgr, chain_info: &BlockChainInfo, hash: u64, version: u1
     res_body.put_slice(&total_difficulty_buf.to_vec());
     res_body.put_slice(&best_hash);
     res_body.put_slice(&genesis_hash);
+    res_body.put_slice(&fork_filter.current(chain_info.best_block_number).to_bytes());
+    res_body.push(capabilities);
 
     cb.body.put_slice(res_body.as_slice());
     cb.head.len = cb.body.len() as u32;
@@ -102,7 +114,8 @@ pub fn receive_res(
     cb_in: ChannelBuffer,
     network_best_td: Arc<RwLock<U256>>,
     network_best_block_number: Arc<RwLock<u64>>,
-    local_genesis_hash: H256,
+    fork_filter: &ForkFilter,
+    local_best_block_number: u64,
 )
 {
     trace!(target: "sync_res", "status/receive_res");
@@ -110,6 +123,7 @@ pub fn receive_res(
     // check channelbuffer len
     if (cb_in.head.len as usize) < mem::size_of::<u64>() + mem::size_of::<u8>() + 2 * HASH_LENGTH {
         debug!(target: "sync_res", "status res channelbuffer length is too short" );
+        p2p.report(hash, Behaviour::MalformedMessage);
         return;
     }
 
@@ -121,16 +135,30 @@ pub fn receive_res(
     // check total_difficulty_len
     if req_body_rest.len() < total_difficulty_len + 2 * HASH_LENGTH {
         debug!(target: "sync_res", "status res with wrong total_difficulty length " );
+        p2p.report(hash, Behaviour::MalformedMessage);
         return;
     }
 
     let (total_difficulty, req_body_rest) = req_body_rest.split_at(total_difficulty_len);
     let (best_hash, rest) = req_body_rest.split_at(HASH_LENGTH);
-    let (genesis_hash, _rest) = rest.split_at(HASH_LENGTH);
+    let (genesis_hash, rest) = rest.split_at(HASH_LENGTH);
 
     let total_difficulty = U256::from(total_difficulty);
     let best_hash = H256::from(best_hash);
     let genesis_hash = H256::from(genesis_hash);
+    let local_genesis_hash = fork_filter.genesis_hash();
+
+    // fork id is absent in status of nodes before fork id support
+    if genesis_hash == local_genesis_hash && rest.len() >= FORK_ID_LENGTH {
+        if let Some(fork_id) = ForkId::from_bytes(&rest[..FORK_ID_LENGTH]) {
+            if let Err(e) = fork_filter.validate(local_best_block_number, &fork_id) {
+                debug!(target: "sync_res", "Bad status res from node:{} {}, local: {:?}, node: {:?}", hash, e, fork_filter.current(local_best_block_number), fork_id);
+                p2p.report(hash, Behaviour::BadHandshake);
+                return;
+            }
+        }
+    }
+
     if genesis_hash == local_genesis_hash {
         // Update network best block
         let mut network_best_td = network_best_td.write();
@@ -154,11 +182,14 @@ pub fn receive_res(
             info.best_block_hash = best_hash;
             info.best_block_number = best_block_num;
             info.total_difficulty = total_difficulty;
+            // capabilities follow the fork id, absent in status of older nodes
+            info.compact_blocks = rest.len() > FORK_ID_LENGTH
+                && rest[FORK_ID_LENGTH] & CAPABILITY_COMPACT_BLOCKS != 0;
         }
 
         p2p.update_node(&hash);
     } else {
         error!(target: "sync_res", "Bad status res from node:{} invalid genesis, local genesis: {}, node genesis: {}",hash, local_genesis_hash, genesis_hash);
-        // TODO: move node to black list
+        p2p.report(hash, Behaviour::BadHandshake);
     }
 }
@@ -0,0 +1,331 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Light client sync. Keeps the header chain in sync with the network and sends state
+//! proof requests to full nodes on behalf of the light client.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+
+use futures::sync::oneshot;
+use futures::sync::oneshot::Sender;
+use futures::{Future, Stream};
+use parking_lot::{Mutex, RwLock};
+use rand::{thread_rng, Rng};
+use rlp::UntrustedRlp;
+use tokio::runtime::TaskExecutor;
+use tokio::timer::Interval;
+
+use aion_types::U256;
+use header::Header;
+use light::{Error, Fetcher, LightChainClient, LightClient, Request, Response};
+use p2p::{
+    Behaviour, Callable, ChannelBuffer, Config, Mgr, NetworkTraffic, PeerReputation,
+    PROTOCAL_VERSION,
+};
+use sync::action::Action;
+use sync::handler::{headers, proofs, status};
+use sync::node_info::NodeInfo;
+use sync::fork_id::ForkFilter;
+use sync::sync_provider::{
+    SyncProvider, SyncStatus, PeerInfo, LocalNodeInfo, peers_info, local_node_info,
+};
+use sync::{token_rules, with_route_limits, INTERVAL_STATUS};
+use types::error::{Error as CoreError, ImportError};
+
+const INTERVAL_LIGHT_HEADERS: u64 = 1000;
+const HEADERS_REQUEST_SIZE: u32 = 24;
+/// Number of local headers requested again so forks near the head are picked up.
+const OVERLAPPING_HEADERS: u64 = 15;
+const REQUEST_TIMEOUT: u64 = 5000;
+
+/// Light sync manager
+pub struct LightSync {
+    /// Light client
+    client: Arc<LightClient>,
+
+    /// Oneshots to shutdown threads
+    shutdown_hooks: Arc<Mutex<Vec<Sender<()>>>>,
+
+    /// P2p manager
+    p2p: Mgr,
+
+    /// active nodes info
+    node_info: Arc<RwLock<HashMap<u64, RwLock<NodeInfo>>>>,
+
+    /// network best td
+    network_best_td: Arc<RwLock<U256>>,
+
+    /// network best block number
+    network_best_block_number: Arc<RwLock<u64>>,
+
+    /// Requests waiting for a response, by request id
+    pending: Mutex<HashMap<u64, mpsc::Sender<Option<Response>>>>,
+
+    /// Id of the next request
+    next_req_id: AtomicUsize,
+
+    /// fork ids of the local chain
+    fork_filter: ForkFilter,
+}
+
+impl LightSync {
+    /// constructor
+    pub fn new(config: Config, client: Arc<LightClient>, fork_blocks: Vec<u64>) -> LightSync {
+        let chain_info = client.chain_info();
+        let fork_filter = ForkFilter::new(chain_info.genesis_hash, fork_blocks);
+
+        LightSync {
+            client,
+            p2p: Mgr::new(with_route_limits(config), token_rules()),
+            shutdown_hooks: Arc::new(Mutex::new(Vec::new())),
+            node_info: Arc::new(RwLock::new(HashMap::new())),
+            network_best_td: Arc::new(RwLock::new(chain_info.total_difficulty)),
+            network_best_block_number: Arc::new(RwLock::new(chain_info.best_block_number)),
+            pending: Mutex::new(HashMap::new()),
+            next_req_id: AtomicUsize::new(0),
+            fork_filter,
+        }
+    }
+
+    /// register callback
+    pub fn register_callback(&self, callback: Weak<Callable>) {
+        self.p2p.register_callback(callback);
+    }
+
+    /// run light sync instance
+    pub fn run(&self, executor: TaskExecutor) {
+        let p2p = &self.p2p.clone();
+        let mut p2p_0 = p2p.clone();
+        p2p_0.run(executor.clone());
+
+        let mut shutdown_hooks = self.shutdown_hooks.lock();
+
+        // status thread
+        let p2p_status = p2p.clone();
+        let node_info_status = self.node_info.clone();
+        let (tx, rx) = oneshot::channel::<()>();
+        executor.spawn(
+            Interval::new(Instant::now(), Duration::from_millis(INTERVAL_STATUS))
+                .for_each(move |_| {
+                    status::send_req(p2p_status.clone(), node_info_status.clone());
+                    Ok(())
+                })
+                .map_err(|err| error!(target: "sync_status", "executor status: {:?}", err))
+                .select(rx.map_err(|_| {}))
+                .map(|_| ())
+                .map_err(|_| ()),
+        );
+        shutdown_hooks.push(tx);
+
+        // sync headers thread
+        let p2p_header = p2p.clone();
+        let node_info_header = self.node_info.clone();
+        let client_header = self.client.clone();
+        let (tx, rx) = oneshot::channel::<()>();
+        executor.spawn(
+            Interval::new(Instant::now(), Duration::from_millis(INTERVAL_LIGHT_HEADERS))
+                .for_each(move |_| {
+                    sync_headers(
+                        p2p_header.clone(),
+                        node_info_header.clone(),
+                        client_header.clone(),
+                    );
+                    Ok(())
+                })
+                .map_err(|err| error!(target: "sync_headers", "executor header: {:?}", err))
+                .select(rx.map_err(|_| {}))
+                .map(|_| ())
+                .map_err(|_| ()),
+        );
+        shutdown_hooks.push(tx);
+    }
+
+    /// shutdown routine
+    pub fn shutdown(&self) {
+        info!(target:"sync_shutdown", "light sync shutdown start");
+        let mut shutdown_hooks = self.shutdown_hooks.lock();
+        while let Some(shutdown_hook) = shutdown_hooks.pop() {
+            if let Err(err) = shutdown_hook.send(()) {
+                debug!(target: "sync_shutdown", "shutdown err: {:?}", err);
+            }
+        }
+        // fail the requests still waiting for a response
+        self.pending.lock().clear();
+        self.p2p.shutdown();
+        self.p2p.clear_callback();
+        info!(target:"sync_shutdown", "light sync shutdown finished");
+    }
+
+    /// get local node info to fill back to config file
+    pub fn get_local_node_info(&self) -> &String { self.p2p.get_local_node_info() }
+
+    fn import_headers(&self, hash: u64, cb: &ChannelBuffer) {
+        let rlp = UntrustedRlp::new(cb.body.as_slice());
+        for header_rlp in rlp.iter() {
+            let header: Header = match header_rlp.as_val() {
+                Ok(header) => header,
+                Err(e) => {
+                    debug!(target: "sync_res", "Invalid header from node {}: {:?}", hash, e);
+                    self.p2p.report(hash, Behaviour::MalformedMessage);
+                    return;
+                }
+            };
+            let number = header.number();
+            match self.client.import_header(header) {
+                Ok(_) | Err(CoreError::Import(ImportError::AlreadyInChain)) => {}
+                Err(e) => {
+                    debug!(target: "sync_res", "Header #{} from node {} rejected: {}", number, hash, e);
+                    return;
+                }
+            }
+        }
+        self.p2p.update_node(&hash);
+    }
+}
+
+/// Request headers from a random node with a higher total difficulty.
+fn sync_headers(
+    p2p: Mgr,
+    node_info: Arc<RwLock<HashMap<u64, RwLock<NodeInfo>>>>,
+    client: Arc<LightClient>,
+)
+{
+    let chain_info = client.chain_info();
+    let candidates: Vec<u64> = node_info
+        .read()
+        .iter()
+        .filter(|&(_, info)| info.read().total_difficulty > chain_info.total_difficulty)
+        .map(|(hash, _)| *hash)
+        .collect();
+    if candidates.is_empty() {
+        return;
+    }
+    let hash = candidates[thread_rng().gen_range(0, candidates.len())];
+    let from = if chain_info.best_block_number > OVERLAPPING_HEADERS {
+        chain_info.best_block_number - OVERLAPPING_HEADERS
+    } else {
+        1
+    };
+    headers::send(p2p, hash, from, HEADERS_REQUEST_SIZE);
+}
+
+impl Fetcher for LightSync {
+    fn fetch(&self, request: Request) -> Result<Response, Error> {
+        let hashes = self.p2p.get_active_nodes_hashes();
+        if hashes.is_empty() {
+            return Err(Error::NoPeers);
+        }
+        let hash = hashes[thread_rng().gen_range(0, hashes.len())];
+
+        let req_id = self.next_req_id.fetch_add(1, Ordering::SeqCst) as u64;
+        let (tx, rx) = mpsc::channel();
+        self.pending.lock().insert(req_id, tx);
+        if !proofs::send_req(self.p2p.clone(), hash, req_id, &request) {
+            self.pending.lock().remove(&req_id);
+            return Err(Error::Unavailable);
+        }
+
+        let result = rx.recv_timeout(Duration::from_millis(REQUEST_TIMEOUT));
+        self.pending.lock().remove(&req_id);
+        match result {
+            Ok(Some(response)) => Ok(response),
+            Ok(None) => Err(Error::Unavailable),
+            Err(_) => Err(Error::Timeout),
+        }
+    }
+}
+
+impl SyncProvider for LightSync {
+    /// Get sync status for rpc request
+    fn status(&self) -> SyncStatus {
+        SyncStatus {
+            protocol_version: PROTOCAL_VERSION as u8,
+            network_id: self.p2p.get_net_id(),
+            start_block_number: self.client.chain_info().best_block_number,
+            highest_block_number: Some(*self.network_best_block_number.read()),
+            num_peers: self.p2p.get_active_nodes_len() as usize,
+        }
+    }
+
+    fn peer_reputations(&self) -> Vec<PeerReputation> { self.p2p.get_reputations() }
+
+    fn traffic(&self) -> NetworkTraffic { self.p2p.get_traffic() }
+
+    fn peers(&self) -> Vec<PeerInfo> { peers_info(&self.p2p, &self.node_info) }
+
+    fn node_info(&self) -> Option<LocalNodeInfo> { Some(local_node_info(&self.p2p)) }
+
+    fn add_peer(&self, node: &str) -> Result<(), String> { self.p2p.add_peer(node) }
+
+    fn remove_peer(&self, peer: &str) -> bool { self.p2p.remove_peer(peer) }
+
+    fn ban_peer(&self, ip: &str) -> Result<(), String> { self.p2p.ban_peer(ip) }
+}
+
+impl Callable for LightSync {
+    fn handle(&self, hash: u64, cb: ChannelBuffer) {
+        let p2p = self.p2p.clone();
+        match Action::from(cb.head.action) {
+            Action::STATUSREQ => {
+                let chain_info = &self.client.chain_info();
+                status::receive_req(p2p, chain_info, &self.fork_filter, hash, cb.head.ver, 0)
+            }
+            Action::STATUSRES => {
+                let best_block_number = self.client.chain_info().best_block_number;
+                status::receive_res(
+                    p2p,
+                    self.node_info.clone(),
+                    hash,
+                    cb,
+                    self.network_best_td.clone(),
+                    self.network_best_block_number.clone(),
+                    &self.fork_filter,
+                    best_block_number,
+                )
+            }
+            Action::HEADERSRES => self.import_headers(hash, &cb),
+            Action::ACCOUNTPROOFRES
+            | Action::STORAGEPROOFRES
+            | Action::RECEIPTSRES
+            | Action::EXECUTIONPROOFRES => {
+                match proofs::receive_res(&cb) {
+                    Ok((req_id, response)) => {
+                        if let Some(tx) = self.pending.lock().remove(&req_id) {
+                            let _ = tx.send(response);
+                        }
+                    }
+                    Err(e) => debug!(target: "sync_res", "Invalid proof response: {:?}", e),
+                }
+            }
+            // light clients don't serve chain data
+            _ => (),
+        };
+    }
+
+    fn disconnect(&self, hash: u64) {
+        debug!(target: "sync_disconnect", "stop syncing from disconnected node: {}", &hash);
+        self.node_info.write().remove(&hash);
+    }
+}
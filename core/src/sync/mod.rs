@@ -28,20 +28,25 @@
 //! * bodies: to get/send a number of continuous block bodies from/to other nodes
 //! * import: to import downloaded blocks to verification queue
 //! * broadcast: to get/send the newest transactions and block from/to other nodes
+//! * compact: to get/send new blocks as short transaction ids and fill in missing transactions
+//! * proofs: to serve state proofs and receipts to light clients
 
 mod handler;
 mod action;
 mod wrappers;
 mod node_info;
+mod fork_id;
 mod storage;
+mod scheduler;
 mod sync_provider;
+mod light;
 
 use std::sync::{Arc,Weak};
 use std::time::Duration;
 use std::time::Instant;
 use itertools::Itertools;
 use std::collections::{HashMap};
-use client::{BlockId, BlockChainClient, ChainNotify};
+use client::{BlockId, BlockChainClient, ChainNotify, ProvingBlockChainClient};
 use transaction::UnverifiedTransaction;
 use aion_types::{H256,U256};
 use futures::Future;
@@ -52,18 +57,28 @@ use futures::sync::oneshot;
 use futures::sync::oneshot::Sender;
 use parking_lot::{Mutex, RwLock};
 
-use p2p::{ ChannelBuffer, Config, Mgr, Callable, PROTOCAL_VERSION, Module};
+use p2p::{ ChannelBuffer, Config, Mgr, Callable, PROTOCAL_VERSION, Module, route_key};
 use sync::action::Action;
 use sync::handler::status;
 use sync::handler::bodies;
 use sync::handler::headers;
 use sync::handler::broadcast;
+use sync::handler::compact;
+use sync::handler::compact::CAPABILITY_COMPACT_BLOCKS;
 use sync::handler::import;
+use sync::handler::proofs;
 use sync::node_info::{NodeInfo, Mode};
+use sync::fork_id::ForkFilter;
 use sync::storage::SyncStorage;
-use sync::sync_provider::SyncStatus;
+use sync::sync_provider::{SyncStatus, peers_info, local_node_info};
 
-pub use sync::sync_provider::SyncProvider;
+pub use sync::sync_provider::{SyncProvider, PeerInfo, LocalNodeInfo};
+pub use p2p::{
+    CompressionStats, NetworkTraffic, PeerReputation, PeerTraffic, RouteTraffic, TrafficStats,
+};
+#[cfg(feature = "sim")]
+pub use p2p::{LinkConfig, SimNetwork, SimStats};
+pub use sync::light::LightSync;
 
 const INTERVAL_TRANSACTIONS_BROADCAST: u64 = 50;
 const INTERVAL_STATUS: u64 = 5000;
@@ -72,11 +87,65 @@ const INTERVAL_BODIES: u64 = 100;
 const INTERVAL_IMPORT: u64 = 50;
 const INTERVAL_STATISICS: u64 = 10;
 
+/// Token rules of the request/response pairs of the sync module
+fn token_rules() -> Vec<[u32; 2]> {
+    let sync_rule_base = ((PROTOCAL_VERSION as u32) << 16) + ((Module::SYNC.value() as u32) << 8);
+    vec![
+        [Action::STATUSREQ, Action::STATUSRES],
+        [Action::HEADERSREQ, Action::HEADERSRES],
+        [Action::BODIESREQ, Action::BODIESRES],
+        [Action::ACCOUNTPROOFREQ, Action::ACCOUNTPROOFRES],
+        [Action::STORAGEPROOFREQ, Action::STORAGEPROOFRES],
+        [Action::RECEIPTSREQ, Action::RECEIPTSRES],
+        [Action::EXECUTIONPROOFREQ, Action::EXECUTIONPROOFRES],
+        [Action::BLOCKTXSREQ, Action::BLOCKTXSRES],
+    ]
+    .iter()
+    .map(|pair| {
+        [
+            sync_rule_base + pair[0].value() as u32,
+            sync_rule_base + pair[1].value() as u32,
+        ]
+    })
+    .collect()
+}
+
+/// Add per peer message limits of sync requests and broadcasts, configured limits take precedence
+fn with_route_limits(mut config: Config) -> Config {
+    let limits = vec![
+        (Action::STATUSREQ, 5),
+        (Action::HEADERSREQ, 20),
+        (Action::BODIESREQ, 20),
+        (Action::BROADCASTTX, 200),
+        (Action::BROADCASTBLOCK, 20),
+        (Action::COMPACTBLOCK, 20),
+        (Action::BLOCKTXSREQ, 20),
+        (Action::ACCOUNTPROOFREQ, 50),
+        (Action::STORAGEPROOFREQ, 50),
+        (Action::RECEIPTSREQ, 50),
+        (Action::EXECUTIONPROOFREQ, 10),
+    ];
+    for (action, limit) in limits {
+        config
+            .rate_limit
+            .routes
+            .entry(route_key(Module::SYNC.value(), action.value()))
+            .or_insert(limit);
+    }
+    config
+}
+
 /// Sync manager
 pub struct Sync {
     /// Blockchain kernel interface
     client: Arc<BlockChainClient>,
 
+    /// Proof provider for light clients
+    prover: Arc<ProvingBlockChainClient>,
+
+    /// Execution proofs served off the network threads
+    execution_server: proofs::ExecutionServer,
+
     /// Oneshots to shutdown threads
     shutdown_hooks: Arc<Mutex<Vec<Sender<()>>>>,
 
@@ -94,38 +163,39 @@ pub struct Sync {
 
     /// network best block number
     network_best_block_number: Arc<RwLock<u64>>,
+
+    /// fork ids of the local chain
+    fork_filter: ForkFilter,
 }
 
 impl Sync {
-    /// constructor
-    pub fn new(config: Config, client: Arc<BlockChainClient>) -> Sync {
+    /// constructor, light clients are served executions with up to `max_proof_gas`
+    pub fn new(
+        config: Config,
+        client: Arc<BlockChainClient>,
+        prover: Arc<ProvingBlockChainClient>,
+        fork_blocks: Vec<u64>,
+        max_proof_gas: U256,
+    ) -> Sync
+    {
         let local_best_td: U256 = client.chain_info().total_difficulty;
         let local_best_block_number: u64 = client.chain_info().best_block_number;
-
-        let mut token_rules: Vec<[u32; 2]> = vec![];
-        let sync_rule_base =
-            ((PROTOCAL_VERSION as u32) << 16) + ((Module::SYNC.value() as u32) << 8);
-        token_rules.push([
-            sync_rule_base + Action::STATUSREQ.value() as u32,
-            sync_rule_base + Action::STATUSRES.value() as u32,
-        ]);
-        token_rules.push([
-            sync_rule_base + Action::HEADERSREQ.value() as u32,
-            sync_rule_base + Action::HEADERSRES.value() as u32,
-        ]);
-        token_rules.push([
-            sync_rule_base + Action::BODIESREQ.value() as u32,
-            sync_rule_base + Action::BODIESRES.value() as u32,
-        ]);
+        let fork_filter = ForkFilter::new(client.chain_info().genesis_hash, fork_blocks);
+        let p2p = Mgr::new(with_route_limits(config), token_rules());
+        let execution_server =
+            proofs::ExecutionServer::new(p2p.clone(), prover.clone(), max_proof_gas);
 
         Sync {
             client,
-            p2p: Mgr::new(config, token_rules),
+            prover,
+            execution_server,
+            p2p,
             shutdown_hooks: Arc::new(Mutex::new(Vec::new())),
             storage: Arc::new(SyncStorage::new()),
             node_info: Arc::new(RwLock::new(HashMap::new())),
             network_best_td: Arc::new(RwLock::new(local_best_td)),
             network_best_block_number: Arc::new(RwLock::new(local_best_block_number)),
+            fork_filter,
         }
     }
 
@@ -134,6 +204,10 @@ impl Sync {
         self.p2p.register_callback(callback);
     }
 
+    /// connect through simulated network instead of tcp, to be called before run
+    #[cfg(feature = "sim")]
+    pub fn set_network(&self, network: &SimNetwork) { self.p2p.set_network(network); }
+
     /// run sync instance
     pub fn run(&self, executor: TaskExecutor) {
         // init p2p
@@ -166,6 +240,8 @@ impl Sync {
                     debug!(target: "sync_statics", "recorded cache size/capacity {}/{}", recorded_blocks_size, recorded_blocks_capacity);
                     debug!(target: "sync_statics", "staged cache size/capacity {}/{}", staged_blocks_size, staged_blocks_capacity);
                     debug!(target: "sync_statics", "lightning syncing height: {}", storage_statics.lightning_base());
+                    let (in_flight, retries, buffered) = storage_statics.scheduler().statics();
+                    debug!(target: "sync_statics", "scheduled requests/retries/buffered {}/{}/{}", in_flight, retries, buffered);
                     info!(target: "sync_statics", "{:-^130}", "");
                     info!(target: "sync_statics", "                                 td         bn          bh                    addr                 rev      conn  seed       mode");
                     info!(target: "sync_statics", "{:-^130}", "");
@@ -262,12 +338,17 @@ impl Sync {
 
         // sync bodies thread
         let p2p_body = p2p.clone();
+        let node_info_body = self.node_info.clone();
         let storage_body = self.storage.clone();
         let (tx, rx) = oneshot::channel::<()>();
         executor.spawn(
             Interval::new(Instant::now(), Duration::from_millis(INTERVAL_BODIES))
                 .for_each(move |_| {
-                    bodies::sync_bodies(p2p_body.clone(), storage_body.clone());
+                    bodies::sync_bodies(
+                        p2p_body.clone(),
+                        node_info_body.clone(),
+                        storage_body.clone(),
+                    );
                     Ok(())
                 })
                 .map_err(|err| error!(target: "sync_bodies", "executor body: {:?}", err))
@@ -278,6 +359,7 @@ impl Sync {
         shutdown_hooks.push(tx);
 
         // import thread
+        let p2p_import = p2p.clone();
         let client_import = self.client.clone();
         let storage_import = self.storage.clone();
         let node_info_import = self.node_info.clone();
@@ -286,6 +368,7 @@ impl Sync {
             Interval::new(Instant::now(), Duration::from_millis(INTERVAL_IMPORT))
                 .for_each(move |_| {
                     import::import_blocks(
+                        p2p_import.clone(),
                         client_import.clone(),
                         storage_import.clone(),
                         node_info_import.clone(),
@@ -375,6 +458,20 @@ impl SyncProvider for Sync {
             num_peers: self.p2p.get_active_nodes_len() as usize,
         }
     }
+
+    fn peer_reputations(&self) -> Vec<PeerReputation> { self.p2p.get_reputations() }
+
+    fn traffic(&self) -> NetworkTraffic { self.p2p.get_traffic() }
+
+    fn peers(&self) -> Vec<PeerInfo> { peers_info(&self.p2p, &self.node_info) }
+
+    fn node_info(&self) -> Option<LocalNodeInfo> { Some(local_node_info(&self.p2p)) }
+
+    fn add_peer(&self, node: &str) -> Result<(), String> { self.p2p.add_peer(node) }
+
+    fn remove_peer(&self, peer: &str) -> bool { self.p2p.remove_peer(peer) }
+
+    fn ban_peer(&self, ip: &str) -> Result<(), String> { self.p2p.ban_peer(ip) }
 }
 
 impl ChainNotify for Sync {
@@ -420,7 +517,12 @@ impl ChainNotify for Sync {
         // Broadcast the new main-chain blocks unless the node is syncing
         if !self.is_syncing() && !enacted.is_empty() {
             trace!(target: "sync_notify", "Propagating blocks...");
-            broadcast::propagate_new_blocks(self.p2p.clone(), enacted, self.client.clone());
+            broadcast::propagate_new_blocks(
+                self.p2p.clone(),
+                enacted,
+                self.client.clone(),
+                self.node_info.clone(),
+            );
         }
     }
 
@@ -462,10 +564,17 @@ impl Callable for Sync {
                     // TODO: kill the node
                 }
                 let chain_info = &self.client.chain_info();
-                status::receive_req(p2p, chain_info, hash, cb.head.ver)
+                status::receive_req(
+                    p2p,
+                    chain_info,
+                    &self.fork_filter,
+                    hash,
+                    cb.head.ver,
+                    CAPABILITY_COMPACT_BLOCKS,
+                )
             }
             Action::STATUSRES => {
-                let genesis_hash = self.client.chain_info().genesis_hash;
+                let best_block_number = self.client.chain_info().best_block_number;
                 status::receive_res(
                     p2p,
                     self.node_info.clone(),
@@ -473,7 +582,8 @@ impl Callable for Sync {
                     cb,
                     self.network_best_td.clone(),
                     self.network_best_block_number.clone(),
-                    genesis_hash,
+                    &self.fork_filter,
+                    best_block_number,
                 )
             }
             Action::HEADERSREQ => {
@@ -486,6 +596,15 @@ impl Callable for Sync {
                 bodies::receive_req(p2p, hash, client, cb)
             }
             Action::BODIESRES => bodies::receive_res(p2p, hash, cb, self.storage.clone()),
+            Action::ACCOUNTPROOFREQ | Action::STORAGEPROOFREQ | Action::RECEIPTSREQ => {
+                proofs::receive_req(p2p, hash, self.prover.clone(), cb)
+            }
+            Action::EXECUTIONPROOFREQ => self.execution_server.queue(p2p, hash, cb),
+            // only light clients send proof requests
+            Action::ACCOUNTPROOFRES
+            | Action::STORAGEPROOFRES
+            | Action::RECEIPTSRES
+            | Action::EXECUTIONPROOFRES => (),
             Action::BROADCASTTX => {
                 let client = self.client.clone();
                 broadcast::handle_broadcast_tx(
@@ -505,10 +624,38 @@ impl Callable for Sync {
                     hash,
                     cb,
                     client,
+                    self.node_info.clone(),
+                    self.storage.clone(),
+                    self.network_best_block_number.clone(),
+                )
+            }
+            Action::COMPACTBLOCK => {
+                let client = self.client.clone();
+                compact::handle_compact_block(
+                    p2p,
+                    hash,
+                    cb,
+                    client,
+                    self.node_info.clone(),
                     self.storage.clone(),
                     self.network_best_block_number.clone(),
                 )
             }
+            Action::BLOCKTXSREQ => {
+                let client = self.client.clone();
+                compact::receive_req(p2p, hash, client, cb)
+            }
+            Action::BLOCKTXSRES => {
+                let client = self.client.clone();
+                compact::receive_res(
+                    p2p,
+                    hash,
+                    cb,
+                    client,
+                    self.node_info.clone(),
+                    self.storage.clone(),
+                )
+            }
             // TODO: kill the node
             Action::UNKNOWN => (),
         };
@@ -521,13 +668,20 @@ impl Callable for Sync {
         drop(node_info);
         trace!(target: "sync_disconnect", "finish dropping node_info");
 
+        self.storage.scheduler().peer_disconnected(hash);
+        trace!(target: "sync_disconnect", "finish requeueing scheduled headers");
+
+        // Requested bodies are requested from other nodes
         let mut headers = self.storage.headers_with_bodies_requested().lock();
-        headers.remove(&hash);
+        let headers_wrapper = headers.remove(&hash);
         drop(headers);
-        trace!(target: "sync_disconnect", "finish dropping headers_with_bodies_requested");
-
-        let mut headers = self.storage.downloaded_headers().lock();
-        headers.retain(|x| x.node_hash != hash);
+        if let Some(headers_wrapper) = headers_wrapper {
+            self.storage
+                .downloaded_headers()
+                .lock()
+                .push_front(headers_wrapper);
+        }
+        trace!(target: "sync_disconnect", "finish requeueing headers_with_bodies_requested");
 
         trace!(target: "sync_disconnect", "finish cleaning disconnected node: {}", &hash);
     }
@@ -60,6 +60,8 @@ pub struct NodeInfo {
     pub mode: Mode,
     /// base number for backward, forward and lightning syncing
     pub sync_base_number: u64,
+    /// node accepts compact blocks
+    pub compact_blocks: bool,
 }
 
 impl NodeInfo {
@@ -72,6 +74,7 @@ impl NodeInfo {
             last_broadcast_timestamp: UNIX_EPOCH,
             mode: Mode::Normal,
             sync_base_number: 0u64,
+            compact_blocks: false,
         }
     }
 
@@ -0,0 +1,463 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Download scheduler
+//!
+//! While the node is far behind the network, header ranges are split across all capable peers
+//! instead of one random peer per round. The scheduler tracks one in-flight header request per
+//! peer, re-queues ranges of timed out, partial and disconnected requests, and sizes each
+//! peer's batches after its throughput. Downloaded blocks are buffered and released to import
+//! in block number order, a batch whose predecessors do not show up is released anyway after a
+//! while so a lost range can not stall the import.
+
+use std::cmp;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+use sync::wrappers::BlocksWrapper;
+
+/// smallest header batch
+pub const MIN_BATCH_SIZE: u32 = 8;
+/// largest header batch, peers refuse larger requests
+pub const MAX_BATCH_SIZE: u32 = 44;
+/// header batch of peers without throughput records
+const INITIAL_BATCH_SIZE: u32 = 24;
+/// batch growth after a quick full response
+const BATCH_STEP: u32 = 4;
+/// header request timeout
+const HEADERS_TIMEOUT: Duration = Duration::from_secs(5);
+/// max distance of scheduled headers from the next block to import
+const MAX_AHEAD: u64 = MAX_BATCH_SIZE as u64 * 64;
+/// time a downloaded batch waits for its predecessors
+const REORDER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// in-flight header request
+#[derive(Clone, Debug, PartialEq)]
+pub struct Request {
+    /// first block number requested
+    pub from: u64,
+    /// number of headers requested
+    pub size: u32,
+    /// send time
+    pub sent: Instant,
+}
+
+/// peer download records
+#[derive(Clone, Debug)]
+struct PeerStats {
+    /// header batch size
+    batch_size: u32,
+    /// smoothed headers per second
+    throughput: f64,
+}
+
+impl PeerStats {
+    fn new() -> PeerStats {
+        PeerStats {
+            batch_size: INITIAL_BATCH_SIZE,
+            throughput: 0f64,
+        }
+    }
+
+    fn record(&mut self, received: usize, elapsed: Duration) {
+        let secs = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1e9;
+        let rate = received as f64 / secs.max(1e-3);
+        self.throughput = if self.throughput == 0f64 {
+            rate
+        } else {
+            self.throughput * 0.75 + rate * 0.25
+        };
+    }
+
+    fn grow(&mut self) { self.batch_size = cmp::min(self.batch_size + BATCH_STEP, MAX_BATCH_SIZE); }
+
+    fn shrink(&mut self) { self.batch_size = cmp::max(self.batch_size / 2, MIN_BATCH_SIZE); }
+}
+
+struct State {
+    /// next block number never scheduled
+    next_number: u64,
+    /// ranges to schedule again, size by first block number
+    retries: BTreeMap<u64, u32>,
+    /// in-flight header requests by peer
+    in_flight: HashMap<u64, Request>,
+    /// download records by peer
+    peers: HashMap<u64, PeerStats>,
+    /// downloaded batches by first block number, waiting for their predecessors
+    completed: BTreeMap<u64, Vec<BlocksWrapper>>,
+    /// next block number expected by import
+    next_import: u64,
+    /// last time a batch was released to import
+    last_release: Instant,
+}
+
+/// parallel header download scheduler
+pub struct DownloadScheduler {
+    state: Mutex<State>,
+}
+
+impl DownloadScheduler {
+    pub fn new() -> Self {
+        DownloadScheduler {
+            state: Mutex::new(State {
+                next_number: 1,
+                retries: BTreeMap::new(),
+                in_flight: HashMap::new(),
+                peers: HashMap::new(),
+                completed: BTreeMap::new(),
+                next_import: 1,
+                last_release: Instant::now(),
+            }),
+        }
+    }
+
+    /// order peers by throughput, fastest first
+    pub fn rank(&self, peers: &mut Vec<u64>) {
+        let state = self.state.lock();
+        let throughput = |peer: &u64| state.peers.get(peer).map_or(0f64, |stats| stats.throughput);
+        peers.sort_by(|a, b| {
+            throughput(b)
+                .partial_cmp(&throughput(a))
+                .unwrap_or(cmp::Ordering::Equal)
+        });
+    }
+
+    /// return true if peer has a header request in flight
+    pub fn is_busy(&self, peer: u64) -> bool { self.state.lock().in_flight.contains_key(&peer) }
+
+    /// assign the next header range to an idle peer, none if the peer is busy or has nothing to
+    /// offer
+    pub fn assign(&self, peer: u64, peer_best: u64, local_best: u64) -> Option<(u64, u32)> {
+        let mut state = self.state.lock();
+        if state.in_flight.contains_key(&peer) {
+            return None;
+        }
+
+        // forget what has been imported meanwhile
+        if state.next_number <= local_best {
+            state.next_number = local_best + 1;
+        }
+        if state.next_import <= local_best {
+            state.next_import = local_best + 1;
+        }
+        let stale: Vec<u64> = state
+            .retries
+            .range(..local_best + 1)
+            .map(|(from, _)| *from)
+            .collect();
+        for from in stale {
+            let size = state.retries.remove(&from).unwrap_or(0);
+            if from + size as u64 > local_best + 1 {
+                // merged with a range already pending there
+                retry(&mut state, local_best + 1, (from + size as u64 - local_best - 1) as u32);
+            }
+        }
+
+        let batch_size = state
+            .peers
+            .entry(peer)
+            .or_insert_with(PeerStats::new)
+            .batch_size;
+
+        // retry lost ranges first
+        let retry = state
+            .retries
+            .iter()
+            .map(|(from, size)| (*from, *size))
+            .find(|&(from, _)| from <= peer_best);
+        let (from, size) = match retry {
+            Some((from, size)) => {
+                state.retries.remove(&from);
+                let assigned = cmp::min(size, batch_size);
+                if assigned < size {
+                    state.retries.insert(from + assigned as u64, size - assigned);
+                }
+                (from, assigned)
+            }
+            None => {
+                let from = state.next_number;
+                if from > peer_best || from >= state.next_import + MAX_AHEAD {
+                    return None;
+                }
+                let size = cmp::min(batch_size as u64, peer_best - from + 1) as u32;
+                state.next_number = from + size as u64;
+                (from, size)
+            }
+        };
+
+        state.in_flight.insert(
+            peer,
+            Request {
+                from,
+                size,
+                sent: Instant::now(),
+            },
+        );
+        Some((from, size))
+    }
+
+    /// give up a request which could not be sent
+    pub fn cancel(&self, peer: u64) {
+        let mut state = self.state.lock();
+        if let Some(request) = state.in_flight.remove(&peer) {
+            retry(&mut state, request.from, request.size);
+        }
+    }
+
+    /// record header response with `received` consecutive headers starting at `first`
+    pub fn headers_received(&self, peer: u64, first: u64, received: usize) {
+        let mut state = self.state.lock();
+        let request = match state.in_flight.remove(&peer) {
+            Some(request) => request,
+            None => return,
+        };
+        // response to an earlier unscheduled request
+        let received = if first == request.from { received } else { 0 };
+        let received = cmp::min(received, request.size as usize) as u32;
+        if received < request.size {
+            retry(&mut state, request.from + received as u64, request.size - received);
+        }
+
+        let elapsed = request.sent.elapsed();
+        let stats = state.peers.entry(peer).or_insert_with(PeerStats::new);
+        stats.record(received as usize, elapsed);
+        if received == request.size && elapsed < HEADERS_TIMEOUT / 4 {
+            stats.grow();
+        } else if received < request.size {
+            stats.shrink();
+        }
+    }
+
+    /// re-queue ranges of timed out requests, return peers which timed out
+    pub fn expire(&self) -> Vec<u64> {
+        let mut state = self.state.lock();
+        let expired: Vec<u64> = state
+            .in_flight
+            .iter()
+            .filter(|(_, request)| request.sent.elapsed() >= HEADERS_TIMEOUT)
+            .map(|(peer, _)| *peer)
+            .collect();
+        for peer in &expired {
+            if let Some(request) = state.in_flight.remove(peer) {
+                retry(&mut state, request.from, request.size);
+            }
+            if let Some(stats) = state.peers.get_mut(peer) {
+                stats.shrink();
+            }
+        }
+        expired
+    }
+
+    /// re-queue range of a disconnected peer and forget it
+    pub fn peer_disconnected(&self, peer: u64) {
+        let mut state = self.state.lock();
+        if let Some(request) = state.in_flight.remove(&peer) {
+            retry(&mut state, request.from, request.size);
+        }
+        state.peers.remove(&peer);
+    }
+
+    /// buffer downloaded blocks until their predecessors are released
+    pub fn blocks_downloaded(&self, blocks_wrapper: BlocksWrapper) {
+        let first = match blocks_wrapper.blocks.first() {
+            Some(block) => block.header.number(),
+            None => return,
+        };
+        let mut state = self.state.lock();
+        state
+            .completed
+            .entry(first)
+            .or_insert_with(Vec::new)
+            .push(blocks_wrapper);
+    }
+
+    /// take batches ready for import in block number order
+    pub fn release(&self, local_best: u64) -> Vec<BlocksWrapper> {
+        let mut state = self.state.lock();
+        if state.next_import <= local_best {
+            state.next_import = local_best + 1;
+        }
+        let mut released = Vec::new();
+        loop {
+            let first = match state.completed.keys().next() {
+                Some(first) => *first,
+                None => break,
+            };
+            // release out of order after a while, lost ranges are downloaded again
+            if first > state.next_import && state.last_release.elapsed() < REORDER_TIMEOUT {
+                break;
+            }
+            if let Some(batches) = state.completed.remove(&first) {
+                for batch in batches {
+                    if let Some(last) = batch.blocks.last() {
+                        let next = last.header.number() + 1;
+                        if next > state.next_import {
+                            state.next_import = next;
+                        }
+                    }
+                    released.push(batch);
+                }
+            }
+            state.last_release = Instant::now();
+        }
+        if state.completed.is_empty() {
+            state.last_release = Instant::now();
+        }
+        released
+    }
+
+    /// number of in-flight requests, queued retries and buffered batches
+    pub fn statics(&self) -> (usize, usize, usize) {
+        let state = self.state.lock();
+        (state.in_flight.len(), state.retries.len(), state.completed.len())
+    }
+}
+
+/// queue range to be scheduled again
+fn retry(state: &mut State, from: u64, size: u32) {
+    if size > 0 {
+        let pending = state.retries.entry(from).or_insert(size);
+        *pending = cmp::max(*pending, size);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use block::Block;
+
+    fn blocks_wrapper(from: u64, count: u64) -> BlocksWrapper {
+        let mut blocks_wrapper = BlocksWrapper::new();
+        for number in from..from + count {
+            let mut block = Block::default();
+            block.header.set_number(number);
+            blocks_wrapper.blocks.push(block);
+        }
+        blocks_wrapper
+    }
+
+    #[test]
+    fn test_assign_splits_ranges() {
+        let scheduler = DownloadScheduler::new();
+        assert_eq!(scheduler.assign(1, 1000, 100), Some((101, INITIAL_BATCH_SIZE)));
+        assert_eq!(scheduler.assign(2, 1000, 100), Some((125, INITIAL_BATCH_SIZE)));
+        // one request in flight per peer
+        assert_eq!(scheduler.assign(1, 1000, 100), None);
+        // nothing beyond the peer best block
+        assert_eq!(scheduler.assign(3, 140, 100), None);
+        assert_eq!(scheduler.statics(), (2, 0, 0));
+    }
+
+    #[test]
+    fn test_partial_and_lost_ranges_retried() {
+        let scheduler = DownloadScheduler::new();
+        assert_eq!(scheduler.assign(1, 1000, 0), Some((1, 24)));
+        assert_eq!(scheduler.assign(2, 1000, 0), Some((25, 24)));
+
+        // partial response, the missing tail is retried first
+        scheduler.headers_received(1, 1, 10);
+        assert_eq!(scheduler.assign(3, 1000, 0), Some((11, 14)));
+
+        // disconnected peer range is retried, in smaller batches after the partial response
+        scheduler.peer_disconnected(2);
+        assert_eq!(scheduler.assign(1, 1000, 0), Some((25, 12)));
+        assert_eq!(scheduler.assign(4, 1000, 0), Some((37, 12)));
+
+        // response to another request does not complete the range
+        scheduler.headers_received(4, 1, 12);
+        assert_eq!(scheduler.assign(5, 1000, 0), Some((37, 12)));
+
+        // cancelled request is retried
+        scheduler.cancel(5);
+        assert_eq!(scheduler.statics(), (2, 1, 0));
+    }
+
+    #[test]
+    fn test_stale_retry_kept_with_pending_one() {
+        let scheduler = DownloadScheduler::new();
+        {
+            let mut state = scheduler.state.lock();
+            state.retries.insert(5, 10);
+            state.retries.insert(10, 20);
+        }
+        assert_eq!(scheduler.assign(1, 1000, 9), Some((10, 20)));
+
+        let scheduler = DownloadScheduler::new();
+        {
+            let mut state = scheduler.state.lock();
+            state.retries.insert(5, 20);
+            state.retries.insert(8, 3);
+        }
+        assert_eq!(scheduler.assign(1, 1000, 9), Some((10, 15)));
+    }
+
+    #[test]
+    fn test_batch_size_adapts() {
+        let scheduler = DownloadScheduler::new();
+        assert_eq!(scheduler.assign(1, 1000, 0), Some((1, 24)));
+        scheduler.headers_received(1, 1, 24);
+        assert_eq!(scheduler.assign(1, 1000, 0), Some((25, 28)));
+        scheduler.headers_received(1, 25, 0);
+        assert_eq!(scheduler.assign(1, 1000, 0), Some((25, 14)));
+        scheduler.headers_received(1, 25, 14);
+
+        for _ in 0..20 {
+            let (from, size) = scheduler.assign(1, 10000, 0).unwrap();
+            scheduler.headers_received(1, from, size as usize);
+        }
+        let (_, size) = scheduler.assign(1, 10000, 0).unwrap();
+        assert_eq!(size, MAX_BATCH_SIZE);
+    }
+
+    #[test]
+    fn test_rank() {
+        let scheduler = DownloadScheduler::new();
+        scheduler.assign(1, 1000, 0);
+        scheduler.assign(2, 1000, 0);
+        scheduler.headers_received(2, 25, 24);
+        let mut peers = vec![1, 2, 3];
+        scheduler.rank(&mut peers);
+        assert_eq!(peers[0], 2);
+    }
+
+    #[test]
+    fn test_release_in_order() {
+        let scheduler = DownloadScheduler::new();
+        scheduler.blocks_downloaded(blocks_wrapper(21, 10));
+        scheduler.blocks_downloaded(blocks_wrapper(31, 10));
+        assert!(scheduler.release(0).is_empty());
+
+        scheduler.blocks_downloaded(blocks_wrapper(1, 20));
+        let released: Vec<u64> = scheduler
+            .release(0)
+            .iter()
+            .map(|blocks_wrapper| blocks_wrapper.blocks[0].header.number())
+            .collect();
+        assert_eq!(released, vec![1, 21, 31]);
+
+        // overlapping batches are released at once
+        scheduler.blocks_downloaded(blocks_wrapper(35, 10));
+        assert_eq!(scheduler.release(40).len(), 1);
+        assert_eq!(scheduler.statics(), (0, 0, 0));
+    }
+}
@@ -24,7 +24,8 @@ use lru_cache::LruCache;
 use parking_lot::{Mutex, RwLock};
 
 use aion_types::H256;
-use sync::wrappers::{HeadersWrapper, BlocksWrapper};
+use sync::scheduler::DownloadScheduler;
+use sync::wrappers::{HeadersWrapper, BlocksWrapper, CompactBlockWrapper};
 
 // const MAX_DOWNLOADED_HEADERS_COUNT: usize = 4096;
 const MAX_CACHED_BLOCK_HASHES: usize = 32;
@@ -56,6 +57,12 @@ pub struct SyncStorage {
 
     // Lightning sync block height
     lightning_base: RwLock<u64>,
+
+    /// Parallel download scheduler
+    scheduler: DownloadScheduler,
+
+    /// Compact blocks waiting for their missing transactions
+    compact_blocks: Mutex<HashMap<H256, CompactBlockWrapper>>,
 }
 
 impl SyncStorage {
@@ -69,9 +76,17 @@ impl SyncStorage {
             recorded_transaction_hashes: Mutex::new(LruCache::new(MAX_CACHED_TRANSACTION_HASHES)),
             received_transactions: Mutex::new(VecDeque::new()),
             lightning_base: RwLock::new(0u64),
+            scheduler: DownloadScheduler::new(),
+            compact_blocks: Mutex::new(HashMap::new()),
         }
     }
 
+    pub fn scheduler(&self) -> &DownloadScheduler { &self.scheduler }
+
+    pub fn compact_blocks(&self) -> &Mutex<HashMap<H256, CompactBlockWrapper>> {
+        &self.compact_blocks
+    }
+
     pub fn downloaded_headers(&self) -> &Mutex<VecDeque<HeadersWrapper>> {
         &self.downloaded_headers
     }
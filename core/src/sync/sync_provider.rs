@@ -19,10 +19,111 @@
  *
  ******************************************************************************/
 
+use std::collections::HashMap;
+use std::time::Duration;
+
+use aion_types::U256;
+use p2p::{Mgr, NetworkTraffic, PeerReputation};
+use parking_lot::RwLock;
+use sync::node_info::NodeInfo;
+
 /// to provide sync info to rpc module
 pub trait SyncProvider: Send + Sync {
     /// Get sync status for rpc request
     fn status(&self) -> SyncStatus;
+
+    /// Get reputation of known peers
+    fn peer_reputations(&self) -> Vec<PeerReputation> { Vec::new() }
+
+    /// Get inbound traffic counters
+    fn traffic(&self) -> NetworkTraffic { NetworkTraffic::default() }
+
+    /// Get info of active peers
+    fn peers(&self) -> Vec<PeerInfo> { Vec::new() }
+
+    /// Get local node info
+    fn node_info(&self) -> Option<LocalNodeInfo> { None }
+
+    /// Connect to node `p2p://<id>@<ip>:<port>`
+    fn add_peer(&self, _node: &str) -> Result<(), String> { Err("Not supported".into()) }
+
+    /// Disconnect peer by node id or address, return false if it is unknown
+    fn remove_peer(&self, _peer: &str) -> bool { false }
+
+    /// Ban ip and disconnect its peers
+    fn ban_peer(&self, _ip: &str) -> Result<(), String> { Err("Not supported".into()) }
+}
+
+/// Active peer info
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeerInfo {
+    /// node id
+    pub id: String,
+    /// connected address
+    pub addr: String,
+    /// revision reported in handshake
+    pub revision: String,
+    /// connection direction, inbound or outbound
+    pub direction: String,
+    /// best block number
+    pub best_block_number: u64,
+    /// total difficulty
+    pub total_difficulty: U256,
+    /// syncing mode
+    pub mode: String,
+    /// request/response round trip time
+    pub latency: Option<Duration>,
+}
+
+/// Local node info
+#[derive(Clone, Debug, PartialEq)]
+pub struct LocalNodeInfo {
+    /// node id
+    pub id: String,
+    /// node url, `p2p://<id>@<ip>:<port>`
+    pub url: String,
+    /// listening address
+    pub listen_addr: String,
+    /// network id
+    pub net_id: u32,
+}
+
+/// Collect info of active peers with their sync state
+pub fn peers_info(p2p: &Mgr, node_info: &RwLock<HashMap<u64, RwLock<NodeInfo>>>) -> Vec<PeerInfo> {
+    let nodes = p2p.get_active_nodes();
+    let node_info = node_info.read();
+    nodes
+        .into_iter()
+        .map(|node| {
+            let info = node_info
+                .get(&node.hash)
+                .map(|info| info.read().clone())
+                .unwrap_or_else(NodeInfo::new);
+            PeerInfo {
+                id: node.get_id_string(),
+                addr: node.addr.to_string(),
+                revision: String::from_utf8_lossy(&node.revision)
+                    .trim_matches(|c| c == ' ' || c == '\u{0}')
+                    .to_owned(),
+                direction: format!("{}", node.connection),
+                best_block_number: info.best_block_number,
+                total_difficulty: info.total_difficulty,
+                mode: format!("{}", info.mode),
+                latency: node.latency,
+            }
+        })
+        .collect()
+}
+
+/// Collect local node info
+pub fn local_node_info(p2p: &Mgr) -> LocalNodeInfo {
+    let (id, listen_addr) = p2p.get_local_node_id_and_binding();
+    LocalNodeInfo {
+        id,
+        url: p2p.get_local_node_info().clone(),
+        listen_addr,
+        net_id: p2p.get_net_id(),
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -22,6 +22,7 @@
 use std::time::SystemTime;
 use header::Header;
 use block::Block;
+use transaction::UnverifiedTransaction;
 
 #[derive(Clone, PartialEq)]
 pub struct HeadersWrapper {
@@ -56,3 +57,24 @@ impl BlocksWrapper {
         }
     }
 }
+
+#[derive(Clone, PartialEq)]
+pub struct CompactBlockWrapper {
+    pub node_hash: u64,
+    pub timestamp: SystemTime,
+    pub header: Header,
+    pub transactions: Vec<Option<UnverifiedTransaction>>,
+    pub requested_all: bool,
+}
+
+impl CompactBlockWrapper {
+    pub fn new(node_hash: u64, header: Header) -> Self {
+        CompactBlockWrapper {
+            node_hash,
+            timestamp: SystemTime::now(),
+            header,
+            transactions: Vec::new(),
+            requested_all: false,
+        }
+    }
+}
@@ -24,11 +24,12 @@ use std::str::FromStr;
 use std::sync::Arc;
 use io::IoChannel;
 use client::{BlockChainClient, MiningBlockChainClient, Client, ClientConfig, BlockId};
+use client::{DifferentialConfig, DifferentialExecutor};
 use state::{CleanupMode};
-use block::IsBlock;
+use block::{IsBlock, LockedBlock, OpenBlock};
 use types::filter::Filter;
 use aion_types::{Address, U256};
-use kvdb::{DatabaseConfig, DbRepository, RepositoryConfig};
+use kvdb::{DatabaseConfig, DbRepository, MockDbRepository, RepositoryConfig};
 use miner::Miner;
 use spec::Spec;
 use views::BlockView;
@@ -36,7 +37,14 @@ use key::Ed25519Secret;
 use transaction::{PendingTransaction, Transaction, Action, Condition};
 use miner::MinerService;
 use tempdir::TempDir;
+use snapshot::{PackedReader, PackedWriter, SnapshotReader, SnapshotWriter};
+use std::path::Path;
+use acore_bytes::Bytes;
+use blake2b::blake2b;
+use rlp::{RlpStream, UntrustedRlp};
 use helpers::*;
+use tests::common::TestBlockChainClient;
+use vms::FastVMBackend;
 use aion_types::H256;
 
 #[test]
@@ -235,6 +243,218 @@ fn imports_block_sequence() {
     assert!(!block.into_inner().is_empty());
 }
 
+#[test]
+fn snapshot_restores_into_fresh_client() {
+    let tempdir = TempDir::new("").unwrap();
+    let path = tempdir.path().join("snapshot");
+    let client = generate_dummy_client(20);
+    client
+        .take_snapshot(PackedWriter::new(&path).unwrap(), BlockId::Latest)
+        .unwrap();
+
+    let restored = fresh_client();
+    let reader = PackedReader::new(&path).unwrap();
+    restored.restore_snapshot(&reader).unwrap();
+
+    assert_eq!(restored.chain_info().best_block_number, 20);
+    assert_eq!(
+        restored.chain_info().best_block_hash,
+        client.chain_info().best_block_hash
+    );
+    assert_eq!(
+        restored.block_header(BlockId::Latest).unwrap().state_root(),
+        client.block_header(BlockId::Latest).unwrap().state_root()
+    );
+    assert_eq!(
+        restored.block_total_difficulty(BlockId::Latest),
+        client.block_total_difficulty(BlockId::Latest)
+    );
+
+    // a second restore is refused.
+    assert!(restored.restore_snapshot(&reader).is_err());
+}
+
+fn fresh_client() -> Arc<Client> {
+    let spec = get_test_spec();
+    Client::new(
+        ClientConfig::default(),
+        &spec,
+        new_db(),
+        Arc::new(Miner::with_spec(&spec)),
+        IoChannel::disconnected(),
+    )
+    .unwrap()
+}
+
+// Copy the snapshot at `from` to `to`, rewriting the parent total difficulty and the
+// blocks of each block chunk with `f`. Chunk hashes are recomputed like a forger would.
+fn rewrite_snapshot<F>(from: &Path, to: &Path, f: F)
+where F: Fn(&mut U256, &mut Vec<Bytes>)
+{
+    let reader = PackedReader::new(from).unwrap();
+    let mut manifest = reader.manifest().clone();
+    let mut writer = PackedWriter::new(to).unwrap();
+    for hash in &manifest.state_hashes {
+        writer
+            .write_state_chunk(*hash, &reader.chunk(*hash).unwrap())
+            .unwrap();
+    }
+    let mut block_hashes = Vec::new();
+    for hash in &manifest.block_hashes {
+        let chunk = reader.chunk(*hash).unwrap();
+        let rlp = UntrustedRlp::new(&chunk);
+        let mut parent_td: U256 = rlp.val_at(0).unwrap();
+        let mut blocks: Vec<Bytes> = rlp
+            .at(1)
+            .unwrap()
+            .iter()
+            .map(|entry| entry.as_raw().to_vec())
+            .collect();
+        f(&mut parent_td, &mut blocks);
+
+        let mut stream = RlpStream::new_list(2);
+        stream.append(&parent_td);
+        stream.begin_list(blocks.len());
+        for block in &blocks {
+            stream.append_raw(block, 1);
+        }
+        let chunk = stream.out();
+        let hash = blake2b(&chunk);
+        writer.write_block_chunk(hash, &chunk).unwrap();
+        block_hashes.push(hash);
+    }
+    manifest.block_hashes = block_hashes;
+    writer.finish(manifest).unwrap();
+}
+
+#[test]
+fn snapshot_with_forged_chain_is_refused() {
+    let tempdir = TempDir::new("").unwrap();
+    let path = tempdir.path().join("snapshot");
+    let client = generate_dummy_client(20);
+    client
+        .take_snapshot(PackedWriter::new(&path).unwrap(), BlockId::Latest)
+        .unwrap();
+
+    // a block missing from the chain
+    let forged = tempdir.path().join("gap");
+    rewrite_snapshot(&path, &forged, |_, blocks| {
+        blocks.remove(5);
+    });
+    let restored = fresh_client();
+    assert!(
+        restored
+            .restore_snapshot(&PackedReader::new(&forged).unwrap())
+            .is_err()
+    );
+    assert_eq!(restored.chain_info().best_block_number, 0);
+
+    // an inflated total difficulty
+    let forged = tempdir.path().join("difficulty");
+    rewrite_snapshot(&path, &forged, |parent_td, _| {
+        *parent_td = *parent_td + U256::from(1_000_000);
+    });
+    let restored = fresh_client();
+    assert!(
+        restored
+            .restore_snapshot(&PackedReader::new(&forged).unwrap())
+            .is_err()
+    );
+    assert_eq!(restored.chain_info().best_block_number, 0);
+
+    // a chain that does not start from genesis
+    let forged = tempdir.path().join("headless");
+    rewrite_snapshot(&path, &forged, |_, blocks| {
+        blocks.remove(0);
+    });
+    let restored = fresh_client();
+    assert!(
+        restored
+            .restore_snapshot(&PackedReader::new(&forged).unwrap())
+            .is_err()
+    );
+}
+
+#[test]
+fn differential_execution_agrees_on_good_blocks() {
+    let tempdir = TempDir::new("").unwrap();
+    let report_path = tempdir.path().join("divergence.jsonl");
+    let spec = get_test_spec();
+    let mut config = ClientConfig::default();
+    config.differential = Some(DifferentialConfig {
+        fvm_backend: FastVMBackend::Interpreter,
+        report_path: report_path.clone(),
+    });
+    let client = Client::new(
+        config,
+        &spec,
+        new_db(),
+        Arc::new(Miner::with_spec(&spec)),
+        IoChannel::disconnected(),
+    )
+    .unwrap();
+
+    for block in get_good_dummy_block_seq(3) {
+        client.import_block(block).unwrap();
+    }
+    client.flush_queue();
+    client.import_verified_blocks();
+
+    assert_eq!(client.chain_info().best_block_number, 3);
+    assert!(!report_path.exists());
+}
+
+#[test]
+fn differential_execution_reports_failures() {
+    let tempdir = TempDir::new("").unwrap();
+    let report_path = tempdir.path().join("divergence.jsonl");
+    let spec = Spec::new_test();
+    let genesis_header = spec.genesis_header();
+    let client = TestBlockChainClient::new_with_spec(spec.clone());
+    let db = spec
+        .ensure_db_good(get_temp_state_db(), &Default::default())
+        .unwrap();
+    let block = OpenBlock::new(
+        &*spec.engine,
+        Default::default(),
+        db,
+        &genesis_header,
+        Default::default(),
+        None,
+        None,
+        Arc::new(vec![genesis_header.hash()]),
+        Address::zero(),
+        (3141562.into(), 31415620.into()),
+        vec![],
+        Arc::new(MockDbRepository::init(vec![])),
+        None,
+        &client,
+    )
+    .unwrap()
+    .close_and_lock();
+
+    let differential = DifferentialExecutor::new(
+        DifferentialConfig {
+            fvm_backend: FastVMBackend::Interpreter,
+            report_path: report_path.clone(),
+        },
+        &Default::default(),
+    );
+    let header = block.header().clone();
+    assert!(differential.check(&header, &block, Ok(block.clone())).is_none());
+
+    let report = differential
+        .check(&header, &block, Err::<LockedBlock, _>("execution failed"))
+        .unwrap();
+    assert_eq!(report.block_number, header.number());
+    assert_eq!(report.mismatches.len(), 1);
+    assert_eq!(report.mismatches[0].field, "execution");
+
+    let written = ::std::fs::read_to_string(&report_path).unwrap();
+    assert_eq!(written.lines().count(), 1);
+    assert!(written.contains("\"fvm_backend\":\"interpreter\""));
+}
+
 #[test]
 fn can_client_collect_garbage() {
     let client = generate_dummy_client(100);
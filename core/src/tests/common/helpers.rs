@@ -43,6 +43,8 @@ use kvdb::{MockDbRepository, DBTransaction, KeyValueDB};
 use machine::EthereumMachine;
 use std::collections::BTreeMap;
 use db;
+use factory::{Factories, VmFactory};
+use vms::FastVMBackend;
 use tests::common::TestBlockChainClient;
 
 pub fn make_aion_machine() -> EthereumMachine {
@@ -453,6 +455,23 @@ pub fn get_temp_state() -> State<::db::StateDB> {
     )
 }
 
+/// Temporary state executing FastVM code with the given backend.
+pub fn get_temp_state_with_fvm(backend: FastVMBackend) -> State<::db::StateDB> {
+    let journal_db = get_temp_state_db();
+    let factories = Factories {
+        vm: VmFactory::with_fvm_backend(backend),
+        ..Default::default()
+    };
+    State::new(
+        journal_db,
+        U256::from(0),
+        factories,
+        Arc::new(MockDbRepository::init(vec![String::from(
+            ::db::COL_AVM_GRAPH,
+        )])),
+    )
+}
+
 pub fn get_temp_state_with_nonce() -> State<::db::StateDB> {
     let journal_db = get_temp_state_db();
     State::new(
@@ -21,5 +21,7 @@
 pub mod helpers;
 pub mod test_client;
 pub mod null_engine;
+#[cfg(feature = "sim")]
+pub mod sync_network;
 
 pub use self::test_client::{EachBlockWith, TestBlockChainClient};
@@ -66,6 +66,10 @@ impl Engine for NullEngine {
 
     fn machine(&self) -> &EthereumMachine { &self.machine }
 
+    fn block_reward(&self, _header: &<EthereumMachine as Machine>::Header) -> U256 {
+        self.params.block_reward
+    }
+
     fn on_close_block(
         &self,
         block: &mut <EthereumMachine as Machine>::LiveBlock,
@@ -0,0 +1,162 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Multi-node sync harness
+//!
+//! Runs several client and sync instances in this process, connected through a simulated p2p
+//! network whose links and partitions are controlled by the test.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use aion_types::{H256, U256};
+use client::{BlockChainClient, ChainNotify, Client};
+use p2p::Config;
+use sync::{SimNetwork, Sync};
+use tokio::runtime::{Builder, Runtime};
+
+const EMPTY_ID: &str = "00000000-0000-0000-0000-000000000000";
+const INTERVAL_IMPORT: u64 = 50;
+const INTERVAL_POLL: u64 = 100;
+
+/// listening address of the node at `index`
+pub fn binding(index: usize) -> String { format!("10.0.0.{}:30303", index + 1) }
+
+/// poll `condition` until it holds, false if it doesn't within `timeout`
+pub fn wait_until<F>(timeout: Duration, condition: F) -> bool
+where
+    F: Fn() -> bool,
+{
+    let start = Instant::now();
+    loop {
+        if condition() {
+            return true;
+        }
+        if start.elapsed() >= timeout {
+            return false;
+        }
+        thread::sleep(Duration::from_millis(INTERVAL_POLL));
+    }
+}
+
+/// node of the simulated network
+pub struct SimNode {
+    pub client: Arc<Client>,
+    pub sync: Arc<Sync>,
+    pub binding: String,
+}
+
+impl SimNode {
+    pub fn best_block_hash(&self) -> H256 { self.client.chain_info().best_block_hash }
+
+    pub fn best_block_number(&self) -> u64 { self.client.chain_info().best_block_number }
+}
+
+/// nodes sharing one simulated network
+pub struct SimCluster {
+    pub network: SimNetwork,
+    pub nodes: Vec<SimNode>,
+    running: Arc<AtomicBool>,
+    runtime: Runtime,
+}
+
+impl SimCluster {
+    /// create a node per client booting from all nodes before it, `seed` drives message loss
+    pub fn new(seed: u64, clients: Vec<Arc<Client>>) -> SimCluster {
+        let network = SimNetwork::new(seed);
+        let mut nodes = Vec::new();
+        for (index, client) in clients.into_iter().enumerate() {
+            let mut config = Config::new();
+            config.local_node = format!("p2p://{}@{}", EMPTY_ID, binding(index));
+            config.boot_nodes = (0..index)
+                .map(|boot| format!("p2p://{}@{}", EMPTY_ID, binding(boot)))
+                .collect();
+            let sync = Arc::new(Sync::new(
+                config,
+                client.clone(),
+                client.clone(),
+                Vec::new(),
+                U256::from(10_000_000),
+            ));
+            sync.register_callback(Arc::downgrade(&sync));
+            client.add_notify(sync.clone() as Arc<ChainNotify>);
+            sync.set_network(&network);
+            nodes.push(SimNode {
+                client,
+                sync,
+                binding: binding(index),
+            });
+        }
+        let runtime = Builder::new()
+            .name_prefix("sim-p2p #")
+            .build()
+            .expect("sim runtime init failed");
+        SimCluster {
+            network,
+            nodes,
+            running: Arc::new(AtomicBool::new(true)),
+            runtime,
+        }
+    }
+
+    /// start all nodes
+    pub fn run(&self) {
+        for node in &self.nodes {
+            node.sync.run(self.runtime.executor());
+
+            // test clients have no io service importing verified blocks
+            let client = node.client.clone();
+            let running = self.running.clone();
+            thread::spawn(move || {
+                while running.load(Ordering::SeqCst) {
+                    client.import_verified_blocks();
+                    thread::sleep(Duration::from_millis(INTERVAL_IMPORT));
+                }
+            });
+        }
+    }
+
+    /// split nodes into groups of node indexes
+    pub fn partition(&self, groups: &[&[usize]]) {
+        self.network.partition(
+            groups
+                .iter()
+                .map(|group| group.iter().map(|index| binding(*index)).collect())
+                .collect(),
+        );
+    }
+
+    /// true if all nodes have the same best block
+    pub fn is_synced(&self) -> bool {
+        let best = self.nodes[0].best_block_hash();
+        self.nodes.iter().all(|node| node.best_block_hash() == best)
+    }
+}
+
+impl Drop for SimCluster {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        for node in &self.nodes {
+            node.sync.shutdown();
+        }
+    }
+}
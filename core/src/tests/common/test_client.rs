@@ -48,7 +48,7 @@ use header::{BlockNumber, Header as BlockHeader, SealType};
 use itertools::Itertools;
 use journaldb;
 use key::{generate_keypair, public_to_address_ed25519};
-use kvdb::{DatabaseConfig, DbRepository, RepositoryConfig, KeyValueDB, MockDbRepository};
+use kvdb::{DatabaseConfig, DBValue, DbRepository, RepositoryConfig, KeyValueDB, MockDbRepository};
 use log_entry::LocalizedLogEntry;
 use miner::{Miner, MinerService};
 use parking_lot::RwLock;
@@ -95,6 +95,8 @@ pub struct TestBlockChainClient {
     pub storage: RwLock<HashMap<(Address, H128), H128>>,
     /// Code.
     pub code: RwLock<HashMap<Address, Bytes>>,
+    /// Coinbases of the registered stakers, by signing address.
+    pub coinbases: RwLock<HashMap<Address, Address>>,
     /// Execution result.
     pub execution_result: RwLock<Option<Result<Executed, CallError>>>,
     /// Transaction receipts.
@@ -115,8 +117,10 @@ pub struct TestBlockChainClient {
     pub ancient_block: RwLock<Option<(H256, u64)>>,
     /// First block info.
     pub first_block: RwLock<Option<(H256, u64)>>,
-    /// Pruning history size to report.
+    /// Pruning history size to report, stakes are not available below it.
     pub history: RwLock<Option<u64>>,
+    /// Number of stake lookups.
+    pub stake_calls: AtomicUsize,
     // db
     pub db: Arc<KeyValueDB>,
 }
@@ -167,6 +171,7 @@ impl TestBlockChainClient {
             nonces: RwLock::new(HashMap::new()),
             storage: RwLock::new(HashMap::new()),
             code: RwLock::new(HashMap::new()),
+            coinbases: RwLock::new(HashMap::new()),
             execution_result: RwLock::new(None),
             receipts: RwLock::new(HashMap::new()),
             logs: RwLock::new(Vec::new()),
@@ -178,6 +183,7 @@ impl TestBlockChainClient {
             ancient_block: RwLock::new(None),
             first_block: RwLock::new(None),
             history: RwLock::new(None),
+            stake_calls: AtomicUsize::new(0),
             db: Arc::new(MockDbRepository::init(vec![])),
         };
 
@@ -507,13 +513,20 @@ impl BlockChainClient for TestBlockChainClient {
         Ok(res)
     }
 
-    fn get_stake(&self, _pk: &H256, _a: Address, _block_id: BlockId) -> Option<BigUint> {
+    fn get_stake(&self, _pk: &H256, _a: Address, block_id: BlockId) -> Option<BigUint> {
+        self.stake_calls.fetch_add(1, AtomicOrder::SeqCst);
+        let number = self.block_number(block_id)?;
+        if number < self.pruning_info().earliest_state {
+            return None;
+        }
         Some(BigUint::from(10000u32))
     }
 
     fn get_total_stake(&self, _id: BlockId) -> Option<U256> { Some(U256::from(10000u32)) }
 
-    fn get_coinbase(&self, _address: Address) -> Option<Address> { None }
+    fn get_coinbase(&self, address: Address) -> Option<Address> {
+        self.coinbases.read().get(&address).cloned()
+    }
 
     fn estimate_gas(&self, _t: &SignedTransaction, _block: BlockId) -> Result<U256, CallError> {
         Ok(21000.into())
@@ -527,11 +540,11 @@ impl BlockChainClient for TestBlockChainClient {
         &self,
         _block: BlockId,
         _analytics: CallAnalytics,
-    ) -> Result<Box<Iterator<Item = Executed>>, CallError>
+    ) -> Result<Box<Iterator<Item = Result<Executed, CallError>>>, CallError>
     {
-        Ok(Box::new(
-            self.execution_result.read().clone().unwrap().into_iter(),
-        ))
+        Ok(Box::new(::std::iter::once(
+            self.execution_result.read().clone().unwrap(),
+        )))
     }
 
     fn block_total_difficulty(&self, _id: BlockId) -> Option<U256> { Some(U256::zero()) }
@@ -977,9 +990,14 @@ impl ProvingBlockChainClient for TestBlockChainClient {
 
     fn prove_account(&self, _: H256, _: BlockId) -> Option<(Vec<Bytes>, BasicAccount)> { None }
 
-    // fn prove_transaction(&self, _: SignedTransaction, _: BlockId) -> Option<(Bytes, Vec<DBValue>)> {
-    //     None
-    // }
+    fn prove_transaction(
+        &self,
+        _: SignedTransaction,
+        _: BlockId,
+    ) -> Option<(Bytes, Vec<DBValue>)>
+    {
+        None
+    }
 }
 
 impl ::client::EngineClient for TestBlockChainClient {
@@ -29,14 +29,54 @@ use vms::{
     EnvInfo,
     FvmExecutionResult as ExecutionResult,
     ExecStatus,
+    FastVMBackend,
     ReturnData
 };
 use state::{Substate, CleanupMode};
 use transaction::{Action, Transaction, SignedTransaction, DEFAULT_TRANSACTION_TYPE};
 use types::error::{ExecutionError};
+use types::trace::TraceCallType;
 use executor::fvm_exec::{contract_address, Executive};
 
-use helpers::{get_temp_state,make_aion_machine};
+use helpers::{get_temp_state_with_fvm, make_aion_machine};
+
+/// Every executor test runs against both FastVM backends.
+macro_rules! fvm_backend_tests {
+    ($($name:ident),*) => {
+        mod native {
+            $(
+                #[test]
+                fn $name() { super::$name(::vms::FastVMBackend::Native); }
+            )*
+        }
+
+        mod interpreter {
+            $(
+                #[test]
+                fn $name() { super::$name(::vms::FastVMBackend::Interpreter); }
+            )*
+        }
+    };
+}
+
+fvm_backend_tests!(
+    bytearraymap_test,
+    test_create_contract,
+    fibonacci,
+    recursive,
+    transfer,
+    wallet,
+    test_internal_transactions,
+    trace_internal_transactions,
+    error_cases_rejected,
+    error_cases_revert,
+    error_cases_failure,
+    error_cases_out_of_gas,
+    create_empty_contract,
+    static_call,
+    contract_create_non_empty_internal,
+    contract_create_non_empty
+);
 
 #[test]
 fn test_contract_address() {
@@ -53,9 +93,8 @@ fn test_contract_address() {
     );
 }
 
-#[test]
 // Tracing is not suported in JIT
-fn bytearraymap_test() {
+fn bytearraymap_test(backend: FastVMBackend) {
     let code = "60506040526000356c01000000000000000000000000900463ffffffff16806326121ff01461004957806375ed12351461005f578063e2179b8e146100fd57610043565b60006000fd5b34156100555760006000fd5b61005d61018d565b005b341561006b5760006000fd5b6100816004808035906010019091905050610275565b6040518080601001828103825283818151815260100191508051906010019080838360005b838110156100c25780820151818401525b6010810190506100a6565b50505050905090810190600f1680156100ef5780820380516001836010036101000a031916815260100191505b509250505060405180910390f35b34156101095760006000fd5b61011161032c565b6040518080601001828103825283818151815260100191508051906010019080838360005b838110156101525780820151818401525b601081019050610136565b50505050905090810190600f16801561017f5780820380516001836010036101000a031916815260100191505b509250505060405180910390f35b6101956103f5565b6104006040518059106101a55750595b9080825280601002601001820160405280156101bc575b5090506f610000000000000000000000000000008160008151811015156101df57fe5b9060100101906effffffffffffffffffffffffffffff1916908160001a9053506f62000000000000000000000000000000816103ff81518110151561022057fe5b9060100101906effffffffffffffffffffffffffffff1916908160001a9053508060006000506000602081526010019081526010016000209050600050908051906010019061027092919061040c565b505b50565b600060005060105280600052602060002090506000915090508054600181600116156101000203166002900480600f0160108091040260100160405190810160405280929190818152601001828054600181600116156101000203166002900480156103245780600f106102f757610100808354040283529160100191610324565b8201919060005260106000209050905b81548152906001019060100180831161030757829003600f168201915b505050505081565b6103346103f5565b600060005060006020815260100190815260100160002090506000508054600181600116156101000203166002900480600f0160108091040260100160405190810160405280929190818152601001828054600181600116156101000203166002900480156103e65780600f106103b9576101008083540402835291601001916103e6565b8201919060005260106000209050905b8154815290600101906010018083116103c957829003600f168201915b505050505090506103f2565b90565b601060405190810160405280600081526010015090565b8280546001816001161561010002031660029004906000526010600020905090600f016010900481019282600f1061044f57805160ff1916838001178555610482565b82800160010185558215610482579182015b828111156104815782518260005090905591601001919060010190610461565b5b50905061048f9190610493565b5090565b6104bb919061049d565b808211156104b7576000818150600090555060010161049d565b5090565b905600a165627a7a72305820a9b457c98ced88e9dda94a6ec2b32e69b1dc8ed693342b427da048636174f4c60029".from_hex().unwrap();
 
     let sender = Address::from_slice(b"cd1722f3947def4cf144679da39c4c32bdc35681");
@@ -73,7 +112,7 @@ fn bytearraymap_test() {
     params.call_type = CallType::Call;
     params.gas_price = U256::from(0);
     params.data = Some("26121ff0".from_hex().unwrap());
-    let mut state = get_temp_state();
+    let mut state = get_temp_state_with_fvm(backend);
     state
         .add_balance(&sender, &U256::from(100), CleanupMode::NoEmpty)
         .unwrap();
@@ -129,8 +168,7 @@ fn bytearraymap_test() {
     assert_eq!(return_data, expected_result);
 }
 
-#[test]
-fn test_create_contract() {
+fn test_create_contract(backend: FastVMBackend) {
     // Tracing is not supported in JIT
     // code:
     //
@@ -158,7 +196,7 @@ fn test_create_contract() {
     params.gas = U256::from(100_000);
     params.code = Some(Arc::new(code));
     params.value = ActionValue::Transfer(0.into());
-    let mut state = get_temp_state();
+    let mut state = get_temp_state_with_fvm(backend);
     state
         .add_balance(&sender, &U256::from(100), CleanupMode::NoEmpty)
         .unwrap();
@@ -181,9 +219,8 @@ fn test_create_contract() {
     assert_eq!(status_code, ExecStatus::Success);
 }
 
-#[test]
 // Tracing is not suported in JIT
-fn fibonacci() {
+fn fibonacci(backend: FastVMBackend) {
     let code = "60506040526000356c01000000000000000000000000900463ffffffff1680631dae897214610054578063231e93d41461008c5780639d4cd86c146100c4578063ff40565e146100fc5761004e565b60006000fd5b34156100605760006000fd5b6100766004808035906010019091905050610134565b6040518082815260100191505060405180910390f35b34156100985760006000fd5b6100ae6004808035906010019091905050610264565b6040518082815260100191505060405180910390f35b34156100d05760006000fd5b6100e660048080359060100190919050506102ae565b6040518082815260100191505060405180910390f35b34156101085760006000fd5b61011e60048080359060100190919050506102da565b6040518082815260100191505060405180910390f35b600061013e610344565b60006001841115156101565783925061025d5661025c565b600184016040518059106101675750595b90808252806010026010018201604052801561017e575b509150600082600081518110151561019257fe5b9060100190601002019090818152601001505060018260018151811015156101b657fe5b90601001906010020190908181526010015050600290505b838111151561023d5781600282038151811015156101e857fe5b90601001906010020151826001830381518110151561020357fe5b9060100190601002015101828281518110151561021c57fe5b906010019060100201909081815260100150505b80600101905080506101ce565b818481518110151561024b57fe5b90601001906010020151925061025d565b5b5050919050565b600060018211151561027c578190506102a9566102a8565b61028e6002830361026463ffffffff16565b6102a06001840361026463ffffffff16565b0190506102a9565b5b919050565b600060328211156102bf5760006000fd5b6102ce826102da63ffffffff16565b90506102d5565b919050565b600060006000600060006001861115156102fa5785945061033b5661033a565b600193506001925060009150600290505b858110156103325782840191508150829350835081925082505b806001019050805061030b565b82945061033b565b5b50505050919050565b6010604051908101604052806000815260100150905600a165627a7a72305820f201655c186b2446b8c23b5699eabfb66793e2b97e8cadb17b212b3bc71afe600029".from_hex().unwrap();
 
     let sender = Address::from_slice(b"cd1722f3947def4cf144679da39c4c32bdc35681");
@@ -201,7 +238,7 @@ fn fibonacci() {
     let mut call_data = "ff40565e".from_hex().unwrap();
     call_data.append(&mut <[u8; 16]>::from(U128::from(6)).to_vec());
     params.data = Some(call_data);
-    let mut state = get_temp_state();
+    let mut state = get_temp_state_with_fvm(backend);
     state
         .add_balance(&sender, &U256::from(100), CleanupMode::NoEmpty)
         .unwrap();
@@ -340,9 +377,8 @@ fn fibonacci() {
     assert_eq!(status_code, ExecStatus::Revert);
 }
 
-#[test]
 // Tracing is not suported in JIT
-fn recursive() {
+fn recursive(backend: FastVMBackend) {
     let code = "605060405234156100105760006000fd5b610015565b610199806100246000396000f30060506040526000356c01000000000000000000000000900463ffffffff1680632d7df21a146100335761002d565b60006000fd5b341561003f5760006000fd5b6100666004808080601001359035909160200190919290803590601001909190505061007c565b6040518082815260100191505060405180910390f35b6000600060007f66fa32225b641331dff20698cd66d310b3149e86d875926af7ea2f2a9079e80b856040518082815260100191505060405180910390a18585915091506001841115156100d55783925061016456610163565b60018282632d7df21a898960018a036000604051601001526040518463ffffffff166c010000000000000000000000000281526004018084848252816010015260200182815260100193505050506010604051808303816000888881813b151561013f5760006000fd5b5af1151561014d5760006000fd5b5050505060405180519060100150019250610164565b5b505093925050505600a165627a7a72305820c4755a8b960e01280a2c8d85fae255d08e1be318b2c2685a948e7b42660c2f5c0029".from_hex().unwrap();
 
     let sender = Address::from_slice(b"cd1722f3947def4cf144679da39c4c32bdc35681");
@@ -355,7 +391,7 @@ fn recursive() {
     params.gas = U256::from(100_000);
     params.code = Some(Arc::new(code.clone()));
     params.value = ActionValue::Transfer(0.into());
-    let mut state = get_temp_state();
+    let mut state = get_temp_state_with_fvm(backend);
     state
         .add_balance(&sender, &U256::from(100), CleanupMode::NoEmpty)
         .unwrap();
@@ -450,8 +486,7 @@ fn recursive() {
     assert_eq!(status_code, ExecStatus::Revert);
 }
 
-#[test]
-fn transfer() {
+fn transfer(backend: FastVMBackend) {
     let code = "605060405260636000600050909055341561001a5760006000fd5b61001f565b60c88061002d6000396000f30060506040526000356c01000000000000000000000000900463ffffffff168063c1cfb99a14603b578063f43fa805146057576035565b60006000fd5b6041607e565b6040518082815260100191505060405180910390f35b341560625760006000fd5b6068608b565b6040518082815260100191505060405180910390f35b6000303190506088565b90565b600060006000505490506099565b905600a165627a7a723058209d7cceee22377b5b19f0cbdfb6548ba4a3c94538bd66831bf7d16349565dfdf10029".from_hex().unwrap();
 
     let sender = Address::from_slice(b"cd1722f3947def4cf144679da39c4c32bdc35681");
@@ -464,7 +499,7 @@ fn transfer() {
     params.gas = U256::from(100_000);
     params.code = Some(Arc::new(code.clone()));
     params.value = ActionValue::Transfer(0.into());
-    let mut state = get_temp_state();
+    let mut state = get_temp_state_with_fvm(backend);
     state
         .add_balance(&sender, &U256::from(100), CleanupMode::NoEmpty)
         .unwrap();
@@ -554,8 +589,7 @@ fn transfer() {
     };
 }
 
-#[test]
-fn wallet() {
+fn wallet(backend: FastVMBackend) {
     let code = "60506040523415620000115760006000fd5b60405162001e6338038062001e63833981016040528080518201919060100180519060100190919080519060100190919050505b805b83835b6000600183510160016000508190909055503360026000506001610100811015156200007257fe5b90906002020160005b508282909180600101839055555050506001610202600050600033825281601001526020019081526010016000209050600050819090905550600090505b82518110156200016a578281815181101515620000d257fe5b9060100190602002018060100151905160026000508360020161010081101515620000f957fe5b90906002020160005b5082829091806001018390555550505080600201610202600050600085848151811015156200012d57fe5b906010019060200201806010015190518252816010015260200190815260100160002090506000508190909055505b8060010190508050620000b9565b8160006000508190909055505b50505080610205600050819090905550620001a5620001bd6401000000000262001aa8176401000000009004565b6102076000508190909055505b505b505050620001da565b60006201518042811515620001ce57fe5b049050620001d7565b90565b611c7980620001ea6000396000f300605060405236156100ee576000356c01000000000000000000000000900463ffffffff168063173825d9146101425780632f54bf6e1461016e57806335397dc0146101b25780634123cb6b146101f1578063523750931461021b5780635c52c2f514610245578063659010e71461025b5780637065cb4814610285578063746c9171146102b1578063797af627146102db578063a915f20614610325578063b75c7dc614610395578063c2cf7326146103c7578063cbf0b0c014610422578063f00d4b5d1461044e578063f165b2fe1461048b578063f1736d86146104af578063f1f06fb6146104d9576100ee565b5b600034111561013f577fc6dcd8d437d8b3537583463d84a6ba9d7e3e013fa4e004da9b6dee1482038be5333460405180848482528160100152602001828152601001935050505060405180910390a15b5b005b341561014e5760006000fd5b61016c600480808060100135903590916020019091929050506104fd565b005b341561017a5760006000fd5b61019860048080806010013590359091602001909192905050610644565b604051808215151515815260100191505060405180910390f35b34156101be5760006000fd5b6101d46004808035906010019091905050610679565b604051808383825281601001526020019250505060405180910390f35b34156101fd5760006000fd5b6102056106b2565b6040518082815260100191505060405180910390f35b34156102275760006000fd5b61022f6106bb565b6040518082815260100191505060405180910390f35b34156102515760006000fd5b6102596106c5565b005b34156102675760006000fd5b61026f610713565b6040518082815260100191505060405180910390f35b34156102915760006000fd5b6102af6004808080601001359035909160200190919290505061071d565b005b34156102bd5760006000fd5b6102c561086f565b6040518082815260100191505060405180910390f35b34156102e75760006000fd5b61030b60048080806010013590359060001916909091602001909192905050610878565b604051808215151515815260100191505060405180910390f35b34156103315760006000fd5b61036c600480808060100135903590916020019091929080359060100190919080359060100190820180359060100191909192905050610c21565b604051808383906000191690906000191690825281601001526020019250505060405180910390f35b34156103a15760006000fd5b6103c560048080806010013590359060001916909091602001909192905050610f07565b005b34156103d35760006000fd5b610408600480808060100135903590600019169090916020019091929080806010013590359091602001909192905050611018565b604051808215151515815260100191505060405180910390f35b341561042e5760006000fd5b61044c600480808060100135903590916020019091929050506110ad565b005b341561045a5760006000fd5b6104896004808080601001359035909160200190919290808060100135903590916020019091929050506110f2565b005b34156104975760006000fd5b6104ad6004808035906010019091905050611264565b005b34156104bb5760006000fd5b6104c36112b2565b6040518082815260100191505060405180910390f35b34156104e55760006000fd5b6104fb60048080359060100190919050506112bc565b005b600060003660405180838380828437820191505092505050604051809103902061052d828261136063ffffffff16565b15156105395760006000fd5b61020260005060008686825281601001526020019081526010016000209050600050549250600083141561056c5761063c565b60016001600050540360006000505411156105865761063c565b600060006002600050856101008110151561059d57fe5b90906002020160005b508282909180600101839055555050506000610202600050600087878252816010015260200190815260100160002090506000508190909055506105ee6115f363ffffffff16565b6105fc6116ba63ffffffff16565b7f58619076adf5bb0943d100ef88d52d7c3fd691b19d3a9071b555b651fbf418da8686604051808383825281601001526020019250505060405180910390a15b5b5050505050565b600060006102026000506000858582528160100152602001908152601001600020905060005054119050610673565b92915050565b600060006002600050600184016101008110151561069357fe5b90906002020160005b5080600101549054915091506106ad565b915091565b60016000505481565b6102076000505481565b6000366040518083838082843782019150509250505060405180910390206106f3828261136063ffffffff16565b15156106ff5760006000fd5b60006102066000508190909055505b5b5050565b6102066000505481565b60003660405180838380828437820191505092505050604051809103902061074b828261136063ffffffff16565b15156107575760006000fd5b610767848461064463ffffffff16565b1561077157610868565b61077f6115f363ffffffff16565b60fa60016000505410151561079d5761079c6116ba63ffffffff16565b5b60fa6001600050541015156107b157610868565b6001600081815054809291906001019190509090555083836002600050600160005054610100811015156107e157fe5b90906002020160005b50828290918060010183905555505050600160005054610202600050600086868252816010015260200190815260100160002090506000508190909055507f994a936646fe87ffe4f1e469d3d6aa417d6b855598397f323de5b449f765f0c38585604051808383825281601001526020019250505060405180910390a15b5b50505050565b60006000505481565b6000828261088c828261136063ffffffff16565b15156108985760006000fd5b600060006102086000506000888890600019169090600019169082528160100152602001908152601001600020905060005060000160005080600101549054909114919014161515610c1757610208600050600086869060001916909060001916908252816010015260200190815260100160002090506000506000016000508060010154905461020860005060008888906000191690906000191690825281601001526020019081526010016000209050600050600201600050546102086000506000898990600019169090600019169082528160100152602001908152601001600020905060005060030160005060405180828054600181600116156101000203166002900480156109ef5780600f106109c2576101008083540402835291601001916109ef565b8201919060005260106000209050905b8154815290600101906010018083116109d257829003600f168201915b50509150506000604051808303818588885af193505050501515610a135760006000fd5b7f9e0c482edabde7c5a28339467e14a00819cb5c09ef7efa27fa2b2a29ee75319a33888861020860005060008c8c9060001916909060001916908252816010015260200190815260100160002090506000506002016000505461020860005060008d8d9060001916909060001916908252816010015260200190815260100160002090506000506000016000508060010154905461020860005060008f8f9060001916909060001916908252816010015260200190815260100160002090506000506003016000506040518089898252816010015260200187879060001916909060001916908252816010015260200185815260100184848252816010015260200180601001828103825283818154600181600116156101000203166002900481526010019150805460018160011615610100020316600290048015610b9c5780600f10610b6f57610100808354040283529160100191610b9c565b8201919060005260106000209050905b815481529060010190601001808311610b7f57829003600f168201915b5050995050505050505050505060405180910390a1610208600050600086869060001916909060001916908252816010015260200190815260100160002090506000600082016000508060009055600101600090556002820160005060009055600382016000610c0c9190611ac3565b505060019250610c18565b5b5b505092915050565b60006000610c343361064463ffffffff16565b1515610c405760006000fd5b610c4f856118e863ffffffff16565b15610d10577f5b61ec5dfbe4cce36b7d3de1b7a363c3ad8e4e3ccaadd7b7969fc9aa6e4965c033888b8b8a8a6040518088888252816010015260200186815260100185858252816010015260200180601001828103825284848281815260100192508082843782019150509850505050505050505060405180910390a18686868686604051808383808284378201915050925050506000604051808303818588885af193505050501515610d035760006000fd5b6000600091509150610efc565b60003643604051808484808284378201915050828152601001935050505060405180910390209150915081815050610d4e828261087863ffffffff16565b158015610d9d5750600060006102086000506000858590600019169090600019169082528160100152602001908152601001600020905060005060000160005080600101549054909114919014165b15610efb5786866102086000506000858590600019169090600019169082528160100152602001908152601001600020905060005060000160005082829091806001018390555550505084610208600050600084849060001916909060001916908252816010015260200190815260100160002090506000506002016000508190909055508383610208600050600085859060001916909060001916908252816010015260200190815260100160002090506000506003016000509190610e65929190611b0d565b507fc522d6b7d06299b13c7597702937d15519e7a366b35b87fe5456326e37c8c3978383338a8d8d8c8c604051808a8a9060001916909060001916908252816010015260200188888252816010015260200186815260100185858252816010015260200180601001828103825284848281815260100192508082843782019150509a505050505050505050505060405180910390a15b5b5b9550959350505050565b6000600060006102026000506000338252816010015260200190815260100160002090506000505492506000831415610f3f57611011565b8260020a9150610203600050600086869060001916909060001916908252816010015260200190815260100160002090506000509050600082826001016000505416111561101057806000016000818150548092919060010191905090905550818160010160008282825054039250508190909055507fc7fb647e59b18047309aa15aad418e5d7ca96d173ad704f1031a2c3d7591734b3388886040518085858252816010015260200183839060001916909060001916908252816010015260200194505050505060405180910390a15b5b5050505050565b600060006000600061020360005060008989906000191690906000191690825281601001526020019081526010016000209050600050925061020260005060008787825281601001526020019081526010016000209050600050549150600082141561108757600093506110a2565b8160020a9050600081846001016000505416141593506110a2565b505050949350505050565b6000366040518083838082843782019150509250505060405180910390206110db828261136063ffffffff16565b15156110e75760006000fd5b8383ff5b5b50505050565b6000600036604051808383808284378201915050925050506040518091039020611122828261136063ffffffff16565b151561112e5760006000fd5b61113e858561064463ffffffff16565b156111485761125a565b61020260005060008888825281601001526020019081526010016000209050600050549250600083141561117b5761125a565b6111896115f363ffffffff16565b84846002600050856101008110151561119e57fe5b90906002020160005b5082829091806001018390555550505060006102026000506000898982528160100152602001908152601001600020905060005081909090555082610202600050600087878252816010015260200190815260100160002090506000508190909055507fb532073b38c83145e3e5135377a08bf9aab55bc0fd7c1179cd4fb995d2a5159c888888886040518085858252816010015260200183838252816010015260200194505050505060405180910390a15b5b50505050505050565b600036604051808383808284378201915050925050506040518091039020611292828261136063ffffffff16565b151561129e5760006000fd5b826102056000508190909055505b5b505050565b6102056000505481565b6000366040518083838082843782019150509250505060405180910390206112ea828261136063ffffffff16565b15156112f65760006000fd5b6001600050548311156113085761135a565b8260006000508190909055506113226115f363ffffffff16565b7fd9a37dd2a911cc717a3127f430cde03b03bcf2694f289138cff455a5430ea9fd846040518082815260100191505060405180910390a15b5b505050565b6000600060006000610202600050600033825281601001526020019081526010016000209050600050549250600083141561139a576115ea565b6102036000506000878790600019169090600019169082528160100152602001908152601001600020905060005091506000826000016000505414156114695760006000505482600001600050819090905550600082600101600050819090905550610204600050805480919060010190906114169190611b94565b826002016000508190909055508585610204600050846002016000505481548110151561143f57fe5b9060005260106000209050906002020160005b508282906000191690909180600101839055555050505b8260020a905060008183600101600050541614156115e9577fe1c52dc63b719ade82e8bea94cc41a0d5d28e4aaf536adb5e9cccc9ff8c1aeda3389896040518085858252816010015260200183839060001916909060001916908252816010015260200194505050505060405180910390a1600182600001600050541115156115b057610204600050610203600050600088889060001916909060001916908252816010015260200190815260100160002090506000506002016000505481548110151561153357fe5b9060005260106000209050906002020160005b508060009055600101600090556102036000506000878790600019169090600019169082528160100152602001908152601001600020905060006000820160005060009055600182016000506000905560028201600050600090555050600193506115ea566115e8565b8160000160008181505480929190600190039190509090555080826001016000828282505417925050819090905550600093506115ea565b5b5b50505092915050565b60006000610204600050805490509150600090505b818110156116a75761020860005060006102046000508381548110151561162b57fe5b9060005260106000209050906002020160005b508060010154905490600019169090600019169082528160100152602001908152601001600020905060006000820160005080600090556001016000905560028201600050600090556003820160006116979190611ac3565b50505b8060010190508050611608565b6116b56119a763ffffffff16565b5b5050565b6000600190505b6001600050548110156118e4575b6001600050548110801561170f575060006000600260005083610100811015156116f557fe5b90906002020160005b508060010154905490911491901416155b156117215780806001019150506116cf565b5b600160016000505411801561176757506000600060026000506001600050546101008110151561174e57fe5b90906002020160005b5080600101549054909114919014165b15611788576001600081815054809291906001900391905090905550611722565b600160005054811080156117cd5750600060006002600050600160005054610100811015156117b357fe5b90906002020160005b508060010154905490911491901416155b8015611804575060006000600260005083610100811015156117eb57fe5b90906002020160005b5080600101549054909114919014165b156118df5760026000506001600050546101008110151561182157fe5b90906002020160005b50806001015490546002600050836101008110151561184557fe5b90906002020160005b508282909180600101839055555050508061020260005060006002600050846101008110151561187a57fe5b90906002020160005b5080600101549054825281601001526020019081526010016000209050600050819090905550600060006002600050600160005054610100811015156118c557fe5b90906002020160005b508282909180600101839055555050505b6116c1565b5b50565b60006118f93361064463ffffffff16565b15156119055760006000fd5b6102076000505461191a611aa863ffffffff16565b111561194957600061020660005081909090555061193c611aa863ffffffff16565b6102076000508190909055505b610206600050548261020660005054011015801561197557506102056000505482610206600050540111155b1561199857816102066000828282505401925050819090905550600190506119a1565b600090506119a1565b5b919050565b60006000610204600050805490509150600090505b81811015611a945760006000610204600050838154811015156119db57fe5b9060005260106000209050906002020160005b5080600101549054906000191690909114919014161515611a8657610203600050600061020460005083815481101515611a2457fe5b9060005260106000209050906002020160005b5080600101549054906000191690906000191690825281601001526020019081526010016000209050600060008201600050600090556001820160005060009055600282016000506000905550505b5b80600101905080506119bc565b6102046000611aa39190611bc8565b5b5050565b60006201518042811515611ab857fe5b049050611ac0565b90565b50805460018160011615610100020316600290046000825580600f10611ae95750611b0a565b600f0160109004906000526010600020905090810190611b099190611bef565b5b50565b8280546001816001161561010002031660029004906000526010600020905090600f016010900481019282600f10611b5057803560ff1916838001178555611b83565b82800160010185558215611b83579182015b82811115611b825782358260005090905591601001919060010190611b62565b5b509050611b909190611bef565b5090565b815481835581811511611bc3576002028160020283600052601060002090509182019101611bc29190611c1a565b5b505050565b50805460008255600202906000526010600020905090810190611beb9190611c1a565b5b50565b611c179190611bf9565b80821115611c135760008181506000905550600101611bf9565b5090565b90565b611c4a9190611c24565b80821115611c4657600081815080600090556001016000905550600201611c24565b5090565b905600a165627a7a7230582008ce2c2eb5bf9c20836844338da08c6dceae1b44c52145e973d02634c22ce9bb0029".from_hex().unwrap();
 
     let sender = Address::from_slice(b"cd1722f3947def4cf144679da39c4c32bdc35681");
@@ -568,7 +602,7 @@ fn wallet() {
     params.gas = U256::from(10_000_000);
     params.code = Some(Arc::new(code.clone()));
     params.value = ActionValue::Transfer(0.into());
-    let mut state = get_temp_state();
+    let mut state = get_temp_state_with_fvm(backend);
     state
         .add_balance(&sender, &U256::from(100), CleanupMode::NoEmpty)
         .unwrap();
@@ -631,13 +665,12 @@ fn wallet() {
     assert_eq!(status_code, ExecStatus::Success);
 }
 
-#[test]
 // Internal transactions
-fn test_internal_transactions() {
+fn test_internal_transactions(backend: FastVMBackend) {
     // internal_transactions.sol
     let code = "60506040525b5b61000b565b6104f88061001a6000396000f30060506040523615610054576000356c01000000000000000000000000900463ffffffff1680631e4198e01461008f5780636d73ac71146100af578063cc8066c8146100f8578063efc81a8c1461012d57610054565b5b7f656718b7d7f0803b58a7a46a3a5ca0a26696492f223276e7a227baba40fb95b7346040518082815260100191505060405180910390a15b005b6100ad6004808080601001359035909160200190919290505061015e565b005b34156100bb5760006000fd5b6100e2600480808060100135903590916020019091929080359060100190919050506101c8565b6040518082815260100191505060405180910390f35b34156101045760006000fd5b61012b60048080806010013590359091602001909192908035906010019091905050610295565b005b34156101395760006000fd5b610141610300565b604051808383825281601001526020019250505060405180910390f35b81816108fc34908115029060405160006040518083038185898989f1945050505050151561018c5760006000fd5b7f281a259dfd2e4aaf4447339f7e35909b8a423be045a738057d6c3c01e8d1f5a2346040518082815260100191505060405180910390a15b5050565b60006000600060008686925092506002838363f65a554b886000604051601001526040518263ffffffff166c01000000000000000000000000028152600401808281526010019150506010604051808303816000888881813b151561022d5760006000fd5b5af1151561023b5760006000fd5b50505050604051805190601001500190507ff56ebbc311e11d9790970c4f650e868d7c17a8c35e5c268477d6933ae010d1f4826040518082815260100191505060405180910390a180935061028b565b5050509392505050565b82826108fc83908115029060405160006040518083038185898989f194505050505015156102c35760006000fd5b7f3f418b40de968f04f1770399699cdfb8221fb37431187bf6ba88c8ef1cde63de826040518082815260100191505060405180910390a15b505050565b600060006000600061031061037e565b604051809103906000f080158215161561032a5760006000fd5b915091507f6092db4a9f98e713a99420bc27f1f1cdfcfd435af45397aeb05ffbee8d567d8e8383604051808383825281601001526020019250505060405180910390a1818193509350610378565b50509091565b60405161013e8061038f833901905600605060405234156100105760006000fd5b610015565b61011a806100246000396000f300605060405236156030576000356c01000000000000000000000000900463ffffffff168063f65a554b14606b576030565b3415603b5760006000fd5b5b7f6684c6fb8e464ba954e17ed3f5aed6e2d49231ce285b7770f422d1f52cef503b60405160405180910390a15b005b341560765760006000fd5b608a600480803590601001909190505060a0565b6040518082815260100191505060405180910390f35b600060006001830190507f3bc83dc4da931c34301105d9c2aff52e35bb96133cd1cf0a835faa9bb607422c826040518082815260100191505060405180910390a180915060e8565b509190505600a165627a7a72305820ec84292d19105cb4d6f311689eed4db3cb4e4251249a9cf06f071e1746e74de60029a165627a7a723058209d38411c6f215aa8daa7dd8150890ea7576a1b0e117cedffc3c002a95810948e0029".from_hex().unwrap();
     let sender = Address::from_slice(b"cd1722f3947def4cf144679da39c4c32bdc35681");
-    let mut state = get_temp_state();
+    let mut state = get_temp_state_with_fvm(backend);
     state
         .add_balance(&sender, &U256::from(100), CleanupMode::NoEmpty)
         .unwrap();
@@ -805,13 +838,102 @@ fn test_internal_transactions() {
     assert_eq!(state.nonce(&new_address).unwrap(), U256::from(1));
 }
 
-#[test]
-fn error_cases_rejected() {
+fn trace_internal_transactions(backend: FastVMBackend) {
+    // internal_transactions.sol
+    let code = "60506040525b5b61000b565b6104f88061001a6000396000f30060506040523615610054576000356c01000000000000000000000000900463ffffffff1680631e4198e01461008f5780636d73ac71146100af578063cc8066c8146100f8578063efc81a8c1461012d57610054565b5b7f656718b7d7f0803b58a7a46a3a5ca0a26696492f223276e7a227baba40fb95b7346040518082815260100191505060405180910390a15b005b6100ad6004808080601001359035909160200190919290505061015e565b005b34156100bb5760006000fd5b6100e2600480808060100135903590916020019091929080359060100190919050506101c8565b6040518082815260100191505060405180910390f35b34156101045760006000fd5b61012b60048080806010013590359091602001909192908035906010019091905050610295565b005b34156101395760006000fd5b610141610300565b604051808383825281601001526020019250505060405180910390f35b81816108fc34908115029060405160006040518083038185898989f1945050505050151561018c5760006000fd5b7f281a259dfd2e4aaf4447339f7e35909b8a423be045a738057d6c3c01e8d1f5a2346040518082815260100191505060405180910390a15b5050565b60006000600060008686925092506002838363f65a554b886000604051601001526040518263ffffffff166c01000000000000000000000000028152600401808281526010019150506010604051808303816000888881813b151561022d5760006000fd5b5af1151561023b5760006000fd5b50505050604051805190601001500190507ff56ebbc311e11d9790970c4f650e868d7c17a8c35e5c268477d6933ae010d1f4826040518082815260100191505060405180910390a180935061028b565b5050509392505050565b82826108fc83908115029060405160006040518083038185898989f194505050505015156102c35760006000fd5b7f3f418b40de968f04f1770399699cdfb8221fb37431187bf6ba88c8ef1cde63de826040518082815260100191505060405180910390a15b505050565b600060006000600061031061037e565b604051809103906000f080158215161561032a5760006000fd5b915091507f6092db4a9f98e713a99420bc27f1f1cdfcfd435af45397aeb05ffbee8d567d8e8383604051808383825281601001526020019250505060405180910390a1818193509350610378565b50509091565b60405161013e8061038f833901905600605060405234156100105760006000fd5b610015565b61011a806100246000396000f300605060405236156030576000356c01000000000000000000000000900463ffffffff168063f65a554b14606b576030565b3415603b5760006000fd5b5b7f6684c6fb8e464ba954e17ed3f5aed6e2d49231ce285b7770f422d1f52cef503b60405160405180910390a15b005b341560765760006000fd5b608a600480803590601001909190505060a0565b6040518082815260100191505060405180910390f35b600060006001830190507f3bc83dc4da931c34301105d9c2aff52e35bb96133cd1cf0a835faa9bb607422c826040518082815260100191505060405180910390a180915060e8565b509190505600a165627a7a72305820ec84292d19105cb4d6f311689eed4db3cb4e4251249a9cf06f071e1746e74de60029a165627a7a723058209d38411c6f215aa8daa7dd8150890ea7576a1b0e117cedffc3c002a95810948e0029".from_hex().unwrap();
+    let sender = Address::from_slice(b"cd1722f3947def4cf144679da39c4c32bdc35681");
+    let mut state = get_temp_state_with_fvm(backend);
+    state
+        .add_balance(&sender, &U256::from(100), CleanupMode::NoEmpty)
+        .unwrap();
+    let mut info = EnvInfo::default();
+    info.number = 1;
+    info.gas_limit = U256::from(1000000);
+    info.author = Address::from(1);
+    let machine = make_aion_machine();
+    let address = contract_address(&sender, &U256::zero()).0;
+    // Create contract InternalTransaction
+    let mut params = ActionParams::default();
+    params.address = address.clone();
+    params.sender = sender.clone();
+    params.origin = sender.clone();
+    params.gas = U256::from(10_000_000);
+    params.code = Some(Arc::new(code.clone()));
+    params.value = ActionValue::Transfer(10.into());
+    let mut substate = Substate::with_tracing(true);
+    let result = {
+        let mut ex = Executive::new(&mut state, &info, &machine);
+        ex.create(params, &mut substate)
+    };
+    assert_eq!(result.status_code, ExecStatus::Success);
+    assert_eq!(substate.traces.len(), 1);
+    assert_eq!(substate.traces[0].call_type, TraceCallType::Create);
+    assert_eq!(substate.traces[0].to, address);
+    assert_eq!(substate.traces[0].value, U256::from(10));
+    assert_eq!(substate.traces[0].output, result.return_data.to_vec());
+
+    // Ask contract contract to transfer value
+    let receiver = Address::from_slice(b"ef1722f3947def4cf144679da39c4c32bdc35681");
+    let mut params = ActionParams::default();
+    params.address = address.clone();
+    params.code_address = address.clone();
+    params.sender = sender.clone();
+    params.origin = sender.clone();
+    params.gas = U256::from(1_000_000);
+    params.code = Some(Arc::new(result.return_data.to_vec()));
+    params.value = ActionValue::Transfer(U256::from(0));
+    params.call_type = CallType::Call;
+    params.gas_price = U256::from(0);
+    let mut call_data = "cc8066c8".from_hex().unwrap();
+    call_data.append(&mut <[u8; 32]>::from(receiver.clone()).to_vec());
+    call_data.append(&mut <[u8; 16]>::from(U128::from(5)).to_vec());
+    params.data = Some(call_data.clone());
+    let mut substate = Substate::with_tracing(true);
+    let result = {
+        let mut ex = Executive::new(&mut state, &info, &machine);
+        ex.call(params, &mut substate)
+    };
+    assert_eq!(result.status_code, ExecStatus::Success);
+    assert_eq!(substate.traces.len(), 1);
+
+    let trace = &substate.traces[0];
+    assert_eq!(trace.call_type, TraceCallType::Call);
+    assert_eq!(trace.from, sender);
+    assert_eq!(trace.to, address);
+    assert_eq!(trace.input, call_data);
+    assert_eq!(trace.gas_used, U256::from(1_000_000) - result.gas_left);
+    assert_eq!(trace.error, None);
+    assert_eq!(trace.calls.len(), 1);
+    assert_eq!(trace.calls[0].from, address);
+    assert_eq!(trace.calls[0].to, receiver);
+    assert_eq!(trace.calls[0].value, U256::from(5));
+    assert!(trace.calls[0].calls.is_empty());
+
+    // Untraced execution records nothing
+    let mut substate = Substate::new();
+    let mut params = ActionParams::default();
+    params.address = address.clone();
+    params.code_address = address.clone();
+    params.sender = sender.clone();
+    params.origin = sender.clone();
+    params.gas = U256::from(1_000_000);
+    params.code = state.code(&address).unwrap();
+    params.value = ActionValue::Transfer(U256::from(0));
+    params.call_type = CallType::Call;
+    params.data = Some(call_data);
+    {
+        let mut ex = Executive::new(&mut state, &info, &machine);
+        ex.call(params, &mut substate);
+    }
+    assert!(substate.traces.is_empty());
+}
+
+fn error_cases_rejected(backend: FastVMBackend) {
     let sender = Address::from_slice(b"cd1722f3947def4cf144679da39c4c32bdc35681");
     let machine = make_aion_machine();
     let mut info = EnvInfo::default();
     info.gas_limit = U256::from(3_000_000);
-    let mut state = get_temp_state();
+    let mut state = get_temp_state_with_fvm(backend);
     state
         .add_balance(&sender, &U256::from(100), CleanupMode::NoEmpty)
         .unwrap();
@@ -997,8 +1119,7 @@ fn error_cases_rejected() {
     );
 }
 
-#[test]
-fn error_cases_revert() {
+fn error_cases_revert(backend: FastVMBackend) {
     let code = "605060405234156100105760006000fd5b610015565b610199806100246000396000f30060506040526000356c01000000000000000000000000900463ffffffff1680632d7df21a146100335761002d565b60006000fd5b341561003f5760006000fd5b6100666004808080601001359035909160200190919290803590601001909190505061007c565b6040518082815260100191505060405180910390f35b6000600060007f66fa32225b641331dff20698cd66d310b3149e86d875926af7ea2f2a9079e80b856040518082815260100191505060405180910390a18585915091506001841115156100d55783925061016456610163565b60018282632d7df21a898960018a036000604051601001526040518463ffffffff166c010000000000000000000000000281526004018084848252816010015260200182815260100193505050506010604051808303816000888881813b151561013f5760006000fd5b5af1151561014d5760006000fd5b5050505060405180519060100150019250610164565b5b505093925050505600a165627a7a72305820c4755a8b960e01280a2c8d85fae255d08e1be318b2c2685a948e7b42660c2f5c0029".from_hex().unwrap();
 
     let sender = Address::from_slice(b"cd1722f3947def4cf144679da39c4c32bdc35681");
@@ -1012,7 +1133,7 @@ fn error_cases_revert() {
     params.gas = U256::from(10_000_000);
     params.code = Some(Arc::new(code.clone()));
     params.value = ActionValue::Transfer(1.into());
-    let mut state = get_temp_state();
+    let mut state = get_temp_state_with_fvm(backend);
     state
         .add_balance(&sender, &U256::from(100), CleanupMode::NoEmpty)
         .unwrap();
@@ -1126,8 +1247,7 @@ fn error_cases_revert() {
     assert_eq!(status_code, ExecStatus::Revert);
 }
 
-#[test]
-fn error_cases_failure() {
+fn error_cases_failure(backend: FastVMBackend) {
     // Create contract on already existing address
     let code = "605060405234156100105760006000fd5b610015565b610199806100246000396000f30060506040526000356c01000000000000000000000000900463ffffffff1680632d7df21a146100335761002d565b60006000fd5b341561003f5760006000fd5b6100666004808080601001359035909160200190919290803590601001909190505061007c565b6040518082815260100191505060405180910390f35b6000600060007f66fa32225b641331dff20698cd66d310b3149e86d875926af7ea2f2a9079e80b856040518082815260100191505060405180910390a18585915091506001841115156100d55783925061016456610163565b60018282632d7df21a898960018a036000604051601001526040518463ffffffff166c010000000000000000000000000281526004018084848252816010015260200182815260100193505050506010604051808303816000888881813b151561013f5760006000fd5b5af1151561014d5760006000fd5b5050505060405180519060100150019250610164565b5b505093925050505600a165627a7a72305820c4755a8b960e01280a2c8d85fae255d08e1be318b2c2685a948e7b42660c2f5c0029".from_hex().unwrap();
     let sender = Address::from_slice(b"cd1722f3947def4cf144679da39c4c32bdc35681");
@@ -1138,7 +1258,7 @@ fn error_cases_failure() {
     params.gas = U256::from(100_000);
     params.code = Some(Arc::new(code.clone()));
     params.value = ActionValue::Transfer(0.into());
-    let mut state = get_temp_state();
+    let mut state = get_temp_state_with_fvm(backend);
     state
         .add_balance(&sender, &U256::from(100), CleanupMode::NoEmpty)
         .unwrap();
@@ -1160,11 +1280,10 @@ fn error_cases_failure() {
     assert_eq!(gas_left, U256::from(0));
 }
 
-#[test]
-fn error_cases_out_of_gas() {
+fn error_cases_out_of_gas(backend: FastVMBackend) {
     let sender = Address::from_slice(b"cd1722f3947def4cf144679da39c4c32bdc35681");
     let address = contract_address(&sender, &U256::zero()).0;
-    let mut state = get_temp_state();
+    let mut state = get_temp_state_with_fvm(backend);
     state
         .add_balance(&sender, &U256::from(100), CleanupMode::NoEmpty)
         .unwrap();
@@ -1251,8 +1370,7 @@ fn error_cases_out_of_gas() {
     assert_eq!(status_code, ExecStatus::OutOfGas);
 }
 
-#[test]
-fn create_empty_contract() {
+fn create_empty_contract(backend: FastVMBackend) {
     // Create contract on already existing address
     let code: Vec<u8> = Vec::new();
     let sender = Address::from_slice(b"cd1722f3947def4cf144679da39c4c32bdc35681");
@@ -1264,7 +1382,7 @@ fn create_empty_contract() {
     params.gas = U256::from(1_000_000);
     params.code = Some(Arc::new(code.clone()));
     params.value = ActionValue::Transfer(0.into());
-    let mut state = get_temp_state();
+    let mut state = get_temp_state_with_fvm(backend);
     state
         .add_balance(&sender, &U256::from(100), CleanupMode::NoEmpty)
         .unwrap();
@@ -1295,11 +1413,10 @@ fn create_empty_contract() {
     );
 }
 
-#[test]
-fn static_call() {
+fn static_call(backend: FastVMBackend) {
     let sender = Address::from_slice(b"cd1722f3947def4cf144679da39c4c32bdc35681");
     let address = contract_address(&sender, &U256::zero()).0;
-    let mut state = get_temp_state();
+    let mut state = get_temp_state_with_fvm(backend);
     state
         .add_balance(&sender, &U256::from(100), CleanupMode::NoEmpty)
         .unwrap();
@@ -1510,8 +1627,7 @@ fn static_call() {
     assert_eq!(state.balance(&params.address).unwrap(), U256::from(30));
 }
 
-#[test]
-fn contract_create_non_empty_internal() {
+fn contract_create_non_empty_internal(backend: FastVMBackend) {
     // test internal creation to address with balance
     let code = "605060405234156100105760006000fd5b5b6000600061001d610043565b604051809103906000f08015821516156100375760006000fd5b915091505b5050610052565b60405160648061009a83390190565b603a806100606000396000f30060506040526008565b60006000fd00a165627a7a72305820fd53915fee1b05fe9bfd2e5c002fbc0a06e4b569cdf9cee967125a1bad38bbae0029605060405260006000600050909055341560195760006000fd5b601d565b603a80602a6000396000f30060506040526008565b60006000fd00a165627a7a7230582067ea917dd1ec8e15669ee107500f563855e03f1941e0a857e305c3f1754c16a40029".from_hex().unwrap();
     let sender = Address::from_slice(b"cd1722f3947def4cf144679da39c4c32bdc35681");
@@ -1527,7 +1643,7 @@ fn contract_create_non_empty_internal() {
     params.call_type = CallType::Call;
     params.gas_price = U256::from(0);
 
-    let mut state = get_temp_state();
+    let mut state = get_temp_state_with_fvm(backend);
     state
         .add_balance(&sender, &U256::from(100), CleanupMode::NoEmpty)
         .unwrap();
@@ -1567,7 +1683,7 @@ fn contract_create_non_empty_internal() {
 
     // Case 2
     // create contract on account with storage and balance after avm fork
-    let mut state = get_temp_state();
+    let mut state = get_temp_state_with_fvm(backend);
     state
         .add_balance(&sender, &U256::from(10000000), CleanupMode::NoEmpty)
         .unwrap();
@@ -1598,7 +1714,7 @@ fn contract_create_non_empty_internal() {
     assert_eq!(status_code, ExecStatus::Success);
 
     // Case 3 code
-    let mut state = get_temp_state();
+    let mut state = get_temp_state_with_fvm(backend);
     let mut substate = Substate::new();
     state
         .add_balance(&sender, &U256::from(100), CleanupMode::NoEmpty)
@@ -1623,7 +1739,7 @@ fn contract_create_non_empty_internal() {
     assert_eq!(status_code, ExecStatus::Revert);
 
     // Case 4 storage
-    let mut state = get_temp_state();
+    let mut state = get_temp_state_with_fvm(backend);
     state
         .add_balance(&sender, &U256::from(100), CleanupMode::NoEmpty)
         .unwrap();
@@ -1660,7 +1776,7 @@ fn contract_create_non_empty_internal() {
     assert_eq!(status_code, ExecStatus::Revert);
 
     // Case 5 nonce
-    let mut state = get_temp_state();
+    let mut state = get_temp_state_with_fvm(backend);
     state
         .add_balance(&sender, &U256::from(100), CleanupMode::NoEmpty)
         .unwrap();
@@ -1683,8 +1799,7 @@ fn contract_create_non_empty_internal() {
     assert_eq!(status_code, ExecStatus::Revert);
 }
 
-#[test]
-fn contract_create_non_empty() {
+fn contract_create_non_empty(backend: FastVMBackend) {
     let code = "60506040526000356c01000000000000000000000000900463ffffffff16806326121ff01461004957806375ed12351461005f578063e2179b8e146100fd57610043565b60006000fd5b34156100555760006000fd5b61005d61018d565b005b341561006b5760006000fd5b6100816004808035906010019091905050610275565b6040518080601001828103825283818151815260100191508051906010019080838360005b838110156100c25780820151818401525b6010810190506100a6565b50505050905090810190600f1680156100ef5780820380516001836010036101000a031916815260100191505b509250505060405180910390f35b34156101095760006000fd5b61011161032c565b6040518080601001828103825283818151815260100191508051906010019080838360005b838110156101525780820151818401525b601081019050610136565b50505050905090810190600f16801561017f5780820380516001836010036101000a031916815260100191505b509250505060405180910390f35b6101956103f5565b6104006040518059106101a55750595b9080825280601002601001820160405280156101bc575b5090506f610000000000000000000000000000008160008151811015156101df57fe5b9060100101906effffffffffffffffffffffffffffff1916908160001a9053506f62000000000000000000000000000000816103ff81518110151561022057fe5b9060100101906effffffffffffffffffffffffffffff1916908160001a9053508060006000506000602081526010019081526010016000209050600050908051906010019061027092919061040c565b505b50565b600060005060105280600052602060002090506000915090508054600181600116156101000203166002900480600f0160108091040260100160405190810160405280929190818152601001828054600181600116156101000203166002900480156103245780600f106102f757610100808354040283529160100191610324565b8201919060005260106000209050905b81548152906001019060100180831161030757829003600f168201915b505050505081565b6103346103f5565b600060005060006020815260100190815260100160002090506000508054600181600116156101000203166002900480600f0160108091040260100160405190810160405280929190818152601001828054600181600116156101000203166002900480156103e65780600f106103b9576101008083540402835291601001916103e6565b8201919060005260106000209050905b8154815290600101906010018083116103c957829003600f168201915b505050505090506103f2565b90565b601060405190810160405280600081526010015090565b8280546001816001161561010002031660029004906000526010600020905090600f016010900481019282600f1061044f57805160ff1916838001178555610482565b82800160010185558215610482579182015b828111156104815782518260005090905591601001919060010190610461565b5b50905061048f9190610493565b5090565b6104bb919061049d565b808211156104b7576000818150600090555060010161049d565b5090565b905600a165627a7a72305820a9b457c98ced88e9dda94a6ec2b32e69b1dc8ed693342b427da048636174f4c60029".from_hex().unwrap();
 
     let sender = Address::from_slice(b"cd1722f3947def4cf144679da39c4c32bdc35681");
@@ -1702,7 +1817,7 @@ fn contract_create_non_empty() {
     params.call_type = CallType::Call;
     params.gas_price = U256::from(0);
     params.data = Some("26121ff0".from_hex().unwrap());
-    let mut state = get_temp_state();
+    let mut state = get_temp_state_with_fvm(backend);
     state
         .add_balance(&sender, &U256::from(10000000), CleanupMode::NoEmpty)
         .unwrap();
@@ -1735,7 +1850,7 @@ fn contract_create_non_empty() {
 
     // Case 2
     // create contract on account with storage and balance after avm fork
-    let mut state = get_temp_state();
+    let mut state = get_temp_state_with_fvm(backend);
     state
         .add_balance(&sender, &U256::from(10000000), CleanupMode::NoEmpty)
         .unwrap();
@@ -1762,7 +1877,7 @@ fn contract_create_non_empty() {
     assert_eq!(status_code, ExecStatus::Success);
 
     // Case 3 code
-    let mut state = get_temp_state();
+    let mut state = get_temp_state_with_fvm(backend);
     let mut substate = Substate::new();
     state
         .add_balance(&sender, &U256::from(100), CleanupMode::NoEmpty)
@@ -1785,7 +1900,7 @@ fn contract_create_non_empty() {
     assert_eq!(status_code, ExecStatus::Failure);
 
     // Case 4 storage
-    let mut state = get_temp_state();
+    let mut state = get_temp_state_with_fvm(backend);
     state
         .add_balance(&sender, &U256::from(100), CleanupMode::NoEmpty)
         .unwrap();
@@ -1814,7 +1929,7 @@ fn contract_create_non_empty() {
     assert_eq!(status_code, ExecStatus::Failure);
 
     // Case 5 nonce
-    let mut state = get_temp_state();
+    let mut state = get_temp_state_with_fvm(backend);
     state
         .add_balance(&sender, &U256::from(100), CleanupMode::NoEmpty)
         .unwrap();
@@ -28,5 +28,7 @@ mod pod_account;
 mod pod_state;
 mod state;
 mod externalities;
+#[cfg(feature = "sim")]
+mod sync;
 
 pub mod common;
@@ -0,0 +1,122 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+use std::sync::Arc;
+use std::time::Duration;
+use client::{BlockChainClient, Client};
+use sync::{LinkConfig, SyncProvider};
+use helpers::{generate_dummy_client, push_blocks_to_client};
+use tests::common::sync_network::{wait_until, SimCluster};
+
+const SYNC_TIMEOUT: u64 = 60;
+
+/// append `count` blocks to the best block, `timestamp_salt` tells forks apart
+fn extend_chain(client: &Arc<Client>, timestamp_salt: u64, count: usize) {
+    let next = client.chain_info().best_block_number as usize + 1;
+    push_blocks_to_client(client, timestamp_salt, next, count);
+    client.flush_queue();
+}
+
+fn client_with_blocks(count: usize) -> Arc<Client> {
+    let client = generate_dummy_client(0);
+    extend_chain(&client, 0, count);
+    client
+}
+
+fn wait_connected(cluster: &SimCluster, peers: usize) -> bool {
+    wait_until(Duration::from_secs(SYNC_TIMEOUT), || {
+        cluster
+            .nodes
+            .iter()
+            .all(|node| node.sync.peers().len() >= peers)
+    })
+}
+
+#[test]
+fn catch_up_sync() {
+    let cluster = SimCluster::new(1, vec![client_with_blocks(20), generate_dummy_client(0)]);
+    cluster.run();
+
+    assert!(wait_until(Duration::from_secs(SYNC_TIMEOUT), || cluster.is_synced()));
+    assert_eq!(cluster.nodes[1].best_block_number(), 20);
+}
+
+#[test]
+fn partition_reorg_to_heavier_fork() {
+    let cluster = SimCluster::new(2, vec![client_with_blocks(5), client_with_blocks(5)]);
+    cluster.run();
+    assert!(wait_connected(&cluster, 1));
+
+    // both sides keep building their own fork
+    cluster.partition(&[&[0], &[1]]);
+    extend_chain(&cluster.nodes[0].client, 1, 3);
+    extend_chain(&cluster.nodes[1].client, 2, 6);
+    assert!(!cluster.is_synced());
+
+    cluster.network.heal();
+    let heavier = cluster.nodes[1].best_block_hash();
+    assert!(wait_until(Duration::from_secs(SYNC_TIMEOUT), || {
+        cluster.nodes[0].best_block_hash() == heavier
+    }));
+    assert_eq!(cluster.nodes[0].best_block_number(), 11);
+}
+
+#[test]
+fn sync_over_lossy_links() {
+    let cluster = SimCluster::new(3, vec![generate_dummy_client(0), generate_dummy_client(0)]);
+    cluster.run();
+    assert!(wait_connected(&cluster, 1));
+
+    cluster.network.set_default_link(LinkConfig {
+        latency: Duration::from_millis(20),
+        loss: 0.1,
+    });
+    extend_chain(&cluster.nodes[0].client, 0, 30);
+
+    assert!(wait_until(Duration::from_secs(SYNC_TIMEOUT * 2), || cluster.is_synced()));
+    assert_eq!(cluster.nodes[1].best_block_number(), 30);
+    assert!(cluster.network.stats().dropped > 0);
+}
+
+#[test]
+fn sync_around_unresponsive_peer() {
+    let cluster = SimCluster::new(
+        4,
+        vec![
+            client_with_blocks(20),
+            client_with_blocks(20),
+            generate_dummy_client(0),
+        ],
+    );
+    // node 1 never answers node 2
+    cluster.network.set_link(
+        &cluster.nodes[1].binding,
+        &cluster.nodes[2].binding,
+        LinkConfig {
+            latency: Duration::from_millis(10),
+            loss: 1.0,
+        },
+    );
+    cluster.run();
+
+    assert!(wait_until(Duration::from_secs(SYNC_TIMEOUT), || cluster.is_synced()));
+    assert_eq!(cluster.nodes[2].best_block_number(), 20);
+}
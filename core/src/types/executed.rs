@@ -27,6 +27,7 @@ use acore_bytes::Bytes;
 use trie;
 use log_entry::LogEntry;
 use state_diff::StateDiff;
+use types::trace::CallTrace;
 
 use std::fmt;
 use std::collections::HashSet;
@@ -69,6 +70,8 @@ pub struct Executed {
     pub output: Bytes,
     /// The state diff, if we traced it.
     pub state_diff: Option<StateDiff>,
+    /// The call tree, if we traced it.
+    pub trace: Option<CallTrace>,
     /// Transaction fee
     pub transaction_fee: U256,
     /// accounts touched by avm
@@ -0,0 +1,163 @@
+/*******************************************************************************
+ * Copyright (c) 2015-2018 Parity Technologies (UK) Ltd.
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Call tree tracing of transaction execution.
+
+use aion_types::{U256, Address};
+use acore_bytes::Bytes;
+use vms::{CallType, ExecStatus};
+
+/// Kind of a traced call frame.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TraceCallType {
+    /// Contract creation.
+    Create,
+    /// CALL.
+    Call,
+    /// CALLCODE.
+    CallCode,
+    /// DELEGATECALL.
+    DelegateCall,
+    /// STATICCALL.
+    StaticCall,
+}
+
+impl From<CallType> for TraceCallType {
+    fn from(call_type: CallType) -> Self {
+        match call_type {
+            CallType::None => TraceCallType::Create,
+            CallType::CallCode => TraceCallType::CallCode,
+            CallType::DelegateCall => TraceCallType::DelegateCall,
+            CallType::StaticCall => TraceCallType::StaticCall,
+            CallType::Call | CallType::BulkBalance => TraceCallType::Call,
+        }
+    }
+}
+
+/// A single call frame together with the calls it made.
+#[derive(Debug, PartialEq, Clone)]
+pub struct CallTrace {
+    /// Kind of the call.
+    pub call_type: TraceCallType,
+    /// Caller.
+    pub from: Address,
+    /// Callee, or the created contract.
+    pub to: Address,
+    /// Value transferred with the call.
+    pub value: U256,
+    /// Gas given to the call.
+    pub gas: U256,
+    /// Gas consumed by the call, including nested calls.
+    pub gas_used: U256,
+    /// Call data, or init code for creations.
+    pub input: Bytes,
+    /// Return data.
+    pub output: Bytes,
+    /// Whether the callee is a precompiled builtin contract.
+    pub builtin: bool,
+    /// Error message if the call did not succeed.
+    pub error: Option<String>,
+    /// Reason passed to `revert`, if the call reverted with one.
+    pub revert_reason: Option<String>,
+    /// Calls made by this frame, in execution order.
+    pub calls: Vec<CallTrace>,
+}
+
+impl CallTrace {
+    /// Fills in the outcome of the call from the status reported by the vm.
+    pub fn set_result(
+        &mut self,
+        status: &ExecStatus,
+        gas_left: U256,
+        output: Bytes,
+        exception: &str,
+    )
+    {
+        self.gas_used = match *status {
+            ExecStatus::Success | ExecStatus::Revert => self.gas.saturating_sub(gas_left),
+            _ => self.gas,
+        };
+        self.error = match *status {
+            ExecStatus::Success => None,
+            _ if !exception.is_empty() => Some(exception.to_owned()),
+            _ => Some(format!("{:?}", status)),
+        };
+        if *status == ExecStatus::Revert {
+            self.revert_reason = decode_revert_reason(&output);
+        }
+        self.output = output;
+    }
+}
+
+/// Decodes the message of a solidity `Error(string)` revert. FastVM words are 16 bytes wide,
+/// so the payload is `selector(4) ++ offset(16) ++ length(16) ++ message`.
+pub fn decode_revert_reason(output: &[u8]) -> Option<String> {
+    const SELECTOR: usize = 4;
+    const WORD: usize = 16;
+
+    if output.len() < SELECTOR + 2 * WORD {
+        return None;
+    }
+    let word = |at: usize| -> Option<usize> {
+        let bytes = output.get(at..at.checked_add(WORD)?)?;
+        if bytes[..WORD - 8].iter().any(|b| *b != 0) {
+            return None;
+        }
+        Some(bytes[WORD - 8..].iter().fold(0usize, |acc, b| (acc << 8) | *b as usize))
+    };
+
+    let offset = word(SELECTOR)?.checked_add(SELECTOR)?;
+    let len = word(offset)?;
+    if len == 0 {
+        return None;
+    }
+    let start = offset.checked_add(WORD)?;
+    let message = output.get(start..start.checked_add(len)?)?;
+    String::from_utf8(message.to_vec()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::decode_revert_reason;
+
+    #[test]
+    fn decodes_revert_reason() {
+        let mut output = vec![0x08, 0xc3, 0x79, 0xa0];
+        let mut offset = vec![0u8; 16];
+        offset[15] = 16;
+        let mut len = vec![0u8; 16];
+        len[15] = 5;
+        output.extend(offset);
+        output.extend(len);
+        output.extend(b"oops!".iter().cloned());
+        output.extend(vec![0u8; 11]);
+
+        assert_eq!(decode_revert_reason(&output), Some("oops!".to_owned()));
+    }
+
+    #[test]
+    fn ignores_malformed_revert_data() {
+        assert_eq!(decode_revert_reason(&[]), None);
+        assert_eq!(decode_revert_reason(&[0u8; 36]), None);
+        assert_eq!(decode_revert_reason(&[0xffu8; 64]), None);
+    }
+}
@@ -25,6 +25,8 @@ pub enum Error {
     NotFound(String),
     OpenError { name: String, desc: String },
     FlushError { name: String, desc: String },
+    IncompatibleVersion { found: u32, supported: u32 },
+    Migration(String),
     Other(String),
 }
 
@@ -40,6 +42,18 @@ impl fmt::Display for Error {
                 ref name,
                 ref desc,
             } => write!(f, "db {} flush error: {}", name, desc),
+            Error::IncompatibleVersion {
+                found,
+                supported,
+            } => {
+                write!(
+                    f,
+                    "db schema version {} is newer than the latest supported version {}, please \
+                     upgrade the client",
+                    found, supported
+                )
+            }
+            Error::Migration(ref info) => write!(f, "db migration failed: {}", info),
             Error::Other(ref info) => write!(f, "db crashed: {}, please clean and resync", info),
         }
     }
@@ -42,6 +42,7 @@ mod rockskvdb;
 mod memorydb;
 mod error;
 mod dbconfigs;
+pub mod migration;
 #[cfg(test)]
 mod tests;
 
@@ -0,0 +1,232 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Database schema versioning and migrations.
+//!
+//! The schema version and the progress of an unfinished migration are kept in a
+//! dedicated meta db. Migrations rewrite one db entry by entry and checkpoint their
+//! progress every `batch_size` entries, so an interrupted migration resumes from the
+//! last checkpoint on the next start.
+
+use rlp::{decode, encode, RlpStream, UntrustedRlp};
+
+use super::Result;
+use dbtransaction::DBTransaction;
+use error::Error;
+use traits::KeyValueDB;
+
+/// Key of the schema version in the meta db.
+pub const VERSION_KEY: &'static [u8] = b"schema_version";
+/// Key of the progress of an unfinished migration in the meta db.
+pub const PROGRESS_KEY: &'static [u8] = b"migration_progress";
+
+/// A migration of the database to a newer schema version.
+///
+/// Entries may be migrated twice if the client is stopped between two checkpoints,
+/// so `migrate` must be idempotent.
+pub trait Migration: Send + Sync {
+    /// Schema version of the database once this migration has completed.
+    fn version(&self) -> u32;
+    /// Short description shown while migrating.
+    fn description(&self) -> &'static str;
+    /// Db whose entries are migrated.
+    fn db_name(&self) -> &'static str;
+    /// Migrate a single entry, adding the resulting changes to `batch`.
+    fn migrate(&self, key: &[u8], value: &[u8], batch: &mut DBTransaction) -> Result<()>;
+}
+
+/// Migration manager configuration.
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Db holding the schema version and migration progress.
+    pub meta_db: &'static str,
+    /// Schema version of databases created before versioning was introduced.
+    pub base_version: u32,
+    /// Number of entries migrated between two progress checkpoints.
+    pub batch_size: usize,
+}
+
+/// Runs migrations in order of their versions.
+pub struct Manager {
+    config: Config,
+    migrations: Vec<Box<Migration>>,
+}
+
+impl Manager {
+    /// Create a manager without migrations.
+    pub fn new(config: Config) -> Self {
+        Manager {
+            config: config,
+            migrations: Vec::new(),
+        }
+    }
+
+    /// Add a migration. Migrations must be added in increasing version order.
+    pub fn add_migration<T: 'static + Migration>(&mut self, migration: T) -> Result<()> {
+        if migration.version() <= self.latest_version() {
+            return Err(Error::Migration(format!(
+                "migration to version {} added after version {}",
+                migration.version(),
+                self.latest_version()
+            )));
+        }
+        self.migrations.push(Box::new(migration));
+        Ok(())
+    }
+
+    /// Schema version of the database once all migrations have run.
+    pub fn latest_version(&self) -> u32 {
+        self.migrations
+            .last()
+            .map_or(self.config.base_version, |m| m.version())
+    }
+
+    /// Schema version of the database.
+    pub fn version(&self, db: &KeyValueDB) -> Result<u32> {
+        Ok(db
+            .get(self.config.meta_db, VERSION_KEY)?
+            .map_or(self.config.base_version, |v| decode(&v)))
+    }
+
+    /// Returns true if the database has to be migrated. Fails if the database was written
+    /// by a newer client.
+    pub fn is_needed(&self, db: &KeyValueDB) -> Result<bool> {
+        let version = self.version(db)?;
+        let latest = self.latest_version();
+        if version > latest {
+            return Err(Error::IncompatibleVersion {
+                found: version,
+                supported: latest,
+            });
+        }
+        Ok(version < latest)
+    }
+
+    /// Run all pending migrations and return the resulting schema version.
+    pub fn execute(&self, db: &KeyValueDB) -> Result<u32> {
+        self.is_needed(db)?;
+        let version = self.version(db)?;
+
+        // (version, last migrated key) of an interrupted migration
+        let progress = match db.get(self.config.meta_db, PROGRESS_KEY)? {
+            Some(p) => {
+                let rlp = UntrustedRlp::new(&p);
+                let progress = rlp
+                    .val_at::<u32>(0)
+                    .and_then(|v| Ok((v, rlp.val_at::<Vec<u8>>(1)?)));
+                Some(progress.map_err(|e| {
+                    Error::Migration(format!("invalid migration progress: {}", e))
+                })?)
+            }
+            None => None,
+        };
+
+        for migration in self.migrations.iter().filter(|m| m.version() > version) {
+            let resume_from = match progress {
+                Some((v, ref key)) if v == migration.version() => Some(key.clone()),
+                _ => None,
+            };
+            self.run(db, &**migration, resume_from)?;
+        }
+
+        let latest = self.latest_version();
+        if db.get(self.config.meta_db, VERSION_KEY)?.is_none() {
+            let mut batch = DBTransaction::new();
+            batch.put_vec(self.config.meta_db, VERSION_KEY, encode(&latest).into_vec());
+            db.write(batch)?;
+            db.flush()?;
+        }
+        Ok(latest)
+    }
+
+    fn run(
+        &self,
+        db: &KeyValueDB,
+        migration: &Migration,
+        resume_from: Option<Vec<u8>>,
+    ) -> Result<()>
+    {
+        let version = migration.version();
+        info!(
+            target: "migration",
+            "{} database to version {}: {}",
+            if resume_from.is_some() { "Resuming migration of" } else { "Migrating" },
+            version,
+            migration.description()
+        );
+
+        let mut batch = DBTransaction::new();
+        let mut pending = 0;
+        let mut migrated = 0usize;
+        let mut last_key = None;
+        let entries = db.iter(migration.db_name()).skip_while(|&(ref key, _)| {
+            resume_from
+                .as_ref()
+                .map_or(false, |from| &key[..] <= &from[..])
+        });
+        for (key, value) in entries {
+            migration.migrate(&key, &value, &mut batch)?;
+            last_key = Some(key);
+            pending += 1;
+            if pending == self.config.batch_size {
+                migrated += pending;
+                pending = 0;
+                let batch = ::std::mem::replace(&mut batch, DBTransaction::new());
+                self.checkpoint(db, batch, version, last_key.as_ref().map(|k| &k[..]))?;
+                info!(
+                    target: "migration",
+                    "Migrated {} entries of {}",
+                    migrated,
+                    migration.db_name()
+                );
+            }
+        }
+
+        // completed: bump the version and drop the progress
+        batch.put_vec(self.config.meta_db, VERSION_KEY, encode(&version).into_vec());
+        batch.delete(self.config.meta_db, PROGRESS_KEY);
+        db.write(batch)?;
+        db.flush()?;
+        info!(
+            target: "migration",
+            "Migration to version {} completed, {} entries migrated",
+            version,
+            migrated + pending
+        );
+        Ok(())
+    }
+
+    fn checkpoint(
+        &self,
+        db: &KeyValueDB,
+        mut batch: DBTransaction,
+        version: u32,
+        last_key: Option<&[u8]>,
+    ) -> Result<()>
+    {
+        let mut progress = RlpStream::new_list(2);
+        progress.append(&version);
+        progress.append(&last_key.unwrap_or(&[]));
+        batch.put_vec(self.config.meta_db, PROGRESS_KEY, progress.out());
+        db.write(batch)?;
+        db.flush()
+    }
+}
@@ -0,0 +1,128 @@
+use super::*;
+use migration::{Config, Manager, Migration, PROGRESS_KEY, VERSION_KEY};
+use rlp::{decode, RlpStream};
+
+struct Double {
+    version: u32,
+}
+
+impl Migration for Double {
+    fn version(&self) -> u32 { self.version }
+
+    fn description(&self) -> &'static str { "double every value" }
+
+    fn db_name(&self) -> &'static str { "data" }
+
+    fn migrate(&self, key: &[u8], value: &[u8], batch: &mut DBTransaction) -> Result<()> {
+        batch.put(
+            "data",
+            key,
+            &value.iter().map(|v| v * 2).collect::<Vec<u8>>(),
+        );
+        Ok(())
+    }
+}
+
+fn config() -> Config {
+    Config {
+        meta_db: "meta",
+        base_version: 1,
+        batch_size: 2,
+    }
+}
+
+fn new_db() -> MockDbRepository {
+    let db = MockDbRepository::init(vec!["data".into(), "meta".into()]);
+    let mut batch = DBTransaction::new();
+    for i in 1..6u8 {
+        batch.put("data", &[i], &[i]);
+    }
+    db.write(batch).unwrap();
+    db
+}
+
+fn values(db: &MockDbRepository) -> Vec<u8> { db.iter("data").map(|(_, v)| v[0]).collect() }
+
+#[test]
+fn unversioned_db_gets_base_version() {
+    let db = new_db();
+    let manager = Manager::new(config());
+    assert_eq!(manager.version(&db).unwrap(), 1);
+    assert!(!manager.is_needed(&db).unwrap());
+
+    assert_eq!(manager.execute(&db).unwrap(), 1);
+    let version = db.get("meta", VERSION_KEY).unwrap().unwrap();
+    assert_eq!(decode::<u32>(&version), 1);
+    assert_eq!(values(&db), vec![1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn migrations_run_in_order() {
+    let db = new_db();
+    let mut manager = Manager::new(config());
+    manager.add_migration(Double { version: 2 }).unwrap();
+    manager.add_migration(Double { version: 3 }).unwrap();
+    assert!(manager.add_migration(Double { version: 3 }).is_err());
+    assert!(manager.is_needed(&db).unwrap());
+
+    assert_eq!(manager.execute(&db).unwrap(), 3);
+    assert_eq!(manager.version(&db).unwrap(), 3);
+    assert!(!manager.is_needed(&db).unwrap());
+    assert!(db.get("meta", PROGRESS_KEY).unwrap().is_none());
+    assert_eq!(values(&db), vec![4, 8, 12, 16, 20]);
+
+    // nothing left to do
+    assert_eq!(manager.execute(&db).unwrap(), 3);
+    assert_eq!(values(&db), vec![4, 8, 12, 16, 20]);
+}
+
+#[test]
+fn skips_applied_migrations() {
+    let db = new_db();
+    let mut manager = Manager::new(config());
+    manager.add_migration(Double { version: 2 }).unwrap();
+    manager.execute(&db).unwrap();
+
+    manager.add_migration(Double { version: 3 }).unwrap();
+    assert_eq!(manager.execute(&db).unwrap(), 3);
+    assert_eq!(values(&db), vec![4, 8, 12, 16, 20]);
+}
+
+#[test]
+fn resumes_interrupted_migration() {
+    let db = new_db();
+    // the first two entries were migrated before the client stopped
+    let mut batch = DBTransaction::new();
+    batch.put("data", &[1], &[2]);
+    batch.put("data", &[2], &[4]);
+    let mut progress = RlpStream::new_list(2);
+    progress.append(&2u32);
+    progress.append(&&[2u8][..]);
+    batch.put_vec("meta", PROGRESS_KEY, progress.out());
+    db.write(batch).unwrap();
+
+    let mut manager = Manager::new(config());
+    manager.add_migration(Double { version: 2 }).unwrap();
+    assert_eq!(manager.execute(&db).unwrap(), 2);
+    assert_eq!(values(&db), vec![2, 4, 6, 8, 10]);
+    assert!(db.get("meta", PROGRESS_KEY).unwrap().is_none());
+}
+
+#[test]
+fn refuses_newer_schema() {
+    let db = new_db();
+    let mut batch = DBTransaction::new();
+    batch.put_vec("meta", VERSION_KEY, ::rlp::encode(&5u32).into_vec());
+    db.write(batch).unwrap();
+
+    let mut manager = Manager::new(config());
+    manager.add_migration(Double { version: 2 }).unwrap();
+    match manager.execute(&db) {
+        Err(Error::IncompatibleVersion {
+            found: 5,
+            supported: 2,
+        }) => {}
+        other => panic!("unexpected result: {:?}", other),
+    }
+    assert_eq!(values(&db), vec![1, 2, 3, 4, 5]);
+}
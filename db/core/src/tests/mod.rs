@@ -2,5 +2,6 @@ use super::*;
 
 mod dbtransaction;
 mod memorydb;
+mod migration;
 mod mocksdb;
 mod rockskvdb;
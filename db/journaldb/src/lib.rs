@@ -43,6 +43,7 @@ use db as kvdb;
 mod traits;
 mod archivedb;
 mod overlayrecentdb;
+mod refcounteddb;
 #[cfg(test)]
 mod tests;
 
@@ -60,6 +61,11 @@ pub enum Algorithm {
     /// Inserts go into memory overlay, which is tried for key fetches. Memory overlay gets
     /// flushed in backing only at end of recent history.
     OverlayRecent,
+    /// Reference-counted pruning with low memory overhead.
+    ///
+    /// Inserts go straight to the backing database with a reference count. Removals are
+    /// journalled and only applied once their era leaves recent history.
+    RefCounted,
 }
 
 impl Default for Algorithm {
@@ -73,6 +79,7 @@ impl str::FromStr for Algorithm {
         match s {
             "archive" => Ok(Algorithm::Archive),
             "fast" => Ok(Algorithm::OverlayRecent),
+            "basic" => Ok(Algorithm::RefCounted),
             e => Err(format!("Invalid algorithm: {}", e)),
         }
     }
@@ -84,6 +91,7 @@ impl Algorithm {
         match *self {
             Algorithm::Archive => "archive",
             Algorithm::OverlayRecent => "fast",
+            Algorithm::RefCounted => "basic",
         }
     }
 
@@ -92,14 +100,26 @@ impl Algorithm {
         match *self {
             Algorithm::Archive => "archive",
             Algorithm::OverlayRecent => "overlayrecent",
+            Algorithm::RefCounted => "refcounted",
         }
     }
 
     /// Returns true if pruning strategy is stable
-    pub fn is_stable(&self) -> bool { true }
+    pub fn is_stable(&self) -> bool {
+        match *self {
+            Algorithm::Archive | Algorithm::OverlayRecent => true,
+            Algorithm::RefCounted => false,
+        }
+    }
 
     /// Returns all algorithm types.
-    pub fn all_types() -> Vec<Algorithm> { vec![Algorithm::Archive, Algorithm::OverlayRecent] }
+    pub fn all_types() -> Vec<Algorithm> {
+        vec![
+            Algorithm::Archive,
+            Algorithm::OverlayRecent,
+            Algorithm::RefCounted,
+        ]
+    }
 }
 
 impl fmt::Display for Algorithm {
@@ -118,6 +138,7 @@ pub fn new(
         Algorithm::OverlayRecent => {
             Box::new(overlayrecentdb::OverlayRecentDB::new(backing, db_name))
         }
+        Algorithm::RefCounted => Box::new(refcounteddb::RefCountedDB::new(backing, db_name)),
     }
 }
 
@@ -0,0 +1,395 @@
+/*******************************************************************************
+ * Copyright (c) 2015-2018 Parity Technologies (UK) Ltd.
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Disk-backed, ref-counted `JournalDB` implementation.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use parking_lot::RwLock;
+use heapsize::HeapSizeOf;
+use rlp::*;
+use super::LATEST_ERA_KEY;
+use kvdb::{KeyValueDB, DBTransaction, HashStore, DBValue, MemoryDB};
+use super::JournalDB;
+use aion_types::H256;
+use plain_hasher::H256FastMap;
+use error::{BaseDataError, UtilError};
+use bytes::Bytes;
+
+/// Implementation of the `JournalDB` trait for a disk-backed database with reference counting
+/// and latent removal.
+///
+/// Every node is stored in the backing database together with its reference count. Insertions
+/// are written out, with their references, as soon as they are journalled. Removals are only
+/// journalled: once an era becomes ancient, the removals of its canonical commit and the
+/// insertions of its non-canonical commits release one reference each, and nodes are deleted
+/// when no reference is left.
+///
+/// Unlike `OverlayRecentDB`, no state is kept in memory across commits apart from the number of
+/// journal records per era, so memory overhead stays low at the cost of extra disk writes.
+pub struct RefCountedDB {
+    forward: MemoryDB,
+    backing: Arc<KeyValueDB>,
+    journal: Arc<RwLock<JournalState>>,
+    inserts: Vec<H256>,
+    removes: Vec<H256>,
+    db_name: &'static str,
+}
+
+struct JournalState {
+    // Reference counts written to a batch which may not have been flushed yet.
+    pending: H256FastMap<(DBValue, u32)>,
+    // Number of journal records for each era.
+    records: HashMap<u64, usize>,
+    latest_era: Option<u64>,
+    earliest_era: Option<u64>,
+    cumulative_size: usize, // cumulative size of all journal records.
+}
+
+impl Clone for RefCountedDB {
+    fn clone(&self) -> RefCountedDB {
+        RefCountedDB {
+            forward: self.forward.clone(),
+            backing: self.backing.clone(),
+            journal: self.journal.clone(),
+            inserts: self.inserts.clone(),
+            removes: self.removes.clone(),
+            db_name: self.db_name,
+        }
+    }
+}
+
+const PADDING: [u8; 10] = [0u8; 10];
+
+fn journal_key(era: u64, index: usize) -> Bytes {
+    let mut r = RlpStream::new_list(3);
+    r.append(&era);
+    r.append(&index);
+    r.append(&&PADDING[..]);
+    r.drain().into_vec()
+}
+
+// Size of a journal record holding the given number of keys.
+fn record_size(keys: usize) -> usize { (keys + 1) * 32 }
+
+impl RefCountedDB {
+    /// Create a new instance.
+    pub fn new(backing: Arc<KeyValueDB>, db_name: &'static str) -> RefCountedDB {
+        let journal = Arc::new(RwLock::new(RefCountedDB::read_journal(&*backing, db_name)));
+        RefCountedDB {
+            forward: MemoryDB::new(),
+            backing: backing,
+            journal: journal,
+            inserts: Vec::new(),
+            removes: Vec::new(),
+            db_name: db_name,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn can_reconstruct_refs(&self) -> bool {
+        let reconstructed = Self::read_journal(&*self.backing, self.db_name);
+        let journal = self.journal.read();
+        journal.records == reconstructed.records
+            && journal.latest_era == reconstructed.latest_era
+            && journal.earliest_era == reconstructed.earliest_era
+            && journal.cumulative_size == reconstructed.cumulative_size
+    }
+
+    fn read_journal(db: &KeyValueDB, db_name: &str) -> JournalState {
+        let mut records = HashMap::new();
+        let mut latest_era = None;
+        let mut earliest_era = None;
+        let mut cumulative_size = 0;
+        if let Some(val) = db
+            .get(db_name, &LATEST_ERA_KEY)
+            .expect("Low-level database error.")
+        {
+            let mut era = decode::<u64>(&val);
+            latest_era = Some(era);
+            loop {
+                let mut index = 0usize;
+                while let Some(rlp_data) = db
+                    .get(db_name, &journal_key(era, index))
+                    .expect("Low-level database error.")
+                {
+                    trace!(target: "refcounted", "read_journal: era={}, index={}", era, index);
+                    let rlp = Rlp::new(&rlp_data);
+                    cumulative_size += record_size(rlp.at(1).item_count() + rlp.at(2).item_count());
+                    index += 1;
+                    earliest_era = Some(era);
+                }
+                if index > 0 {
+                    records.insert(era, index);
+                }
+                if index == 0 || era == 0 {
+                    break;
+                }
+                era -= 1;
+            }
+        }
+        trace!(target: "refcounted", "Recovered {} journal eras", records.len());
+        JournalState {
+            pending: HashMap::default(),
+            records: records,
+            latest_era: latest_era,
+            earliest_era: earliest_era,
+            cumulative_size: cumulative_size,
+        }
+    }
+
+    // Stored value and reference count of a node, taking unflushed writes into account.
+    fn payload(&self, pending: &H256FastMap<(DBValue, u32)>, key: &H256) -> Option<(DBValue, u32)> {
+        if let Some(&(ref value, rc)) = pending.get(key) {
+            return match rc {
+                0 => None,
+                _ => Some((value.clone(), rc)),
+            };
+        }
+        self.backing
+            .get(self.db_name, key)
+            .expect("Low-level database error. Some issue with your hard disk?")
+            .map(|d| {
+                let rlp = Rlp::new(&d);
+                (DBValue::from_slice(rlp.at(0).data()), rlp.val_at(1))
+            })
+    }
+
+    // Apply reference count changes to the backing database.
+    fn commit_refs(
+        &self,
+        journal: &mut JournalState,
+        mut changes: MemoryDB,
+        batch: &mut DBTransaction,
+    ) -> Result<u32, UtilError>
+    {
+        let mut ops = 0;
+        for (key, (value, rc)) in changes.drain() {
+            if rc == 0 {
+                continue;
+            }
+            let (value, total_rc) = match self.payload(&journal.pending, &key) {
+                Some((stored, stored_rc)) => (stored, stored_rc as i32 + rc),
+                None => (value, rc),
+            };
+            if total_rc < 0 {
+                return Err(BaseDataError::NegativelyReferencedHash(key).into());
+            }
+
+            if total_rc == 0 {
+                batch.delete(self.db_name, &key);
+            } else {
+                let mut s = RlpStream::new_list(2);
+                s.append(&&*value);
+                s.append(&(total_rc as u32));
+                batch.put(self.db_name, &key, &s.drain());
+            }
+            journal.pending.insert(key, (value, total_rc as u32));
+            ops += 1;
+        }
+        Ok(ops)
+    }
+}
+
+impl JournalDB for RefCountedDB {
+    fn boxed_clone(&self) -> Box<JournalDB> { Box::new(self.clone()) }
+
+    fn mem_used(&self) -> usize {
+        let journal = self.journal.read();
+        self.forward.mem_used()
+            + self.inserts.heap_size_of_children()
+            + self.removes.heap_size_of_children()
+            + journal.pending.heap_size_of_children()
+            + journal.records.heap_size_of_children()
+    }
+
+    fn journal_size(&self) -> usize { self.journal.read().cumulative_size }
+
+    fn is_empty(&self) -> bool {
+        self.backing
+            .get(self.db_name, &LATEST_ERA_KEY)
+            .expect("Low level database error")
+            .is_none()
+    }
+
+    fn backing(&self) -> &Arc<KeyValueDB> { &self.backing }
+
+    fn latest_era(&self) -> Option<u64> { self.journal.read().latest_era }
+
+    fn earliest_era(&self) -> Option<u64> { self.journal.read().earliest_era }
+
+    fn state(&self, key: &H256) -> Option<Bytes> { self.get(key).map(|v| v.into_vec()) }
+
+    fn journal_under(
+        &mut self,
+        batch: &mut DBTransaction,
+        now: u64,
+        id: &H256,
+    ) -> Result<u32, UtilError>
+    {
+        trace!(target: "journaldb", "entry: #{} ({})", now, id);
+
+        let mut journal = self.journal.write();
+        let journal = &mut *journal;
+
+        // flush previous changes
+        journal.pending.clear();
+
+        let index = journal.records.get(&now).cloned().unwrap_or(0);
+        let mut r = RlpStream::new_list(3);
+        r.append(id);
+        r.append_list(&self.inserts);
+        r.append_list(&self.removes);
+        batch.put_vec(self.db_name, &journal_key(now, index), r.out());
+
+        let ops = self.inserts.len() + self.removes.len();
+        journal.cumulative_size += record_size(ops);
+        journal.records.insert(now, index + 1);
+        self.inserts.clear();
+        self.removes.clear();
+
+        if journal.latest_era.map_or(true, |e| now > e) {
+            trace!(target: "journaldb", "Set latest era to {}", now);
+            batch.put_vec(self.db_name, &LATEST_ERA_KEY, encode(&now).into_vec());
+            journal.latest_era = Some(now);
+        }
+        if journal.earliest_era.map_or(true, |e| e > now) {
+            trace!(target: "journaldb", "Set earliest era to {}", now);
+            journal.earliest_era = Some(now);
+        }
+
+        let forward = ::std::mem::replace(&mut self.forward, MemoryDB::new());
+        self.commit_refs(journal, forward, batch)?;
+        Ok(ops as u32)
+    }
+
+    fn mark_canonical(
+        &mut self,
+        batch: &mut DBTransaction,
+        end_era: u64,
+        canon_id: &H256,
+    ) -> Result<u32, UtilError>
+    {
+        trace!(target: "journaldb", "canonical: #{} ({})", end_era, canon_id);
+
+        let mut journal = self.journal.write();
+        let journal = &mut *journal;
+
+        let count = journal.records.remove(&end_era).unwrap_or(0);
+        let mut released = MemoryDB::new();
+        for index in 0..count {
+            let key = journal_key(end_era, index);
+            let rlp_data = self
+                .backing
+                .get(self.db_name, &key)
+                .expect("Low-level database error.")
+                .ok_or_else(|| BaseDataError::NegativelyReferencedHash(canon_id.clone()))?;
+            let rlp = Rlp::new(&rlp_data);
+            let id: H256 = rlp.val_at(0);
+            let inserts: Vec<H256> = rlp.list_at(1);
+            let removes: Vec<H256> = rlp.list_at(2);
+            trace!(target: "journaldb", "Delete journal for time #{}.{}: {}, (canon was {}): +{} -{} entries", end_era, index, id, canon_id, inserts.len(), removes.len());
+
+            // canonical removals take effect, non-canonical insertions are reverted.
+            let to_remove = if id == *canon_id { removes } else { inserts };
+            for k in &to_remove {
+                released.remove(k);
+            }
+            journal.cumulative_size -= record_size(rlp.at(1).item_count() + rlp.at(2).item_count());
+            batch.delete(self.db_name, &key);
+        }
+
+        journal.earliest_era = journal.records.keys().min().cloned();
+        if journal.earliest_era.is_some() {
+            trace!(target: "journaldb", "Set earliest_era to {:?}", journal.earliest_era);
+        }
+
+        self.commit_refs(journal, released, batch)
+    }
+
+    fn flush(&self) { self.journal.write().pending.clear(); }
+
+    fn inject(&mut self, batch: &mut DBTransaction) -> Result<u32, UtilError> {
+        self.inserts.clear();
+        for key in self.removes.drain(..) {
+            self.forward.remove(&key);
+        }
+        let forward = ::std::mem::replace(&mut self.forward, MemoryDB::new());
+        let mut journal = self.journal.write();
+        self.commit_refs(&mut *journal, forward, batch)
+    }
+
+    fn consolidate(&mut self, mut with: MemoryDB) {
+        for (key, (value, rc)) in with.drain() {
+            for _ in 0..rc {
+                self.emplace(key.clone(), value.clone());
+            }
+            for _ in rc..0 {
+                self.remove(&key);
+            }
+        }
+    }
+}
+
+impl HashStore for RefCountedDB {
+    fn keys(&self) -> HashMap<H256, i32> {
+        let mut ret: HashMap<H256, i32> = self
+            .backing
+            .iter(self.db_name)
+            .filter(|&(ref key, _)| key.len() == 32)
+            .map(|(key, value)| {
+                let rc: u32 = Rlp::new(&value).val_at(1);
+                (H256::from_slice(&*key), rc as i32)
+            })
+            .collect();
+
+        for (key, refs) in self.forward.keys() {
+            *ret.entry(key).or_insert(0) += refs;
+        }
+        ret
+    }
+
+    fn get(&self, key: &H256) -> Option<DBValue> {
+        if let Some((d, rc)) = self.forward.raw(key) {
+            if rc > 0 {
+                return Some(d);
+            }
+        }
+        let journal = self.journal.read();
+        self.payload(&journal.pending, key).map(|(d, _)| d)
+    }
+
+    fn contains(&self, key: &H256) -> bool { self.get(key).is_some() }
+
+    fn insert(&mut self, value: &[u8]) -> H256 {
+        let key = self.forward.insert(value);
+        self.inserts.push(key.clone());
+        key
+    }
+
+    fn emplace(&mut self, key: H256, value: DBValue) {
+        self.inserts.push(key.clone());
+        self.forward.emplace(key, value);
+    }
+
+    fn remove(&mut self, key: &H256) { self.removes.push(key.clone()); }
+}
@@ -23,6 +23,7 @@
 //! Engine deserialization.
 use super::{UnityEngine};
 use super::{NullEngine};
+use super::{InstantSeal};
 
 /// Engine deserialization.
 #[derive(Debug, PartialEq, Deserialize)]
@@ -30,6 +31,8 @@ pub enum Engine {
     UnityEngine(UnityEngine),
     #[serde(rename = "null")]
     Null(NullEngine),
+    #[serde(rename = "instantSeal")]
+    InstantSeal(InstantSeal),
 }
 
 #[cfg(test)]
@@ -52,5 +55,17 @@ mod tests {
             Engine::Null(_) => {} // unit test in its own file.
             _ => panic!(),
         }
+
+        let s = r#"{
+            "instantSeal": {
+                "params": {}
+            }
+        }"#;
+
+        let deserialized: Engine = serde_json::from_str(s).unwrap();
+        match deserialized {
+            Engine::InstantSeal(_) => {} // unit test in its own file.
+            _ => panic!(),
+        }
     }
 }
@@ -0,0 +1,68 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Instant seal engine params deserialization.
+
+use uint::Uint;
+
+/// Instant seal engine params deserialization.
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct InstantSealParams {
+    /// Block reward.
+    #[serde(rename = "blockReward")]
+    pub block_reward: Option<Uint>,
+    /// Min seconds between sealed blocks, blocks are sealed as soon as transactions arrive
+    /// if zero.
+    #[serde(rename = "sealInterval")]
+    pub seal_interval: Option<u64>,
+}
+
+/// Instant seal engine descriptor
+#[derive(Debug, PartialEq, Deserialize)]
+pub struct InstantSeal {
+    /// Instant seal engine params.
+    pub params: InstantSealParams,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+    use uint::Uint;
+    use aion_types::U256;
+    use super::*;
+
+    #[test]
+    fn instant_seal_deserialization() {
+        let s = r#"{
+            "params": {
+                "blockReward": "0x0d",
+                "sealInterval": 5
+            }
+        }"#;
+
+        let deserialized: InstantSeal = serde_json::from_str(s).unwrap();
+        assert_eq!(
+            deserialized.params.block_reward,
+            Some(Uint(U256::from(0x0d)))
+        );
+        assert_eq!(deserialized.params.seal_interval, Some(5));
+    }
+}
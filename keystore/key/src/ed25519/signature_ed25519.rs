@@ -100,7 +100,12 @@ impl Deref for Ed25519Signature {
 }
 
 pub fn sign_ed25519(key: &Ed25519Secret, message: &Message) -> Result<Ed25519Signature, Error> {
-    let sig = signature(message, &key.0).to_vec();
+    sign_data_ed25519(key, message)
+}
+
+/// Sign data of any length, e.g. a 64 bytes PoS seed.
+pub fn sign_data_ed25519(key: &Ed25519Secret, data: &[u8]) -> Result<Ed25519Signature, Error> {
+    let sig = signature(data, &key.0).to_vec();
     let mut result = [0u8; 96];
     result[0..32].copy_from_slice(&key.0[32..]);
     result[32..96].copy_from_slice(&sig);
@@ -23,7 +23,8 @@
 use std::hash::{Hash, Hasher};
 use std::path::PathBuf;
 use std::cmp::Ordering;
-use key::{Address, Ed25519Signature, Ed25519Secret, Message, sign_ed25519};
+use key::{Address, Ed25519KeyPair, Ed25519Public, Ed25519Signature, Ed25519Secret, Message};
+use key::{sign_data_ed25519, sign_ed25519};
 use Error;
 use json::{Uuid, OpaqueKeyFile};
 use aion_types::H256;
@@ -117,6 +118,21 @@ pub trait SecretStore: SimpleSecretStore {
         Ok(sign_ed25519(&secret.0, message)?)
     }
 
+    /// Signs data of any length with raw secret.
+    fn sign_data_with_secret(
+        &self,
+        secret: &OpaqueSecretEd25519,
+        data: &[u8],
+    ) -> Result<Ed25519Signature, Error>
+    {
+        Ok(sign_data_ed25519(&secret.0, data)?)
+    }
+
+    /// Public key of raw secret, key files only hold the encrypted secret.
+    fn public_with_secret(&self, secret: &OpaqueSecretEd25519) -> Result<Ed25519Public, Error> {
+        Ok(Ed25519KeyPair::from_secret(secret.0.clone())?.public().clone())
+    }
+
     /// Checks if password matches given account.
     fn test_password(&self, account: &StoreAccountRef, password: &str) -> Result<bool, Error>;
 
@@ -0,0 +1,333 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Reference signer for the internal staker.
+//!
+//! Holds the staker key of a keystore account and answers the signing requests of a node
+//! started with `--staker-signer`, over a Unix domain socket. See `acore::miner::RemoteStaker`
+//! for the protocol.
+
+extern crate dir;
+extern crate docopt;
+extern crate keychain;
+extern crate panic_hook;
+extern crate parking_lot;
+extern crate rustc_hex;
+extern crate serde;
+extern crate serde_json;
+
+#[macro_use]
+extern crate serde_derive;
+
+#[cfg(test)]
+extern crate tempdir;
+
+mod protection;
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::Arc;
+use std::{env, process, fs, fmt, thread};
+
+use docopt::Docopt;
+use keychain::accounts_dir::RootDiskDirectory;
+use keychain::{EthStore, OpaqueSecretEd25519, SecretStore, StoreAccountRef};
+use parking_lot::Mutex;
+use rustc_hex::{FromHex, ToHex};
+
+use protection::SignGuard;
+
+pub const USAGE: &'static str = r#"
+staker signer.
+  Copyright (c) 2018-2019 Aion foundation.
+
+Usage:
+    staker-signer <address> --password FILE --socket PATH --state FILE [--dir DIR]
+    staker-signer [-h | --help]
+
+Options:
+    -h, --help               Display this message and exit.
+    --password FILE          File containing the password of the staker account.
+    --socket PATH            Unix domain socket to listen on for signing requests.
+    --state FILE             File recording the last signed seed and the parent
+                             of the last signed seal. Seeds and seals conflicting
+                             with them are refused.
+    --dir DIR                Specify the secret store directory. It may be either
+                             aion, aion-(chain)
+                             or a path [default: aion].
+"#;
+
+#[derive(Debug, Deserialize)]
+struct Args {
+    arg_address: String,
+    flag_password: String,
+    flag_socket: String,
+    flag_state: String,
+    flag_dir: String,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "method", rename_all = "snake_case")]
+enum Request {
+    Public,
+    SignSeed { number: u64, data: String },
+    SignSeal { number: u64, parent: String, data: String },
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Response {
+    Result(String),
+    Error(String),
+}
+
+enum Error {
+    Ethstore(keychain::Error),
+    Docopt(docopt::Error),
+}
+
+impl From<keychain::Error> for Error {
+    fn from(err: keychain::Error) -> Self { Error::Ethstore(err) }
+}
+
+impl From<docopt::Error> for Error {
+    fn from(err: docopt::Error) -> Self { Error::Docopt(err) }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Ethstore(ref err) => fmt::Display::fmt(err, f),
+            Error::Docopt(ref err) => fmt::Display::fmt(err, f),
+        }
+    }
+}
+
+/// Staker key together with its double-sign protection.
+struct Signer {
+    store: EthStore,
+    secret: OpaqueSecretEd25519,
+    public: String,
+    guard: SignGuard,
+}
+
+impl Signer {
+    fn sign(&self, data: &[u8]) -> Result<String, String> {
+        let signature = self
+            .store
+            .sign_data_with_secret(&self.secret, data)
+            .map_err(|e| e.to_string())?;
+        Ok(format!("0x{}", signature[32..96].to_hex()))
+    }
+
+    fn handle(&mut self, request: Request) -> Result<String, String> {
+        match request {
+            Request::Public => Ok(self.public.clone()),
+            Request::SignSeed {
+                number,
+                data,
+            } => {
+                let seed = decode(&data)?;
+                self.guard.check_seed(number, &seed)?;
+                println!("Signing seed at #{}", number);
+                self.sign(&seed)
+            }
+            Request::SignSeal {
+                number,
+                parent,
+                data,
+            } => {
+                let parent = decode(&parent)?;
+                let mine_hash = decode(&data)?;
+                if parent.len() != 32 || mine_hash.len() != 32 {
+                    return Err("parent and mine hash have to be 32 bytes".into());
+                }
+                self.guard.check_seal(number, &parent)?;
+                println!("Signing seal at #{}", number);
+                self.sign(&mine_hash)
+            }
+        }
+    }
+}
+
+fn decode(data: &str) -> Result<Vec<u8>, String> {
+    data.trim_left_matches("0x")
+        .from_hex()
+        .map_err(|e| format!("invalid data: {}", e))
+}
+
+fn main() {
+    panic_hook::set();
+
+    if let Err(err) = execute(env::args()) {
+        println!("{}", err);
+        process::exit(1);
+    }
+}
+
+fn key_dir(location: &str) -> Result<RootDiskDirectory, Error> {
+    let path = match location {
+        path if path.starts_with("aion") => {
+            let chain = path.split('-').nth(1).unwrap_or("aion");
+            dir::aion(chain)
+        }
+        path => path.into(),
+    };
+    Ok(RootDiskDirectory::create(path)?)
+}
+
+fn load_password(path: &str) -> Result<String, Error> {
+    let mut file = fs::File::open(path).map_err(|e| {
+        keychain::Error::Custom(format!("Error opening password file {}: {}", path, e))
+    })?;
+    let mut password = String::new();
+    file.read_to_string(&mut password).map_err(|e| {
+        keychain::Error::Custom(format!("Error reading password file {}: {}", path, e))
+    })?;
+    // drop EOF
+    let _ = password.pop();
+    Ok(password)
+}
+
+/// Listen on `path`, readable and writable by the owner only. A stale socket left by a previous
+/// run is replaced, any other file is kept.
+fn bind(path: &str) -> Result<UnixListener, Error> {
+    let custom = |e: ::std::io::Error| keychain::Error::Custom(format!("{}: {}", path, e));
+    if let Ok(metadata) = fs::symlink_metadata(path) {
+        if metadata.file_type().is_socket() {
+            fs::remove_file(path).map_err(&custom)?;
+        }
+    }
+    let listener = UnixListener::bind(path).map_err(&custom)?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600)).map_err(&custom)?;
+    Ok(listener)
+}
+
+fn serve(stream: UnixStream, signer: &Mutex<Signer>) -> ::std::io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 0 {
+        let response = match serde_json::from_str(&line) {
+            Ok(request) => signer.lock().handle(request),
+            Err(e) => Err(format!("invalid request: {}", e)),
+        };
+        let response = match response {
+            Ok(result) => Response::Result(result),
+            Err(error) => {
+                println!("Request refused: {}", error);
+                Response::Error(error)
+            }
+        };
+        let mut json =
+            serde_json::to_string(&response).expect("response serialization cannot fail; qed");
+        json.push('\n');
+        writer.write_all(json.as_bytes())?;
+        line.clear();
+    }
+    Ok(())
+}
+
+fn execute<S, I>(command: I) -> Result<(), Error>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    let args: Args = Docopt::new(USAGE).and_then(|d| d.argv(command).deserialize())?;
+
+    let store = EthStore::open(Box::new(key_dir(&args.flag_dir)?))?;
+    let address = args
+        .arg_address
+        .parse()
+        .map_err(|_| keychain::Error::InvalidAccount)?;
+    let password = load_password(&args.flag_password)?;
+    let secret = store.raw_secret(&StoreAccountRef::new(address), &password)?;
+    let public = store.sign_data_with_secret(&secret, &[])?.get_public();
+    let guard = SignGuard::open(&args.flag_state).map_err(keychain::Error::Custom)?;
+    let signer = Arc::new(Mutex::new(Signer {
+        store,
+        secret,
+        public: format!("0x{}", public.to_hex()),
+        guard,
+    }));
+
+    let listener = bind(&args.flag_socket)?;
+    println!("Signing for 0x{:?} on {}", address, args.flag_socket);
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let signer = signer.clone();
+                thread::spawn(move || {
+                    if let Err(e) = serve(stream, &signer) {
+                        println!("Connection closed: {}", e);
+                    }
+                });
+            }
+            Err(e) => println!("Failed to accept connection: {}", e),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use keychain::accounts_dir::MemoryDirectory;
+    use keychain::ethkey::generate_keypair;
+    use keychain::{EthStore, SecretStore, SimpleSecretStore};
+    use rustc_hex::ToHex;
+    use tempdir::TempDir;
+    use protection::SignGuard;
+    use super::{Request, Signer};
+
+    fn seal(number: u64, parent: u8, mine_hash: u8) -> Request {
+        Request::SignSeal {
+            number,
+            parent: format!("0x{}", [parent; 32].to_hex()),
+            data: format!("0x{}", [mine_hash; 32].to_hex()),
+        }
+    }
+
+    #[test]
+    fn restakes_same_height_with_new_timestamp() {
+        let dir = TempDir::new("staker_signer").unwrap();
+        let store = EthStore::open(Box::new(MemoryDirectory::default())).unwrap();
+        let kp = generate_keypair();
+        let account = store
+            .insert_account_ed25519(kp.secret().clone(), "test")
+            .unwrap();
+        let secret = store.raw_secret(&account, "test").unwrap();
+        let mut signer = Signer {
+            store,
+            secret,
+            public: String::new(),
+            guard: SignGuard::open(dir.path().join("state.json")).unwrap(),
+        };
+
+        // the block sealed at #10 was not imported, staking again on the same parent gives
+        // another timestamp and so another mine hash
+        assert!(signer.handle(seal(10, 1, 4)).is_ok());
+        assert!(signer.handle(seal(10, 1, 5)).is_ok());
+        // a block on a competing parent at the same height is refused
+        assert!(signer.handle(seal(10, 2, 6)).is_err());
+        assert!(signer.handle(seal(11, 7, 6)).is_ok());
+    }
+}
@@ -0,0 +1,215 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Double-sign protection.
+//!
+//! Another seed at the same height means staking on a competing parent, and a seal on another
+//! parent at the same height is a competing block on a competing fork. Signing either below an
+//! earlier one lets the staker build a fork as well. The last signed seed, and the parent of the
+//! last signed seal, are recorded on disk before a signature is released, and the signer refuses
+//! any other seed or seal parent at the same or a lower block number.
+//!
+//! Seals on the recorded parent are allowed. The mine hash includes the timestamp, so the node
+//! seals a new mine hash whenever it stakes again on the same parent, for instance after its
+//! previous block was not imported. Signing a recorded seed again is allowed as well, the node
+//! asks for the seed on every staking attempt.
+
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use rustc_hex::ToHex;
+use serde_json;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct LastSigned {
+    number: u64,
+    data: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct Record {
+    #[serde(default)]
+    seed: Option<LastSigned>,
+    #[serde(default)]
+    seal: Option<LastSigned>,
+}
+
+/// Check `data` against the last signed one of its kind, true if it is the recorded one.
+fn check(last: &Option<LastSigned>, kind: &str, number: u64, data: &str) -> Result<bool, String> {
+    match *last {
+        Some(ref last) if number == last.number && data == last.data => Ok(true),
+        Some(ref last) if number == last.number => {
+            Err(format!("already signed another {} at #{}", kind, number))
+        }
+        Some(ref last) if number < last.number => {
+            Err(format!(
+                "already signed a {} at #{}, refusing #{}",
+                kind, last.number, number
+            ))
+        }
+        _ => Ok(false),
+    }
+}
+
+/// Record of the last signed seed and seal, persisted to a file.
+pub struct SignGuard {
+    path: PathBuf,
+    record: Record,
+}
+
+impl SignGuard {
+    /// Open the record stored at `path`. A missing file means nothing was signed yet.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<SignGuard, String> {
+        let path = path.as_ref().to_path_buf();
+        let record = match fs::File::open(&path) {
+            Ok(file) => {
+                serde_json::from_reader(file).map_err(|e| {
+                    format!("Invalid signing record {}: {}", path.display(), e)
+                })?
+            }
+            Err(ref e) if e.kind() == ::std::io::ErrorKind::NotFound => Record::default(),
+            Err(e) => return Err(format!("Cannot read signing record {}: {}", path.display(), e)),
+        };
+        Ok(SignGuard {
+            path,
+            record,
+        })
+    }
+
+    /// Check that `seed` may be signed for block `number` and record it.
+    pub fn check_seed(&mut self, number: u64, seed: &[u8]) -> Result<(), String> {
+        let seed = format!("0x{}", seed.to_hex());
+        if check(&self.record.seed, "seed", number, &seed)? {
+            return Ok(());
+        }
+        let mut record = self.record.clone();
+        record.seed = Some(LastSigned {
+            number,
+            data: seed,
+        });
+        self.store(record)
+    }
+
+    /// Check that a block `number` on top of `parent` may be sealed and record the parent.
+    pub fn check_seal(&mut self, number: u64, parent: &[u8]) -> Result<(), String> {
+        let parent = format!("0x{}", parent.to_hex());
+        if check(&self.record.seal, "seal parent", number, &parent)? {
+            return Ok(());
+        }
+        let mut record = self.record.clone();
+        record.seal = Some(LastSigned {
+            number,
+            data: parent,
+        });
+        self.store(record)
+    }
+
+    /// Write the record to a temporary file first so that a crash never leaves it truncated.
+    fn store(&mut self, record: Record) -> Result<(), String> {
+        let tmp = self.path.with_extension("tmp");
+        let result = fs::File::create(&tmp).and_then(|mut file| {
+            let json = serde_json::to_vec(&record).expect("record serialization cannot fail; qed");
+            file.write_all(&json)?;
+            file.sync_all()
+        });
+        result
+            .and_then(|_| fs::rename(&tmp, &self.path))
+            .map_err(|e| format!("Cannot write signing record {}: {}", self.path.display(), e))?;
+        self.record = record;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempdir::TempDir;
+    use super::SignGuard;
+
+    #[test]
+    fn refuses_second_seed_at_same_height() {
+        let dir = TempDir::new("sign_guard").unwrap();
+        let path = dir.path().join("state.json");
+        let mut guard = SignGuard::open(&path).unwrap();
+
+        assert!(guard.check_seed(10, &[1u8; 64]).is_ok());
+        assert!(guard.check_seed(10, &[1u8; 64]).is_ok());
+        assert!(guard.check_seed(10, &[2u8; 64]).is_err());
+        assert!(guard.check_seed(9, &[3u8; 64]).is_err());
+        assert!(guard.check_seed(11, &[2u8; 64]).is_ok());
+    }
+
+    #[test]
+    fn refuses_seal_on_another_parent_at_same_height() {
+        let dir = TempDir::new("sign_guard").unwrap();
+        let path = dir.path().join("state.json");
+        let mut guard = SignGuard::open(&path).unwrap();
+
+        assert!(guard.check_seed(10, &[1u8; 64]).is_ok());
+        assert!(guard.check_seal(10, &[4u8; 32]).is_ok());
+        assert!(guard.check_seal(10, &[5u8; 32]).is_err());
+        assert!(guard.check_seal(9, &[6u8; 32]).is_err());
+        assert!(guard.check_seal(11, &[5u8; 32]).is_ok());
+
+        // refused seals are not recorded
+        let mut guard = SignGuard::open(&path).unwrap();
+        assert!(guard.check_seal(11, &[5u8; 32]).is_ok());
+        assert!(guard.check_seal(11, &[4u8; 32]).is_err());
+    }
+
+    #[test]
+    fn allows_restaking_same_parent() {
+        let dir = TempDir::new("sign_guard").unwrap();
+        let path = dir.path().join("state.json");
+        let mut guard = SignGuard::open(&path).unwrap();
+
+        // the first block at #10 was not imported, the node stakes again on the same parent
+        // with a new timestamp, which gives another mine hash
+        assert!(guard.check_seed(10, &[1u8; 64]).is_ok());
+        assert!(guard.check_seal(10, &[4u8; 32]).is_ok());
+        assert!(guard.check_seed(10, &[1u8; 64]).is_ok());
+        assert!(guard.check_seal(10, &[4u8; 32]).is_ok());
+
+        let mut guard = SignGuard::open(&path).unwrap();
+        assert!(guard.check_seed(10, &[1u8; 64]).is_ok());
+        assert!(guard.check_seal(10, &[4u8; 32]).is_ok());
+        assert!(guard.check_seal(10, &[7u8; 32]).is_err());
+    }
+
+    #[test]
+    fn keeps_record_across_restarts() {
+        let dir = TempDir::new("sign_guard").unwrap();
+        let path = dir.path().join("state.json");
+        {
+            let mut guard = SignGuard::open(&path).unwrap();
+            assert!(guard.check_seed(10, &[1u8; 64]).is_ok());
+        }
+
+        let mut guard = SignGuard::open(&path).unwrap();
+        assert!(guard.check_seed(10, &[2u8; 64]).is_err());
+        assert!(guard.check_seed(10, &[1u8; 64]).is_ok());
+        assert!(guard.check_seal(10, &[4u8; 32]).is_ok());
+
+        let mut guard = SignGuard::open(&path).unwrap();
+        assert!(guard.check_seal(10, &[5u8; 32]).is_err());
+        assert!(guard.check_seed(10, &[1u8; 64]).is_ok());
+    }
+}
@@ -20,27 +20,88 @@
  ******************************************************************************/
 
 use std::io;
+use std::sync::Arc;
+use parking_lot::Mutex;
 use tokio_codec::Decoder;
 use tokio_codec::Encoder;
 use acore_bytes::to_hex;
 use bincode::config;
 use bytes::BytesMut;
+use compress::{CompressionCounters, SharedCompression};
 use node::HEADER_LENGTH;
 use msg::ChannelBuffer;
-use route::Version;
+use msg::Head;
+use route::{Action,Module,Version};
+use secure::{SharedSession,MAC_LENGTH};
 
-pub struct Codec;
+/// frame codec, seals frames once the connection session is established and compresses them
+/// once compression is negotiated
+pub struct Codec {
+    session: SharedSession,
+    compression: SharedCompression,
+    counters: Arc<CompressionCounters>,
+}
+
+impl Codec {
+    pub fn new(session: SharedSession) -> Codec {
+        Codec {
+            session,
+            compression: Arc::new(Mutex::new(None)),
+            counters: Arc::new(CompressionCounters::default()),
+        }
+    }
+
+    /// share negotiated compression and counters with the codec
+    pub fn with_compression(
+        mut self,
+        compression: SharedCompression,
+        counters: Arc<CompressionCounters>,
+    ) -> Codec
+    {
+        self.compression = compression;
+        self.counters = counters;
+        self
+    }
+}
+
+/// handshake frames carry the session negotiation and are never sealed
+pub fn is_handshake(head: &Head) -> bool {
+    head.ctrl == Module::P2P.value()
+        && (head.action == Action::HANDSHAKEREQ.value()
+            || head.action == Action::HANDSHAKERES.value())
+}
 
 impl Encoder for Codec {
     type Item = ChannelBuffer;
     type Error = io::Error;
 
-    fn encode(&mut self, item: ChannelBuffer, dst: &mut BytesMut) -> io::Result<()> {
+    fn encode(&mut self, mut item: ChannelBuffer, dst: &mut BytesMut) -> io::Result<()> {
         let mut encoder = config();
         let encoder = encoder.big_endian();
-        if let Ok(encoded) = encoder.serialize(&item.head) {
-            dst.extend_from_slice(encoded.as_slice());
-            dst.extend_from_slice(item.body.as_slice());
+        if !is_handshake(&item.head) {
+            if let Some(compression) = *self.compression.lock() {
+                let encoded = compression.encode(item.body.as_slice());
+                self.counters.sent(item.body.len(), encoded.len());
+                item.body = encoded;
+                item.head.len = item.body.len() as u32;
+            }
+        }
+        let mut session = self.session.lock();
+        match *session {
+            Some(ref mut session) if !is_handshake(&item.head) => {
+                item.head.len = (item.body.len() + MAC_LENGTH) as u32;
+                if let Ok(encoded) = encoder.serialize(&item.head) {
+                    let sealed = session.seal(encoded.as_slice(), item.body.as_slice());
+                    dst.extend_from_slice(encoded.as_slice());
+                    dst.extend_from_slice(sealed.as_slice());
+                }
+            }
+            _ => {
+                if let Ok(encoded) = encoder.serialize(&item.head) {
+                    dst.extend_from_slice(encoded.as_slice());
+                    dst.extend_from_slice(item.body.as_slice());
+                }
+            }
         }
 
         Ok(())
@@ -77,8 +138,34 @@ impl Decoder for Codec {
                 Ok(None)
             } else {
                 let buf = src.split_to(decoded.head.len as usize + HEADER_LENGTH);
-                let (_, body) = buf.split_at(HEADER_LENGTH);
-                decoded.body.extend_from_slice(body);
+                let (head_raw, body) = buf.split_at(HEADER_LENGTH);
+                let mut session = self.session.lock();
+                match *session {
+                    Some(ref mut session) if !is_handshake(&decoded.head) => {
+                        match session.open(head_raw, body) {
+                            Some(opened) => {
+                                decoded.head.len = opened.len() as u32;
+                                decoded.body = opened;
+                            }
+                            None => {
+                                return Err(io::Error::new(
+                                    io::ErrorKind::InvalidData,
+                                    "frame authentication failed",
+                                ));
+                            }
+                        }
+                    }
+                    _ => decoded.body.extend_from_slice(body),
+                }
+                drop(session);
+                if !is_handshake(&decoded.head) {
+                    if let Some(compression) = *self.compression.lock() {
+                        let raw = compression.decode(decoded.body.as_slice())?;
+                        self.counters.received(raw.len(), decoded.body.len());
+                        decoded.head.len = raw.len() as u32;
+                        decoded.body = raw;
+                    }
+                }
                 Ok(Some(decoded))
             }
         } else {
@@ -89,3 +176,132 @@ impl Decoder for Codec {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+    use bytes::BytesMut;
+    use parking_lot::Mutex;
+    use tokio_codec::{Decoder,Encoder};
+    use compress::{Compression,CompressionCounters,COMPRESSION_THRESHOLD};
+    use msg::ChannelBuffer;
+    use route::{Action,Module,Version};
+    use secure::{Ephemeral,Session,SharedSession,MAC_LENGTH};
+    use node::HEADER_LENGTH;
+    use super::Codec;
+
+    fn sessions() -> (SharedSession, SharedSession) {
+        let initiator = Ephemeral::generate();
+        let responder = Ephemeral::generate();
+        let shared = initiator.agree(&responder.public).unwrap();
+        (
+            Arc::new(Mutex::new(Some(Session::new(
+                &shared,
+                &initiator.public,
+                &responder.public,
+                true,
+            )))),
+            Arc::new(Mutex::new(Some(Session::new(
+                &shared,
+                &initiator.public,
+                &responder.public,
+                false,
+            )))),
+        )
+    }
+
+    fn sync_msg(body: Vec<u8>) -> ChannelBuffer {
+        let mut cb = ChannelBuffer::new1(Version::V0.value(), Module::SYNC.value(), 3, 0);
+        cb.head.len = body.len() as u32;
+        cb.body = body;
+        cb
+    }
+
+    #[test]
+    fn test_plain_roundtrip() {
+        let mut codec = Codec::new(Arc::new(Mutex::new(None)));
+        let cb = sync_msg(vec![1, 2, 3]);
+        let mut buf = BytesMut::new();
+        codec.encode(cb.clone(), &mut buf).unwrap();
+        assert_eq!(buf.len(), HEADER_LENGTH + 3);
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(cb));
+    }
+
+    #[test]
+    fn test_sealed_roundtrip() {
+        let (initiator, responder) = sessions();
+        let mut encoder = Codec::new(initiator);
+        let mut decoder = Codec::new(responder);
+        let mut buf = BytesMut::new();
+        for body in vec![vec![], vec![9u8; 10], vec![1, 2, 3]] {
+            let cb = sync_msg(body.clone());
+            encoder.encode(cb.clone(), &mut buf).unwrap();
+            assert_eq!(buf.len(), HEADER_LENGTH + body.len() + MAC_LENGTH);
+            assert_eq!(decoder.decode(&mut buf).unwrap(), Some(cb));
+        }
+    }
+
+    #[test]
+    fn test_handshake_not_sealed() {
+        let (initiator, _) = sessions();
+        let mut codec = Codec::new(initiator);
+        let mut cb = ChannelBuffer::new1(
+            Version::V1.value(),
+            Module::P2P.value(),
+            Action::HANDSHAKERES.value(),
+            0,
+        );
+        cb.body = vec![1, 0];
+        cb.head.len = 2;
+        let mut buf = BytesMut::new();
+        codec.encode(cb.clone(), &mut buf).unwrap();
+        assert_eq!(buf.len(), HEADER_LENGTH + 2);
+        assert_eq!(codec.decode(&mut buf).unwrap(), Some(cb));
+    }
+
+    #[test]
+    fn test_tampered_frame() {
+        let (initiator, responder) = sessions();
+        let mut encoder = Codec::new(initiator);
+        let mut decoder = Codec::new(responder);
+        let mut buf = BytesMut::new();
+        encoder.encode(sync_msg(vec![1, 2, 3]), &mut buf).unwrap();
+        buf[HEADER_LENGTH] ^= 0xff;
+        assert!(decoder.decode(&mut buf).is_err());
+
+        // plain frames are rejected once the session is established
+        let mut plain = Codec::new(Arc::new(Mutex::new(None)));
+        let mut buf = BytesMut::new();
+        plain.encode(sync_msg(vec![0u8; 40]), &mut buf).unwrap();
+        assert!(decoder.decode(&mut buf).is_err());
+    }
+
+    #[test]
+    fn test_compressed_roundtrip() {
+        let (initiator, responder) = sessions();
+        let counters = Arc::new(CompressionCounters::default());
+        let mut encoder = Codec::new(initiator)
+            .with_compression(Arc::new(Mutex::new(Some(Compression::Snappy))), counters.clone());
+        let mut decoder = Codec::new(responder)
+            .with_compression(Arc::new(Mutex::new(Some(Compression::Snappy))), counters.clone());
+        let mut buf = BytesMut::new();
+
+        let large = vec![5u8; 2 * COMPRESSION_THRESHOLD];
+        let cb = sync_msg(large.clone());
+        encoder.encode(cb.clone(), &mut buf).unwrap();
+        assert!(buf.len() < HEADER_LENGTH + large.len());
+        assert_eq!(decoder.decode(&mut buf).unwrap(), Some(cb));
+
+        // small bodies only get the marker byte
+        let cb = sync_msg(vec![1, 2, 3]);
+        encoder.encode(cb.clone(), &mut buf).unwrap();
+        assert_eq!(buf.len(), HEADER_LENGTH + 1 + 3 + MAC_LENGTH);
+        assert_eq!(decoder.decode(&mut buf).unwrap(), Some(cb));
+
+        let stats = counters.stats();
+        assert_eq!(stats.raw_bytes_sent, large.len() as u64 + 3);
+        assert_eq!(stats.raw_bytes_received, stats.raw_bytes_sent);
+        assert!(stats.compressed_bytes_sent < stats.raw_bytes_sent);
+    }
+}
@@ -0,0 +1,253 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Payload compression
+//!
+//! Nodes advertise the algorithms they accept during the handshake and both sides pick the same
+//! one from the common set, zstd before snappy. Once negotiated, every non handshake frame body
+//! starts with a marker byte telling whether the rest is compressed. Only bodies above
+//! `COMPRESSION_THRESHOLD` are compressed, small messages don't gain anything from it.
+
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use parking_lot::Mutex;
+use snap;
+use zstd;
+
+/// capability flags, bit 0 of the handshake res byte is taken by the result
+pub const CAPABILITY_SNAPPY: u8 = 0x02;
+pub const CAPABILITY_ZSTD: u8 = 0x04;
+/// min body size compressed
+pub const COMPRESSION_THRESHOLD: usize = 1024;
+/// max decompressed body size, guards against decompression bombs
+pub const MAX_DECOMPRESSED_LENGTH: usize = 16 * 1024 * 1024;
+
+const MARKER_RAW: u8 = 0;
+const MARKER_COMPRESSED: u8 = 1;
+const ZSTD_LEVEL: i32 = 1;
+
+/// compression negotiated for the connection, shared between node and codec
+pub type SharedCompression = Arc<Mutex<Option<Compression>>>;
+
+/// payload compression algorithm
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Snappy,
+    Zstd,
+}
+
+impl Compression {
+    /// capability flag
+    pub fn flag(&self) -> u8 {
+        match *self {
+            Compression::Snappy => CAPABILITY_SNAPPY,
+            Compression::Zstd => CAPABILITY_ZSTD,
+        }
+    }
+
+    /// parse algorithm name
+    pub fn from_str(name: &str) -> Option<Compression> {
+        match name {
+            "snappy" => Some(Compression::Snappy),
+            "zstd" => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    fn compress(&self, raw: &[u8]) -> io::Result<Vec<u8>> {
+        match *self {
+            Compression::Snappy => {
+                snap::Encoder::new()
+                    .compress_vec(raw)
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))
+            }
+            Compression::Zstd => zstd::block::compress(raw, ZSTD_LEVEL),
+        }
+    }
+
+    fn decompress(&self, compressed: &[u8]) -> io::Result<Vec<u8>> {
+        match *self {
+            Compression::Snappy => {
+                let len = snap::decompress_len(compressed)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                if len > MAX_DECOMPRESSED_LENGTH {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "decompressed frame too long",
+                    ));
+                }
+                snap::Decoder::new()
+                    .decompress_vec(compressed)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            }
+            Compression::Zstd => zstd::block::decompress(compressed, MAX_DECOMPRESSED_LENGTH),
+        }
+    }
+
+    /// encode body, prefixed with marker byte
+    pub fn encode(&self, body: &[u8]) -> Vec<u8> {
+        if body.len() >= COMPRESSION_THRESHOLD {
+            match self.compress(body) {
+                Ok(ref compressed) if compressed.len() < body.len() => {
+                    let mut encoded = Vec::with_capacity(compressed.len() + 1);
+                    encoded.push(MARKER_COMPRESSED);
+                    encoded.extend_from_slice(compressed);
+                    return encoded;
+                }
+                Ok(_) => {}
+                Err(e) => debug!(target: "p2p_codec", "failed to compress frame: {}", e),
+            }
+        }
+        let mut encoded = Vec::with_capacity(body.len() + 1);
+        encoded.push(MARKER_RAW);
+        encoded.extend_from_slice(body);
+        encoded
+    }
+
+    /// decode body prefixed with marker byte
+    pub fn decode(&self, encoded: &[u8]) -> io::Result<Vec<u8>> {
+        match encoded.split_first() {
+            Some((&MARKER_RAW, body)) => Ok(body.to_vec()),
+            Some((&MARKER_COMPRESSED, compressed)) => self.decompress(compressed),
+            _ => Err(io::Error::new(io::ErrorKind::InvalidData, "invalid compression marker")),
+        }
+    }
+}
+
+/// capability flags of accepted algorithms
+pub fn capabilities(accepted: &[Compression]) -> u8 {
+    accepted.iter().fold(0u8, |flags, compression| flags | compression.flag())
+}
+
+/// pick algorithm accepted by both sides, both sides come to the same result
+pub fn negotiate(accepted: &[Compression], peer_capabilities: u8) -> Option<Compression> {
+    let common = capabilities(accepted) & peer_capabilities;
+    [Compression::Zstd, Compression::Snappy]
+        .iter()
+        .find(|compression| common & compression.flag() != 0)
+        .cloned()
+}
+
+/// bytes before and after compression on connections with compression negotiated
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CompressionStats {
+    pub raw_bytes_sent: u64,
+    pub compressed_bytes_sent: u64,
+    pub raw_bytes_received: u64,
+    pub compressed_bytes_received: u64,
+}
+
+impl CompressionStats {
+    /// compressed to raw size of all traffic, 1 if nothing was compressed
+    pub fn ratio(&self) -> f64 {
+        let raw = self.raw_bytes_sent + self.raw_bytes_received;
+        let compressed = self.compressed_bytes_sent + self.compressed_bytes_received;
+        if raw == 0 {
+            1.0
+        } else {
+            compressed as f64 / raw as f64
+        }
+    }
+}
+
+/// compression counters updated by codecs of all connections
+#[derive(Default)]
+pub struct CompressionCounters {
+    raw_bytes_sent: AtomicUsize,
+    compressed_bytes_sent: AtomicUsize,
+    raw_bytes_received: AtomicUsize,
+    compressed_bytes_received: AtomicUsize,
+}
+
+impl CompressionCounters {
+    pub fn sent(&self, raw: usize, compressed: usize) {
+        self.raw_bytes_sent.fetch_add(raw, Ordering::Relaxed);
+        self.compressed_bytes_sent
+            .fetch_add(compressed, Ordering::Relaxed);
+    }
+
+    pub fn received(&self, raw: usize, compressed: usize) {
+        self.raw_bytes_received.fetch_add(raw, Ordering::Relaxed);
+        self.compressed_bytes_received
+            .fetch_add(compressed, Ordering::Relaxed);
+    }
+
+    pub fn stats(&self) -> CompressionStats {
+        CompressionStats {
+            raw_bytes_sent: self.raw_bytes_sent.load(Ordering::Relaxed) as u64,
+            compressed_bytes_sent: self.compressed_bytes_sent.load(Ordering::Relaxed) as u64,
+            raw_bytes_received: self.raw_bytes_received.load(Ordering::Relaxed) as u64,
+            compressed_bytes_received: self.compressed_bytes_received.load(Ordering::Relaxed)
+                as u64,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_negotiate() {
+        let all = vec![Compression::Snappy, Compression::Zstd];
+        assert_eq!(capabilities(&all), CAPABILITY_SNAPPY | CAPABILITY_ZSTD);
+        assert_eq!(negotiate(&all, CAPABILITY_SNAPPY | CAPABILITY_ZSTD), Some(Compression::Zstd));
+        assert_eq!(negotiate(&all, CAPABILITY_SNAPPY), Some(Compression::Snappy));
+        assert_eq!(negotiate(&[Compression::Snappy], CAPABILITY_ZSTD), None);
+        assert_eq!(negotiate(&[], CAPABILITY_SNAPPY | CAPABILITY_ZSTD), None);
+        // result bit of older nodes is no capability
+        assert_eq!(negotiate(&all, 0x01), None);
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let large = vec![7u8; 4 * COMPRESSION_THRESHOLD];
+        for compression in vec![Compression::Snappy, Compression::Zstd] {
+            let encoded = compression.encode(&large);
+            assert_eq!(encoded[0], MARKER_COMPRESSED);
+            assert!(encoded.len() < large.len());
+            assert_eq!(compression.decode(&encoded).unwrap(), large);
+
+            // small bodies are sent as they are
+            let encoded = compression.encode(&[1, 2, 3]);
+            assert_eq!(encoded, vec![MARKER_RAW, 1, 2, 3]);
+            assert_eq!(compression.decode(&encoded).unwrap(), vec![1, 2, 3]);
+
+            assert!(compression.decode(&[]).is_err());
+            assert!(compression.decode(&[9, 1, 2]).is_err());
+            assert!(compression.decode(&[MARKER_COMPRESSED, 1, 2]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_stats() {
+        let counters = CompressionCounters::default();
+        assert!((counters.stats().ratio() - 1.0).abs() < 1e-9);
+        counters.sent(1000, 300);
+        counters.received(1000, 100);
+        let stats = counters.stats();
+        assert_eq!(stats.raw_bytes_sent, 1000);
+        assert_eq!(stats.compressed_bytes_received, 100);
+        assert!((stats.ratio() - 0.2).abs() < 1e-9);
+    }
+}
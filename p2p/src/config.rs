@@ -18,6 +18,12 @@
  *     If not, see <https://www.gnu.org/licenses/>.
  *
  ******************************************************************************/
+use std::path::PathBuf;
+use key::{Ed25519KeyPair, Ed25519Secret};
+use secure::node_id_from_public;
+use compress::Compression;
+use rate_limit::RateLimitConfig;
+
 /// P2p Config
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Config {
@@ -33,6 +39,19 @@ pub struct Config {
     pub sync_from_boot_nodes_only: bool,
     /// ip black list
     pub ip_black_list: Vec<String>,
+    /// node key, local node id is derived from its public key
+    pub node_key: Option<Ed25519Secret>,
+    /// accept and fall back to unauthenticated (V0) handshakes, a legacy peer gives way to an
+    /// authenticated one claiming its id
+    pub allow_legacy_peers: bool,
+    /// file peer bans are persisted to
+    pub bans_path: Option<PathBuf>,
+    /// file known nodes are persisted to
+    pub nodes_path: Option<PathBuf>,
+    /// inbound traffic limits
+    pub rate_limit: RateLimitConfig,
+    /// payload compression algorithms accepted, none disables compression
+    pub compression: Vec<Compression>,
 }
 
 impl Config {
@@ -45,9 +64,26 @@ impl Config {
             local_node: String::from("p2p://00000000-0000-0000-0000-000000000000@0.0.0.0:30303"),
             sync_from_boot_nodes_only: false,
             ip_black_list: Vec::new(),
+            node_key: None,
+            allow_legacy_peers: true,
+            bans_path: None,
+            nodes_path: None,
+            rate_limit: RateLimitConfig::new(),
+            compression: vec![Compression::Snappy, Compression::Zstd],
         }
     }
 
+    /// set node key and replace local node id with the one derived from it
+    pub fn set_node_key(&mut self, secret: Ed25519Secret) -> Result<(), String> {
+        let keypair = Ed25519KeyPair::from_secret(secret.clone())
+            .map_err(|e| format!("Invalid node key: {}", e))?;
+        let id = node_id_from_public(keypair.public());
+        let (_, binding) = self.get_id_and_binding();
+        self.local_node = format!("p2p://{}@{}", String::from_utf8_lossy(&id), binding);
+        self.node_key = Some(secret);
+        Ok(())
+    }
+
     /// get id & binding
     pub fn get_id_and_binding(&self) -> (String, String) {
         let local = &self.local_node.clone().replace("\"", "");
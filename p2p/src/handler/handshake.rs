@@ -18,20 +18,27 @@
  *     If not, see <https://www.gnu.org/licenses/>.
  *
  ******************************************************************************/
-
 use std::mem;
+use std::str;
 use bytes::BufMut;
 use byteorder::BigEndian;
 use byteorder::ByteOrder;
 use byteorder::ReadBytesExt;
 use version::short_version;
+use key::Ed25519Public;
+use Behaviour;
 use ChannelBuffer;
+use compress::{capabilities, negotiate};
 use node::MAX_REVISION_LENGTH;
 use node::IP_LENGTH;
 use node::NODE_ID_LENGTH;
 use node::REVISION_PREFIX;
 use node::convert_ip_string;
 use route::Action;
+use route::Version;
+use secure::{AUTH_LENGTH, EPHEMERAL_LENGTH};
+use secure::{Ephemeral, Session};
+use secure::{node_id_from_public, req_message, res_message, sign, verify};
 use state::STATE;
 use super::super::Mgr;
 
@@ -39,13 +46,47 @@ use super::{channel_buffer_template,channel_buffer_template_with_version};
 
 //TODO: remove it
 const VERSION: &str = "02";
+/// handshake res result byte of accepted req, higher bits carry capabilities
+const RESULT_ACCEPTED: u8 = 0x01;
+
+/// capabilities written as hex after VERSION, older nodes only check the version length
+fn parse_capabilities(version: &[u8]) -> u8 {
+    if version.len() < VERSION.len() + 2 {
+        return 0;
+    }
+    str::from_utf8(&version[VERSION.len()..VERSION.len() + 2])
+        .ok()
+        .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        .unwrap_or(0)
+}
+
+/// split auth trailer off an authenticated (V1) handshake body
+fn split_auth(ver: u16, body: &[u8]) -> Option<(&[u8], Option<&[u8]>)> {
+    if ver == Version::V1.value() {
+        if body.len() < AUTH_LENGTH {
+            return None;
+        }
+        let (body, auth) = body.split_at(body.len() - AUTH_LENGTH);
+        Some((body, Some(auth)))
+    } else {
+        Some((body, None))
+    }
+}
 
 // TODO: validate len
 pub fn send(p2p: Mgr, hash: u64) {
     debug!(target: "p2p_send", "handshake/send");
 
+    // peers which never answered an authenticated handshake get a legacy one
+    let legacy = p2p.config.allow_legacy_peers && p2p.legacy_peers.lock().contains(&hash);
+
     // header
-    let mut req = channel_buffer_template(Action::HANDSHAKEREQ.value());
+    let mut req = match legacy {
+        true => channel_buffer_template(Action::HANDSHAKEREQ.value()),
+        false => {
+            channel_buffer_template_with_version(Version::V1.value(), Action::HANDSHAKEREQ.value())
+        }
+    };
 
     // write id
     let (id, _) = p2p.config.get_id_and_binding();
@@ -69,9 +110,28 @@ pub fn send(p2p: Mgr, hash: u64) {
     req.body.push(revision.len() as u8);
     req.body.put_slice(revision.as_bytes());
 
-    // write version
-    req.body.push((VERSION.len() / 2) as u8);
-    req.body.put_slice(VERSION.as_bytes());
+    // write version and capabilities
+    let version = format!("{}{:02x}", VERSION, capabilities(&p2p.config.compression));
+    req.body.push((version.len() / 2) as u8);
+    req.body.put_slice(version.as_bytes());
+
+    // write ephemeral key & signature
+    if !legacy {
+        let ephemeral = Ephemeral::generate();
+        let message = req_message(id.as_bytes(), p2p.config.net_id, &ephemeral.public);
+        let signature = match sign(&p2p.keypair, &message) {
+            Some(signature) => signature,
+            None => return,
+        };
+        req.body.put_slice(&ephemeral.public);
+        req.body.put_slice(&signature);
+
+        let nodes_read = p2p.nodes.read();
+        match nodes_read.get(&hash) {
+            Some(node_lock) => node_lock.write().ephemeral = Some(ephemeral),
+            None => return,
+        }
+    }
 
     // get bodylen
     req.head.len = req.body.len() as u32;
@@ -81,20 +141,34 @@ pub fn send(p2p: Mgr, hash: u64) {
 }
 
 /// 1. decode handshake msg
-/// 2. validate and prove incoming connection to active
-/// 3. acknowledge sender if it is proved
-/// 4. update new hash
+/// 2. verify signature of authenticated handshake and set up session
+/// 3. validate and prove incoming connection to active
+/// 4. acknowledge sender if it is proved
+/// 5. update new hash
 pub fn receive_req(p2p: Mgr, hash: u64, cb_in: ChannelBuffer) {
     debug!(target: "p2p_req", "handshake/receive_req");
 
+    let (req_body, auth) = match split_auth(cb_in.head.ver, &cb_in.body) {
+        Some(split) => split,
+        None => {
+            debug!(target: "p2p_req", "handshake req auth is too short" );
+            return;
+        }
+    };
+    if auth.is_none() && !p2p.config.allow_legacy_peers {
+        debug!(target: "p2p_req", "legacy handshake req rejected" );
+        return;
+    }
+
     // check channelbuffer len
-    if (cb_in.head.len as usize) < NODE_ID_LENGTH + 2 * mem::size_of::<i32>() + IP_LENGTH + 2 {
+    if req_body.len() < NODE_ID_LENGTH + 2 * mem::size_of::<i32>() + IP_LENGTH + 2 {
         debug!(target: "p2p_req", "handshake req channelbuffer length is too short" );
         return;
     }
 
-    let (node_id, req_body_rest) = cb_in.body.split_at(NODE_ID_LENGTH);
-    {
+    // an authenticated node may take over the id of a legacy one, checked in confirm
+    let (node_id, req_body_rest) = req_body.split_at(NODE_ID_LENGTH);
+    if auth.is_none() {
         let id_set = p2p.nodes_id.lock();
         if id_set.contains(&String::from_utf8_lossy(&node_id).to_string()) {
             return;
@@ -105,6 +179,7 @@ pub fn receive_req(p2p: Mgr, hash: u64, cb_in: ChannelBuffer) {
     let local_net_id = p2p.config.net_id;
     if peer_net_id != local_net_id {
         debug!(target: "p2p_req", "Node: {:?}, invalid net id {}, should be {}.", node_id, peer_net_id, local_net_id);
+        p2p.report(hash, Behaviour::BadHandshake);
         return;
     }
 
@@ -129,11 +204,48 @@ pub fn receive_req(p2p: Mgr, hash: u64, cb_in: ChannelBuffer) {
         return;
     }
 
+    let compression = negotiate(&p2p.config.compression, parse_capabilities(version));
+
+    // verify node id is owned by the signer and answer with own signed ephemeral key
+    let mut secured: Option<(Ed25519Public, Session, Vec<u8>)> = None;
+    if let Some(auth) = auth {
+        let (peer_ephemeral, signature) = auth.split_at(EPHEMERAL_LENGTH);
+        let public = match verify(signature, &req_message(node_id, peer_net_id, peer_ephemeral)) {
+            Some(ref public) if &node_id_from_public(public)[..] == node_id => public.clone(),
+            _ => {
+                debug!(target: "p2p_req", "Node: {:?}, handshake req with invalid signature", node_id);
+                p2p.report(hash, Behaviour::BadHandshake);
+                return;
+            }
+        };
+        let ephemeral = Ephemeral::generate();
+        let shared = match ephemeral.agree(peer_ephemeral) {
+            Some(shared) => shared,
+            None => {
+                debug!(target: "p2p_req", "Node: {:?}, invalid ephemeral key", node_id);
+                return;
+            }
+        };
+        let (id, _) = p2p.config.get_id_and_binding();
+        let message = res_message(id.as_bytes(), peer_ephemeral, &ephemeral.public);
+        let signature = match sign(&p2p.keypair, &message) {
+            Some(signature) => signature,
+            None => return,
+        };
+        let mut trailer = Vec::with_capacity(AUTH_LENGTH);
+        trailer.put_slice(&ephemeral.public);
+        trailer.put_slice(&signature);
+        let session = Session::new(&shared, peer_ephemeral, &ephemeral.public, false);
+        secured = Some((public, session, trailer));
+    }
+
     let nodes_read = p2p.nodes.read();
     if let Some(node_lock) = nodes_read.get(&hash) {
         let mut node = node_lock.write();
-        debug!(target: "p2p_req", "inbound node state: connected -> active");
-        node.id.copy_from_slice(node_id);
+        if node.is_active() || node.session.lock().is_some() {
+            debug!(target: "p2p_req", "handshake req on active node ignored");
+            return;
+        }
         let addr_ip = node.addr.ip;
         trace!(target: "p2p_req", "ip:{:?} - {:?}", addr_ip, ip);
         if ip == &[0u8; 8] {
@@ -144,26 +256,47 @@ pub fn receive_req(p2p: Mgr, hash: u64, cb_in: ChannelBuffer) {
         let port = port.read_u32::<BigEndian>().unwrap_or(30303);
         trace!(target: "p2p_req", "port:{} - {}", node.addr.port, port);
         node.real_addr.port = port;
-        node.state = STATE::ACTIVE;
-        {
-            let mut id_set = p2p.nodes_id.lock();
-            id_set.insert(node.get_id_string());
-        }
         if revision_len > MAX_REVISION_LENGTH {
             node.revision[0..MAX_REVISION_LENGTH].copy_from_slice(&revision[..MAX_REVISION_LENGTH]);
         } else {
             node.revision[0..revision_len].copy_from_slice(revision);
         }
 
+        // a signed req can be captured and replayed, so an authenticated node only takes its
+        // id once its first sealed frame proves it holds the session keys, see confirm
+        if secured.is_none() {
+            debug!(target: "p2p_req", "inbound node state: connected -> active");
+            node.id.copy_from_slice(node_id);
+            node.state = STATE::ACTIVE;
+            {
+                let mut id_set = p2p.nodes_id.lock();
+                id_set.insert(node.get_id_string());
+            }
+            p2p.node_table
+                .lock()
+                .record_success(&node.id, &node.real_addr, &node.revision);
+        }
+
         let mut cb_out =
             channel_buffer_template_with_version(cb_in.head.ver, Action::HANDSHAKERES.value());;
         let mut res_body = Vec::new();
 
-        res_body.push(1 as u8);
+        res_body.push(RESULT_ACCEPTED | capabilities(&p2p.config.compression));
         let mut revision = short_version();
         revision.insert_str(0, REVISION_PREFIX);
         res_body.push(revision.len() as u8);
         res_body.put_slice(revision.as_bytes());
+
+        // session is installed before the res is queued, everything after it is sealed
+        if let Some((public, session, trailer)) = secured {
+            res_body.put_slice(trailer.as_slice());
+            node.public_key = Some(public);
+            *node.session.lock() = Some(session);
+        }
+        if let Some(compression) = compression {
+            debug!(target: "p2p_req", "Node: {}, {:?} compression", node.get_id_string(), compression);
+        }
+        *node.compression.lock() = compression;
         cb_out.body.put_slice(res_body.as_slice());
         cb_out.head.len = cb_out.body.len() as u32;
 
@@ -178,17 +311,27 @@ pub fn receive_req(p2p: Mgr, hash: u64, cb_in: ChannelBuffer) {
 }
 
 /// 1. decode handshake res msg
-/// 2. update outbound node to active
+/// 2. verify responder signature and set up session
+/// 3. update outbound node to active
 pub fn receive_res(p2p: Mgr, hash: u64, cb_in: ChannelBuffer) {
     debug!(target: "p2p_res", "handshake/receive_res");
 
+    let (res_body, auth) = match split_auth(cb_in.head.ver, &cb_in.body) {
+        Some(split) => split,
+        None => {
+            debug!(target: "p2p_res", "handshake res auth is too short" );
+            return;
+        }
+    };
+
     // check channelbuffer len
-    if cb_in.head.len < 2 {
+    if res_body.len() < 2 {
         debug!(target: "p2p_res", "handshake res channelbuffer length is too short" );
         return;
     }
 
-    let (_, revision) = cb_in.body.split_at(1);
+    let (result, revision) = res_body.split_at(1);
+    let compression = negotiate(&p2p.config.compression, result[0] & !RESULT_ACCEPTED);
     let (revision_len, revision_bytes) = revision.split_at(1);
     let revision_len = revision_len[0] as usize;
 
@@ -198,9 +341,70 @@ pub fn receive_res(p2p: Mgr, hash: u64, cb_in: ChannelBuffer) {
         return;
     }
 
+    // take pending ephemeral key of authenticated handshake
+    let ephemeral = match p2p.nodes.read().get(&hash) {
+        Some(node_lock) => node_lock.write().ephemeral.take(),
+        None => return,
+    };
+
+    // verify responder, its id is derived from the key which signed the res
+    let authenticated = auth.is_some();
+    let mut secured = None;
+    match (auth, ephemeral) {
+        (Some(auth), Some(ephemeral)) => {
+            let (peer_ephemeral, signature) = auth.split_at(EPHEMERAL_LENGTH);
+            let id = node_id_from_public(&Ed25519Public::from_slice(&signature[..32]));
+            let message = res_message(&id, &ephemeral.public, peer_ephemeral);
+            match (verify(signature, &message), ephemeral.agree(peer_ephemeral)) {
+                (Some(public), Some(shared)) => {
+                    let session = Session::new(&shared, &ephemeral.public, peer_ephemeral, true);
+                    secured = Some((id, public, session));
+                }
+                _ => {
+                    debug!(target: "p2p_res", "handshake res with invalid signature");
+                    p2p.report(hash, Behaviour::BadHandshake);
+                    return;
+                }
+            }
+        }
+        (None, None) => {}
+        _ => {
+            debug!(target: "p2p_res", "handshake res version does not match req");
+            return;
+        }
+    }
+
+    if let Some((ref id, _, _)) = secured {
+        if !evict_legacy(&p2p, hash, id) {
+            debug!(target: "p2p_res", "node id {} is already active", String::from_utf8_lossy(id));
+            return;
+        }
+    }
+
     let nodes_read = p2p.nodes.read();
     if let Some(node_lock) = nodes_read.get(&hash) {
         let mut node = node_lock.write();
+        if node.is_active() {
+            debug!(target: "p2p_res", "handshake res on active node ignored");
+            return;
+        }
+
+        if let Some((id, public, session)) = secured {
+            if node.id[..] != id[..] {
+                debug!(target: "p2p_res", "node id {} replaced by key derived {}", node.get_id_string(), String::from_utf8_lossy(&id));
+                if p2p.nodes_id.lock().contains(&String::from_utf8_lossy(&id).to_string()) {
+                    return;
+                }
+                node.id = id;
+            }
+            node.public_key = Some(public);
+            *node.session.lock() = Some(session);
+        }
+        if let Some(compression) = compression {
+            debug!(target: "p2p_res", "Node: {}, {:?} compression", node.get_id_string(), compression);
+        }
+        *node.compression.lock() = compression;
+
         if revision_len > MAX_REVISION_LENGTH {
             node.revision[0..MAX_REVISION_LENGTH]
                 .copy_from_slice(&revision_bytes[..MAX_REVISION_LENGTH]);
@@ -209,7 +413,167 @@ pub fn receive_res(p2p: Mgr, hash: u64, cb_in: ChannelBuffer) {
         }
 
         node.state = STATE::ACTIVE;
+        p2p.node_table
+            .lock()
+            .record_success(&node.id, &node.real_addr, &node.revision);
         let mut id_set = p2p.nodes_id.lock();
         id_set.insert(node.get_id_string());
+    } else {
+        return;
+    }
+    drop(nodes_read);
+
+    // first sealed frame, it confirms the session to the responder
+    if authenticated {
+        p2p.send(hash, channel_buffer_template(Action::ACTIVENODESREQ.value()));
+    }
+}
+
+/// make room for the id of an authenticated node, a legacy node can claim any id so it gives
+/// way. false if the id is held by another authenticated node
+fn evict_legacy(p2p: &Mgr, hash: u64, id: &[u8]) -> bool {
+    let mut holders = Vec::new();
+    for (node_hash, node_lock) in p2p.nodes.read().iter() {
+        if *node_hash == hash {
+            continue;
+        }
+        let node = node_lock.read();
+        if node.is_active() && &node.id[..] == id {
+            if node.public_key.is_some() {
+                return false;
+            }
+            holders.push(*node_hash);
+        }
+    }
+    for holder in holders {
+        debug!(target: "p2p_req", "legacy node {} gives way to authenticated {}", holder, String::from_utf8_lossy(id));
+        p2p.drop_node(holder);
+    }
+    true
+}
+
+/// activate inbound authenticated node on its first sealed frame, the codec has opened it
+/// so the peer owns the session keys and its id can not have been taken by a replayed req.
+/// false if the id is already used by another authenticated node
+pub fn confirm(p2p: &Mgr, hash: u64) -> bool {
+    let id = match p2p.nodes.read().get(&hash) {
+        Some(node_lock) => {
+            let node = node_lock.read();
+            match node.public_key {
+                Some(ref public) if !node.is_active() && node.session.lock().is_some() => {
+                    node_id_from_public(public)
+                }
+                _ => return true,
+            }
+        }
+        None => return true,
+    };
+    if !evict_legacy(p2p, hash, &id) {
+        debug!(target: "p2p_req", "node id {} is already active", String::from_utf8_lossy(&id));
+        return false;
+    }
+
+    let nodes_read = p2p.nodes.read();
+    if let Some(node_lock) = nodes_read.get(&hash) {
+        let mut node = node_lock.write();
+        if node.is_active() {
+            return true;
+        }
+        {
+            let mut id_set = p2p.nodes_id.lock();
+            let id_string = String::from_utf8_lossy(&id).to_string();
+            if id_set.contains(&id_string) {
+                return false;
+            }
+            id_set.insert(id_string);
+        }
+        debug!(target: "p2p_req", "inbound node state: connected -> active");
+        node.id = id;
+        node.state = STATE::ACTIVE;
+        p2p.node_table
+            .lock()
+            .record_success(&node.id, &node.real_addr, &node.revision);
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+
+    use futures::sync::{mpsc, oneshot};
+    use futures::Stream;
+    use parking_lot::RwLock;
+    use config::Config;
+    use node::{Connection, IpAddr, Node, NODE_ID_LENGTH};
+    use Mgr;
+    use super::*;
+
+    fn add_node(
+        p2p: &Mgr,
+        port: u32,
+        connection: Connection,
+    ) -> (u64, mpsc::Receiver<ChannelBuffer>)
+    {
+        let (tx, rx) = mpsc::channel(16);
+        let (tx_thread, _) = oneshot::channel::<()>();
+        let node = Node::new_simulated(
+            IpAddr::new1([0, 0, 0, 0, 0, 0, 0, 1], port),
+            [b'0'; NODE_ID_LENGTH],
+            connection,
+            tx,
+            tx_thread,
+        );
+        let hash = node.hash;
+        p2p.nodes.write().insert(hash, RwLock::new(node));
+        (hash, rx)
+    }
+
+    fn is_active(p2p: &Mgr, hash: u64) -> bool {
+        p2p.nodes.read().get(&hash).unwrap().read().is_active()
+    }
+
+    #[test]
+    fn test_replayed_req_does_not_take_id() {
+        let initiator = Mgr::new(Config::new(), vec![]);
+        let responder = Mgr::new(Config::new(), vec![]);
+        let (id, _) = initiator.config.get_id_and_binding();
+
+        let (hash, rx) = add_node(&initiator, 30303, Connection::OUTBOUND);
+        send(initiator.clone(), hash);
+        let req = rx.wait().next().unwrap().unwrap();
+
+        // genuine req and a replay of it on another connection
+        let (genuine, _rx_genuine) = add_node(&responder, 30304, Connection::INBOUND);
+        let (replayed, _rx_replayed) = add_node(&responder, 30305, Connection::INBOUND);
+        receive_req(responder.clone(), genuine, req.clone());
+        receive_req(responder.clone(), replayed, req);
+        assert!(!is_active(&responder, genuine));
+        assert!(!is_active(&responder, replayed));
+        assert!(responder.nodes_id.lock().is_empty());
+
+        // only the connection which sealed a frame gets the id
+        assert!(confirm(&responder, genuine));
+        assert!(is_active(&responder, genuine));
+        assert!(responder.nodes_id.lock().contains(&id));
+        assert!(!confirm(&responder, replayed));
+        assert!(!is_active(&responder, replayed));
+    }
+
+    #[test]
+    fn test_legacy_node_gives_way() {
+        let p2p = Mgr::new(Config::new(), vec![]);
+        let (legacy, _rx_legacy) = add_node(&p2p, 30303, Connection::INBOUND);
+        let (authenticated, _rx_authenticated) = add_node(&p2p, 30304, Connection::INBOUND);
+        let id = [b'1'; NODE_ID_LENGTH];
+        {
+            let nodes_read = p2p.nodes.read();
+            let mut node = nodes_read.get(&legacy).unwrap().write();
+            node.id = id;
+            node.state = STATE::ACTIVE;
+            p2p.nodes_id.lock().insert(node.get_id_string());
+        }
+        assert!(evict_legacy(&p2p, authenticated, &id));
+        assert!(p2p.nodes.read().get(&legacy).is_none());
+        assert!(p2p.nodes_id.lock().is_empty());
     }
 }
@@ -47,6 +47,11 @@ extern crate aion_version as version;
 extern crate bytes;
 extern crate byteorder;
 extern crate parking_lot;
+extern crate key;
+extern crate crypto;
+extern crate blake2b;
+extern crate snap;
+extern crate zstd;
 
 #[cfg(test)]
 mod test;
@@ -55,9 +60,16 @@ mod route;
 mod msg;
 mod node;
 mod codec;
+mod compress;
+mod secure;
+mod reputation;
+mod node_table;
+mod rate_limit;
 mod state;
 mod handler;
 mod callable;
+#[cfg(any(test, feature = "sim"))]
+mod sim;
 
 use std::io;
 use std::sync::{Arc,Weak};
@@ -65,7 +77,6 @@ use std::collections::{VecDeque,HashMap,HashSet};
 use std::time::Duration;
 use std::time::Instant;
 use std::net::TcpStream as StdTcpStream;
-use std::net::Shutdown;
 use std::net::SocketAddr;
 use rand::random;
 use futures::prelude::*;
@@ -80,24 +91,39 @@ use tokio::prelude::*;
 use tokio::runtime::TaskExecutor;
 use tokio::timer::Interval;
 use tokio_codec::{Decoder,Framed};
-use codec::Codec;
+use codec::{Codec,is_handshake};
+use compress::{CompressionCounters, SharedCompression};
 use route::Version;
 use route::Action;
 use state::STATE;
 use handler::handshake;
 use handler::active_nodes;
-use node::TempNode;
+use node::{IpAddr,TempNode,HEADER_LENGTH};
 use parking_lot::{Mutex,RwLock};
+use key::{generate_keypair,Ed25519KeyPair};
+use secure::SharedSession;
+use reputation::Reputation;
+use node_table::NodeTable;
+use rate_limit::RateLimiter;
 
 pub use msg::ChannelBuffer;
 pub use node::Node;
 pub use config::Config;
 pub use callable::Callable;
+pub use reputation::{Behaviour, PeerReputation};
+pub use compress::{Compression, CompressionStats};
+#[cfg(any(test, feature = "sim"))]
+pub use sim::{LinkConfig, SimNetwork, SimStats};
+pub use rate_limit::{
+    route_key, NetworkTraffic, PeerTraffic, RateLimitConfig, RouteTraffic, TrafficStats,
+};
 
 const INTERVAL_OUTBOUND_CONNECT: u64 = 1;
 const INTERVAL_TIMEOUT: u64 = 5;
 const INTERVAL_ACTIVE_NODES: u64 = 3;
 const TIMEOUT_MAX: u64 = 30;
+const INTERVAL_NODE_TABLE: u64 = 60;
+const INTERVAL_TRAFFIC: u64 = 60;
 const TEMP_MAX: usize = 64;
 
 /// Protocal version for p2p/sync
@@ -121,11 +147,35 @@ pub struct Mgr {
     tokens_rule: Arc<HashMap<u32, u32>>,
     /// nodes ID
     nodes_id: Arc<Mutex<HashSet<String>>>,
+    /// node keypair, signs authenticated handshakes
+    keypair: Arc<Ed25519KeyPair>,
+    /// hashes of peers which did not answer an authenticated handshake
+    legacy_peers: Arc<Mutex<HashSet<u64>>>,
+    /// peer scores and bans
+    reputation: Arc<Mutex<Reputation>>,
+    /// persisted nodes seen in earlier runs
+    node_table: Arc<Mutex<NodeTable>>,
+    /// inbound traffic limits and counters
+    rate_limiter: Arc<Mutex<RateLimiter>>,
+    /// compression counters of all connections
+    compression_counters: Arc<CompressionCounters>,
+    /// simulated network replacing tcp connections
+    #[cfg(any(test, feature = "sim"))]
+    network: Arc<RwLock<Option<SimNetwork>>>,
 }
 
 impl Mgr {
     /// constructor
     pub fn new(mut config: Config, tokens_pairs: Vec<[u32; 2]>) -> Mgr {
+        // load node key, a random one is used for this run if none is configured
+        let keypair = match config.node_key.clone() {
+            Some(secret) => Ed25519KeyPair::from_secret(secret).expect("invalid node key"),
+            None => generate_keypair(),
+        };
+        config
+            .set_node_key(keypair.secret().clone())
+            .expect("invalid node key");
+
         // load local node
         let temp_local = TempNode::new_from_str(config.local_node);
         config.local_node = format!(
@@ -143,6 +193,19 @@ impl Mgr {
             temp_queue.push_back(TempNode::new_from_str(boot_node_str.to_string()));
         }
 
+        // load known nodes, they are tried along with seeds so boot node outages are survived
+        let node_table = NodeTable::new(config.nodes_path.clone());
+        if !config.sync_from_boot_nodes_only {
+            let seeds: Vec<IpAddr> = temp_queue.iter().map(|node| node.addr).collect();
+            let count = TEMP_MAX.saturating_sub(temp_queue.len());
+            for temp_node in node_table.select(count) {
+                if !seeds.contains(&temp_node.addr) {
+                    trace!(target: "run", "known node: {}", temp_node.addr.to_string());
+                    temp_queue.push_back(temp_node);
+                }
+            }
+        }
+
         // parse token rules
         let mut tokens_rule: HashMap<u32, u32> = HashMap::new();
         for pair in tokens_pairs {
@@ -158,6 +221,10 @@ impl Mgr {
             p2p_rule_base + Action::ACTIVENODESREQ.value() as u32,
         );
 
+        // load persisted bans
+        let reputation = Reputation::new(config.bans_path.clone());
+        let rate_limiter = RateLimiter::new(config.rate_limit.clone());
+
         Mgr {
             shutdown_hooks: Arc::new(Mutex::new(Vec::new())),
             callback: Arc::new(RwLock::new(None)),
@@ -166,9 +233,57 @@ impl Mgr {
             nodes: Arc::new(RwLock::new(HashMap::new())),
             tokens_rule: Arc::new(tokens_rule),
             nodes_id: Arc::new(Mutex::new(id_set)),
+            keypair: Arc::new(keypair),
+            legacy_peers: Arc::new(Mutex::new(HashSet::new())),
+            reputation: Arc::new(Mutex::new(reputation)),
+            node_table: Arc::new(Mutex::new(node_table)),
+            rate_limiter: Arc::new(Mutex::new(rate_limiter)),
+            compression_counters: Arc::new(CompressionCounters::default()),
+            #[cfg(any(test, feature = "sim"))]
+            network: Arc::new(RwLock::new(None)),
         }
     }
 
+    /// connect through simulated network instead of tcp, to be called before run
+    #[cfg(any(test, feature = "sim"))]
+    pub fn set_network(&self, network: &SimNetwork) {
+        network.register(self);
+        *self.network.write() = Some(network.clone());
+    }
+
+    #[cfg(any(test, feature = "sim"))]
+    fn is_simulated(&self) -> bool { self.network.read().is_some() }
+
+    #[cfg(not(any(test, feature = "sim")))]
+    fn is_simulated(&self) -> bool { false }
+
+    /// connect through simulated network if set, false if tcp is to be used
+    #[cfg(any(test, feature = "sim"))]
+    fn connect_simulated(&self, executor: &TaskExecutor, temp_node: &TempNode) -> bool {
+        let network = self.network.read().clone();
+        match network {
+            Some(network) => {
+                network.connect(executor, self, temp_node);
+                true
+            }
+            None => false,
+        }
+    }
+
+    #[cfg(not(any(test, feature = "sim")))]
+    fn connect_simulated(&self, _executor: &TaskExecutor, _temp_node: &TempNode) -> bool { false }
+
+    #[cfg(any(test, feature = "sim"))]
+    fn leave_simulated(&self) {
+        let network = self.network.write().take();
+        if let Some(network) = network {
+            network.unregister(&self.config.get_id_and_binding().1);
+        }
+    }
+
+    #[cfg(not(any(test, feature = "sim")))]
+    fn leave_simulated(&self) {}
+
     /// bind sync module callback
     pub fn register_callback(&self, callback: Weak<Callable>) {
         *self.callback.write() = Some(callback);
@@ -193,11 +308,148 @@ impl Mgr {
     /// 4. always return true if there is no token rule applied
     pub fn token_check(&self, clear_token: u32, node: &mut Node) -> bool {
         match &self.tokens_rule.get(&clear_token) {
-            Some(&flag_token) => node.tokens.remove(&flag_token),
+            Some(&flag_token) => {
+                node.record_response(flag_token);
+                node.tokens.remove(&flag_token)
+            }
             None => true,
         }
     }
 
+    /// report peer behaviour, a peer which gets banned is disconnected
+    pub fn report(&self, hash: u64, behaviour: Behaviour) {
+        let ip = match self.nodes.read().get(&hash) {
+            Some(node_lock) => node_lock.read().addr.get_ip(),
+            None => return,
+        };
+        trace!(target: "p2p_reputation", "node {} ip {}: {:?}", hash, ip, behaviour);
+        let banned = self.reputation.lock().record(&ip, behaviour);
+        if banned {
+            info!(target: "p2p_reputation", "ip {} banned after {:?}", ip, behaviour);
+            self.drop_node(hash);
+        }
+    }
+
+    /// close connection to node and remove it
+    fn drop_node(&self, hash: u64) -> bool {
+        let removed = self.nodes.write().remove(&hash);
+        match removed {
+            Some(node_lock) => {
+                let node = node_lock.into_inner();
+                if let Err(err) = node.shutdown_stream() {
+                    debug!(target: "p2p_node", "shutdown err: {:?}", err);
+                }
+                let _ = node.shutdown_tcp_thread();
+                self.disconnect(hash, node.get_id_string());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// queue node `p2p://<id>@<ip>:<port>` for connection, it is reconnected like a seed
+    pub fn add_peer(&self, node_str: &str) -> Result<(), String> {
+        let temp_node = match TempNode::parse(node_str) {
+            Some(temp_node) => temp_node,
+            None => return Err(format!("Invalid node: {}", node_str)),
+        };
+        if self.is_banned(&temp_node.addr.get_ip()) {
+            return Err(format!("Node is banned: {}", node_str));
+        }
+        let mut temp = self.temp.lock();
+        temp.retain(|node| node.addr != temp_node.addr);
+        temp.push_front(temp_node);
+        Ok(())
+    }
+
+    /// disconnect peer by node id or address `<ip>:<port>` and stop reconnecting it,
+    /// return false if no such peer is connected or queued
+    pub fn remove_peer(&self, peer: &str) -> bool {
+        let matches = |id: &[u8], addr: &IpAddr| {
+            String::from_utf8_lossy(id) == peer || addr.to_string() == peer
+        };
+        let queued = {
+            let mut temp = self.temp.lock();
+            let len = temp.len();
+            temp.retain(|node| !matches(&node.id, &node.addr));
+            temp.len() != len
+        };
+        let hashes: Vec<u64> = self
+            .nodes
+            .read()
+            .values()
+            .map(|node_lock| node_lock.read())
+            .filter(|node| matches(&node.id, &node.addr) || matches(&node.id, &node.real_addr))
+            .map(|node| node.hash)
+            .collect();
+        let mut removed = false;
+        for hash in hashes {
+            removed |= self.drop_node(hash);
+        }
+        removed || queued
+    }
+
+    /// ban ip and disconnect its peers
+    pub fn ban_peer(&self, ip: &str) -> Result<(), String> {
+        if ip.parse::<::std::net::Ipv4Addr>().is_err() {
+            return Err(format!("Invalid ip: {}", ip));
+        }
+        info!(target: "p2p_reputation", "ip {} banned", ip);
+        self.reputation.lock().ban(ip);
+        let hashes: Vec<u64> = self
+            .nodes
+            .read()
+            .values()
+            .map(|node_lock| node_lock.read())
+            .filter(|node| node.addr.get_ip() == ip)
+            .map(|node| node.hash)
+            .collect();
+        for hash in hashes {
+            self.drop_node(hash);
+        }
+        Ok(())
+    }
+
+    /// return true if ip is black listed or banned
+    fn is_banned(&self, ip: &str) -> bool {
+        self.config.ip_black_list.iter().any(|black| black == ip)
+            || self.reputation.lock().is_banned(ip)
+    }
+
+    /// get reputation of all known peers
+    pub fn get_reputations(&self) -> Vec<PeerReputation> {
+        self.reputation.lock().get_reputations()
+    }
+
+    /// get inbound traffic counters of all and of connected peers
+    pub fn get_traffic(&self) -> NetworkTraffic {
+        let peers: Vec<(u64, String, String)> = self
+            .nodes
+            .read()
+            .values()
+            .map(|node_lock| node_lock.read())
+            .map(|node| (node.hash, node.get_id_string(), node.addr.to_string()))
+            .collect();
+        let rate_limiter = self.rate_limiter.lock();
+        NetworkTraffic {
+            total: rate_limiter.total(),
+            peers: peers
+                .into_iter()
+                .filter_map(|(hash, id, addr)| {
+                    rate_limiter.peer_stats(hash).map(|(total, routes)| {
+                        PeerTraffic {
+                            id,
+                            addr,
+                            total,
+                            routes,
+                        }
+                    })
+                })
+                .collect(),
+            compression: self.compression_counters.stats(),
+        }
+    }
+
     /// send msg
     pub fn send(&self, hash: u64, cb: ChannelBuffer) -> bool {
         let nodes = &self.nodes;
@@ -250,6 +502,9 @@ impl Mgr {
                 if let Some(node_lock) = nodes.read().get(&hash) {
                     let mut node = node_lock.write();
                     node.tokens.insert(route);
+                    if self.tokens_rule.values().any(|flag_token| *flag_token == route) {
+                        node.requests.insert(route, Instant::now());
+                    }
                 } else {
                     trace!(target:"p2p_send", "send: node not found hash {}", hash);
                     return false;
@@ -302,7 +557,19 @@ impl Mgr {
                             match nodes_write.remove(&hash) {
                                 Some(node_lock) => {
                                     let mut node = node_lock.write();
+                                    // outbound authenticated handshake never answered,
+                                    // retry the peer with a legacy one
+                                    if node.ephemeral.is_some()
+                                        && p2p_timeout.config.allow_legacy_peers
+                                    {
+                                        p2p_timeout.legacy_peers.lock().insert(hash);
+                                    }
                                     node.tx.close().unwrap();
+                                    let ip = node.addr.get_ip();
+                                    p2p_timeout.reputation.lock().record(&ip, Behaviour::Timeout);
+                                    if !node.is_active() {
+                                        p2p_timeout.node_table.lock().record_failure(&node.real_addr);
+                                    }
                                     removed_nodes.insert(hash, node.get_id_string());
                                     debug!(target: "p2p_timeout", "timeout hash/id/ip {}/{}/{}", &node.hash, &node.get_id_string(), &node.addr.to_string());
                                 },
@@ -326,6 +593,55 @@ impl Mgr {
             shutdown_hooks.push(tx);
         }
 
+        // interval node table
+        let p2p_node_table = self.clone();
+        let (tx, rx) = oneshot::channel::<()>();
+        executor.spawn(
+            Interval::new(
+                Instant::now() + Duration::from_secs(INTERVAL_NODE_TABLE),
+                Duration::from_secs(INTERVAL_NODE_TABLE),
+            ).for_each(move |_| {
+                p2p_node_table.node_table.lock().save();
+                Ok(())
+            })
+            .map_err(|err| error!(target: "p2p_node_table", "executor node table: {:?}", err))
+            .select(rx.map_err(|_| {}))
+            .map(|_| ())
+            .map_err(|_| ())
+        );
+        {
+            let mut shutdown_hooks = self.shutdown_hooks.lock();
+            shutdown_hooks.push(tx);
+        }
+
+        // interval traffic
+        let p2p_traffic = self.clone();
+        let (tx, rx) = oneshot::channel::<()>();
+        executor.spawn(
+            Interval::new(
+                Instant::now() + Duration::from_secs(INTERVAL_TRAFFIC),
+                Duration::from_secs(INTERVAL_TRAFFIC),
+            ).for_each(move |_| {
+                let traffic = p2p_traffic.get_traffic();
+                info!(target: "p2p_traffic", "in msgs/bytes {}/{}, dropped msgs/bytes {}/{}", traffic.total.messages, traffic.total.bytes, traffic.total.dropped_messages, traffic.total.dropped_bytes);
+                info!(target: "p2p_traffic", "compression out/in raw bytes {}/{}, ratio {:.2}", traffic.compression.raw_bytes_sent, traffic.compression.raw_bytes_received, traffic.compression.ratio());
+                for peer in traffic.peers {
+                    if peer.total.dropped_messages > 0 {
+                        debug!(target: "p2p_traffic", "{} {} dropped msgs/bytes {}/{}", peer.id, peer.addr, peer.total.dropped_messages, peer.total.dropped_bytes);
+                    }
+                }
+                Ok(())
+            })
+            .map_err(|err| error!(target: "p2p_traffic", "executor traffic: {:?}", err))
+            .select(rx.map_err(|_| {}))
+            .map(|_| ())
+            .map_err(|_| ())
+        );
+        {
+            let mut shutdown_hooks = self.shutdown_hooks.lock();
+            shutdown_hooks.push(tx);
+        }
+
         // interval outbound
         let executor_outbound_0 = executor.clone();
         let p2p_outbound = self.clone();
@@ -363,6 +679,12 @@ impl Mgr {
                     // process outbound connection
                     let temp_node = temp_node_opt.unwrap();
 
+                    // return if banned
+                    if p2p_outbound_0.is_banned(&temp_node.addr.get_ip()) {
+                        trace!(target: "p2p_outbound", "skip banned {}", temp_node.addr.to_string());
+                        return Ok(());
+                    }
+
                     // return if exist
                     let hash = temp_node.get_hash();
                     {
@@ -375,6 +697,11 @@ impl Mgr {
                         }
                     }
 
+                    // connect through simulated network if set
+                    if p2p_outbound_0.connect_simulated(&executor_outbound_0, &temp_node) {
+                        return Ok(());
+                    }
+
                     // counters
                     let executor_outbound_1 = executor_outbound_0.clone();
                     let executor_outbound_2 = executor_outbound_0.clone();
@@ -403,6 +730,8 @@ impl Mgr {
                                     // construct node instance and store it
                                     let (mut tx, rx) = mpsc::channel(409600);
                                     let (mut tx_thread, rx_thread) = oneshot::channel::<()>();
+                                    let session;
+                                    let compression;
                                     if let Ok(ts_0) = ts.try_clone() {
                                         let node = match Node::new_outbound(
                                             ts_0,
@@ -417,6 +746,8 @@ impl Mgr {
                                                 return Ok(());
                                             }
                                         };
+                                        session = node.session.clone();
+                                        compression = node.compression.clone();
 
                                         let mut new_node = false;
                                         {
@@ -439,7 +770,12 @@ impl Mgr {
                                     }
 
                                     // binding io futures
-                                    let (sink, stream) = split_frame(ts);
+                                    let (sink, stream) = split_frame(
+                                        ts,
+                                        session,
+                                        compression,
+                                        p2p_outbound_0.compression_counters.clone(),
+                                    );
                                     let read = stream.for_each(move |cb| {
                                         p2p_outbound_2.handle(hash.clone(), cb);
                                         Ok(())
@@ -466,7 +802,7 @@ impl Mgr {
                                 }
                             },
                             Err(_err) => {
-
+                                p2p_outbound_0.node_table.lock().record_failure(&temp_node.addr);
                             }
                         }
                     }
@@ -504,6 +840,11 @@ impl Mgr {
                 shutdown_hooks.push(tx);
             }
 
+            // simulated nodes accept connections through the network
+            if self.is_simulated() {
+                return;
+            }
+
             // interval inbound
             let executor_inbound_0 = executor.clone();
             let executor_inbound_1 = executor.clone();
@@ -516,7 +857,14 @@ impl Mgr {
                     // counters
                     let p2p_inbound_1 = p2p_inbound.clone();
 
-                    // TODO: black list check
+                    // reject black listed and banned peers
+                    if let Ok(addr) = ts.peer_addr() {
+                        if p2p_inbound.is_banned(&addr.ip().to_string()) {
+                            debug!(target: "p2p_inbound", "rejected banned {}", addr);
+                            return Ok(());
+                        }
+                    }
+
                     if p2p_inbound.get_active_nodes_len() >= p2p_inbound.config.max_peers {
                         debug!(target: "p2p_inbound", "max peers reached");
                         return Ok(());
@@ -548,6 +896,8 @@ impl Mgr {
                             }
                         };
                         let hash = node.hash;
+                        let session = node.session.clone();
+                        let compression = node.compression.clone();
 
                         let mut new_node = false;
                         {
@@ -566,7 +916,12 @@ impl Mgr {
                         }
 
                         // binding io futures
-                        let (sink, stream) = split_frame(ts);
+                        let (sink, stream) = split_frame(
+                            ts,
+                            session,
+                            compression,
+                            p2p_inbound.compression_counters.clone(),
+                        );
                         let read = stream.for_each(move |cb| {
                             p2p_inbound_1.handle(hash.clone(), cb);
                             Ok(())
@@ -601,6 +956,7 @@ impl Mgr {
             let mut id_set = self.nodes_id.lock();
             id_set.remove(&id);
         }
+        self.rate_limiter.lock().remove(hash);
         if let Some(ref callback) = *self.callback.read() {
             match Weak::upgrade(callback) {
                 Some(arc_callback) => arc_callback.disconnect(hash),
@@ -633,7 +989,7 @@ impl Mgr {
         let mut nodes_write = self.nodes.write();
         for (_hash, node_lock) in nodes_write.iter_mut() {
             let mut node = node_lock.write();
-            match node.ts.shutdown(Shutdown::Both) {
+            match node.shutdown_stream() {
                 Ok(_) => {
                     debug!(target: "p2p_shutdown", "close connection id/ip {}/{}", &node.get_id_string(), &node.addr.to_string());
                 }
@@ -653,6 +1009,12 @@ impl Mgr {
         }
         nodes_write.clear();
 
+        // Store known nodes
+        self.node_table.lock().save();
+
+        // Leave simulated network
+        self.leave_simulated();
+
         info!(target: "p2p_shutdown" , "p2p shutdown finished");
     }
 
@@ -782,12 +1144,46 @@ impl Mgr {
     /// get local node info to fill back to config file
     pub fn get_local_node_info(&self) -> &String { &self.config.local_node }
 
+    /// get local node id and listening address
+    pub fn get_local_node_id_and_binding(&self) -> (String, String) {
+        self.config.get_id_and_binding()
+    }
+
     /// messages with module code other than p2p module
     /// should flow into external handlers
     fn handle(&self, hash: u64, cb: ChannelBuffer) {
         // check body length
         if cb.head.len as usize != cb.body.len() {
             debug!(target: "p2p_handle", "Length does not match!! hash/ver/ctrl/action {}/{}/{}/{}", hash, cb.head.ver, cb.head.ctrl, cb.head.action);
+            self.report(hash, Behaviour::MalformedMessage);
+            return;
+        }
+
+        // drop traffic over the limits
+        let route = route_key(cb.head.ctrl, cb.head.action);
+        let (checked, disconnect) = {
+            let mut rate_limiter = self.rate_limiter.lock();
+            let checked = rate_limiter.check(hash, route, HEADER_LENGTH + cb.body.len());
+            (checked, rate_limiter.disconnect())
+        };
+        if let Err(limit) = checked {
+            trace!(target: "p2p_handle", "rate limited {:?}: hash/ctrl/action {}/{}/{}", limit, hash, cb.head.ctrl, cb.head.action);
+            // only the peer's own limits count against it, network limits just drop the message
+            if !limit.is_peer() {
+                return;
+            }
+            if disconnect {
+                debug!(target: "p2p_handle", "disconnect node {} over traffic limits", hash);
+                self.drop_node(hash);
+            } else {
+                self.report(hash, Behaviour::ExceededRateLimit);
+            }
+            return;
+        }
+
+        // first sealed frame of an inbound authenticated node makes it active
+        if !is_handshake(&cb.head) && !handshake::confirm(self, hash) {
+            self.drop_node(hash);
             return;
         }
 
@@ -836,11 +1232,21 @@ impl Mgr {
                         }
                     }
                 }
-                //Version::V1 => handshake::send(p2p, hash),
+                // authenticated handshake, the rest of the protocol stays on V0
+                Version::V1 if cb.head.ctrl == Module::P2P.value() => {
+                    match Action::from(cb.head.action) {
+                        Action::HANDSHAKEREQ => handshake::receive_req(p2p, hash, cb),
+                        Action::HANDSHAKERES => handshake::receive_res(p2p, hash, cb),
+                        _ => {
+                            trace!(target: "p2p_handle", "invalid action {}", cb.head.action)
+                        }
+                    };
+                }
                 _ => trace!(target: "p2p_handle", "invalid version {}", cb.head.ver),
             };
         } else {
             debug!(target: "p2p_handle", "not pass token check: hash/ver/ctrl/action {}/{}/{}/{}", &hash, cb.head.ver, cb.head.ctrl, cb.head.action);
+            self.report(hash, Behaviour::UnrequestedResponse);
         }
     }
 }
@@ -856,11 +1262,17 @@ fn config_stream(stream: &TcpStream) -> Result<(), io::Error> {
 /// helper function for tokio io frame
 fn split_frame(
     socket: TcpStream,
+    session: SharedSession,
+    compression: SharedCompression,
+    counters: Arc<CompressionCounters>,
 ) -> (
     stream::SplitSink<Framed<TcpStream, Codec>>,
     stream::SplitStream<Framed<TcpStream, Codec>>,
 ) {
-    Codec.framed(socket).split()
+    Codec::new(session)
+        .with_compression(compression, counters)
+        .framed(socket)
+        .split()
 }
 
 #[cfg(test)]
@@ -20,11 +20,13 @@
  ******************************************************************************/
 
 use std::fmt;
-use std::time::SystemTime;
-use std::net::SocketAddr;
+use std::time::{Duration, Instant, SystemTime};
+use std::io;
+use std::net::{Shutdown, SocketAddr};
 use std::sync::Arc;
 use std::collections::hash_map::DefaultHasher;
 use std::collections::hash_set::HashSet;
+use std::collections::HashMap;
 use std::hash::Hash;
 use std::hash::Hasher;
 use uuid::Uuid;
@@ -34,6 +36,9 @@ use super::msg::*;
 use super::state::STATE;
 use futures::sync::oneshot::Sender;
 use parking_lot::Mutex;
+use key::Ed25519Public;
+use compress::SharedCompression;
+use secure::{Ephemeral,SharedSession};
 
 const EMPTY_ID: &str = "00000000-0000-0000-0000-000000000000";
 
@@ -44,6 +49,20 @@ pub const MAX_REVISION_LENGTH: usize = 24;
 pub const REVISION_PREFIX: &str = "r-";
 pub const IP_LENGTH: usize = 8;
 
+/// tcp stream of a node, none for simulated connections
+#[cfg(any(test, feature = "sim"))]
+pub type NodeStream = Option<Arc<TcpStream>>;
+
+/// tcp stream of a node
+#[cfg(not(any(test, feature = "sim")))]
+pub type NodeStream = Arc<TcpStream>;
+
+#[cfg(any(test, feature = "sim"))]
+fn node_stream(ts: TcpStream) -> NodeStream { Some(Arc::new(ts)) }
+
+#[cfg(not(any(test, feature = "sim")))]
+fn node_stream(ts: TcpStream) -> NodeStream { Arc::new(ts) }
+
 fn calculate_hash<T: Hash>(t: &T) -> u64 {
     let mut s = DefaultHasher::new();
     t.hash(&mut s);
@@ -68,7 +87,7 @@ pub struct Node {
     /// revision
     pub revision: [u8; MAX_REVISION_LENGTH],
     /// tcp stream
-    pub ts: Arc<TcpStream>,
+    pub ts: NodeStream,
     /// message channel transporter
     pub tx: mpsc::Sender<ChannelBuffer>,
     /// node state
@@ -85,6 +104,19 @@ pub struct Node {
     pub tokens: HashSet<u32>,
     /// tcp stream shutdown signal sender
     pub tx_thread: Arc<Mutex<Vec<Sender<()>>>>,
+    /// send time of pending requests by flag token
+    pub requests: HashMap<u32, Instant>,
+    /// smoothed request/response round trip time
+    pub latency: Option<Duration>,
+
+    /// public key proved in handshake, none for legacy (V0) peers
+    pub public_key: Option<Ed25519Public>,
+    /// ephemeral key of pending outbound authenticated handshake
+    pub ephemeral: Option<Ephemeral>,
+    /// session shared with the connection codec
+    pub session: SharedSession,
+    /// compression negotiated in handshake, shared with the connection codec
+    pub compression: SharedCompression,
 }
 
 impl Node {
@@ -112,7 +144,7 @@ impl Node {
             addr,
 
             revision: [b' '; MAX_REVISION_LENGTH],
-            ts: Arc::new(ts),
+            ts: node_stream(ts),
             tx,
             state: STATE::CONNECTED,
             connection: Connection::OUTBOUND,
@@ -121,6 +153,13 @@ impl Node {
 
             tokens: HashSet::new(),
             tx_thread: Arc::new(Mutex::new(tx_thread_vec)),
+            requests: HashMap::new(),
+            latency: None,
+
+            public_key: None,
+            ephemeral: None,
+            session: Arc::new(Mutex::new(None)),
+            compression: Arc::new(Mutex::new(None)),
         })
     }
 
@@ -150,7 +189,7 @@ impl Node {
             },
 
             revision: [b' '; MAX_REVISION_LENGTH],
-            ts: Arc::new(ts),
+            ts: node_stream(ts),
             tx,
             state: STATE::CONNECTED,
             connection: Connection::INBOUND,
@@ -159,9 +198,57 @@ impl Node {
 
             tokens: HashSet::new(),
             tx_thread: Arc::new(Mutex::new(tx_thread_vec)),
+            requests: HashMap::new(),
+            latency: None,
+
+            public_key: None,
+            ephemeral: None,
+            session: Arc::new(Mutex::new(None)),
+            compression: Arc::new(Mutex::new(None)),
         })
     }
 
+    /// construct node of a simulated connection
+    #[cfg(any(test, feature = "sim"))]
+    pub fn new_simulated(
+        addr: IpAddr,
+        id: [u8; NODE_ID_LENGTH],
+        connection: Connection,
+        tx: mpsc::Sender<ChannelBuffer>,
+        tx_thread: Sender<()>,
+    ) -> Node
+    {
+        let real_addr = match connection {
+            Connection::OUTBOUND => addr,
+            Connection::INBOUND => IpAddr::new(),
+        };
+        Node {
+            hash: calculate_hash(&addr.to_string()),
+            id,
+            net_id: 0,
+            addr,
+            real_addr,
+
+            revision: [b' '; MAX_REVISION_LENGTH],
+            ts: None,
+            tx,
+            state: STATE::CONNECTED,
+            connection,
+            if_seed: false,
+            update: SystemTime::now(),
+
+            tokens: HashSet::new(),
+            tx_thread: Arc::new(Mutex::new(vec![tx_thread])),
+            requests: HashMap::new(),
+            latency: None,
+
+            public_key: None,
+            ephemeral: None,
+            session: Arc::new(Mutex::new(None)),
+            compression: Arc::new(Mutex::new(None)),
+        }
+    }
+
     /// get nodes id
     pub fn get_id_string(&self) -> String { String::from_utf8_lossy(&self.id).into() }
 
@@ -174,6 +261,33 @@ impl Node {
     /// return true if node is active
     pub fn is_active(&self) -> bool { self.state == STATE::ACTIVE }
 
+    /// record response to the request sent with flag token
+    pub fn record_response(&mut self, flag_token: u32) {
+        if let Some(sent) = self.requests.remove(&flag_token) {
+            let rtt = sent.elapsed();
+            self.latency = Some(match self.latency {
+                Some(latency) => (latency * 3 + rtt) / 4,
+                None => rtt,
+            });
+        }
+    }
+
+    /// return true if node proved its id and traffic is sealed
+    pub fn is_authenticated(&self) -> bool { self.public_key.is_some() }
+
+    /// shutdown tcp stream
+    #[cfg(any(test, feature = "sim"))]
+    pub fn shutdown_stream(&self) -> io::Result<()> {
+        match self.ts {
+            Some(ref ts) => ts.shutdown(Shutdown::Both),
+            None => Ok(()),
+        }
+    }
+
+    /// shutdown tcp stream
+    #[cfg(not(any(test, feature = "sim")))]
+    pub fn shutdown_stream(&self) -> io::Result<()> { self.ts.shutdown(Shutdown::Both) }
+
     /// shutdown tcp thread
     pub fn shutdown_tcp_thread(&self) -> Result<(), ()> {
         let mut tx_thread_vec = self.tx_thread.lock();
@@ -278,6 +392,21 @@ impl TempNode {
 
     pub fn get_id_string(&self) -> String { String::from_utf8_lossy(&self.id).into() }
 
+    /// parse node string `p2p://<id>@<ip>:<port>`, none if malformed
+    pub fn parse(node_str: &str) -> Option<TempNode> {
+        if !node_str.starts_with("p2p://") {
+            return None;
+        }
+        let (_, rest) = node_str.split_at(PROTOCOL_LENGTH);
+        if rest.len() <= NODE_ID_LENGTH || rest.as_bytes()[NODE_ID_LENGTH] != b'@' {
+            return None;
+        }
+        match rest[NODE_ID_LENGTH + 1..].parse::<SocketAddr>() {
+            Ok(SocketAddr::V4(_)) => Some(TempNode::new_from_str(node_str.to_owned())),
+            _ => None,
+        }
+    }
+
     // construct node from seed config
     // constrait check
     // TODO: return Option<TempNode>
@@ -338,4 +467,17 @@ mod node_tests {
         let tn = TempNode::new_from_str(node_str);
         assert_eq!(tn.addr.to_string(), "0.0.0.0:30303".to_string());
     }
+
+    #[test]
+    fn test_parse_checked() {
+        let id = "c33d1066-8c7e-496c-9c4e-c89318280274";
+        let tn = TempNode::parse(&format!("p2p://{}@1.2.3.4:30303", id)).unwrap();
+        assert_eq!(tn.addr.to_string(), "1.2.3.4:30303".to_string());
+        assert_eq!(tn.get_id_string(), id);
+
+        assert!(TempNode::parse(&format!("{}@1.2.3.4:30303", id)).is_none());
+        assert!(TempNode::parse("p2p://c33d1066@1.2.3.4:30303").is_none());
+        assert!(TempNode::parse(&format!("p2p://{}@1.2.3.4", id)).is_none());
+        assert!(TempNode::parse(&format!("p2p://{}@[::1]:30303", id)).is_none());
+    }
 }
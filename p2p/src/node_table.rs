@@ -0,0 +1,406 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Persistent node table
+//!
+//! Nodes which completed a handshake are remembered with their last seen time, connection
+//! success/failure counts and revision. The table is stored under the data directory and used
+//! to seed outbound connections on startup so the node does not depend on boot nodes alone.
+//! Nodes are bucketed by /16 address prefix and picked round robin across shuffled buckets,
+//! keeping a single network range from filling the seed queue.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use rand::{thread_rng, Rng};
+use acore_bytes::to_hex;
+use node::{IpAddr, TempNode, NODE_ID_LENGTH, MAX_REVISION_LENGTH};
+
+/// max number of nodes stored
+const MAX_NODES: usize = 1024;
+/// nodes not seen for longer are dropped, in seconds
+const MAX_AGE: u64 = 7 * 24 * 3600;
+/// nodes failing more often than succeeding by this count are dropped
+const MAX_FAILURE_DIFF: u32 = 10;
+
+/// stored node
+#[derive(Clone)]
+pub struct NodeEntry {
+    /// node id
+    pub id: [u8; NODE_ID_LENGTH],
+    /// address we can connect to
+    pub addr: IpAddr,
+    /// last successful handshake
+    pub last_seen: SystemTime,
+    /// successful handshakes
+    pub successes: u32,
+    /// failed connections and unanswered handshakes
+    pub failures: u32,
+    /// revision reported in handshake
+    pub revision: String,
+}
+
+impl NodeEntry {
+    fn is_stale(&self, now: SystemTime) -> bool {
+        match now.duration_since(self.last_seen) {
+            Ok(age) => age.as_secs() > MAX_AGE,
+            Err(_) => false,
+        }
+    }
+
+    fn is_failing(&self) -> bool { self.failures > self.successes + MAX_FAILURE_DIFF }
+
+    /// /16 prefix bucket
+    fn bucket(&self) -> (u8, u8) { (self.addr.ip[1], self.addr.ip[3]) }
+}
+
+/// known nodes keyed by address
+pub struct NodeTable {
+    nodes: HashMap<String, NodeEntry>,
+    path: Option<PathBuf>,
+}
+
+impl NodeTable {
+    /// initialization, load persisted nodes
+    pub fn new(path: Option<PathBuf>) -> NodeTable {
+        let mut table = NodeTable {
+            nodes: HashMap::new(),
+            path,
+        };
+        table.load();
+        table
+    }
+
+    /// record successful handshake
+    pub fn record_success(
+        &mut self,
+        id: &[u8; NODE_ID_LENGTH],
+        addr: &IpAddr,
+        revision: &[u8; MAX_REVISION_LENGTH],
+    )
+    {
+        let key = addr.to_string();
+        if !self.nodes.contains_key(&key) && self.nodes.len() >= MAX_NODES {
+            self.prune();
+        }
+        // handshakes pad the revision with spaces, older ones with zeros
+        let revision = String::from_utf8_lossy(revision)
+            .trim_matches(|c: char| c == '\u{0}' || c.is_whitespace())
+            .to_owned();
+        let entry = self.nodes.entry(key).or_insert_with(|| {
+            NodeEntry {
+                id: *id,
+                addr: *addr,
+                last_seen: SystemTime::now(),
+                successes: 0,
+                failures: 0,
+                revision: String::new(),
+            }
+        });
+        entry.id = *id;
+        entry.last_seen = SystemTime::now();
+        entry.successes = entry.successes.saturating_add(1);
+        if !revision.is_empty() {
+            entry.revision = revision;
+        }
+    }
+
+    /// record failed connection, unknown nodes are not tracked
+    pub fn record_failure(&mut self, addr: &IpAddr) {
+        let failing = match self.nodes.get_mut(&addr.to_string()) {
+            Some(entry) => {
+                entry.failures = entry.failures.saturating_add(1);
+                entry.is_failing()
+            }
+            None => return,
+        };
+        if failing {
+            debug!(target: "p2p_node_table", "drop failing node {}", addr.to_string());
+            self.nodes.remove(&addr.to_string());
+        }
+    }
+
+    /// number of stored nodes
+    pub fn len(&self) -> usize { self.nodes.len() }
+
+    /// pick up to `count` nodes to connect to, spread over address buckets
+    pub fn select(&self, count: usize) -> Vec<TempNode> {
+        let mut rng = thread_rng();
+        let mut buckets: HashMap<(u8, u8), Vec<&NodeEntry>> = HashMap::new();
+        for entry in self.nodes.values() {
+            buckets.entry(entry.bucket()).or_insert_with(Vec::new).push(entry);
+        }
+        let mut buckets: Vec<Vec<&NodeEntry>> = buckets.into_iter().map(|(_, b)| b).collect();
+        rng.shuffle(&mut buckets);
+        for bucket in buckets.iter_mut() {
+            // random order among equally reliable nodes, most reliable picked first
+            rng.shuffle(bucket);
+            bucket.sort_by_key(|entry| entry.successes as i64 - entry.failures as i64);
+        }
+
+        let mut selected = Vec::with_capacity(count);
+        while selected.len() < count {
+            let mut picked = false;
+            for bucket in buckets.iter_mut() {
+                if selected.len() >= count {
+                    break;
+                }
+                if let Some(entry) = bucket.pop() {
+                    let mut temp = TempNode::default();
+                    temp.id = entry.id;
+                    temp.addr = entry.addr;
+                    selected.push(temp);
+                    picked = true;
+                }
+            }
+            if !picked {
+                break;
+            }
+        }
+        selected
+    }
+
+    /// drop stale and failing nodes, then least recently seen ones down to half capacity
+    fn prune(&mut self) {
+        let now = SystemTime::now();
+        self.nodes.retain(|_, entry| !entry.is_stale(now) && !entry.is_failing());
+        if self.nodes.len() >= MAX_NODES {
+            let mut seen: Vec<(String, SystemTime)> = self
+                .nodes
+                .iter()
+                .map(|(key, entry)| (key.clone(), entry.last_seen))
+                .collect();
+            seen.sort_by(|a, b| a.1.cmp(&b.1));
+            let excess = self.nodes.len() - MAX_NODES / 2;
+            for (key, _) in seen.into_iter().take(excess) {
+                self.nodes.remove(&key);
+            }
+        }
+    }
+
+    /// load nodes, one `<id> <ip:port> <last seen unix seconds> <successes> <failures>
+    /// <revision>` per line, revision `-` if unknown
+    fn load(&mut self) {
+        let path = match self.path {
+            Some(ref path) => path.clone(),
+            None => return,
+        };
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(_) => return,
+        };
+        let now = SystemTime::now();
+        for line in content.lines() {
+            if let Some(entry) = parse_entry(line) {
+                if !entry.is_stale(now) && !entry.is_failing() && self.nodes.len() < MAX_NODES {
+                    self.nodes.insert(entry.addr.to_string(), entry);
+                }
+            }
+        }
+        info!(target: "p2p_node_table", "{} known nodes loaded", self.nodes.len());
+    }
+
+    /// store nodes
+    pub fn save(&self) {
+        let path = match self.path {
+            Some(ref path) => path,
+            None => return,
+        };
+        let mut content = String::new();
+        for entry in self.nodes.values() {
+            if let Ok(secs) = entry.last_seen.duration_since(UNIX_EPOCH) {
+                content.push_str(&format!(
+                    "{} {} {} {} {} {}\n",
+                    String::from_utf8_lossy(&entry.id),
+                    entry.addr.to_string(),
+                    secs.as_secs(),
+                    entry.successes,
+                    entry.failures,
+                    encode_revision(&entry.revision)
+                ));
+            }
+        }
+        if let Err(e) = fs::write(path, content) {
+            warn!(target: "p2p_node_table", "failed to store nodes to {:?}: {}", path, e);
+        }
+    }
+}
+
+/// revision is sent by the peer and may hold whitespace, it is stored hex encoded to keep
+/// the line format
+fn encode_revision(revision: &str) -> String {
+    match revision.is_empty() {
+        true => "-".to_owned(),
+        false => to_hex(revision.as_bytes()),
+    }
+}
+
+/// revision which does not decode is dropped, the entry is kept
+fn decode_revision(field: &str) -> String {
+    if field.len() % 2 != 0 {
+        return String::new();
+    }
+    (0..field.len())
+        .step_by(2)
+        .map(|i| field.get(i..i + 2).and_then(|byte| u8::from_str_radix(byte, 16).ok()))
+        .collect::<Option<Vec<u8>>>()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .unwrap_or_default()
+}
+
+fn parse_entry(line: &str) -> Option<NodeEntry> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    if fields.len() != 6 || fields[0].len() != NODE_ID_LENGTH {
+        return None;
+    }
+    let mut id = [0u8; NODE_ID_LENGTH];
+    id.copy_from_slice(fields[0].as_bytes());
+    let addr = match fields[1].parse() {
+        Ok(sa) => IpAddr::parse(sa),
+        Err(_) => return None,
+    };
+    let last_seen = UNIX_EPOCH + Duration::from_secs(fields[2].parse::<u64>().ok()?);
+    let revision = match fields[5] {
+        "-" => String::new(),
+        revision => decode_revision(revision),
+    };
+    Some(NodeEntry {
+        id,
+        addr,
+        last_seen,
+        successes: fields[3].parse().ok()?,
+        failures: fields[4].parse().ok()?,
+        revision,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::env;
+    use std::fs;
+    use std::net::SocketAddr;
+    use super::*;
+
+    const ID: &[u8; NODE_ID_LENGTH] = b"c33d1066-8c7e-496c-9c4e-c89318280274";
+
+    fn addr(s: &str) -> IpAddr { IpAddr::parse(s.parse::<SocketAddr>().unwrap()) }
+
+    fn revision() -> [u8; MAX_REVISION_LENGTH] {
+        let mut revision = [0u8; MAX_REVISION_LENGTH];
+        revision[..7].copy_from_slice(b"r-1.0.0");
+        revision
+    }
+
+    #[test]
+    fn test_record() {
+        let mut table = NodeTable::new(None);
+        // unknown nodes are not tracked on failure
+        table.record_failure(&addr("1.2.3.4:30303"));
+        assert_eq!(table.len(), 0);
+
+        table.record_success(ID, &addr("1.2.3.4:30303"), &revision());
+        table.record_success(ID, &addr("1.2.3.4:30303"), &revision());
+        table.record_failure(&addr("1.2.3.4:30303"));
+        let entry = table.nodes.get("1.2.3.4:30303").unwrap();
+        assert_eq!(entry.successes, 2);
+        assert_eq!(entry.failures, 1);
+        assert_eq!(entry.revision, "r-1.0.0");
+
+        // failing nodes are dropped
+        for _ in 0..(MAX_FAILURE_DIFF + 1) {
+            table.record_failure(&addr("1.2.3.4:30303"));
+        }
+        assert_eq!(table.len(), 0);
+    }
+
+    #[test]
+    fn test_select_spreads_buckets() {
+        let mut table = NodeTable::new(None);
+        for i in 0..10 {
+            table.record_success(ID, &addr(&format!("1.2.3.{}:30303", i)), &revision());
+        }
+        table.record_success(ID, &addr("5.6.7.8:30303"), &revision());
+        table.record_success(ID, &addr("9.10.11.12:30303"), &revision());
+
+        let selected = table.select(3);
+        assert_eq!(selected.len(), 3);
+        let mut prefixes: Vec<(u8, u8)> =
+            selected.iter().map(|n| (n.addr.ip[1], n.addr.ip[3])).collect();
+        prefixes.sort();
+        prefixes.dedup();
+        assert_eq!(prefixes.len(), 3);
+        assert!(selected.iter().all(|n| !n.if_seed));
+
+        assert_eq!(table.select(100).len(), 12);
+    }
+
+    #[test]
+    fn test_select_prefers_reliable() {
+        let mut table = NodeTable::new(None);
+        table.record_success(ID, &addr("1.2.3.4:30303"), &revision());
+        table.record_success(ID, &addr("1.2.3.5:30303"), &revision());
+        table.record_failure(&addr("1.2.3.5:30303"));
+        for _ in 0..10 {
+            assert_eq!(table.select(1)[0].addr.to_string(), "1.2.3.4:30303");
+        }
+    }
+
+    #[test]
+    fn test_nodes_persisted() {
+        let path = env::temp_dir().join(format!("p2p-nodes-{}", ::rand::random::<u64>()));
+        {
+            let mut table = NodeTable::new(Some(path.clone()));
+            table.record_success(ID, &addr("1.2.3.4:30303"), &revision());
+            table.record_success(ID, &addr("5.6.7.8:30304"), &[0u8; MAX_REVISION_LENGTH]);
+            table.record_failure(&addr("5.6.7.8:30304"));
+            table.save();
+        }
+        let table = NodeTable::new(Some(path.clone()));
+        assert_eq!(table.len(), 2);
+        let entry = table.nodes.get("5.6.7.8:30304").unwrap();
+        assert_eq!(&entry.id[..], &ID[..]);
+        assert_eq!(entry.successes, 1);
+        assert_eq!(entry.failures, 1);
+        assert_eq!(entry.revision, "");
+        assert_eq!(table.nodes.get("1.2.3.4:30303").unwrap().revision, "r-1.0.0");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_space_padded_revision_persisted() {
+        let path = env::temp_dir().join(format!("p2p-nodes-{}", ::rand::random::<u64>()));
+        // revision as filled in by the handshake, spaces inside and as padding
+        let mut revision = [b' '; MAX_REVISION_LENGTH];
+        revision[..9].copy_from_slice(b"r-1.0 abc");
+        {
+            let mut table = NodeTable::new(Some(path.clone()));
+            table.record_success(ID, &addr("1.2.3.4:30303"), &revision);
+            assert_eq!(table.nodes.get("1.2.3.4:30303").unwrap().revision, "r-1.0 abc");
+            table.save();
+        }
+        let table = NodeTable::new(Some(path.clone()));
+        assert_eq!(table.len(), 1);
+        assert_eq!(table.nodes.get("1.2.3.4:30303").unwrap().revision, "r-1.0 abc");
+        fs::remove_file(&path).unwrap();
+    }
+}
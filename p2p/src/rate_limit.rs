@@ -0,0 +1,432 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Traffic limits
+//!
+//! Inbound messages are metered with token buckets: messages and bytes per peer, messages per
+//! peer for routes with their own limit, and messages and bytes shared by all peers. A message
+//! over any limit is dropped and takes no tokens. A peer going over one of its own limits is
+//! penalised, or disconnected if so configured. Traffic and drops are counted per peer and route
+//! for operators.
+
+use std::collections::HashMap;
+use std::time::Instant;
+use compress::CompressionStats;
+
+/// limits of inbound traffic, 0 for no limit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RateLimitConfig {
+    /// messages per second per peer
+    pub peer_messages: u32,
+    /// bytes per second per peer
+    pub peer_bytes: u32,
+    /// messages per second of all peers
+    pub global_messages: u32,
+    /// bytes per second of all peers
+    pub global_bytes: u32,
+    /// messages per second per peer by route, keyed by `module << 8 | action`
+    pub routes: HashMap<u16, u32>,
+    /// disconnect peers going over one of their own limits instead of dropping their messages
+    pub disconnect: bool,
+}
+
+impl RateLimitConfig {
+    pub fn new() -> Self {
+        RateLimitConfig {
+            peer_messages: 500,
+            peer_bytes: 8 * 1024 * 1024,
+            global_messages: 5000,
+            global_bytes: 64 * 1024 * 1024,
+            routes: HashMap::new(),
+            disconnect: false,
+        }
+    }
+}
+
+/// route key of module and action
+pub fn route_key(module: u8, action: u8) -> u16 { ((module as u16) << 8) + action as u16 }
+
+/// token bucket refilled at `rate` per second, holding up to one second of tokens
+struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    last: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: u32, now: Instant) -> TokenBucket {
+        TokenBucket {
+            capacity: rate as f64,
+            tokens: rate as f64,
+            last: now,
+        }
+    }
+
+    /// add tokens for the time passed since last refill
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.last);
+        let secs = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1e9;
+        self.tokens = (self.tokens + secs * self.capacity).min(self.capacity);
+        self.last = now;
+    }
+
+    /// a full bucket pays for an amount larger than its capacity and refills from debt
+    fn allows(&self, amount: f64) -> bool { self.tokens >= amount.min(self.capacity) }
+
+    /// take `amount` tokens if the bucket allows it
+    fn take(&mut self, amount: f64, now: Instant) -> bool {
+        self.refill(now);
+        if self.allows(amount) {
+            self.tokens -= amount;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// bucket of a limit, none for no limit
+fn bucket(rate: u32, now: Instant) -> Option<TokenBucket> {
+    if rate > 0 {
+        Some(TokenBucket::new(rate, now))
+    } else {
+        None
+    }
+}
+
+fn allows(bucket: Option<&mut TokenBucket>, amount: f64, now: Instant) -> bool {
+    match bucket {
+        Some(bucket) => {
+            bucket.refill(now);
+            bucket.allows(amount)
+        }
+        None => true,
+    }
+}
+
+fn consume(bucket: Option<&mut TokenBucket>, amount: f64) {
+    if let Some(bucket) = bucket {
+        bucket.tokens -= amount;
+    }
+}
+
+/// limit a message went over
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Limit {
+    /// messages per peer of the route
+    Route,
+    /// messages per peer
+    PeerMessages,
+    /// bytes per peer
+    PeerBytes,
+    /// messages of all peers
+    NetworkMessages,
+    /// bytes of all peers
+    NetworkBytes,
+}
+
+impl Limit {
+    /// limit of the sending peer alone, the shared ones are not its fault
+    pub fn is_peer(&self) -> bool {
+        match self {
+            Limit::Route | Limit::PeerMessages | Limit::PeerBytes => true,
+            Limit::NetworkMessages | Limit::NetworkBytes => false,
+        }
+    }
+}
+
+/// traffic counters
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrafficStats {
+    /// messages accepted
+    pub messages: u64,
+    /// bytes accepted
+    pub bytes: u64,
+    /// messages dropped over a limit
+    pub dropped_messages: u64,
+    /// bytes dropped over a limit
+    pub dropped_bytes: u64,
+}
+
+impl TrafficStats {
+    fn record(&mut self, bytes: usize, accepted: bool) {
+        if accepted {
+            self.messages += 1;
+            self.bytes += bytes as u64;
+        } else {
+            self.dropped_messages += 1;
+            self.dropped_bytes += bytes as u64;
+        }
+    }
+
+    fn add(&mut self, other: &TrafficStats) {
+        self.messages += other.messages;
+        self.bytes += other.bytes;
+        self.dropped_messages += other.dropped_messages;
+        self.dropped_bytes += other.dropped_bytes;
+    }
+}
+
+/// traffic of a route
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteTraffic {
+    /// route module
+    pub module: u8,
+    /// route action
+    pub action: u8,
+    /// counters
+    pub stats: TrafficStats,
+}
+
+/// traffic of a peer
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeerTraffic {
+    /// node id
+    pub id: String,
+    /// remote address
+    pub addr: String,
+    /// counters of all routes
+    pub total: TrafficStats,
+    /// counters by route
+    pub routes: Vec<RouteTraffic>,
+}
+
+/// traffic of all peers
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NetworkTraffic {
+    /// counters of all peers since start
+    pub total: TrafficStats,
+    /// connected peers
+    pub peers: Vec<PeerTraffic>,
+    /// payload compression of all peers since start
+    pub compression: CompressionStats,
+}
+
+struct PeerLimiter {
+    messages: Option<TokenBucket>,
+    bytes: Option<TokenBucket>,
+    routes: HashMap<u16, TokenBucket>,
+    stats: HashMap<u16, TrafficStats>,
+}
+
+/// inbound traffic limiter
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    messages: Option<TokenBucket>,
+    bytes: Option<TokenBucket>,
+    peers: HashMap<u64, PeerLimiter>,
+    total: TrafficStats,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> RateLimiter {
+        let now = Instant::now();
+        RateLimiter {
+            messages: bucket(config.global_messages, now),
+            bytes: bucket(config.global_bytes, now),
+            config,
+            peers: HashMap::new(),
+            total: TrafficStats::default(),
+        }
+    }
+
+    /// meter inbound message of peer, return the limit it goes over. Tokens are only taken
+    /// once all limits accept the message
+    pub fn check(&mut self, hash: u64, route: u16, bytes: usize) -> Result<(), Limit> {
+        let now = Instant::now();
+        let config = &self.config;
+        let peer = self.peers.entry(hash).or_insert_with(|| {
+            PeerLimiter {
+                messages: bucket(config.peer_messages, now),
+                bytes: bucket(config.peer_bytes, now),
+                routes: HashMap::new(),
+                stats: HashMap::new(),
+            }
+        });
+
+        let route_allowed = match config.routes.get(&route) {
+            Some(rate) if *rate > 0 => {
+                let route_bucket =
+                    peer.routes.entry(route).or_insert_with(|| TokenBucket::new(*rate, now));
+                allows(Some(route_bucket), 1f64, now)
+            }
+            _ => true,
+        };
+        let result = if !route_allowed {
+            Err(Limit::Route)
+        } else if !allows(peer.messages.as_mut(), 1f64, now) {
+            Err(Limit::PeerMessages)
+        } else if !allows(peer.bytes.as_mut(), bytes as f64, now) {
+            Err(Limit::PeerBytes)
+        } else if !allows(self.messages.as_mut(), 1f64, now) {
+            Err(Limit::NetworkMessages)
+        } else if !allows(self.bytes.as_mut(), bytes as f64, now) {
+            Err(Limit::NetworkBytes)
+        } else {
+            Ok(())
+        };
+        if result.is_ok() {
+            consume(peer.routes.get_mut(&route), 1f64);
+            consume(peer.messages.as_mut(), 1f64);
+            consume(peer.bytes.as_mut(), bytes as f64);
+            consume(self.messages.as_mut(), 1f64);
+            consume(self.bytes.as_mut(), bytes as f64);
+        }
+
+        peer.stats
+            .entry(route)
+            .or_insert_with(TrafficStats::default)
+            .record(bytes, result.is_ok());
+        self.total.record(bytes, result.is_ok());
+        result
+    }
+
+    /// disconnect peers going over a limit
+    pub fn disconnect(&self) -> bool { self.config.disconnect }
+
+    /// forget disconnected peer
+    pub fn remove(&mut self, hash: u64) { self.peers.remove(&hash); }
+
+    /// counters of all peers since start
+    pub fn total(&self) -> TrafficStats { self.total.clone() }
+
+    /// counters of peer, all routes and by route
+    pub fn peer_stats(&self, hash: u64) -> Option<(TrafficStats, Vec<RouteTraffic>)> {
+        self.peers.get(&hash).map(|peer| {
+            let mut total = TrafficStats::default();
+            let mut routes: Vec<RouteTraffic> = peer
+                .stats
+                .iter()
+                .map(|(route, stats)| {
+                    total.add(stats);
+                    RouteTraffic {
+                        module: (*route >> 8) as u8,
+                        action: *route as u8,
+                        stats: stats.clone(),
+                    }
+                })
+                .collect();
+            routes.sort_by_key(|route| (route.module, route.action));
+            (total, routes)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn config() -> RateLimitConfig {
+        let mut config = RateLimitConfig::new();
+        config.peer_messages = 10;
+        config.peer_bytes = 1000;
+        config.global_messages = 0;
+        config.global_bytes = 0;
+        config
+    }
+
+    #[test]
+    fn test_token_bucket() {
+        let now = Instant::now();
+        let mut bucket = TokenBucket::new(10, now);
+        for _ in 0..10 {
+            assert!(bucket.take(1f64, now));
+        }
+        assert!(!bucket.take(1f64, now));
+        assert!(bucket.take(1f64, now + Duration::from_millis(100)));
+        assert!(!bucket.take(1f64, now + Duration::from_millis(100)));
+
+        // oversized amount passes a full bucket and leaves debt
+        let mut bucket = TokenBucket::new(10, now);
+        assert!(bucket.take(25f64, now));
+        assert!(!bucket.take(1f64, now + Duration::from_secs(1)));
+        assert!(bucket.take(1f64, now + Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn test_peer_message_limit() {
+        let mut limiter = RateLimiter::new(config());
+        let route = route_key(1, 6);
+        for _ in 0..10 {
+            assert!(limiter.check(1, route, 10).is_ok());
+        }
+        assert_eq!(limiter.check(1, route, 10), Err(Limit::PeerMessages));
+        // other peers are not affected
+        assert!(limiter.check(2, route, 10).is_ok());
+
+        let (total, routes) = limiter.peer_stats(1).unwrap();
+        assert_eq!(total.messages, 10);
+        assert_eq!(total.dropped_messages, 1);
+        assert_eq!(total.dropped_bytes, 10);
+        assert_eq!(routes.len(), 1);
+        assert_eq!((routes[0].module, routes[0].action), (1, 6));
+        assert_eq!(limiter.total().messages, 11);
+
+        limiter.remove(1);
+        assert!(limiter.peer_stats(1).is_none());
+    }
+
+    #[test]
+    fn test_peer_byte_limit() {
+        let mut limiter = RateLimiter::new(config());
+        assert!(limiter.check(1, route_key(1, 5), 600).is_ok());
+        assert_eq!(limiter.check(1, route_key(1, 5), 600), Err(Limit::PeerBytes));
+    }
+
+    #[test]
+    fn test_route_limit() {
+        let mut config = config();
+        config.routes.insert(route_key(1, 2), 2);
+        let mut limiter = RateLimiter::new(config);
+        assert!(limiter.check(1, route_key(1, 2), 10).is_ok());
+        assert!(limiter.check(1, route_key(1, 2), 10).is_ok());
+        assert_eq!(limiter.check(1, route_key(1, 2), 10), Err(Limit::Route));
+        // other routes keep the peer limit
+        assert!(limiter.check(1, route_key(1, 3), 10).is_ok());
+    }
+
+    #[test]
+    fn test_global_limit() {
+        let mut config = config();
+        config.peer_messages = 0;
+        config.global_messages = 3;
+        let mut limiter = RateLimiter::new(config);
+        assert!(limiter.check(1, route_key(1, 6), 10).is_ok());
+        assert!(limiter.check(2, route_key(1, 6), 10).is_ok());
+        assert!(limiter.check(3, route_key(1, 6), 10).is_ok());
+        assert_eq!(limiter.check(4, route_key(1, 6), 10), Err(Limit::NetworkMessages));
+        assert!(!Limit::NetworkMessages.is_peer());
+    }
+
+    #[test]
+    fn test_rejected_message_takes_no_tokens() {
+        let mut config = config();
+        config.peer_messages = 2;
+        config.global_bytes = 100;
+        let mut limiter = RateLimiter::new(config);
+        assert!(limiter.check(1, route_key(1, 6), 100).is_ok());
+        // peer buckets are not charged for messages over the network limit, the peer would
+        // go over its own limit on the second one otherwise
+        assert_eq!(limiter.check(1, route_key(1, 6), 10), Err(Limit::NetworkBytes));
+        assert_eq!(limiter.check(1, route_key(1, 6), 10), Err(Limit::NetworkBytes));
+    }
+}
@@ -0,0 +1,330 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Peer reputation
+//!
+//! Peers are scored by ip on the behaviour reported by p2p and sync handlers. A peer whose score
+//! drops to the ban threshold is disconnected and refused for a while, bans are written to disk
+//! so they survive restarts.
+
+use std::cmp;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub const MAX_SCORE: i32 = 100;
+pub const BAN_THRESHOLD: i32 = -100;
+pub const BAN_DURATION: u64 = 3600;
+/// max number of peers tracked, peers in good standing are dropped first
+const MAX_PEERS_TRACKED: usize = 2048;
+
+/// peer behaviour reported to reputation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Behaviour {
+    /// response which moved sync forward
+    UsefulResponse,
+    /// node timed out
+    Timeout,
+    /// response without a pending request, failed token check
+    UnrequestedResponse,
+    /// message which could not be decoded
+    MalformedMessage,
+    /// block header failed validation
+    InvalidHeader,
+    /// block body failed validation or block failed import
+    InvalidBlock,
+    /// invalid handshake or status, wrong net id, genesis or signature
+    BadHandshake,
+    /// message over a traffic limit
+    ExceededRateLimit,
+    /// request over the serving limits, e.g. an execution with too much gas
+    ExpensiveRequest,
+}
+
+impl Behaviour {
+    /// score delta
+    pub fn value(&self) -> i32 {
+        match self {
+            Behaviour::UsefulResponse => 1,
+            Behaviour::Timeout => -10,
+            Behaviour::UnrequestedResponse => -5,
+            Behaviour::MalformedMessage => -20,
+            Behaviour::InvalidHeader => -50,
+            Behaviour::InvalidBlock => -50,
+            Behaviour::BadHandshake => -40,
+            Behaviour::ExceededRateLimit => -1,
+            Behaviour::ExpensiveRequest => -5,
+        }
+    }
+}
+
+/// reputation of a single peer
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeerReputation {
+    /// peer ip
+    pub ip: String,
+    /// current score
+    pub score: i32,
+    /// ban expiry, none if peer is not banned
+    pub banned_until: Option<SystemTime>,
+}
+
+impl PeerReputation {
+    fn new(ip: &str) -> PeerReputation {
+        PeerReputation {
+            ip: ip.to_owned(),
+            score: 0,
+            banned_until: None,
+        }
+    }
+}
+
+/// reputation of all known peers
+pub struct Reputation {
+    peers: HashMap<String, PeerReputation>,
+    path: Option<PathBuf>,
+}
+
+impl Reputation {
+    /// initialization, load persisted bans
+    pub fn new(path: Option<PathBuf>) -> Reputation {
+        let mut reputation = Reputation {
+            peers: HashMap::new(),
+            path,
+        };
+        reputation.load();
+        reputation
+    }
+
+    /// record peer behaviour, return true if it got the peer banned
+    pub fn record(&mut self, ip: &str, behaviour: Behaviour) -> bool {
+        if !self.peers.contains_key(ip) && self.peers.len() >= MAX_PEERS_TRACKED {
+            self.prune();
+        }
+        let banned = {
+            let peer = self
+                .peers
+                .entry(ip.to_owned())
+                .or_insert_with(|| PeerReputation::new(ip));
+            if peer.banned_until.is_some() {
+                return false;
+            }
+            peer.score = cmp::min(peer.score + behaviour.value(), MAX_SCORE);
+            if peer.score <= BAN_THRESHOLD {
+                peer.banned_until = Some(SystemTime::now() + Duration::from_secs(BAN_DURATION));
+                true
+            } else {
+                false
+            }
+        };
+        if banned {
+            self.save();
+        }
+        banned
+    }
+
+    /// ban peer regardless of its score
+    pub fn ban(&mut self, ip: &str) {
+        {
+            let peer = self
+                .peers
+                .entry(ip.to_owned())
+                .or_insert_with(|| PeerReputation::new(ip));
+            peer.score = cmp::min(peer.score, BAN_THRESHOLD);
+            peer.banned_until = Some(SystemTime::now() + Duration::from_secs(BAN_DURATION));
+        }
+        self.save();
+    }
+
+    /// return true if peer is banned, expired bans are lifted
+    pub fn is_banned(&mut self, ip: &str) -> bool {
+        let expired = match self.peers.get(ip) {
+            Some(peer) => {
+                match peer.banned_until {
+                    Some(until) if until > SystemTime::now() => return true,
+                    Some(_) => true,
+                    None => return false,
+                }
+            }
+            None => return false,
+        };
+        if expired {
+            debug!(target: "p2p_reputation", "ban on {} expired", ip);
+            self.peers.remove(ip);
+            self.save();
+        }
+        false
+    }
+
+    /// reputation of all known peers, lowest score first
+    pub fn get_reputations(&self) -> Vec<PeerReputation> {
+        let mut reputations: Vec<PeerReputation> = self.peers.values().cloned().collect();
+        reputations.sort_by(|a, b| a.score.cmp(&b.score).then_with(|| a.ip.cmp(&b.ip)));
+        reputations
+    }
+
+    /// drop peers which are not banned, keep the worst scores
+    fn prune(&mut self) {
+        let mut scores: Vec<(String, i32)> = self
+            .peers
+            .values()
+            .filter(|peer| peer.banned_until.is_none())
+            .map(|peer| (peer.ip.clone(), peer.score))
+            .collect();
+        scores.sort_by(|a, b| b.1.cmp(&a.1));
+        for (ip, _) in scores.into_iter().take(MAX_PEERS_TRACKED / 2) {
+            self.peers.remove(&ip);
+        }
+    }
+
+    /// load bans, one `<ip> <expiry unix seconds>` per line
+    fn load(&mut self) {
+        let path = match self.path {
+            Some(ref path) => path.clone(),
+            None => return,
+        };
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(_) => return,
+        };
+        let now = SystemTime::now();
+        for line in content.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() != 2 {
+                continue;
+            }
+            let until = match fields[1].parse::<u64>() {
+                Ok(secs) => UNIX_EPOCH + Duration::from_secs(secs),
+                Err(_) => continue,
+            };
+            if until > now {
+                let mut peer = PeerReputation::new(fields[0]);
+                peer.score = BAN_THRESHOLD;
+                peer.banned_until = Some(until);
+                self.peers.insert(fields[0].to_owned(), peer);
+            }
+        }
+        info!(target: "p2p_reputation", "{} banned peers loaded", self.peers.len());
+    }
+
+    /// store bans
+    fn save(&self) {
+        let path = match self.path {
+            Some(ref path) => path,
+            None => return,
+        };
+        let mut content = String::new();
+        for peer in self.peers.values() {
+            if let Some(until) = peer.banned_until {
+                if let Ok(secs) = until.duration_since(UNIX_EPOCH) {
+                    content.push_str(&format!("{} {}\n", peer.ip, secs.as_secs()));
+                }
+            }
+        }
+        if let Err(e) = fs::write(path, content) {
+            warn!(target: "p2p_reputation", "failed to store bans to {:?}: {}", path, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::env;
+    use std::fs;
+    use std::time::{Duration, SystemTime};
+    use super::*;
+
+    #[test]
+    fn test_score_and_ban() {
+        let mut reputation = Reputation::new(None);
+        assert!(!reputation.record("1.2.3.4", Behaviour::UsefulResponse));
+        assert!(!reputation.record("1.2.3.4", Behaviour::InvalidHeader));
+        assert!(!reputation.record("1.2.3.4", Behaviour::InvalidBlock));
+        assert!(!reputation.is_banned("1.2.3.4"));
+        assert!(reputation.record("1.2.3.4", Behaviour::MalformedMessage));
+        assert!(reputation.is_banned("1.2.3.4"));
+        assert!(!reputation.is_banned("1.2.3.5"));
+
+        // banned peers are not scored again
+        assert!(!reputation.record("1.2.3.4", Behaviour::BadHandshake));
+
+        let reputations = reputation.get_reputations();
+        assert_eq!(reputations.len(), 1);
+        assert_eq!(reputations[0].score, 1 - 50 - 50 - 20);
+        assert!(reputations[0].banned_until.is_some());
+    }
+
+    #[test]
+    fn test_bad_handshake_repeated() {
+        let mut reputation = Reputation::new(None);
+        // a single failure may be a misconfigured peer, e.g. on another net id
+        assert!(!reputation.record("1.2.3.4", Behaviour::BadHandshake));
+        assert!(!reputation.record("1.2.3.4", Behaviour::BadHandshake));
+        assert!(!reputation.is_banned("1.2.3.4"));
+        assert!(reputation.record("1.2.3.4", Behaviour::BadHandshake));
+        assert!(reputation.is_banned("1.2.3.4"));
+    }
+
+    #[test]
+    fn test_manual_ban() {
+        let mut reputation = Reputation::new(None);
+        reputation.record("1.2.3.4", Behaviour::UsefulResponse);
+        reputation.ban("1.2.3.4");
+        assert!(reputation.is_banned("1.2.3.4"));
+        assert_eq!(reputation.get_reputations()[0].score, BAN_THRESHOLD);
+    }
+
+    #[test]
+    fn test_score_capped() {
+        let mut reputation = Reputation::new(None);
+        for _ in 0..(2 * MAX_SCORE) {
+            reputation.record("1.2.3.4", Behaviour::UsefulResponse);
+        }
+        assert_eq!(reputation.get_reputations()[0].score, MAX_SCORE);
+    }
+
+    #[test]
+    fn test_ban_expiry() {
+        let mut reputation = Reputation::new(None);
+        reputation.ban("1.2.3.4");
+        reputation.peers.get_mut("1.2.3.4").unwrap().banned_until =
+            Some(SystemTime::now() - Duration::from_secs(1));
+        assert!(!reputation.is_banned("1.2.3.4"));
+        assert!(reputation.get_reputations().is_empty());
+    }
+
+    #[test]
+    fn test_bans_persisted() {
+        let path = env::temp_dir().join(format!("p2p-bans-{}", ::rand::random::<u64>()));
+        {
+            let mut reputation = Reputation::new(Some(path.clone()));
+            reputation.ban("1.2.3.4");
+            reputation.record("5.6.7.8", Behaviour::Timeout);
+        }
+        let mut reputation = Reputation::new(Some(path.clone()));
+        assert!(reputation.is_banned("1.2.3.4"));
+        assert!(!reputation.is_banned("5.6.7.8"));
+        assert_eq!(reputation.get_reputations().len(), 1);
+        fs::remove_file(&path).unwrap();
+    }
+}
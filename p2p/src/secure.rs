@@ -0,0 +1,355 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Authenticated p2p transport
+//!
+//! Node ids are derived from the node's ed25519 public key, so a peer can only claim the id of a
+//! key it holds. During the V1 handshake both sides sign a fresh x25519 ephemeral key, agree on a
+//! shared secret and from then on every non handshake frame is encrypted with AES-128-CTR and
+//! authenticated with HMAC-SHA256 by the codec.
+
+use std::sync::Arc;
+use byteorder::BigEndian;
+use byteorder::ByteOrder;
+use parking_lot::Mutex;
+use rand::random;
+use blake2b::blake2b;
+use crypto::aes;
+use crypto::curve25519::{curve25519, curve25519_base};
+use crypto::hmac::Hmac;
+use crypto::mac::Mac;
+use crypto::sha2::Sha256;
+use crypto::util::fixed_time_eq;
+use key::{sign_ed25519, verify_signature_ed25519, Ed25519KeyPair, Ed25519Public, Ed25519Signature};
+use key::H256;
+use node::NODE_ID_LENGTH;
+
+pub const EPHEMERAL_LENGTH: usize = 32;
+/// ed25519 signature prefixed with the signer's public key
+pub const SIGNATURE_LENGTH: usize = 96;
+/// handshake auth trailer: ephemeral key followed by signature
+pub const AUTH_LENGTH: usize = EPHEMERAL_LENGTH + SIGNATURE_LENGTH;
+pub const MAC_LENGTH: usize = 32;
+
+const REQ_DOMAIN: &[u8] = b"aion-p2p-handshake-req";
+const RES_DOMAIN: &[u8] = b"aion-p2p-handshake-res";
+
+/// connection session shared between node and codec, set once the handshake completes
+pub type SharedSession = Arc<Mutex<Option<Session>>>;
+
+/// derive node id from ed25519 public key, formatted as a hyphenated uuid
+pub fn node_id_from_public(public: &Ed25519Public) -> [u8; NODE_ID_LENGTH] {
+    let hash = blake2b(&public[..]);
+    let hex: String = hash[..16].iter().map(|b| format!("{:02x}", b)).collect();
+    let formatted = format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    );
+    let mut id = [0u8; NODE_ID_LENGTH];
+    id.copy_from_slice(formatted.as_bytes());
+    id
+}
+
+/// message signed by handshake initiator, binds its node id, net id and ephemeral key
+pub fn req_message(node_id: &[u8], net_id: u32, ephemeral: &[u8]) -> H256 {
+    let mut net_id_bytes = [0u8; 4];
+    BigEndian::write_u32(&mut net_id_bytes, net_id);
+    let mut message = Vec::with_capacity(REQ_DOMAIN.len() + NODE_ID_LENGTH + 4 + EPHEMERAL_LENGTH);
+    message.extend_from_slice(REQ_DOMAIN);
+    message.extend_from_slice(node_id);
+    message.extend_from_slice(&net_id_bytes);
+    message.extend_from_slice(ephemeral);
+    blake2b(message)
+}
+
+/// message signed by handshake responder, binds its node id to both ephemeral keys
+/// so the response can not be replayed against another request
+pub fn res_message(node_id: &[u8], initiator_ephemeral: &[u8], responder_ephemeral: &[u8]) -> H256 {
+    let mut message =
+        Vec::with_capacity(RES_DOMAIN.len() + NODE_ID_LENGTH + 2 * EPHEMERAL_LENGTH);
+    message.extend_from_slice(RES_DOMAIN);
+    message.extend_from_slice(node_id);
+    message.extend_from_slice(initiator_ephemeral);
+    message.extend_from_slice(responder_ephemeral);
+    blake2b(message)
+}
+
+/// sign message, signature is prefixed with public key
+pub fn sign(keypair: &Ed25519KeyPair, message: &H256) -> Option<[u8; SIGNATURE_LENGTH]> {
+    match sign_ed25519(keypair.secret(), message) {
+        Ok(signature) => Some(signature.into()),
+        Err(err) => {
+            error!(target: "p2p_secure", "failed to sign handshake: {:?}", err);
+            None
+        }
+    }
+}
+
+/// verify signature, return signer public key if valid
+pub fn verify(signature: &[u8], message: &H256) -> Option<Ed25519Public> {
+    if signature.len() != SIGNATURE_LENGTH {
+        return None;
+    }
+    let signature = Ed25519Signature::from(signature.to_vec());
+    let public = signature.get_public();
+    match verify_signature_ed25519(public, signature, message) {
+        true => Some(public),
+        false => None,
+    }
+}
+
+/// x25519 key pair used for a single handshake
+#[derive(Clone)]
+pub struct Ephemeral {
+    secret: [u8; 32],
+    pub public: [u8; EPHEMERAL_LENGTH],
+}
+
+impl Ephemeral {
+    pub fn generate() -> Ephemeral {
+        let secret: [u8; 32] = random();
+        Ephemeral {
+            public: curve25519_base(&secret),
+            secret,
+        }
+    }
+
+    /// compute shared secret, none for low order remote keys
+    pub fn agree(&self, remote: &[u8]) -> Option<[u8; 32]> {
+        if remote.len() != EPHEMERAL_LENGTH {
+            return None;
+        }
+        let shared = curve25519(&self.secret, remote);
+        match shared == [0u8; 32] {
+            true => None,
+            false => Some(shared),
+        }
+    }
+}
+
+/// keys and frame counter for one direction of a session
+struct Channel {
+    cipher_key: [u8; 16],
+    mac_key: [u8; 32],
+    counter: u64,
+}
+
+impl Channel {
+    fn new(shared: &[u8], transcript: &H256, direction: &str) -> Channel {
+        let derive = |purpose: &str| {
+            let mut input = Vec::new();
+            input.extend_from_slice(shared);
+            input.extend_from_slice(&transcript[..]);
+            input.extend_from_slice(format!("{}-{}", direction, purpose).as_bytes());
+            blake2b(input)
+        };
+        let mut cipher_key = [0u8; 16];
+        cipher_key.copy_from_slice(&derive("cipher")[..16]);
+        let mut mac_key = [0u8; 32];
+        mac_key.copy_from_slice(&derive("mac")[..]);
+        Channel {
+            cipher_key,
+            mac_key,
+            counter: 0,
+        }
+    }
+
+    /// frame counter in the high half so ctr blocks of different frames never overlap
+    fn iv(&self) -> [u8; 16] {
+        let mut iv = [0u8; 16];
+        BigEndian::write_u64(&mut iv[..8], self.counter);
+        iv
+    }
+
+    fn mac(&self, head: &[u8], cipher: &[u8]) -> [u8; MAC_LENGTH] {
+        let mut counter = [0u8; 8];
+        BigEndian::write_u64(&mut counter, self.counter);
+        let mut hmac = Hmac::new(Sha256::new(), &self.mac_key);
+        hmac.input(&counter);
+        hmac.input(head);
+        hmac.input(cipher);
+        let mut mac = [0u8; MAC_LENGTH];
+        hmac.raw_result(&mut mac);
+        mac
+    }
+}
+
+/// established session of an authenticated connection
+pub struct Session {
+    egress: Channel,
+    ingress: Channel,
+}
+
+impl Session {
+    /// derive directional keys from the shared secret and both ephemeral keys
+    pub fn new(
+        shared: &[u8],
+        initiator_ephemeral: &[u8],
+        responder_ephemeral: &[u8],
+        initiator: bool,
+    ) -> Session
+    {
+        let mut transcript = Vec::with_capacity(2 * EPHEMERAL_LENGTH);
+        transcript.extend_from_slice(initiator_ephemeral);
+        transcript.extend_from_slice(responder_ephemeral);
+        let transcript = blake2b(transcript);
+        let outbound = Channel::new(shared, &transcript, "initiator");
+        let inbound = Channel::new(shared, &transcript, "responder");
+        match initiator {
+            true => {
+                Session {
+                    egress: outbound,
+                    ingress: inbound,
+                }
+            }
+            false => {
+                Session {
+                    egress: inbound,
+                    ingress: outbound,
+                }
+            }
+        }
+    }
+
+    /// encrypt body and append mac over encoded head and cipher text
+    pub fn seal(&mut self, head: &[u8], body: &[u8]) -> Vec<u8> {
+        let mut sealed = vec![0u8; body.len()];
+        aes::encrypt(&self.egress.cipher_key, &self.egress.iv(), body, &mut sealed);
+        let mac = self.egress.mac(head, &sealed);
+        sealed.extend_from_slice(&mac);
+        self.egress.counter += 1;
+        sealed
+    }
+
+    /// verify and decrypt sealed body, none if authentication fails
+    pub fn open(&mut self, head: &[u8], sealed: &[u8]) -> Option<Vec<u8>> {
+        if sealed.len() < MAC_LENGTH {
+            return None;
+        }
+        let (cipher, mac) = sealed.split_at(sealed.len() - MAC_LENGTH);
+        if !fixed_time_eq(&self.ingress.mac(head, cipher), mac) {
+            return None;
+        }
+        let mut body = vec![0u8; cipher.len()];
+        aes::decrypt(&self.ingress.cipher_key, &self.ingress.iv(), cipher, &mut body);
+        self.ingress.counter += 1;
+        Some(body)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use key::generate_keypair;
+    use super::*;
+
+    fn session_pair() -> (Session, Session) {
+        let initiator = Ephemeral::generate();
+        let responder = Ephemeral::generate();
+        let shared_0 = initiator.agree(&responder.public).unwrap();
+        let shared_1 = responder.agree(&initiator.public).unwrap();
+        assert_eq!(shared_0, shared_1);
+        (
+            Session::new(&shared_0, &initiator.public, &responder.public, true),
+            Session::new(&shared_1, &initiator.public, &responder.public, false),
+        )
+    }
+
+    #[test]
+    fn test_node_id_from_public() {
+        let keypair = generate_keypair();
+        let id = node_id_from_public(keypair.public());
+        let id = String::from_utf8(id.to_vec()).unwrap();
+        let dashes: Vec<usize> = id
+            .char_indices()
+            .filter(|&(_, c)| c == '-')
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(dashes, vec![8, 13, 18, 23]);
+        assert_eq!(id, String::from_utf8(node_id_from_public(keypair.public()).to_vec()).unwrap());
+        assert_ne!(
+            &node_id_from_public(keypair.public())[..],
+            &node_id_from_public(generate_keypair().public())[..]
+        );
+    }
+
+    #[test]
+    fn test_handshake_signature() {
+        let keypair = generate_keypair();
+        let id = node_id_from_public(keypair.public());
+        let ephemeral = Ephemeral::generate();
+        let message = req_message(&id, 256, &ephemeral.public);
+        let signature = sign(&keypair, &message).unwrap();
+        assert_eq!(verify(&signature, &message), Some(keypair.public().clone()));
+
+        // signature does not cover another net id
+        assert_eq!(verify(&signature, &req_message(&id, 32, &ephemeral.public)), None);
+
+        // public key swapped for another node's one
+        let mut forged = signature;
+        forged[..32].copy_from_slice(&generate_keypair().public()[..]);
+        assert_eq!(verify(&forged, &message), None);
+    }
+
+    #[test]
+    fn test_session_roundtrip() {
+        let (mut initiator, mut responder) = session_pair();
+        let head = [0u8, 0, 1, 4, 0, 0, 0, 37];
+        for body in vec![vec![], vec![1u8, 2, 3], vec![0xAAu8; 1000]] {
+            let sealed = initiator.seal(&head, &body);
+            assert_eq!(sealed.len(), body.len() + MAC_LENGTH);
+            if !body.is_empty() {
+                assert_ne!(&sealed[..body.len()], &body[..]);
+            }
+            assert_eq!(responder.open(&head, &sealed), Some(body.clone()));
+
+            let sealed = responder.seal(&head, &body);
+            assert_eq!(initiator.open(&head, &sealed), Some(body));
+        }
+    }
+
+    #[test]
+    fn test_session_rejects_tampering() {
+        let (mut initiator, mut responder) = session_pair();
+        let head = [0u8, 0, 1, 4, 0, 0, 0, 35];
+        let body = vec![7u8; 3];
+
+        let mut sealed = initiator.seal(&head, &body);
+        sealed[0] ^= 1;
+        assert_eq!(responder.open(&head, &sealed), None);
+
+        // head is authenticated too
+        let sealed = initiator.seal(&head, &body);
+        let mut other_head = head;
+        other_head[3] = 5;
+        assert_eq!(responder.open(&other_head, &sealed), None);
+
+        // frames are bound to their position in the stream
+        let (mut initiator, mut responder) = session_pair();
+        let _skipped = initiator.seal(&head, &body);
+        let sealed = initiator.seal(&head, &body);
+        assert_eq!(responder.open(&head, &sealed), None);
+    }
+}
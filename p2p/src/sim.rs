@@ -0,0 +1,618 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Simulated network
+//!
+//! In-process transport connecting several `Mgr` instances of one process, used to test
+//! multi-node scenarios without sockets. Messages skip framing, encryption and compression and
+//! are handed to the receiving manager by a single delivery thread. Links can be made lossy and
+//! the network can be partitioned.
+//!
+//! The network runs on a virtual clock. A message is due at the current virtual time plus the
+//! latency of its link, never before the previous message of the link, and the delivery thread
+//! hands messages over in order of due time, moving the clock forward to each one instead of
+//! sleeping. Whether a message is lost is drawn from a generator seeded by the network seed and
+//! the link, so the n-th message sent on a link is lost or not whatever happens on other links.
+//! Given the order messages are sent in, delivery order and losses are a function of the seed.
+//! Managers still run their own tokio timers and threads on the wall clock, which may change
+//! that send order between runs.
+//!
+//! Only built with the `sim` feature or for tests of this crate.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::net::SocketAddr;
+use std::sync::{Arc, Weak};
+use std::thread;
+use std::time::Duration;
+use blake2b::blake2b;
+use byteorder::{BigEndian, ByteOrder};
+use futures::sync::{mpsc, oneshot};
+use futures::{Future, Stream};
+use parking_lot::{Condvar, Mutex, RwLock};
+use rand::prng::XorShiftRng;
+use rand::{Rng, SeedableRng};
+use tokio::runtime::TaskExecutor;
+use handler::handshake;
+use msg::ChannelBuffer;
+use node::{Connection, IpAddr, Node, TempNode, NODE_ID_LENGTH};
+use Mgr;
+
+/// max time in ms the delivery thread waits for a message before checking the network is alive
+const MAX_WAIT: u64 = 100;
+/// first port given to simulated inbound connections
+const FIRST_INBOUND_PORT: u32 = 40000;
+
+/// latency and loss of a simulated link
+#[derive(Debug, Clone)]
+pub struct LinkConfig {
+    /// one way delay of each message
+    pub latency: Duration,
+    /// probability in [0, 1] that a message is lost
+    pub loss: f64,
+}
+
+impl Default for LinkConfig {
+    fn default() -> LinkConfig {
+        LinkConfig {
+            latency: Duration::from_millis(10),
+            loss: 0.0,
+        }
+    }
+}
+
+/// counters of simulated messages
+#[derive(Debug, Clone, Default)]
+pub struct SimStats {
+    pub delivered: usize,
+    pub dropped: usize,
+}
+
+/// connection between two simulated nodes, binding and node hash on each side
+struct Link {
+    outbound: (String, u64),
+    inbound: (String, u64),
+}
+
+impl Link {
+    /// the other side of the connection if `binding`/`hash` is one side of it
+    fn peer(&self, binding: &str, hash: u64) -> Option<(String, u64)> {
+        if self.outbound.0 == binding && self.outbound.1 == hash {
+            Some(self.inbound.clone())
+        } else if self.inbound.0 == binding && self.inbound.1 == hash {
+            Some(self.outbound.clone())
+        } else {
+            None
+        }
+    }
+
+    fn crosses(&self, groups: &[Vec<String>]) -> bool {
+        !reachable(groups, &self.outbound.0, &self.inbound.0)
+    }
+}
+
+/// message waiting for its delivery time
+struct Delivery {
+    at: Duration,
+    seq: u64,
+    to: String,
+    hash: u64,
+    cb: ChannelBuffer,
+}
+
+impl PartialEq for Delivery {
+    fn eq(&self, other: &Delivery) -> bool { self.at == other.at && self.seq == other.seq }
+}
+
+impl Eq for Delivery {}
+
+impl PartialOrd for Delivery {
+    fn partial_cmp(&self, other: &Delivery) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+impl Ord for Delivery {
+    /// reversed, the binary heap pops the earliest delivery first
+    fn cmp(&self, other: &Delivery) -> Ordering {
+        other
+            .at
+            .cmp(&self.at)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+struct State {
+    /// registered managers by binding
+    mgrs: HashMap<String, Mgr>,
+    links: Vec<Link>,
+    default_link: LinkConfig,
+    /// link overrides by (from, to) binding
+    link_configs: HashMap<(String, String), LinkConfig>,
+    /// bindings of different groups can't reach each other, empty when healed
+    groups: Vec<Vec<String>>,
+    seed: u64,
+    /// loss generators by (from, to) binding
+    link_rngs: HashMap<(String, String), XorShiftRng>,
+    /// virtual time of the network
+    clock: Duration,
+    queue: BinaryHeap<Delivery>,
+    /// last delivery time by (from, to) binding, keeps messages of a link in order
+    last_delivery: HashMap<(String, String), Duration>,
+    seq: u64,
+    next_port: u32,
+    stats: SimStats,
+}
+
+impl State {
+    fn new(seed: u64) -> State {
+        State {
+            mgrs: HashMap::new(),
+            links: Vec::new(),
+            default_link: LinkConfig::default(),
+            link_configs: HashMap::new(),
+            groups: Vec::new(),
+            seed,
+            link_rngs: HashMap::new(),
+            clock: Duration::from_millis(0),
+            queue: BinaryHeap::new(),
+            last_delivery: HashMap::new(),
+            seq: 0,
+            next_port: FIRST_INBOUND_PORT,
+            stats: SimStats::default(),
+        }
+    }
+
+    /// draw whether the next message from `from` to `to` is lost, one draw per message
+    fn lost(&mut self, from: &str, to: &str, loss: f64) -> bool {
+        let seed = self.seed;
+        let rng = self
+            .link_rngs
+            .entry((from.to_owned(), to.to_owned()))
+            .or_insert_with(|| link_rng(seed, from, to));
+        rng.gen::<f64>() < loss
+    }
+
+    /// virtual time the next message from `from` to `to` is due
+    fn schedule(&mut self, from: &str, to: &str, latency: Duration) -> Duration {
+        let key = (from.to_owned(), to.to_owned());
+        let mut at = self.clock + latency;
+        if let Some(last) = self.last_delivery.get(&key) {
+            if *last > at {
+                at = *last;
+            }
+        }
+        self.last_delivery.insert(key, at);
+        at
+    }
+
+    fn link_config(&self, from: &str, to: &str) -> LinkConfig {
+        self.link_configs
+            .get(&(from.to_owned(), to.to_owned()))
+            .unwrap_or(&self.default_link)
+            .clone()
+    }
+
+    /// remove links crossing the partition, returns the sides to disconnect
+    fn split_links(&mut self) -> Vec<(Mgr, u64)> {
+        let mut sides = Vec::new();
+        let mut kept = Vec::new();
+        for link in self.links.drain(..) {
+            if link.crosses(&self.groups) {
+                for side in vec![&link.outbound, &link.inbound] {
+                    if let Some(mgr) = self.mgrs.get(&side.0) {
+                        sides.push((mgr.clone(), side.1));
+                    }
+                }
+            } else {
+                kept.push(link);
+            }
+        }
+        self.links = kept;
+        sides
+    }
+}
+
+struct Inner {
+    state: Mutex<State>,
+    cond: Condvar,
+}
+
+/// in-process network shared by simulated nodes
+#[derive(Clone)]
+pub struct SimNetwork {
+    inner: Arc<Inner>,
+}
+
+impl SimNetwork {
+    /// create network, `seed` drives message loss
+    pub fn new(seed: u64) -> SimNetwork {
+        let inner = Arc::new(Inner {
+            state: Mutex::new(State::new(seed)),
+            cond: Condvar::new(),
+        });
+        let weak = Arc::downgrade(&inner);
+        thread::Builder::new()
+            .name("sim_network".into())
+            .spawn(move || deliver(weak))
+            .expect("failed to spawn simulated network thread");
+        SimNetwork {
+            inner,
+        }
+    }
+
+    /// set link config of all links without override
+    pub fn set_default_link(&self, config: LinkConfig) {
+        self.inner.state.lock().default_link = config;
+    }
+
+    /// set link config of messages sent from `from` to `to`
+    pub fn set_link(&self, from: &str, to: &str, config: LinkConfig) {
+        self.inner
+            .state
+            .lock()
+            .link_configs
+            .insert((from.to_owned(), to.to_owned()), config);
+    }
+
+    /// split network into groups of bindings, established connections between groups are
+    /// closed. Bindings not listed reach every node.
+    pub fn partition(&self, groups: Vec<Vec<String>>) {
+        let sides = {
+            let mut state = self.inner.state.lock();
+            state.groups = groups;
+            state.split_links()
+        };
+        for (mgr, hash) in sides {
+            mgr.drop_node(hash);
+        }
+    }
+
+    /// remove partition
+    pub fn heal(&self) { self.inner.state.lock().groups.clear(); }
+
+    /// message counters
+    pub fn stats(&self) -> SimStats { self.inner.state.lock().stats.clone() }
+
+    /// virtual time of the network, the due time of the last delivered message
+    pub fn now(&self) -> Duration { self.inner.state.lock().clock }
+
+    /// attach manager, it is reachable by its binding
+    pub fn register(&self, mgr: &Mgr) {
+        let binding = mgr.get_local_node_id_and_binding().1;
+        self.inner.state.lock().mgrs.insert(binding, mgr.clone());
+    }
+
+    /// detach manager and close its connections
+    pub fn unregister(&self, binding: &str) {
+        let sides = {
+            let mut state = self.inner.state.lock();
+            state.mgrs.remove(binding);
+            let mut sides = Vec::new();
+            let mut kept = Vec::new();
+            for link in state.links.drain(..) {
+                if link.outbound.0 == binding {
+                    sides.push(link.inbound.clone());
+                } else if link.inbound.0 == binding {
+                    sides.push(link.outbound.clone());
+                } else {
+                    kept.push(link);
+                }
+            }
+            state.links = kept;
+            sides
+                .into_iter()
+                .filter_map(|(binding, hash)| {
+                    state.mgrs.get(&binding).map(|mgr| (mgr.clone(), hash))
+                })
+                .collect::<Vec<_>>()
+        };
+        for (mgr, hash) in sides {
+            mgr.drop_node(hash);
+        }
+    }
+
+    /// open simulated connection from `from` to `temp_node` and start handshake
+    pub(crate) fn connect(&self, executor: &TaskExecutor, from: &Mgr, temp_node: &TempNode) {
+        let local = from.get_local_node_id_and_binding().1;
+        let remote = temp_node.addr.to_string();
+        let local_addr = match local.parse::<SocketAddr>() {
+            Ok(addr) => IpAddr::parse(addr),
+            Err(_) => return,
+        };
+
+        let (target, port) = {
+            let mut state = self.inner.state.lock();
+            let target = if reachable(&state.groups, &local, &remote) {
+                state.mgrs.get(&remote).cloned()
+            } else {
+                None
+            };
+            let port = state.next_port;
+            state.next_port += 1;
+            (target, port)
+        };
+        let target = match target {
+            Some(target) => target,
+            None => {
+                debug!(target: "p2p_sim", "{} unreachable from {}", remote, local);
+                from.node_table.lock().record_failure(&temp_node.addr);
+                return;
+            }
+        };
+        // same checks as a listening node
+        if target.config.sync_from_boot_nodes_only
+            || target.is_banned(&local_addr.get_ip())
+            || target.get_active_nodes_len() >= target.config.max_peers
+        {
+            debug!(target: "p2p_sim", "{} refused {}", remote, local);
+            from.node_table.lock().record_failure(&temp_node.addr);
+            return;
+        }
+
+        let (tx_outbound, rx_outbound) = mpsc::channel(409600);
+        let (tx_thread_outbound, rx_thread_outbound) = oneshot::channel::<()>();
+        let mut outbound = Node::new_simulated(
+            temp_node.addr,
+            temp_node.id,
+            Connection::OUTBOUND,
+            tx_outbound,
+            tx_thread_outbound,
+        );
+        outbound.if_seed = temp_node.if_seed;
+        let outbound_hash = outbound.hash;
+
+        let (tx_inbound, rx_inbound) = mpsc::channel(409600);
+        let (tx_thread_inbound, rx_thread_inbound) = oneshot::channel::<()>();
+        let inbound = Node::new_simulated(
+            IpAddr::new1(local_addr.ip, port),
+            [b'0'; NODE_ID_LENGTH],
+            Connection::INBOUND,
+            tx_inbound,
+            tx_thread_inbound,
+        );
+        let inbound_hash = inbound.hash;
+
+        {
+            let mut nodes_write = from.nodes.write();
+            if nodes_write.contains_key(&outbound_hash) {
+                return;
+            }
+            nodes_write.insert(outbound_hash, RwLock::new(outbound));
+        }
+        target.nodes.write().insert(inbound_hash, RwLock::new(inbound));
+        self.inner.state.lock().links.push(Link {
+            outbound: (local.clone(), outbound_hash),
+            inbound: (remote.clone(), inbound_hash),
+        });
+        debug!(target: "p2p_sim", "connected {} -> {}", local, remote);
+
+        self.forward(
+            executor,
+            rx_outbound,
+            rx_thread_outbound,
+            (local.clone(), outbound_hash),
+            (remote.clone(), inbound_hash),
+        );
+        self.forward(
+            executor,
+            rx_inbound,
+            rx_thread_inbound,
+            (remote, inbound_hash),
+            (local, outbound_hash),
+        );
+
+        handshake::send(from.clone(), outbound_hash);
+    }
+
+    /// pass messages queued on the `from` node to the network until the node is dropped,
+    /// then close the other side
+    fn forward(
+        &self,
+        executor: &TaskExecutor,
+        rx: mpsc::Receiver<ChannelBuffer>,
+        rx_thread: oneshot::Receiver<()>,
+        from: (String, u64),
+        to: (String, u64),
+    )
+    {
+        let network = self.clone();
+        let network_close = self.clone();
+        let from_binding = from.0.clone();
+        executor.spawn(
+            rx.for_each(move |cb| {
+                network.transmit(&from_binding, &to.0, to.1, cb);
+                Ok(())
+            })
+            .select(rx_thread.map_err(|_| {}))
+            .then(move |_| {
+                network_close.close(&from.0, from.1);
+                Ok(())
+            }),
+        );
+    }
+
+    /// queue message for delivery after the link latency, unless it is lost
+    fn transmit(&self, from: &str, to: &str, hash: u64, cb: ChannelBuffer) {
+        let mut state = self.inner.state.lock();
+        if !reachable(&state.groups, from, to) {
+            state.stats.dropped += 1;
+            return;
+        }
+        let config = state.link_config(from, to);
+        if state.lost(from, to, config.loss) {
+            trace!(target: "p2p_sim", "lost {} -> {} route {}", from, to, cb.head.get_route());
+            state.stats.dropped += 1;
+            return;
+        }
+
+        let at = state.schedule(from, to, config.latency);
+        state.seq += 1;
+        let seq = state.seq;
+        state.queue.push(Delivery {
+            at,
+            seq,
+            to: to.to_owned(),
+            hash,
+            cb,
+        });
+        self.inner.cond.notify_one();
+    }
+
+    /// remove connection after one side is gone and drop the other side
+    fn close(&self, binding: &str, hash: u64) {
+        let peer = {
+            let mut state = self.inner.state.lock();
+            let mut peer = None;
+            let index = state
+                .links
+                .iter()
+                .position(|link| link.peer(binding, hash).is_some());
+            if let Some(index) = index {
+                let link = state.links.remove(index);
+                if let Some((binding, hash)) = link.peer(binding, hash) {
+                    peer = state.mgrs.get(&binding).map(|mgr| (mgr.clone(), hash));
+                }
+            }
+            peer
+        };
+        if let Some((mgr, hash)) = peer {
+            trace!(target: "p2p_sim", "closed {} {}", binding, hash);
+            mgr.drop_node(hash);
+        }
+    }
+}
+
+/// true if `from` and `to` are not in different partition groups
+fn reachable(groups: &[Vec<String>], from: &str, to: &str) -> bool {
+    let group_of = |binding: &str| {
+        groups
+            .iter()
+            .position(|group| group.iter().any(|b| b == binding))
+    };
+    match (group_of(from), group_of(to)) {
+        (Some(a), Some(b)) => a == b,
+        _ => true,
+    }
+}
+
+/// loss generator of the link from `from` to `to`
+fn link_rng(seed: u64, from: &str, to: &str) -> XorShiftRng {
+    let mut input = [0u8; 8].to_vec();
+    BigEndian::write_u64(&mut input, seed);
+    input.extend(from.as_bytes());
+    input.push(0);
+    input.extend(to.as_bytes());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&blake2b(&input)[..16]);
+    XorShiftRng::from_seed(bytes)
+}
+
+/// delivery thread, exits once the network is dropped
+fn deliver(weak: Weak<Inner>) {
+    loop {
+        let inner = match weak.upgrade() {
+            Some(inner) => inner,
+            None => return,
+        };
+        let due = {
+            let mut state = inner.state.lock();
+            let next = state.queue.pop();
+            match next {
+                Some(delivery) => {
+                    if delivery.at > state.clock {
+                        state.clock = delivery.at;
+                    }
+                    let mgr = state.mgrs.get(&delivery.to).cloned();
+                    match mgr {
+                        Some(mgr) => {
+                            state.stats.delivered += 1;
+                            Some((mgr, delivery.hash, delivery.cb))
+                        }
+                        None => {
+                            state.stats.dropped += 1;
+                            None
+                        }
+                    }
+                }
+                None => {
+                    inner
+                        .cond
+                        .wait_for(&mut state, Duration::from_millis(MAX_WAIT));
+                    None
+                }
+            }
+        };
+        if let Some((mgr, hash, cb)) = due {
+            mgr.handle(hash, cb);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use super::State;
+
+    #[test]
+    fn test_loss_depends_on_seed_and_link() {
+        let mut interleaved = State::new(7);
+        let mut forward = Vec::new();
+        let mut backward = Vec::new();
+        for _ in 0..100 {
+            forward.push(interleaved.lost("a", "b", 0.5));
+            backward.push(interleaved.lost("b", "a", 0.5));
+        }
+
+        let mut sequential = State::new(7);
+        let backward_alone: Vec<bool> = (0..100).map(|_| sequential.lost("b", "a", 0.5)).collect();
+        let forward_alone: Vec<bool> = (0..100).map(|_| sequential.lost("a", "b", 0.5)).collect();
+        assert_eq!(forward, forward_alone);
+        assert_eq!(backward, backward_alone);
+        assert!(forward.iter().any(|lost| *lost) && forward.iter().any(|lost| !*lost));
+        assert_ne!(forward, backward);
+
+        let mut other_seed = State::new(8);
+        let forward_other: Vec<bool> = (0..100).map(|_| other_seed.lost("a", "b", 0.5)).collect();
+        assert_ne!(forward, forward_other);
+    }
+
+    #[test]
+    fn test_virtual_latency() {
+        let mut state = State::new(1);
+        let slow = state.schedule("a", "b", Duration::from_millis(50));
+        let fast = state.schedule("b", "a", Duration::from_millis(10));
+        assert_eq!(fast, Duration::from_millis(10));
+        assert_eq!(slow, Duration::from_millis(50));
+
+        // due times follow the clock, not the wall clock
+        state.clock = Duration::from_millis(100);
+        assert_eq!(
+            state.schedule("b", "a", Duration::from_millis(10)),
+            Duration::from_millis(110)
+        );
+
+        // a message never overtakes the previous one of its link
+        state.clock = Duration::from_millis(0);
+        assert_eq!(
+            state.schedule("b", "a", Duration::from_millis(10)),
+            Duration::from_millis(110)
+        );
+    }
+}
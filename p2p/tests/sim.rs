@@ -0,0 +1,128 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+#![cfg(feature = "sim")]
+
+extern crate p2p;
+extern crate tokio;
+
+use std::time::Duration;
+use std::thread;
+use p2p::Mgr;
+use p2p::Config;
+use p2p::{LinkConfig, SimNetwork};
+
+fn simulated_node(network: &SimNetwork, id: &str, binding: &str, boot_nodes: &[&str]) -> Mgr {
+    let mut config = Config::new();
+    config.local_node = format!("p2p://{}@{}", id, binding);
+    for boot_node in boot_nodes {
+        config.boot_nodes.push(boot_node.to_string());
+    }
+    let p2p = Mgr::new(config, vec![]);
+    p2p.set_network(network);
+    p2p
+}
+
+#[test]
+fn test_simulated_network() {
+    let runtime_sync = tokio::runtime::Builder::new()
+        .name_prefix("p2p-loop #")
+        .build()
+        .expect("p2p runtime loop init failed");
+    let executor_p2p = runtime_sync.executor();
+
+    let network = SimNetwork::new(1);
+    let mut p2p_0 = simulated_node(
+        &network,
+        "00000000-0000-0000-0000-000000000001",
+        "10.0.0.1:30303",
+        &[],
+    );
+    let mut p2p_1 = simulated_node(
+        &network,
+        "00000000-0000-0000-0000-000000000002",
+        "10.0.0.2:30303",
+        &["p2p://00000000-0000-0000-0000-000000000001@10.0.0.1:30303"],
+    );
+    p2p_0.run(executor_p2p.clone());
+    p2p_1.run(executor_p2p.clone());
+
+    thread::sleep(Duration::from_secs(3));
+    assert_eq!(1, p2p_0.get_active_nodes().len());
+    assert_eq!(1, p2p_1.get_active_nodes().len());
+
+    // partition closes the connection and prevents reconnecting
+    network.partition(vec![
+        vec!["10.0.0.1:30303".to_owned()],
+        vec!["10.0.0.2:30303".to_owned()],
+    ]);
+    thread::sleep(Duration::from_secs(3));
+    assert_eq!(0, p2p_0.get_active_nodes().len());
+    assert_eq!(0, p2p_1.get_active_nodes().len());
+
+    network.heal();
+    thread::sleep(Duration::from_secs(4));
+    assert_eq!(1, p2p_0.get_active_nodes().len());
+    assert_eq!(1, p2p_1.get_active_nodes().len());
+    assert!(network.stats().delivered > 0);
+
+    p2p_0.shutdown();
+    p2p_1.shutdown();
+}
+
+#[test]
+fn test_simulated_message_loss() {
+    let runtime_sync = tokio::runtime::Builder::new()
+        .name_prefix("p2p-loop #")
+        .build()
+        .expect("p2p runtime loop init failed");
+    let executor_p2p = runtime_sync.executor();
+
+    let network = SimNetwork::new(2);
+    network.set_default_link(LinkConfig {
+        latency: Duration::from_millis(1),
+        loss: 1.0,
+    });
+    let mut p2p_0 = simulated_node(
+        &network,
+        "00000000-0000-0000-0000-000000000001",
+        "10.0.0.1:30303",
+        &[],
+    );
+    let mut p2p_1 = simulated_node(
+        &network,
+        "00000000-0000-0000-0000-000000000002",
+        "10.0.0.2:30303",
+        &["p2p://00000000-0000-0000-0000-000000000001@10.0.0.1:30303"],
+    );
+    p2p_0.run(executor_p2p.clone());
+    p2p_1.run(executor_p2p.clone());
+
+    thread::sleep(Duration::from_secs(2));
+    assert_eq!(0, p2p_0.get_active_nodes().len());
+    assert_eq!(0, p2p_1.get_active_nodes().len());
+    let stats = network.stats();
+    assert_eq!(0, stats.delivered);
+    assert!(stats.dropped > 0);
+
+    p2p_0.shutdown();
+    p2p_1.shutdown();
+}
@@ -27,6 +27,7 @@ use std::fmt;
 
 use acore::account_provider::{SignError as AccountError};
 use acore::{Error as EthcoreError, CallError};
+use acore::light::Error as LightError;
 use jsonrpc_core::{futures, Error, ErrorCode, Value};
 use rlp::DecoderError;
 use acore::transaction::Error as TransactionError;
@@ -483,6 +484,15 @@ pub fn no_light_peers() -> Error {
     }
 }
 
+pub fn light(error: LightError) -> Error {
+    match error {
+        LightError::NoPeers => no_light_peers(),
+        LightError::UnknownBlock => unknown_block(),
+        LightError::Execution(e) => execution(e),
+        e => fetch(e),
+    }
+}
+
 pub fn deprecated<T: Into<Option<String>>>(message: T) -> Error {
     Error {
         code: ErrorCode::ServerError(codes::DEPRECATED),
@@ -27,6 +27,7 @@ pub mod accounts;
 pub mod dispatch;
 pub mod fake_sign;
 pub mod nonce;
+pub mod subscribers;
 
 mod poll_filter;
 mod poll_manager;
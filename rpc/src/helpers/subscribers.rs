@@ -0,0 +1,165 @@
+/*******************************************************************************
+ * Copyright (c) 2015-2018 Parity Technologies (UK) Ltd.
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! A map of subscribers.
+
+use std::{ops, str};
+use std::collections::HashMap;
+use jsonrpc_macros::pubsub::{Subscriber, Sink};
+use jsonrpc_pubsub::SubscriptionId;
+use aion_types::H64;
+use rand::{thread_rng, RngCore};
+use rustc_hex::{FromHex, ToHex};
+
+/// Unique identifier of a subscription.
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct Id(H64);
+
+impl str::FromStr for Id {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !s.starts_with("0x") {
+            return Err("The id must start with 0x".into());
+        }
+        let bytes: Vec<u8> = s[2..]
+            .from_hex()
+            .map_err(|e| format!("Invalid id: {:?}", e))?;
+        if bytes.len() != 8 {
+            return Err("Invalid id length".into());
+        }
+        Ok(Id(H64::from_slice(&bytes)))
+    }
+}
+
+impl Id {
+    /// Hex representation of the id, as handed out to the subscriber.
+    pub fn as_string(&self) -> String { format!("0x{}", (self.0).0.to_hex()) }
+}
+
+/// Subscribers of a single subscription kind, indexed by their random ids.
+pub struct Subscribers<T> {
+    subscriptions: HashMap<Id, T>,
+}
+
+impl<T> Default for Subscribers<T> {
+    fn default() -> Self {
+        Subscribers {
+            subscriptions: HashMap::new(),
+        }
+    }
+}
+
+impl<T> Subscribers<T> {
+    fn next_id(&self) -> Id {
+        let mut data = H64::default();
+        loop {
+            thread_rng().fill_bytes(&mut data.0);
+            let id = Id(data);
+            if !self.subscriptions.contains_key(&id) {
+                return id;
+            }
+        }
+    }
+
+    /// Insert new subscription and return assigned id.
+    pub fn insert(&mut self, val: T) -> SubscriptionId {
+        let id = self.next_id();
+        debug!(target: "pubsub", "Adding subscription id={:?}", id);
+        let s = id.as_string();
+        self.subscriptions.insert(id, val);
+        SubscriptionId::String(s)
+    }
+
+    /// Removes subscription with given id and returns it (if any).
+    pub fn remove(&mut self, id: &SubscriptionId) -> Option<T> {
+        trace!(target: "pubsub", "Removing subscription id={:?}", id);
+        match *id {
+            SubscriptionId::String(ref id) => {
+                match id.parse() {
+                    Ok(id) => self.subscriptions.remove(&id),
+                    Err(_) => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<T> Subscribers<Sink<T>> {
+    /// Assigns id and adds a subscriber to the list.
+    pub fn push(&mut self, sub: Subscriber<T>) {
+        let id = self.next_id();
+        if let Ok(sink) = sub.assign_id(SubscriptionId::String(id.as_string())) {
+            debug!(target: "pubsub", "Adding subscription id={:?}", id);
+            self.subscriptions.insert(id, sink);
+        }
+    }
+}
+
+impl<T, V> Subscribers<(Sink<T>, V)> {
+    /// Assigns id and adds a subscriber with additional data (e.g. a filter) to the list.
+    pub fn push(&mut self, sub: Subscriber<T>, val: V) {
+        let id = self.next_id();
+        if let Ok(sink) = sub.assign_id(SubscriptionId::String(id.as_string())) {
+            debug!(target: "pubsub", "Adding subscription id={:?}", id);
+            self.subscriptions.insert(id, (sink, val));
+        }
+    }
+}
+
+impl<T> ops::Deref for Subscribers<T> {
+    type Target = HashMap<Id, T>;
+
+    fn deref(&self) -> &Self::Target { &self.subscriptions }
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonrpc_pubsub::SubscriptionId;
+    use super::Subscribers;
+
+    #[test]
+    fn should_insert_and_remove_subscriptions() {
+        let mut subscribers = Subscribers::default();
+        let id = subscribers.insert(5u64);
+        assert_eq!(subscribers.len(), 1);
+
+        let invalid = SubscriptionId::String("0x1234".into());
+        assert_eq!(subscribers.remove(&invalid), None);
+        assert_eq!(subscribers.remove(&id), Some(5u64));
+        assert!(subscribers.is_empty());
+    }
+
+    #[test]
+    fn should_produce_parsable_ids() {
+        let mut subscribers = Subscribers::default();
+        let id = subscribers.insert(());
+        match id {
+            SubscriptionId::String(ref s) => {
+                assert_eq!(s.len(), 18);
+                assert!(s.starts_with("0x"));
+            }
+            _ => panic!("Expected string id"),
+        }
+    }
+}
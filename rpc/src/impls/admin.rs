@@ -0,0 +1,84 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Admin rpc implementation.
+
+use std::sync::Arc;
+
+use jsonrpc_core::Result;
+
+use acore::sync::SyncProvider;
+use version::version;
+
+use helpers::errors;
+use traits::Admin;
+use types::{NetworkTraffic, NodeInfo, PeerInfo};
+
+/// Admin rpc implementation.
+pub struct AdminClient<S: ?Sized> {
+    sync: Arc<S>,
+}
+
+impl<S: ?Sized> AdminClient<S>
+where S: SyncProvider
+{
+    /// Creates new AdminClient.
+    pub fn new(sync: &Arc<S>) -> Self {
+        AdminClient {
+            sync: sync.clone(),
+        }
+    }
+}
+
+impl<S: ?Sized> Admin for AdminClient<S>
+where S: SyncProvider + 'static
+{
+    fn peers(&self) -> Result<Vec<PeerInfo>> {
+        Ok(self.sync.peers().into_iter().map(Into::into).collect())
+    }
+
+    fn node_info(&self) -> Result<NodeInfo> {
+        let info = self
+            .sync
+            .node_info()
+            .ok_or_else(errors::network_disabled)?;
+        let protocol_version = self.sync.status().protocol_version;
+        Ok(NodeInfo::new(info, protocol_version, version()))
+    }
+
+    fn add_peer(&self, node: String) -> Result<bool> {
+        self.sync
+            .add_peer(&node)
+            .map(|_| true)
+            .map_err(|e| errors::invalid_params("node", e))
+    }
+
+    fn remove_peer(&self, peer: String) -> Result<bool> { Ok(self.sync.remove_peer(&peer)) }
+
+    fn ban_peer(&self, ip: String) -> Result<bool> {
+        self.sync
+            .ban_peer(&ip)
+            .map(|_| true)
+            .map_err(|e| errors::invalid_params("ip", e))
+    }
+
+    fn peer_traffic(&self) -> Result<NetworkTraffic> { Ok(self.sync.traffic().into()) }
+}
@@ -0,0 +1,89 @@
+/*******************************************************************************
+ * Copyright (c) 2015-2018 Parity Technologies (UK) Ltd.
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Debug rpc implementation.
+
+use std::sync::Arc;
+
+use jsonrpc_core::Result;
+
+use acore::client::{BlockChainClient, CallAnalytics, TransactionId};
+use acore::CallError;
+use aion_types::H256;
+
+use helpers::errors;
+use traits::Debug;
+use types::{BlockNumber, CallTrace};
+
+/// Debug rpc implementation.
+pub struct DebugClient<C> {
+    client: Arc<C>,
+}
+
+impl<C> DebugClient<C> {
+    /// Creates new DebugClient.
+    pub fn new(client: Arc<C>) -> Self {
+        DebugClient {
+            client,
+        }
+    }
+}
+
+fn trace_analytics() -> CallAnalytics {
+    CallAnalytics {
+        transaction_tracing: true,
+        vm_tracing: false,
+        state_diffing: false,
+    }
+}
+
+impl<C> Debug for DebugClient<C>
+where C: BlockChainClient + 'static
+{
+    fn trace_transaction(&self, hash: H256) -> Result<Option<CallTrace>> {
+        match self
+            .client
+            .replay(TransactionId::Hash(hash), trace_analytics())
+        {
+            Ok(executed) => Ok(executed.trace.map(Into::into)),
+            Err(CallError::TransactionNotFound) => Ok(None),
+            Err(e) => Err(errors::call(e)),
+        }
+    }
+
+    fn trace_block_by_number(&self, num: BlockNumber) -> Result<Vec<CallTrace>> {
+        if num == BlockNumber::Pending {
+            return Err(errors::invalid_params("block", "Pending block can not be traced."));
+        }
+
+        let results = self
+            .client
+            .replay_block_transactions(num.into(), trace_analytics())
+            .and_then(|results| results.collect::<::std::result::Result<Vec<_>, _>>())
+            .map_err(errors::call)?;
+        Ok(results
+            .into_iter()
+            .filter_map(|executed| executed.trace)
+            .map(Into::into)
+            .collect())
+    }
+}
@@ -28,6 +28,7 @@ use std::thread;
 use std::time;
 
 use rlp::UntrustedRlp;
+use blake2b::blake2b;
 use aion_types::{H64, H128, U128, H256, U256, Address};
 use serde_json::{self, Value};
 use serde_json::map::Map;
@@ -35,7 +36,9 @@ use dispatch::DynamicGasPrice;
 
 use acore::sync::SyncProvider;
 use acore::account_provider::AccountProvider;
-use acore::client::{MiningBlockChainClient, BlockId, TransactionId};
+use acore::client::{
+    BlockChainClient, MiningBlockChainClient, ProvingBlockChainClient, BlockId, TransactionId,
+};
 use acore::filter::Filter as EthcoreFilter;
 use acore::header::{BlockNumber as EthBlockNumber, SealType};
 use acore::log_entry::LogEntry;
@@ -54,7 +57,8 @@ use helpers::dispatch::{FullDispatcher, default_gas_price};
 use helpers::accounts::unwrap_provider;
 use traits::Eth;
 use types::{
-    Block, BlockTransactions, BlockNumber, Bytes, SyncStatus, Transaction, CallRequest, Index,
+    AccountProof, Block, BlockTransactions, BlockNumber, Bytes, SyncStatus, StorageProof,
+    Transaction, CallRequest, Index,
 Filter, Log, Receipt, Work, Contract, ContractInfo, Abi, AbiIO , SyncInfo, /*AcitvePeerInfo, PbSyncInfo,
                                                                            SimpleReceipt, SimpleReceiptLog,*/
 };
@@ -233,6 +237,32 @@ where M: MinerService {
     result
 }
 
+/// Sync status as reported by `eth_syncing`.
+pub fn sync_status<C, S: ?Sized>(client: &C, sync: &S) -> SyncStatus
+where
+    C: BlockChainClient,
+    S: SyncProvider,
+{
+    let status = sync.status();
+
+    let chain_info = client.chain_info();
+    let current_block = chain_info.best_block_number;
+    let highest_block = status.highest_block_number.unwrap_or(0u64);
+
+    // refer to java's impl: AionImpl.java isSyncComplete.
+    if (current_block + 5) < highest_block {
+        let info = SyncInfo {
+            // to comply with java's impl, return hex string.
+            starting_block: format!("{:#x}", status.start_block_number),
+            current_block: format!("{:#x}", current_block),
+            highest_block: format!("{:#x}", highest_block),
+        };
+        SyncStatus::Info(info)
+    } else {
+        SyncStatus::None
+    }
+}
+
 fn check_known<C>(client: &C, number: BlockNumber) -> Result<()>
 where C: MiningBlockChainClient {
     use acore::block_status::BlockStatus;
@@ -246,7 +276,7 @@ where C: MiningBlockChainClient {
 
 impl<C, S: ?Sized, M, EM> Eth for EthClient<C, S, M, EM>
 where
-    C: MiningBlockChainClient + 'static,
+    C: MiningBlockChainClient + ProvingBlockChainClient + 'static,
     S: SyncProvider + 'static,
     M: MinerService + 'static,
     EM: ExternalMinerService + 'static,
@@ -256,27 +286,7 @@ where
         Ok(format!("{}", version))
     }
 
-    fn syncing(&self) -> Result<SyncStatus> {
-        let status = self.sync.status();
-        let client = &self.client;
-
-        let chain_info = client.chain_info();
-        let current_block = chain_info.best_block_number;
-        let highest_block = status.highest_block_number.unwrap_or(0u64);
-
-        // refer to java's impl: AionImpl.java isSyncComplete.
-        if (current_block + 5) < highest_block {
-            let info = SyncInfo {
-                // to comply with java's impl, return hex string.
-                starting_block: format!("{:#x}", status.start_block_number),
-                current_block: format!("{:#x}", current_block),
-                highest_block: format!("{:#x}", highest_block),
-            };
-            Ok(SyncStatus::Info(info))
-        } else {
-            Ok(SyncStatus::None)
-        }
-    }
+    fn syncing(&self) -> Result<SyncStatus> { Ok(sync_status(&*self.client, &*self.sync)) }
 
     fn author(&self) -> Result<H256> { Ok(H256::from(self.miner.author())) }
 
@@ -393,6 +403,48 @@ where
         Box::new(future::done(res))
     }
 
+    fn proof(
+        &self,
+        address: Address,
+        keys: Vec<U128>,
+        num: Trailing<BlockNumber>,
+    ) -> BoxFuture<AccountProof>
+    {
+        let id = num.unwrap_or_default();
+        try_bf!(check_known(&*self.client, id.clone()));
+        let id: BlockId = id.into();
+
+        let account_key = blake2b(&address);
+        let (account_proof, account) = match self.client.prove_account(account_key, id) {
+            Some(proof) => proof,
+            None => return Box::new(future::err(errors::state_pruned())),
+        };
+
+        let mut storage_proof = Vec::with_capacity(keys.len());
+        for key in keys {
+            let storage_key = blake2b(&H128::from(key));
+            let (proof, value) = match self.client.prove_storage(account_key, storage_key, id) {
+                Some(proof) => proof,
+                None => return Box::new(future::err(errors::state_pruned())),
+            };
+            storage_proof.push(StorageProof {
+                key,
+                value,
+                proof: proof.into_iter().map(Bytes::new).collect(),
+            });
+        }
+
+        Box::new(future::ok(AccountProof {
+            address,
+            account_proof: account_proof.into_iter().map(Bytes::new).collect(),
+            balance: account.balance,
+            nonce: account.nonce,
+            code_hash: account.code_hash,
+            storage_hash: account.storage_root,
+            storage_proof,
+        }))
+    }
+
     fn block_by_hash(&self, hash: H256, include_txs: bool) -> BoxFuture<Option<Block>> {
         Box::new(future::done(self.block(BlockId::Hash(hash), include_txs)))
     }
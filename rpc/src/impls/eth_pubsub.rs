@@ -0,0 +1,339 @@
+/*******************************************************************************
+ * Copyright (c) 2015-2018 Parity Technologies (UK) Ltd.
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Eth PUB-SUB rpc implementation.
+
+use std::sync::{Arc, Weak};
+
+use jsonrpc_core::Result;
+use jsonrpc_core::futures::Future;
+use jsonrpc_macros::Trailing;
+use jsonrpc_macros::pubsub::{Sink, Subscriber};
+use jsonrpc_pubsub::SubscriptionId;
+use parking_lot::{Mutex, RwLock};
+use tokio::runtime::TaskExecutor;
+
+use acore::blockchain::BlockReceipts;
+use acore::client::{BlockChainClient, BlockId, ChainNotify};
+use acore::filter::Filter as EthFilter;
+use acore::log_entry::LocalizedLogEntry;
+use acore::sync::SyncProvider;
+use aion_types::H256;
+use bytes::Bytes;
+use rlp::UntrustedRlp;
+
+use helpers::errors;
+use helpers::subscribers::Subscribers;
+use impls::eth::sync_status;
+use metadata::Metadata;
+use traits::EthPubSub;
+use types::{Log, SyncStatus};
+use types::pubsub::{self, Kind, Params};
+
+type Client = Sink<pubsub::Result>;
+
+/// Eth PubSub implementation.
+///
+/// Subscriptions are bound to the session they were created in: when the session is closed
+/// the pub-sub layer unsubscribes every id it handed out, so no explicit cleanup is required
+/// from the caller.
+///
+/// Clones share the same subscribers and notification handler.
+pub struct EthPubSubClient<C, S: ?Sized> {
+    handler: Arc<ChainNotificationHandler<C, S>>,
+    heads_subscribers: Arc<RwLock<Subscribers<Client>>>,
+    logs_subscribers: Arc<RwLock<Subscribers<(Client, EthFilter)>>>,
+    transactions_subscribers: Arc<RwLock<Subscribers<Client>>>,
+    sync_subscribers: Arc<RwLock<Subscribers<Client>>>,
+}
+
+impl<C, S: ?Sized> Clone for EthPubSubClient<C, S> {
+    fn clone(&self) -> Self {
+        EthPubSubClient {
+            handler: self.handler.clone(),
+            heads_subscribers: self.heads_subscribers.clone(),
+            logs_subscribers: self.logs_subscribers.clone(),
+            transactions_subscribers: self.transactions_subscribers.clone(),
+            sync_subscribers: self.sync_subscribers.clone(),
+        }
+    }
+}
+
+impl<C, S: ?Sized> EthPubSubClient<C, S> {
+    /// Creates new `EthPubSubClient`.
+    pub fn new(client: Arc<C>, sync: Arc<S>, executor: TaskExecutor) -> Self {
+        let heads_subscribers = Arc::new(RwLock::new(Subscribers::default()));
+        let logs_subscribers = Arc::new(RwLock::new(Subscribers::default()));
+        let transactions_subscribers = Arc::new(RwLock::new(Subscribers::default()));
+        let sync_subscribers = Arc::new(RwLock::new(Subscribers::default()));
+
+        EthPubSubClient {
+            handler: Arc::new(ChainNotificationHandler {
+                client,
+                sync,
+                executor,
+                heads_subscribers: heads_subscribers.clone(),
+                logs_subscribers: logs_subscribers.clone(),
+                transactions_subscribers: transactions_subscribers.clone(),
+                sync_subscribers: sync_subscribers.clone(),
+                last_syncing: Mutex::new(None),
+            }),
+            heads_subscribers,
+            logs_subscribers,
+            transactions_subscribers,
+            sync_subscribers,
+        }
+    }
+
+    /// Returns a chain notification handler.
+    pub fn handler(&self) -> Weak<ChainNotificationHandler<C, S>> { Arc::downgrade(&self.handler) }
+}
+
+/// PubSub Notification handler.
+pub struct ChainNotificationHandler<C, S: ?Sized> {
+    client: Arc<C>,
+    sync: Arc<S>,
+    executor: TaskExecutor,
+    heads_subscribers: Arc<RwLock<Subscribers<Client>>>,
+    logs_subscribers: Arc<RwLock<Subscribers<(Client, EthFilter)>>>,
+    transactions_subscribers: Arc<RwLock<Subscribers<Client>>>,
+    sync_subscribers: Arc<RwLock<Subscribers<Client>>>,
+    /// Whether the node was syncing when subscribers were notified last time.
+    last_syncing: Mutex<Option<bool>>,
+}
+
+impl<C, S: ?Sized> ChainNotificationHandler<C, S> {
+    fn notify(executor: &TaskExecutor, subscriber: &Client, result: pubsub::Result) {
+        executor.spawn(
+            subscriber
+                .notify(Ok(result))
+                .map(|_| ())
+                .map_err(|e| warn!(target: "pubsub", "Unable to send notification: {:?}", e)),
+        );
+    }
+
+    /// Notify all subscribers about new pending transactions.
+    pub fn notify_new_transactions(&self, hashes: &[H256]) {
+        for subscriber in self.transactions_subscribers.read().values() {
+            for hash in hashes {
+                Self::notify(
+                    &self.executor,
+                    subscriber,
+                    pubsub::Result::TransactionHash(*hash),
+                );
+            }
+        }
+    }
+}
+
+impl<C, S: ?Sized> ChainNotificationHandler<C, S>
+where
+    C: BlockChainClient,
+    S: SyncProvider,
+{
+    fn notify_heads(&self, enacted: &[H256]) {
+        let subscribers = self.heads_subscribers.read();
+        if subscribers.is_empty() {
+            return;
+        }
+
+        for hash in enacted {
+            let header = match self.client.block_header(BlockId::Hash(*hash)) {
+                Some(header) => header,
+                None => continue,
+            };
+            for subscriber in subscribers.values() {
+                Self::notify(
+                    &self.executor,
+                    subscriber,
+                    pubsub::Result::Header((&header).into()),
+                );
+            }
+        }
+    }
+
+    fn notify_logs(&self, enacted: &[H256], retracted: &[H256]) {
+        let subscribers = self.logs_subscribers.read();
+        if subscribers.is_empty() {
+            return;
+        }
+
+        for &(ref subscriber, ref filter) in subscribers.values() {
+            let mut logs = Vec::new();
+            for hash in retracted {
+                logs.extend(self.block_logs(hash, filter).into_iter().map(|mut log| {
+                    log.log_type = "removed".into();
+                    log
+                }));
+            }
+            for hash in enacted {
+                logs.extend(self.block_logs(hash, filter));
+            }
+            for log in logs {
+                Self::notify(&self.executor, subscriber, pubsub::Result::Log(log));
+            }
+        }
+    }
+
+    fn notify_syncing(&self) {
+        let subscribers = self.sync_subscribers.read();
+        let status = sync_status(&*self.client, &*self.sync);
+        let syncing = status != SyncStatus::None;
+
+        let mut last_syncing = self.last_syncing.lock();
+        if *last_syncing == Some(syncing) {
+            return;
+        }
+        *last_syncing = Some(syncing);
+
+        for subscriber in subscribers.values() {
+            Self::notify(
+                &self.executor,
+                subscriber,
+                pubsub::Result::SyncState(status.clone()),
+            );
+        }
+    }
+
+    /// Logs of the given block matching the filter, read directly from the block receipts so
+    /// that blocks which are no longer canonical can be reported as well.
+    fn block_logs(&self, hash: &H256, filter: &EthFilter) -> Vec<Log> {
+        let block = match self.client.block(BlockId::Hash(*hash)) {
+            Some(block) => block,
+            None => return Vec::new(),
+        };
+        let receipts: BlockReceipts = match self
+            .client
+            .block_receipts(hash)
+            .and_then(|r: Bytes| UntrustedRlp::new(&r).as_val().ok())
+        {
+            Some(receipts) => receipts,
+            None => return Vec::new(),
+        };
+
+        let block_number = block.header_view().number();
+        let mut log_index = 0;
+        let mut logs = Vec::new();
+        for (transaction_index, (receipt, transaction_hash)) in receipts
+            .receipts
+            .into_iter()
+            .zip(block.transaction_hashes())
+            .enumerate()
+        {
+            for (transaction_log_index, entry) in receipt.logs().iter().enumerate() {
+                if filter.matches(entry) {
+                    logs.push(
+                        LocalizedLogEntry {
+                            entry: entry.clone(),
+                            block_hash: *hash,
+                            block_number,
+                            transaction_hash,
+                            transaction_index,
+                            log_index,
+                            transaction_log_index,
+                        }
+                        .into(),
+                    );
+                }
+                log_index += 1;
+            }
+        }
+        logs
+    }
+}
+
+impl<C, S: ?Sized> ChainNotify for ChainNotificationHandler<C, S>
+where
+    C: BlockChainClient,
+    S: SyncProvider,
+{
+    fn new_blocks(
+        &self,
+        _imported: Vec<H256>,
+        _invalid: Vec<H256>,
+        enacted: Vec<H256>,
+        retracted: Vec<H256>,
+        _sealed: Vec<H256>,
+        _proposed: Vec<Bytes>,
+        _duration: u64,
+    )
+    {
+        self.notify_heads(&enacted);
+        self.notify_logs(&enacted, &retracted);
+        self.notify_syncing();
+    }
+}
+
+impl<C, S: ?Sized> EthPubSub for EthPubSubClient<C, S>
+where
+    C: Send + Sync + 'static,
+    S: Send + Sync + 'static,
+{
+    type Metadata = Metadata;
+
+    fn subscribe(
+        &self,
+        _meta: Metadata,
+        subscriber: Subscriber<pubsub::Result>,
+        kind: Kind,
+        params: Trailing<Params>,
+    )
+    {
+        let error = match (kind, params.into()) {
+            (Kind::NewHeads, Params::None) => {
+                self.heads_subscribers.write().push(subscriber);
+                return;
+            }
+            (Kind::Logs, Params::Logs(filter)) => {
+                self.logs_subscribers.write().push(subscriber, filter.into());
+                return;
+            }
+            (Kind::NewPendingTransactions, Params::None) => {
+                self.transactions_subscribers.write().push(subscriber);
+                return;
+            }
+            (Kind::Syncing, Params::None) => {
+                self.sync_subscribers.write().push(subscriber);
+                return;
+            }
+            (Kind::NewHeads, _) => errors::invalid_params("newHeads", "Expected no parameters."),
+            (Kind::NewPendingTransactions, _) => {
+                errors::invalid_params("newPendingTransactions", "Expected no parameters.")
+            }
+            (Kind::Syncing, _) => errors::invalid_params("syncing", "Expected no parameters."),
+            (Kind::Logs, Params::None) => {
+                errors::invalid_params("logs", "Expected a filter object.")
+            }
+        };
+
+        let _ = subscriber.reject(error);
+    }
+
+    fn unsubscribe(&self, _: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool> {
+        let res = self.heads_subscribers.write().remove(&id).is_some();
+        let res2 = self.logs_subscribers.write().remove(&id).is_some();
+        let res3 = self.transactions_subscribers.write().remove(&id).is_some();
+        let res4 = self.sync_subscribers.write().remove(&id).is_some();
+
+        Ok(res || res2 || res3 || res4)
+    }
+}
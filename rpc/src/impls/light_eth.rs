@@ -0,0 +1,243 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Eth rpc implementation for light clients. State queries are answered with proofs
+//! fetched from full nodes, the rest of the api is unsupported.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use aion_types::{H64, H128, U128, H256, U256, Address};
+
+use acore::client::BlockId;
+use acore::light::LightChainClient;
+use acore::sync::SyncProvider;
+
+use jsonrpc_core::{BoxFuture, Result};
+use jsonrpc_core::futures::future;
+use jsonrpc_macros::Trailing;
+
+use helpers::{errors, fake_sign};
+use traits::Eth;
+use types::{
+    AccountProof, Block, BlockNumber, Bytes, CallRequest, Contract, Filter, Index, Log, Receipt,
+    SyncInfo, SyncStatus, Transaction, Work,
+};
+
+/// Light client eth rpc implementation.
+pub struct LightEthClient<C, S: ?Sized> {
+    client: Arc<C>,
+    sync: Arc<S>,
+    gas_price: U256,
+}
+
+impl<C, S: ?Sized> LightEthClient<C, S>
+where
+    C: LightChainClient,
+    S: SyncProvider,
+{
+    /// Creates new LightEthClient.
+    pub fn new(client: &Arc<C>, sync: &Arc<S>, gas_price: U256) -> Self {
+        LightEthClient {
+            client: client.clone(),
+            sync: sync.clone(),
+            gas_price,
+        }
+    }
+
+    /// Resolve the block id, failing for blocks unknown to the header chain.
+    fn block_id(&self, num: Trailing<BlockNumber>) -> Result<BlockId> {
+        let id: BlockId = num.unwrap_or_default().into();
+        match self.client.block_hash(id) {
+            Some(hash) => Ok(BlockId::Hash(hash)),
+            None => Err(errors::unknown_block()),
+        }
+    }
+}
+
+impl<C, S: ?Sized> Eth for LightEthClient<C, S>
+where
+    C: LightChainClient + 'static,
+    S: SyncProvider + 'static,
+{
+    fn protocol_version(&self) -> Result<String> {
+        let version = self.sync.status().protocol_version.to_owned();
+        Ok(format!("{}", version))
+    }
+
+    fn syncing(&self) -> Result<SyncStatus> {
+        let status = self.sync.status();
+        let current_block = self.client.chain_info().best_block_number;
+        let highest_block = status.highest_block_number.unwrap_or(0u64);
+
+        if (current_block + 5) < highest_block {
+            Ok(SyncStatus::Info(SyncInfo {
+                starting_block: format!("{:#x}", status.start_block_number),
+                current_block: format!("{:#x}", current_block),
+                highest_block: format!("{:#x}", highest_block),
+            }))
+        } else {
+            Ok(SyncStatus::None)
+        }
+    }
+
+    fn hashrate(&self) -> Result<String> { Err(errors::light_unimplemented(None)) }
+
+    fn author(&self) -> Result<H256> { Err(errors::light_unimplemented(None)) }
+
+    fn is_mining(&self) -> Result<bool> { Ok(false) }
+
+    fn gas_price(&self) -> Result<U256> { Ok(self.gas_price) }
+
+    fn accounts(&self) -> Result<Vec<H256>> { Ok(Vec::new()) }
+
+    fn block_number(&self) -> Result<u64> { Ok(self.client.chain_info().best_block_number) }
+
+    fn balance(&self, address: H256, num: Trailing<BlockNumber>) -> BoxFuture<U256> {
+        let id = try_bf!(self.block_id(num));
+        Box::new(future::done(self.client.balance(&address, id).map_err(errors::light)))
+    }
+
+    fn storage_at(
+        &self,
+        address: Address,
+        pos: U128,
+        num: Trailing<BlockNumber>,
+    ) -> BoxFuture<H128>
+    {
+        let id = try_bf!(self.block_id(num));
+        Box::new(future::done(
+            self.client
+                .storage_at(&address, &H128::from(pos), id)
+                .map_err(errors::light),
+        ))
+    }
+
+    fn proof(&self, _: Address, _: Vec<U128>, _: Trailing<BlockNumber>) -> BoxFuture<AccountProof> {
+        Box::new(future::err(errors::light_unimplemented(None)))
+    }
+
+    fn block_by_hash(&self, _: H256, _: bool) -> BoxFuture<Option<Block>> {
+        Box::new(future::err(errors::light_unimplemented(None)))
+    }
+
+    fn block_by_number(&self, _: BlockNumber, _: bool) -> BoxFuture<Option<Block>> {
+        Box::new(future::err(errors::light_unimplemented(None)))
+    }
+
+    fn transaction_count(&self, address: Address, num: Trailing<BlockNumber>) -> BoxFuture<U256> {
+        let id = try_bf!(self.block_id(num));
+        Box::new(future::done(self.client.nonce(&address, id).map_err(errors::light)))
+    }
+
+    fn block_transaction_count_by_hash(&self, _: H256) -> BoxFuture<Option<U256>> {
+        Box::new(future::err(errors::light_unimplemented(None)))
+    }
+
+    fn block_transaction_count_by_number(&self, _: BlockNumber) -> BoxFuture<Option<U256>> {
+        Box::new(future::err(errors::light_unimplemented(None)))
+    }
+
+    fn code_at(&self, _: Address, _: Trailing<BlockNumber>) -> BoxFuture<Bytes> {
+        Box::new(future::err(errors::light_unimplemented(None)))
+    }
+
+    fn send_raw_transaction(&self, _: Bytes) -> Result<H256> {
+        Err(errors::light_unimplemented(None))
+    }
+
+    fn submit_transaction(&self, raw: Bytes) -> Result<H256> { self.send_raw_transaction(raw) }
+
+    fn call(&self, request: CallRequest, num: Trailing<BlockNumber>) -> BoxFuture<Bytes> {
+        let request = CallRequest::into(request);
+        let signed = try_bf!(fake_sign::sign_call(request));
+        let id = try_bf!(self.block_id(num));
+
+        Box::new(future::done(
+            self.client
+                .call(&signed, id)
+                .map(|executed| executed.output.into())
+                .map_err(errors::light),
+        ))
+    }
+
+    fn estimate_gas(&self, _: CallRequest, _: Trailing<BlockNumber>) -> BoxFuture<U256> {
+        Box::new(future::err(errors::light_unimplemented(None)))
+    }
+
+    fn transaction_by_hash(&self, _: H256) -> BoxFuture<Option<Transaction>> {
+        Box::new(future::err(errors::light_unimplemented(None)))
+    }
+
+    fn transaction_by_block_hash_and_index(
+        &self,
+        _: H256,
+        _: Index,
+    ) -> BoxFuture<Option<Transaction>>
+    {
+        Box::new(future::err(errors::light_unimplemented(None)))
+    }
+
+    fn transaction_by_block_number_and_index(
+        &self,
+        _: BlockNumber,
+        _: Index,
+    ) -> BoxFuture<Option<Transaction>>
+    {
+        Box::new(future::err(errors::light_unimplemented(None)))
+    }
+
+    fn transaction_receipt(&self, _: H256) -> BoxFuture<Option<Receipt>> {
+        Box::new(future::err(errors::light_unimplemented(None)))
+    }
+
+    fn compilers(&self) -> Result<Vec<String>> { Ok(Vec::new()) }
+
+    fn compile_lll(&self, _: String) -> Result<Bytes> {
+        Err(errors::deprecated(
+            "Compilation of LLL via RPC is deprecated".to_string(),
+        ))
+    }
+
+    fn compile_solidity(&self, _: String) -> Result<HashMap<String, Contract>> {
+        Err(errors::light_unimplemented(None))
+    }
+
+    fn compile_serpent(&self, _: String) -> Result<Bytes> {
+        Err(errors::deprecated(
+            "Compilation of Serpent via RPC is deprecated".to_string(),
+        ))
+    }
+
+    fn logs(&self, _: Filter) -> BoxFuture<Vec<Log>> {
+        Box::new(future::err(errors::light_unimplemented(None)))
+    }
+
+    fn work(&self, _: Trailing<u64>) -> Result<Work> { Err(errors::light_unimplemented(None)) }
+
+    fn submit_work(&self, _: H64, _: H256, _: Bytes) -> Result<bool> {
+        Err(errors::light_unimplemented(None))
+    }
+
+    fn submit_hashrate(&self, _: U256, _: H256) -> Result<bool> {
+        Err(errors::light_unimplemented(None))
+    }
+}
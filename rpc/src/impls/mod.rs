@@ -33,6 +33,8 @@ macro_rules! try_bf {
 #[macro_use]
 mod eth;
 mod eth_filter;
+mod eth_pubsub;
+mod light_eth;
 mod net;
 mod personal;
 mod signing;
@@ -40,9 +42,14 @@ mod rpc;
 mod stratum;
 mod web3;
 mod ping;
+mod debug;
+mod traces;
+mod admin;
 
 pub use self::eth::EthClient;
 pub use self::eth_filter::EthFilterClient;
+pub use self::eth_pubsub::EthPubSubClient;
+pub use self::light_eth::LightEthClient;
 pub use self::net::NetClient;
 pub use self::personal::PersonalClient;
 pub use self::signing::SigningClient;
@@ -50,3 +57,6 @@ pub use self::web3::Web3Client;
 pub use self::rpc::RpcClient;
 pub use self::stratum::StratumClient;
 pub use self::ping::PingClient;
+pub use self::debug::DebugClient;
+pub use self::traces::TracesClient;
+pub use self::admin::AdminClient;
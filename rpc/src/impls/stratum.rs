@@ -42,8 +42,8 @@ use helpers::errors;
 use helpers::accounts::unwrap_provider;
 use traits::Stratum;
 use types::{
-    Work, Info, AddressValidation, MiningInfo, MinerStats, TemplateParam, Bytes, StratumHeader,
-    SimpleHeader, BlockNumber
+    Work, Info, AddressValidation, MiningInfo, MinerStats, StakerStats, TemplateParam, Bytes,
+    StratumHeader, SimpleHeader, BlockNumber
 };
 use aion_types::clean_0x;
 
@@ -54,6 +54,9 @@ const STRATUM_BLKTIME_INCLUDED_COUNT: usize = 32;
 const STRATUM_RECENT_BLK_COUNT: usize = 256;
 // The maximum latest PoW blocks to cache
 const STRATUM_RECENT_POW_BLK_COUNT: usize = 128;
+// The maximum number of blocks covered by the staker stats, checking a PoS block of another
+// staker for a missed opportunity costs a call to the staking contract
+const STRATUM_STAKER_STATS_BLK_COUNT: u64 = 256;
 
 /// Stratum rpc implementation.
 pub struct StratumClient<C, S: ?Sized, M>
@@ -393,6 +396,53 @@ where
         })
     }
 
+    fn get_staker_stats(
+        &self,
+        staker: H256,
+        from: BlockNumber,
+        to: BlockNumber,
+    ) -> Result<StakerStats>
+    {
+        let from = self
+            .client
+            .block_header(from.into())
+            .ok_or_else(errors::unknown_block)?
+            .number();
+        let to = self
+            .client
+            .block_header(to.into())
+            .ok_or_else(errors::unknown_block)?
+            .number();
+        if from > to || to - from >= STRATUM_STAKER_STATS_BLK_COUNT {
+            return Err(errors::invalid_params(
+                "to",
+                format!(
+                    "block range must cover 1 to {} blocks",
+                    STRATUM_STAKER_STATS_BLK_COUNT
+                ),
+            ));
+        }
+
+        let stats = self.miner.staker_stats(&*self.client, &staker, from, to);
+        let mut stake_share = 0_f64;
+        if !stats.total_stake.is_zero() {
+            stake_share = stats.stake.as_f64() / stats.total_stake.as_f64();
+        }
+
+        Ok(StakerStats {
+            stake: stats.stake,
+            total_stake: stats.total_stake,
+            stake_share,
+            expected_block_time: stats.expected_block_time,
+            from,
+            to,
+            blocks_produced: stats.blocks_produced,
+            rewards: stats.rewards,
+            missed_opportunities: stats.missed_opportunities,
+            unchecked_blocks: stats.unchecked_blocks,
+        })
+    }
+
     /// PoS get seed
     fn pos_get_seed(&self) -> Result<H512> {
         // seal map:
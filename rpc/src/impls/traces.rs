@@ -0,0 +1,110 @@
+/*******************************************************************************
+ * Copyright (c) 2015-2018 Parity Technologies (UK) Ltd.
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Traces rpc implementation.
+
+use std::sync::Arc;
+
+use jsonrpc_core::Result;
+use jsonrpc_macros::Trailing;
+
+use acore::client::{BlockChainClient, BlockId, TransactionId};
+use aion_types::H256;
+
+use helpers::{errors, fake_sign};
+use traits::Traces;
+use types::{
+    BlockNumber, CallRequest, TraceOptions, TraceResults, TraceResultsWithTransactionHash,
+    to_call_analytics,
+};
+
+/// Traces rpc implementation.
+pub struct TracesClient<C> {
+    client: Arc<C>,
+}
+
+impl<C> TracesClient<C> {
+    /// Creates new TracesClient.
+    pub fn new(client: Arc<C>) -> Self {
+        TracesClient {
+            client,
+        }
+    }
+}
+
+impl<C> Traces for TracesClient<C>
+where C: BlockChainClient + 'static
+{
+    fn call(
+        &self,
+        request: CallRequest,
+        flags: TraceOptions,
+        block: Trailing<BlockNumber>,
+    ) -> Result<TraceResults>
+    {
+        let block = block.unwrap_or_default();
+        if block == BlockNumber::Pending {
+            return Err(errors::invalid_params("block", "Pending block can not be traced."));
+        }
+
+        let analytics = to_call_analytics(&flags)?;
+        let request = CallRequest::into(request);
+        let signed = fake_sign::sign_call(request)?;
+
+        self.client
+            .call(&signed, analytics, block.into())
+            .map(Into::into)
+            .map_err(errors::call)
+    }
+
+    fn replay_transaction(&self, hash: H256, flags: TraceOptions) -> Result<TraceResults> {
+        self.client
+            .replay(TransactionId::Hash(hash), to_call_analytics(&flags)?)
+            .map(Into::into)
+            .map_err(errors::call)
+    }
+
+    fn replay_block_transactions(
+        &self,
+        block: BlockNumber,
+        flags: TraceOptions,
+    ) -> Result<Vec<TraceResultsWithTransactionHash>>
+    {
+        if block == BlockNumber::Pending {
+            return Err(errors::invalid_params("block", "Pending block can not be traced."));
+        }
+
+        let id: BlockId = block.into();
+        let hashes = self
+            .client
+            .block(id)
+            .ok_or_else(errors::unknown_block)?
+            .transaction_hashes();
+
+        let results = self
+            .client
+            .replay_block_transactions(id, to_call_analytics(&flags)?)
+            .and_then(|results| results.collect::<::std::result::Result<Vec<_>, _>>())
+            .map_err(errors::call)?;
+        Ok(hashes.into_iter().zip(results).map(Into::into).collect())
+    }
+}
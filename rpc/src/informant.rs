@@ -239,6 +239,13 @@ impl ActivityNotifier for ClientNotifier {
     fn active(&self) {}
 }
 
+/// Light client notifier
+pub struct LightClientNotifier;
+
+impl ActivityNotifier for LightClientNotifier {
+    fn active(&self) {}
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -31,6 +31,7 @@ extern crate serde_json;
 extern crate blake2b;
 extern crate trace_time;
 
+extern crate rand;
 extern crate tokio;
 extern crate transient_hashmap;
 
@@ -0,0 +1,55 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Admin rpc interface.
+
+use jsonrpc_core::Result;
+
+use types::{NetworkTraffic, NodeInfo, PeerInfo};
+
+build_rpc_trait! {
+    /// Admin rpc interface, manages peers of the node.
+    pub trait Admin {
+        /// Returns info of active peers.
+        #[rpc(name = "admin_peers")]
+        fn peers(&self) -> Result<Vec<PeerInfo>>;
+
+        /// Returns info of the local node.
+        #[rpc(name = "admin_nodeInfo")]
+        fn node_info(&self) -> Result<NodeInfo>;
+
+        /// Connects to the node `p2p://<id>@<ip>:<port>`.
+        #[rpc(name = "admin_addPeer")]
+        fn add_peer(&self, String) -> Result<bool>;
+
+        /// Disconnects the peer with the given node id or address `<ip>:<port>`.
+        #[rpc(name = "admin_removePeer")]
+        fn remove_peer(&self, String) -> Result<bool>;
+
+        /// Bans the ip and disconnects its peers.
+        #[rpc(name = "admin_banPeer")]
+        fn ban_peer(&self, String) -> Result<bool>;
+
+        /// Returns inbound traffic and messages dropped over the traffic limits.
+        #[rpc(name = "admin_peerTraffic")]
+        fn peer_traffic(&self) -> Result<NetworkTraffic>;
+    }
+}
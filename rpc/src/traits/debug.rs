@@ -0,0 +1,41 @@
+/*******************************************************************************
+ * Copyright (c) 2015-2018 Parity Technologies (UK) Ltd.
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Debug rpc interface.
+
+use jsonrpc_core::Result;
+
+use aion_types::H256;
+use types::{BlockNumber, CallTrace};
+
+build_rpc_trait! {
+    /// Debug rpc interface.
+    pub trait Debug {
+        /// Replays the transaction and returns its call tree.
+        #[rpc(name = "debug_traceTransaction")]
+        fn trace_transaction(&self, H256) -> Result<Option<CallTrace>>;
+
+        /// Replays all transactions of the block and returns their call trees.
+        #[rpc(name = "debug_traceBlockByNumber")]
+        fn trace_block_by_number(&self, BlockNumber) -> Result<Vec<CallTrace>>;
+    }
+}
@@ -27,7 +27,7 @@ use jsonrpc_core::{Result, BoxFuture};
 use jsonrpc_macros::Trailing;
 use aion_types::{H64, H256, U256, U128, H128, Address};
 
-use types::{Block, BlockNumber, Bytes, CallRequest, Filter, FilterChanges, Index};
+use types::{AccountProof, Block, BlockNumber, Bytes, CallRequest, Filter, FilterChanges, Index};
 use types::{Log, Receipt, SyncStatus, Transaction, Work, Contract};
 
 build_rpc_trait! {
@@ -74,6 +74,10 @@ build_rpc_trait! {
         #[rpc(name = "eth_getStorageAt")]
         fn storage_at(&self, Address, U128, Trailing<BlockNumber>) -> BoxFuture<H128>;
 
+        /// Returns the account and storage values of the given account with merkle proofs.
+        #[rpc(name = "eth_getProof")]
+        fn proof(&self, Address, Vec<U128>, Trailing<BlockNumber>) -> BoxFuture<AccountProof>;
+
         /// Returns block with given hash.
         #[rpc(name = "eth_getBlockByHash")]
         fn block_by_hash(&self, H256, bool) -> BoxFuture<Option<Block>>;
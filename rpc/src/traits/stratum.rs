@@ -24,7 +24,7 @@ use jsonrpc_core::Result;
 use aion_types::{H256, H512, U256};
 use jsonrpc_macros::Trailing;
 
-use types::{Work, AddressValidation, Info, MiningInfo, MinerStats, TemplateParam, StratumHeader, BlockNumber};
+use types::{Work, AddressValidation, Info, MiningInfo, MinerStats, StakerStats, TemplateParam, StratumHeader, BlockNumber};
 
 build_rpc_trait! {
     /// Stratum rpc interface.
@@ -57,6 +57,11 @@ build_rpc_trait! {
         #[rpc(name = "getMinerStats")]
         fn get_miner_stats(&self, H256) -> Result<MinerStats>;
 
+        /// Get staker stats over a block range
+        /// staker: signing public key or coinbase of the staker
+        #[rpc(name = "getStakerStats")]
+        fn get_staker_stats(&self, H256, BlockNumber, BlockNumber) -> Result<StakerStats>;
+
         /// Get block header by number
         #[rpc(name = "getHeaderByBlockNumber")]
         fn get_block_by_number(&self, BlockNumber) -> Result<StratumHeader>;
@@ -0,0 +1,89 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Account and storage merkle proofs returned by `eth_getProof`.
+
+use aion_types::{H256, U128, U256};
+
+use types::Bytes;
+
+/// Account proof
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AccountProof {
+    /// Address of the account
+    pub address: H256,
+    /// Trie nodes from the state root to the account
+    #[serde(rename = "accountProof")]
+    pub account_proof: Vec<Bytes>,
+    /// Balance
+    pub balance: U256,
+    /// Nonce
+    pub nonce: U256,
+    /// Hash of the code
+    #[serde(rename = "codeHash")]
+    pub code_hash: H256,
+    /// Root of the storage trie
+    #[serde(rename = "storageHash")]
+    pub storage_hash: H256,
+    /// Proofs of the requested storage keys
+    #[serde(rename = "storageProof")]
+    pub storage_proof: Vec<StorageProof>,
+}
+
+/// Storage proof
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct StorageProof {
+    /// Storage key
+    pub key: U128,
+    /// Storage value, zero if absent
+    pub value: H256,
+    /// Trie nodes from the storage root to the value
+    pub proof: Vec<Bytes>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AccountProof, StorageProof};
+    use serde_json;
+    use types::Bytes;
+
+    #[test]
+    fn account_proof_serialization() {
+        let proof = AccountProof {
+            address: 1.into(),
+            account_proof: vec![Bytes::new(vec![0xc0])],
+            balance: 2.into(),
+            nonce: 3.into(),
+            code_hash: 4.into(),
+            storage_hash: 5.into(),
+            storage_proof: vec![StorageProof {
+                key: 6.into(),
+                value: 7.into(),
+                proof: vec![],
+            }],
+        };
+        let serialized = serde_json::to_string(&proof).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"address":"0x0000000000000000000000000000000000000000000000000000000000000001","accountProof":["0xc0"],"balance":"0x2","nonce":"0x3","codeHash":"0x0000000000000000000000000000000000000000000000000000000000000004","storageHash":"0x0000000000000000000000000000000000000000000000000000000000000005","storageProof":[{"key":"0x6","value":"0x0000000000000000000000000000000000000000000000000000000000000007","proof":[]}]}"#
+        );
+    }
+}
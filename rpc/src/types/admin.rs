@@ -0,0 +1,297 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Peer, local node and traffic info returned by the admin api.
+
+use acore::sync::{
+    CompressionStats as SyncCompressionStats, LocalNodeInfo, NetworkTraffic as SyncNetworkTraffic,
+    PeerInfo as SyncPeerInfo, PeerTraffic as SyncPeerTraffic, RouteTraffic as SyncRouteTraffic,
+    TrafficStats as SyncTrafficStats,
+};
+use aion_types::U256;
+
+/// Active peer info
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PeerInfo {
+    /// Node id
+    pub id: String,
+    /// Remote address
+    pub address: String,
+    /// Revision reported in handshake
+    pub revision: String,
+    /// Connection direction, inbound or outbound
+    pub direction: String,
+    /// Best block number
+    #[serde(rename = "bestBlockNumber")]
+    pub best_block_number: u64,
+    /// Total difficulty
+    #[serde(rename = "totalDifficulty")]
+    pub total_difficulty: U256,
+    /// Syncing mode
+    #[serde(rename = "syncMode")]
+    pub sync_mode: String,
+    /// Request round trip time in milliseconds, none before the first response
+    pub latency: Option<u64>,
+}
+
+impl From<SyncPeerInfo> for PeerInfo {
+    fn from(info: SyncPeerInfo) -> Self {
+        PeerInfo {
+            id: info.id,
+            address: info.addr,
+            revision: info.revision,
+            direction: info.direction,
+            best_block_number: info.best_block_number,
+            total_difficulty: info.total_difficulty,
+            sync_mode: info.mode,
+            latency: info
+                .latency
+                .map(|latency| latency.as_secs() * 1000 + latency.subsec_millis() as u64),
+        }
+    }
+}
+
+/// Local node info
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct NodeInfo {
+    /// Node id
+    pub id: String,
+    /// Node url to connect to
+    pub url: String,
+    /// Listening address
+    #[serde(rename = "listenAddress")]
+    pub listen_address: String,
+    /// Network id
+    #[serde(rename = "netId")]
+    pub net_id: u32,
+    /// Sync protocol version
+    #[serde(rename = "protocolVersion")]
+    pub protocol_version: u8,
+    /// Client version
+    pub version: String,
+}
+
+impl NodeInfo {
+    /// Creates node info from local node and protocol versions
+    pub fn new(info: LocalNodeInfo, protocol_version: u8, version: String) -> Self {
+        NodeInfo {
+            id: info.id,
+            url: info.url,
+            listen_address: info.listen_addr,
+            net_id: info.net_id,
+            protocol_version,
+            version,
+        }
+    }
+}
+
+/// Inbound traffic counters
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct TrafficStats {
+    /// Messages accepted
+    pub messages: u64,
+    /// Bytes accepted
+    pub bytes: u64,
+    /// Messages dropped over a limit
+    #[serde(rename = "droppedMessages")]
+    pub dropped_messages: u64,
+    /// Bytes dropped over a limit
+    #[serde(rename = "droppedBytes")]
+    pub dropped_bytes: u64,
+}
+
+impl From<SyncTrafficStats> for TrafficStats {
+    fn from(stats: SyncTrafficStats) -> Self {
+        TrafficStats {
+            messages: stats.messages,
+            bytes: stats.bytes,
+            dropped_messages: stats.dropped_messages,
+            dropped_bytes: stats.dropped_bytes,
+        }
+    }
+}
+
+/// Inbound traffic of a route
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct RouteTraffic {
+    /// Route module
+    pub module: u8,
+    /// Route action
+    pub action: u8,
+    /// Counters
+    pub stats: TrafficStats,
+}
+
+impl From<SyncRouteTraffic> for RouteTraffic {
+    fn from(traffic: SyncRouteTraffic) -> Self {
+        RouteTraffic {
+            module: traffic.module,
+            action: traffic.action,
+            stats: traffic.stats.into(),
+        }
+    }
+}
+
+/// Inbound traffic of a peer
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct PeerTraffic {
+    /// Node id
+    pub id: String,
+    /// Remote address
+    pub address: String,
+    /// Counters of all routes
+    pub total: TrafficStats,
+    /// Counters by route
+    pub routes: Vec<RouteTraffic>,
+}
+
+impl From<SyncPeerTraffic> for PeerTraffic {
+    fn from(traffic: SyncPeerTraffic) -> Self {
+        PeerTraffic {
+            id: traffic.id,
+            address: traffic.addr,
+            total: traffic.total.into(),
+            routes: traffic.routes.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// Payload compression counters
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct CompressionStats {
+    /// Bytes sent before compression
+    #[serde(rename = "rawBytesSent")]
+    pub raw_bytes_sent: u64,
+    /// Bytes sent after compression
+    #[serde(rename = "compressedBytesSent")]
+    pub compressed_bytes_sent: u64,
+    /// Bytes received after decompression
+    #[serde(rename = "rawBytesReceived")]
+    pub raw_bytes_received: u64,
+    /// Bytes received before decompression
+    #[serde(rename = "compressedBytesReceived")]
+    pub compressed_bytes_received: u64,
+    /// Compressed to raw size
+    pub ratio: f64,
+}
+
+impl From<SyncCompressionStats> for CompressionStats {
+    fn from(stats: SyncCompressionStats) -> Self {
+        CompressionStats {
+            raw_bytes_sent: stats.raw_bytes_sent,
+            compressed_bytes_sent: stats.compressed_bytes_sent,
+            raw_bytes_received: stats.raw_bytes_received,
+            compressed_bytes_received: stats.compressed_bytes_received,
+            ratio: stats.ratio(),
+        }
+    }
+}
+
+/// Inbound traffic of the node
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct NetworkTraffic {
+    /// Counters of all peers since start
+    pub total: TrafficStats,
+    /// Connected peers
+    pub peers: Vec<PeerTraffic>,
+    /// Payload compression of all peers since start
+    pub compression: CompressionStats,
+}
+
+impl From<SyncNetworkTraffic> for NetworkTraffic {
+    fn from(traffic: SyncNetworkTraffic) -> Self {
+        NetworkTraffic {
+            total: traffic.total.into(),
+            peers: traffic.peers.into_iter().map(Into::into).collect(),
+            compression: traffic.compression.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use acore::sync::{
+        CompressionStats, NetworkTraffic as SyncNetworkTraffic, PeerInfo as SyncPeerInfo,
+        PeerTraffic as SyncPeerTraffic, RouteTraffic as SyncRouteTraffic, TrafficStats,
+    };
+    use serde_json;
+    use super::{NetworkTraffic, PeerInfo};
+
+    #[test]
+    fn peer_info_serialization() {
+        let info: PeerInfo = SyncPeerInfo {
+            id: "c33d1066-8c7e-496c-9c4e-c89318280274".into(),
+            addr: "1.2.3.4:30303".into(),
+            revision: "r-1.0.0".into(),
+            direction: "outbound".into(),
+            best_block_number: 10,
+            total_difficulty: 255.into(),
+            mode: "NORMAL".into(),
+            latency: Some(Duration::from_millis(1500)),
+        }
+        .into();
+        let serialized = serde_json::to_string(&info).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"id":"c33d1066-8c7e-496c-9c4e-c89318280274","address":"1.2.3.4:30303","revision":"r-1.0.0","direction":"outbound","bestBlockNumber":10,"totalDifficulty":"0xff","syncMode":"NORMAL","latency":1500}"#
+        );
+    }
+
+    #[test]
+    fn network_traffic_serialization() {
+        let stats = TrafficStats {
+            messages: 3,
+            bytes: 300,
+            dropped_messages: 1,
+            dropped_bytes: 100,
+        };
+        let traffic: NetworkTraffic = SyncNetworkTraffic {
+            total: stats.clone(),
+            peers: vec![SyncPeerTraffic {
+                id: "c33d1066-8c7e-496c-9c4e-c89318280274".into(),
+                addr: "1.2.3.4:30303".into(),
+                total: stats.clone(),
+                routes: vec![SyncRouteTraffic {
+                    module: 1,
+                    action: 6,
+                    stats,
+                }],
+            }],
+            compression: CompressionStats {
+                raw_bytes_sent: 1000,
+                compressed_bytes_sent: 300,
+                raw_bytes_received: 1000,
+                compressed_bytes_received: 100,
+            },
+        }
+        .into();
+        let serialized = serde_json::to_string(&traffic).unwrap();
+        let stats = r#"{"messages":3,"bytes":300,"droppedMessages":1,"droppedBytes":100}"#;
+        assert_eq!(
+            serialized,
+            format!(
+                r#"{{"total":{0},"peers":[{{"id":"c33d1066-8c7e-496c-9c4e-c89318280274","address":"1.2.3.4:30303","total":{0},"routes":[{{"module":1,"action":6,"stats":{0}}}]}}],"compression":{{"rawBytesSent":1000,"compressedBytesSent":300,"rawBytesReceived":1000,"compressedBytesReceived":100,"ratio":0.2}}}}"#,
+                stats
+            )
+        );
+    }
+}
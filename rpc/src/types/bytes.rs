@@ -28,7 +28,7 @@ use serde::{Serialize, Serializer, Deserialize, Deserializer};
 use serde::de::{Error, Visitor};
 
 /// Wrapper structure around vector of bytes.
-#[derive(Debug, PartialEq, Eq, Default, Hash, Clone)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Default, Hash, Clone)]
 pub struct Bytes(pub Vec<u8>);
 
 impl Bytes {
@@ -59,7 +59,7 @@ impl Serialize for Log {
         S: Serializer,
     {
         let mut log = serializer.serialize_struct("Log", 9)?;
-        log.serialize_field("removed", &false)?;
+        log.serialize_field("removed", &(self.log_type == "removed"))?;
         log.serialize_field("logIndex", &self.log_index)?;
         log.serialize_field("transactionIndex", &self.transaction_index)?;
         log.serialize_field("transactionHash", &self.transaction_hash)?;
@@ -116,3 +116,52 @@ impl Serialize for MinerStats {
         stats.end()
     }
 }
+
+// Staker stats
+#[derive(Debug, PartialEq, Serialize)]
+pub struct StakerStats {
+    pub stake: U256,
+    #[serde(rename = "totalStake")]
+    pub total_stake: U256,
+    #[serde(rename = "stakeShare")]
+    pub stake_share: f64,
+    #[serde(rename = "expectedBlockTime")]
+    pub expected_block_time: Option<u64>,
+    pub from: u64,
+    pub to: u64,
+    #[serde(rename = "blocksProduced")]
+    pub blocks_produced: u64,
+    pub rewards: U256,
+    #[serde(rename = "missedOpportunities")]
+    pub missed_opportunities: u64,
+    #[serde(rename = "uncheckedBlocks")]
+    pub unchecked_blocks: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+    use aion_types::U256;
+    use super::StakerStats;
+
+    #[test]
+    fn test_serialize_staker_stats() {
+        let stats = StakerStats {
+            stake: U256::from(1000),
+            total_stake: U256::from(4000),
+            stake_share: 0.25,
+            expected_block_time: Some(40),
+            from: 100,
+            to: 200,
+            blocks_produced: 3,
+            rewards: U256::from(4500),
+            missed_opportunities: 1,
+            unchecked_blocks: 2,
+        };
+        let serialized = serde_json::to_string(&stats).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"stake":"0x3e8","totalStake":"0xfa0","stakeShare":0.25,"expectedBlockTime":40,"from":100,"to":200,"blocksProduced":3,"rewards":"0x1194","missedOpportunities":1,"uncheckedBlocks":2}"#
+        );
+    }
+}
@@ -22,6 +22,8 @@
 
 //! RPC types
 
+mod account_proof;
+mod admin;
 mod stratum_header;
 mod template_param;
 mod block;
@@ -44,7 +46,13 @@ mod transaction;
 mod transaction_request;
 mod transaction_condition;
 mod mining;
+mod trace;
+mod state_diff;
 
+pub mod pubsub;
+
+pub use self::account_proof::{AccountProof, StorageProof};
+pub use self::admin::{NetworkTraffic, NodeInfo, PeerInfo};
 pub use self::bytes::Bytes;
 pub use self::block::{Block, BlockTransactions, Header};
 pub use self::block_number::BlockNumber;
@@ -72,4 +80,8 @@ SyncStatus, SyncInfo, /* Peers, PeerInfo, PeerNetworkInfo, TransactionStats, Cha
 pub use self::transaction::{Transaction, RichRawTransaction};
 pub use self::transaction_request::TransactionRequest;
 pub use self::transaction_condition::TransactionCondition;
-pub use self::mining::{Work, Info, AddressValidation, MiningInfo, MinerStats};
+pub use self::mining::{Work, Info, AddressValidation, MiningInfo, MinerStats, StakerStats};
+pub use self::trace::{
+    CallTrace, TraceOptions, TraceResults, TraceResultsWithTransactionHash, to_call_analytics,
+};
+pub use self::state_diff::StateDiff;
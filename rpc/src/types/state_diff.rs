@@ -0,0 +1,184 @@
+/*******************************************************************************
+ * Copyright (c) 2015-2018 Parity Technologies (UK) Ltd.
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! State diff serialization.
+
+use std::collections::BTreeMap;
+
+use acore::account_diff;
+use acore::state_diff;
+use aion_types::{U256, Address};
+use serde::{Serialize, Serializer};
+use serde::ser::SerializeMap;
+
+use types::Bytes;
+
+/// Serde-friendly `Diff` shadow.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Diff<T>
+where T: Serialize + Eq
+{
+    /// No change
+    Same,
+    /// Value was created
+    Born(T),
+    /// Value was removed
+    Died(T),
+    /// Value changed
+    Changed(ChangedType<T>),
+}
+
+impl<T, U> From<account_diff::Diff<T>> for Diff<U>
+where
+    T: Eq,
+    U: Serialize + Eq + From<T>,
+{
+    fn from(c: account_diff::Diff<T>) -> Self {
+        match c {
+            account_diff::Diff::Same => Diff::Same,
+            account_diff::Diff::Born(t) => Diff::Born(t.into()),
+            account_diff::Diff::Died(t) => Diff::Died(t.into()),
+            account_diff::Diff::Changed(t, u) => {
+                Diff::Changed(ChangedType {
+                    from: t.into(),
+                    to: u.into(),
+                })
+            }
+        }
+    }
+}
+
+impl<T> Serialize for Diff<T>
+where T: Serialize + Eq
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        match *self {
+            Diff::Same => serializer.serialize_str("="),
+            Diff::Born(ref t) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("+", t)?;
+                map.end()
+            }
+            Diff::Died(ref t) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("-", t)?;
+                map.end()
+            }
+            Diff::Changed(ref t) => {
+                let mut map = serializer.serialize_map(Some(1))?;
+                map.serialize_entry("*", t)?;
+                map.end()
+            }
+        }
+    }
+}
+
+/// Value before and after a change.
+#[derive(Debug, Serialize, PartialEq, Eq, Clone)]
+pub struct ChangedType<T>
+where T: Serialize + Eq
+{
+    /// Previous value
+    pub from: T,
+    /// New value
+    pub to: T,
+}
+
+/// Changes of a single account.
+#[derive(Debug, Serialize, PartialEq, Eq, Clone)]
+pub struct AccountDiff {
+    /// Balance change
+    pub balance: Diff<U256>,
+    /// Nonce change
+    pub nonce: Diff<U256>,
+    /// Code change
+    pub code: Diff<Bytes>,
+    /// Storage changes, by key
+    pub storage: BTreeMap<Bytes, Diff<Bytes>>,
+}
+
+impl From<account_diff::AccountDiff> for AccountDiff {
+    fn from(c: account_diff::AccountDiff) -> Self {
+        AccountDiff {
+            balance: c.balance.into(),
+            nonce: c.nonce.into(),
+            code: c.code.into(),
+            storage: c
+                .storage
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        }
+    }
+}
+
+/// Changes of all accounts touched by the execution.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct StateDiff(BTreeMap<Address, AccountDiff>);
+
+impl Serialize for StateDiff {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        Serialize::serialize(&self.0, serializer)
+    }
+}
+
+impl From<state_diff::StateDiff> for StateDiff {
+    fn from(c: state_diff::StateDiff) -> Self {
+        StateDiff(c.raw.into_iter().map(|(k, v)| (k, v.into())).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use serde_json;
+    use acore::account_diff::{self, AccountDiff as EthAccountDiff};
+    use acore::state_diff::StateDiff as EthStateDiff;
+    use super::StateDiff;
+
+    #[test]
+    fn state_diff_serialization() {
+        let mut storage = BTreeMap::new();
+        storage.insert(vec![0x01], account_diff::Diff::Born(vec![0x02]));
+        let mut raw = BTreeMap::new();
+        raw.insert(
+            1.into(),
+            EthAccountDiff {
+                balance: account_diff::Diff::Changed(10.into(), 5.into()),
+                nonce: account_diff::Diff::Same,
+                code: account_diff::Diff::Died(vec![0x60]),
+                storage,
+            },
+        );
+
+        let diff: StateDiff = EthStateDiff {
+            raw,
+        }
+        .into();
+        assert_eq!(
+            serde_json::to_string(&diff).unwrap(),
+            r#"{"0x0000000000000000000000000000000000000000000000000000000000000001":{"balance":{"*":{"from":"0xa","to":"0x5"}},"nonce":"=","code":{"-":"0x60"},"storage":{"0x01":{"+":"0x02"}}}}"#
+        );
+    }
+}
@@ -25,7 +25,7 @@ use serde::{Serialize, Serializer};
 //use aion_types::{U256, H512};
 
 /// Sync info
-#[derive(Default, Debug, Serialize, PartialEq)]
+#[derive(Default, Debug, Clone, Serialize, PartialEq)]
 pub struct SyncInfo {
     /// Starting block, hex representation
     #[serde(rename = "startingBlock")]
@@ -76,7 +76,7 @@ pub struct SyncInfo {
 //}
 
 /// Sync status
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum SyncStatus {
     /// Info when syncing
     Info(SyncInfo),
@@ -0,0 +1,221 @@
+/*******************************************************************************
+ * Copyright (c) 2015-2018 Parity Technologies (UK) Ltd.
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Call tree trace.
+
+use acore::client::CallAnalytics;
+use acore::executed::Executed;
+use acore::trace::{CallTrace as EthCallTrace, TraceCallType};
+use aion_types::{H256, U256, Address};
+use jsonrpc_core::Result;
+
+use helpers::errors;
+use types::{Bytes, StateDiff};
+
+/// Requested outputs of a traced execution: any of "trace" and "stateDiff". VM traces
+/// ("vmTrace") are not supported.
+pub type TraceOptions = Vec<String>;
+
+/// Converts requested trace outputs into call analytics, rejecting unsupported outputs.
+pub fn to_call_analytics(flags: &TraceOptions) -> Result<CallAnalytics> {
+    if let Some(flag) = flags.iter().find(|f| *f != "trace" && *f != "stateDiff") {
+        return Err(errors::invalid_params(
+            "flags",
+            format!("Unsupported trace output {}, expected trace or stateDiff", flag),
+        ));
+    }
+    Ok(CallAnalytics {
+        transaction_tracing: flags.iter().any(|f| f == "trace"),
+        vm_tracing: false,
+        state_diffing: flags.iter().any(|f| f == "stateDiff"),
+    })
+}
+
+/// A traced call frame.
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct CallTrace {
+    /// Call type: CALL, CALLCODE, DELEGATECALL, STATICCALL or CREATE
+    #[serde(rename = "type")]
+    pub call_type: String,
+    /// Caller
+    pub from: Address,
+    /// Callee, or the created contract
+    pub to: Address,
+    /// Transferred value
+    pub value: U256,
+    /// Gas given to the call
+    pub gas: U256,
+    /// Gas used by the call
+    #[serde(rename = "gasUsed")]
+    pub gas_used: U256,
+    /// Call data, or init code
+    pub input: Bytes,
+    /// Return data
+    pub output: Bytes,
+    /// Whether a precompiled contract was called
+    pub builtin: bool,
+    /// Error, if the call failed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Revert reason, if any
+    #[serde(rename = "revertReason", skip_serializing_if = "Option::is_none")]
+    pub revert_reason: Option<String>,
+    /// Nested calls
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub calls: Vec<CallTrace>,
+}
+
+impl From<EthCallTrace> for CallTrace {
+    fn from(t: EthCallTrace) -> Self {
+        let call_type = match t.call_type {
+            TraceCallType::Create => "CREATE",
+            TraceCallType::Call => "CALL",
+            TraceCallType::CallCode => "CALLCODE",
+            TraceCallType::DelegateCall => "DELEGATECALL",
+            TraceCallType::StaticCall => "STATICCALL",
+        };
+        CallTrace {
+            call_type: call_type.into(),
+            from: t.from,
+            to: t.to,
+            value: t.value,
+            gas: t.gas,
+            gas_used: t.gas_used,
+            input: t.input.into(),
+            output: t.output.into(),
+            builtin: t.builtin,
+            error: t.error,
+            revert_reason: t.revert_reason,
+            calls: t.calls.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+/// Outputs of a traced execution.
+#[derive(Debug, Serialize)]
+pub struct TraceResults {
+    /// Return data
+    pub output: Bytes,
+    /// Call tree, if requested
+    pub trace: Option<CallTrace>,
+    /// State changes, if requested
+    #[serde(rename = "stateDiff")]
+    pub state_diff: Option<StateDiff>,
+}
+
+impl From<Executed> for TraceResults {
+    fn from(t: Executed) -> Self {
+        TraceResults {
+            output: t.output.into(),
+            trace: t.trace.map(Into::into),
+            state_diff: t.state_diff.map(Into::into),
+        }
+    }
+}
+
+/// Outputs of a traced execution of a mined transaction.
+#[derive(Debug, Serialize)]
+pub struct TraceResultsWithTransactionHash {
+    /// Return data
+    pub output: Bytes,
+    /// Call tree, if requested
+    pub trace: Option<CallTrace>,
+    /// State changes, if requested
+    #[serde(rename = "stateDiff")]
+    pub state_diff: Option<StateDiff>,
+    /// Transaction hash
+    #[serde(rename = "transactionHash")]
+    pub transaction_hash: H256,
+}
+
+impl From<(H256, Executed)> for TraceResultsWithTransactionHash {
+    fn from(t: (H256, Executed)) -> Self {
+        TraceResultsWithTransactionHash {
+            output: t.1.output.into(),
+            trace: t.1.trace.map(Into::into),
+            state_diff: t.1.state_diff.map(Into::into),
+            transaction_hash: t.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json;
+    use acore::trace::{CallTrace as EthCallTrace, TraceCallType};
+    use super::{CallTrace, to_call_analytics};
+
+    #[test]
+    fn trace_options_to_analytics() {
+        let analytics = to_call_analytics(&vec!["stateDiff".to_owned()]).unwrap();
+        assert!(analytics.state_diffing);
+        assert!(!analytics.transaction_tracing);
+        assert!(!analytics.vm_tracing);
+
+        let analytics = to_call_analytics(&vec!["trace".to_owned()]).unwrap();
+        assert!(!analytics.state_diffing);
+        assert!(analytics.transaction_tracing);
+        assert!(!analytics.vm_tracing);
+
+        // no executor produces VM traces
+        assert!(to_call_analytics(&vec!["trace".to_owned(), "vmTrace".to_owned()]).is_err());
+        assert!(to_call_analytics(&vec!["unknown".to_owned()]).is_err());
+    }
+
+    #[test]
+    fn trace_serialization() {
+        let inner = EthCallTrace {
+            call_type: TraceCallType::StaticCall,
+            from: 1.into(),
+            to: 2.into(),
+            value: 0.into(),
+            gas: 100.into(),
+            gas_used: 100.into(),
+            input: vec![],
+            output: vec![],
+            builtin: true,
+            error: Some("OutOfGas".into()),
+            revert_reason: None,
+            calls: vec![],
+        };
+        let outer = EthCallTrace {
+            call_type: TraceCallType::Call,
+            from: 0.into(),
+            to: 1.into(),
+            value: 5.into(),
+            gas: 1000.into(),
+            gas_used: 300.into(),
+            input: vec![0x12],
+            output: vec![0x34],
+            builtin: false,
+            error: None,
+            revert_reason: None,
+            calls: vec![inner],
+        };
+
+        let serialized = serde_json::to_string(&CallTrace::from(outer)).unwrap();
+        assert_eq!(
+            serialized,
+            r#"{"type":"CALL","from":"0x0000000000000000000000000000000000000000000000000000000000000000","to":"0x0000000000000000000000000000000000000000000000000000000000000001","value":"0x5","gas":"0x3e8","gasUsed":"0x12c","input":"0x12","output":"0x34","builtin":false,"calls":[{"type":"STATICCALL","from":"0x0000000000000000000000000000000000000000000000000000000000000001","to":"0x0000000000000000000000000000000000000000000000000000000000000002","value":"0x0","gas":"0x64","gasUsed":"0x64","input":"0x","output":"0x","builtin":true,"error":"OutOfGas"}]}"#
+        );
+    }
+}
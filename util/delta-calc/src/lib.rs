@@ -72,6 +72,15 @@ pub fn calculate_delta(difficulty: U256, seed: &[u8], stake: BigUint) -> u64 {
     ::std::cmp::max(1u64, delta.to_u64().unwrap_or(1000000000u64))
 }
 
+/// Expected delta of a staker, the mean of `calculate_delta` over all seeds
+///
+/// `ln(2^256) - ln(hash(seed))` is exponentially distributed with mean 1 for uniformly
+/// distributed seed hashes, hence `expected delta = difficulty / stake`. `stake` must not be 0.
+pub fn expected_delta(difficulty: U256, stake: BigUint) -> u64 {
+    let delta: BigUint = BigUint::from(difficulty) / stake;
+    ::std::cmp::max(1u64, delta.to_u64().unwrap_or(1000000000u64))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -115,4 +124,22 @@ mod test {
             (10000f64 * ln_sub / 333f64) as u64
         );
     }
+
+    #[test]
+    fn test_expected_delta() {
+        let difficulty = U256::from(1000000u64);
+        let stake = BigUint::from(10u64);
+        assert_eq!(expected_delta(difficulty, stake.clone()), 100000);
+
+        let samples = 1000u64;
+        let mut total = 0u64;
+        for i in 0..samples {
+            let mut seed = [0u8; 64];
+            seed[0] = (i % 256) as u8;
+            seed[1] = (i / 256) as u8;
+            total += calculate_delta(difficulty, &seed, stake.clone());
+        }
+        let mean = total / samples;
+        assert!(mean > 80000 && mean < 120000);
+    }
 }
@@ -150,6 +150,9 @@ impl DatabaseDirectories {
             .join("db")
     }
 
+    /// Light client path
+    pub fn light_client_path(&self) -> PathBuf { self.db_root_path().join("light").join("db") }
+
     /// DB root path, named after genesis hash
     pub fn db_root_path(&self) -> PathBuf {
         self.spec_root_path()
@@ -25,6 +25,10 @@ extern crate blake2b;
 #[macro_use]
 extern crate log;
 
+mod solver;
+
+pub use solver::EquihashSolver;
+
 use std::collections::HashSet;
 use bytes::bytes_to_i32s;
 use bytes::i32_to_bytes;
@@ -61,6 +65,15 @@ fn extend_array(input: &[u8], output: &mut [u8], bit_len: i32, byte_pad: i32) {
     }
 }
 
+/// Blake2b personalization shared by the solver and the validator.
+fn personalization(n: i32, k: i32) -> Vec<u8> {
+    let mut personalization: Vec<u8> = Vec::with_capacity(16);
+    personalization.extend_from_slice("AION0PoW".as_bytes());
+    personalization.extend_from_slice(&i32_to_bytes_le(n));
+    personalization.extend_from_slice(&i32_to_bytes_le(k));
+    personalization
+}
+
 pub struct EquihashValidator {
     n: i32,
     k: i32,
@@ -104,10 +117,7 @@ impl EquihashValidator {
             return false;
         }
 
-        let mut personalization: Vec<u8> = Vec::with_capacity(16);
-        personalization.extend_from_slice("AION0PoW".as_bytes());
-        personalization.extend_from_slice(&i32_to_bytes_le(self.n));
-        personalization.extend_from_slice(&i32_to_bytes_le(self.k));
+        let personalization = personalization(self.n, self.k);
         let native_hash = self.get_solution_hash(
             &personalization.as_slice(),
             nonce,
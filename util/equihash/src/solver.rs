@@ -0,0 +1,235 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! CPU Equihash solver.
+//!
+//! A straightforward implementation of Wagner's algorithm producing solutions in the minimal
+//! encoding accepted by `EquihashValidator`. Every round keeps the back references of the
+//! collided pairs, so a (210, 9) solve needs around half a gigabyte of memory.
+
+use std::collections::HashSet;
+use blake2b::Blake2b;
+use bytes::i32_to_bytes;
+
+use personalization;
+
+/// Number of hashes taken out of one blake2b output.
+const HASHES_PER_BLAKE: usize = 2;
+/// Length of one blake2b output in bytes.
+const BLAKE_OUTPUT_LENGTH: usize = 54;
+
+/// Equihash solver for the given (n, k) parameters.
+pub struct EquihashSolver {
+    n: usize,
+    k: usize,
+    collision_bit_length: usize,
+    hash_length: usize,
+    param: [u8; 64],
+}
+
+impl EquihashSolver {
+    pub fn new(n: i32, k: i32) -> EquihashSolver {
+        let mut param = [0u8; 64];
+        param[0] = BLAKE_OUTPUT_LENGTH as u8;
+        param[2] = 1;
+        param[3] = 1;
+        param[48..64].copy_from_slice(&personalization(n, k)[..16]);
+        EquihashSolver {
+            n: n as usize,
+            k: k as usize,
+            collision_bit_length: (n / (k + 1)) as usize,
+            hash_length: ((n + 7) / 8) as usize,
+            param,
+        }
+    }
+
+    /// Find all solutions for the given header and nonce. Returns the solutions in minimal
+    /// encoding. `cancelled` is polled between rounds; once it returns true the solver gives up
+    /// and returns no solution.
+    pub fn solve<F>(&self, header: &[u8], nonce: &[u8], cancelled: F) -> Vec<Vec<u8>>
+    where F: Fn() -> bool {
+        let leaves = 1usize << (self.collision_bit_length + 1);
+        let max_entries = 2 * leaves;
+        let mut hashes = self.initial_hashes(header, nonce, leaves);
+        let mut refs: Vec<Vec<(u32, u32)>> = Vec::with_capacity(self.k);
+        let mut solutions = Vec::new();
+
+        for round in 1..(self.k + 1) {
+            if cancelled() {
+                return Vec::new();
+            }
+            let last = round == self.k;
+            let (start, length) = if last {
+                let start = (self.k - 1) * self.collision_bit_length;
+                (start, self.n - start)
+            } else {
+                ((round - 1) * self.collision_bit_length, self.collision_bit_length)
+            };
+
+            let count = hashes.len() / self.hash_length;
+            let mut sorted: Vec<(u64, u32)> = (0..count)
+                .map(|i| (self.bits(self.hash(&hashes, i), start, length), i as u32))
+                .collect();
+            sorted.sort_unstable();
+
+            let mut next_hashes = Vec::new();
+            let mut next_refs = Vec::new();
+            let mut group_start = 0;
+            while group_start < sorted.len() {
+                let mut group_end = group_start + 1;
+                while group_end < sorted.len() && sorted[group_end].0 == sorted[group_start].0 {
+                    group_end += 1;
+                }
+                let group = &sorted[group_start..group_end];
+                for (a, &(_, i)) in group.iter().enumerate() {
+                    for &(_, j) in &group[(a + 1)..] {
+                        if round > 1 && self.share_children(&refs[round - 2], i, j) {
+                            continue;
+                        }
+                        if last {
+                            if let Some(solution) = self.solution(&refs, i, j) {
+                                solutions.push(solution);
+                            }
+                            continue;
+                        }
+                        if next_refs.len() >= max_entries {
+                            continue;
+                        }
+                        let first = self.hash(&hashes, i as usize);
+                        let second = self.hash(&hashes, j as usize);
+                        if first == second {
+                            // identical hashes only lead to solutions with duplicate indices
+                            continue;
+                        }
+                        next_hashes.extend(first.iter().zip(second.iter()).map(|(x, y)| x ^ y));
+                        next_refs.push((i, j));
+                    }
+                }
+                group_start = group_end;
+            }
+
+            if !last {
+                hashes = next_hashes;
+                refs.push(next_refs);
+            }
+        }
+
+        solutions.sort();
+        solutions.dedup();
+        solutions
+    }
+
+    /// Generate the hashes of all the leaves, using the same layout as the validator.
+    fn initial_hashes(&self, header: &[u8], nonce: &[u8], leaves: usize) -> Vec<u8> {
+        let mut hashes = Vec::with_capacity(leaves * self.hash_length);
+        let mut blakehash = [0u8; BLAKE_OUTPUT_LENGTH];
+        for g in 0..(leaves / HASHES_PER_BLAKE) {
+            let mut blake2b = Blake2b::with_params(&self.param);
+            blake2b.update(header);
+            blake2b.update(nonce);
+            let leb: i32 = (g as i32).to_le();
+            blake2b.update(&i32_to_bytes(leb));
+            blake2b.finalize(&mut blakehash);
+            for h in 0..HASHES_PER_BLAKE {
+                let s = h * self.hash_length;
+                hashes.extend_from_slice(&blakehash[s..s + self.hash_length]);
+            }
+        }
+        hashes
+    }
+
+    fn hash<'a>(&self, hashes: &'a [u8], i: usize) -> &'a [u8] {
+        &hashes[i * self.hash_length..(i + 1) * self.hash_length]
+    }
+
+    /// Read `length` (at most 57) big-endian bits of `hash` starting at bit `start`.
+    fn bits(&self, hash: &[u8], start: usize, length: usize) -> u64 {
+        let first = start / 8;
+        let last = (start + length + 7) / 8;
+        let mut acc = 0u64;
+        for byte in &hash[first..last] {
+            acc = (acc << 8) | u64::from(*byte);
+        }
+        (acc >> (last * 8 - start - length)) & ((1u64 << length) - 1)
+    }
+
+    /// Cheap check for pairs built on a common entry of the previous round.
+    fn share_children(&self, refs: &[(u32, u32)], i: u32, j: u32) -> bool {
+        let (a, b) = refs[i as usize];
+        let (c, d) = refs[j as usize];
+        a == c || a == d || b == c || b == d
+    }
+
+    /// Expand the tree below entry `i` of the given round into ordered leaf indices.
+    fn indices(&self, refs: &[Vec<(u32, u32)>], round: usize, i: u32) -> Vec<u32> {
+        if round == 0 {
+            return vec![i];
+        }
+        let (a, b) = refs[round - 1][i as usize];
+        let left = self.indices(refs, round - 1, a);
+        let right = self.indices(refs, round - 1, b);
+        join(left, right)
+    }
+
+    /// Build the minimal encoded solution from the final collision of entries `i` and `j`,
+    /// or `None` if the indices are not distinct.
+    fn solution(&self, refs: &[Vec<(u32, u32)>], i: u32, j: u32) -> Option<Vec<u8>> {
+        let left = self.indices(refs, self.k - 1, i);
+        let right = self.indices(refs, self.k - 1, j);
+        let indices = join(left, right);
+        let distinct: HashSet<u32> = indices.iter().cloned().collect();
+        if distinct.len() != indices.len() {
+            return None;
+        }
+        Some(minimal(&indices, self.collision_bit_length + 1))
+    }
+}
+
+/// Concatenate two subtrees, the one with the smaller first index going first.
+fn join(left: Vec<u32>, right: Vec<u32>) -> Vec<u32> {
+    let (mut first, second) = if right[0] < left[0] {
+        (right, left)
+    } else {
+        (left, right)
+    };
+    first.extend(second);
+    first
+}
+
+/// Pack the indices as big-endian `bit_length` wide integers.
+fn minimal(indices: &[u32], bit_length: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity((indices.len() * bit_length + 7) / 8);
+    let mut acc = 0u64;
+    let mut acc_bits = 0;
+    for index in indices {
+        acc = (acc << bit_length) | u64::from(*index);
+        acc_bits += bit_length;
+        while acc_bits >= 8 {
+            acc_bits -= 8;
+            out.push((acc >> acc_bits) as u8);
+        }
+        acc &= (1u64 << acc_bits) - 1;
+    }
+    if acc_bits > 0 {
+        out.push((acc << (8 - acc_bits)) as u8);
+    }
+    out
+}
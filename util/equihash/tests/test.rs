@@ -2,7 +2,7 @@ extern crate equihash;
 extern crate rustc_hex as hex;
 
 use hex::FromHex;
-use equihash::EquihashValidator;
+use equihash::{EquihashSolver, EquihashValidator};
 
 #[test]
 fn test_has_duplicate_true() {
@@ -261,3 +261,38 @@ fn test_is_valid_solution_solution_invalid_nonce() {
     let result = validator.is_valid_solution(&minimal, &header, &nonce);
     assert_eq!(result, false);
 }
+
+#[test]
+fn test_solver_solutions_are_valid() {
+    let solver = EquihashSolver::new(96, 5);
+    let validator = EquihashValidator::new(96, 5);
+    let header = [7u8; 32];
+    let mut found = 0;
+    for i in 0..4u8 {
+        let nonce = [i; 32];
+        for solution in solver.solve(&header, &nonce, || false) {
+            assert_eq!(solution.len(), 68);
+            assert!(validator.is_valid_solution(&solution, &header, &nonce));
+            found += 1;
+        }
+    }
+    assert!(found > 0);
+}
+
+#[test]
+fn test_solver_solution_rejected_for_other_nonce() {
+    let solver = EquihashSolver::new(96, 5);
+    let validator = EquihashValidator::new(96, 5);
+    let header = [7u8; 32];
+    let nonce = [0u8; 32];
+    let solutions = solver.solve(&header, &nonce, || false);
+    assert!(!solutions.is_empty());
+    assert!(!validator.is_valid_solution(&solutions[0], &header, &[1u8; 32]));
+}
+
+#[test]
+fn test_solver_cancelled() {
+    let solver = EquihashSolver::new(96, 5);
+    let solutions = solver.solve(&[7u8; 32], &[0u8; 32], || true);
+    assert!(solutions.is_empty());
+}
@@ -43,6 +43,7 @@ pub mod triedbmut;
 pub mod sectriedb;
 pub mod sectriedbmut;
 pub mod recorder;
+pub mod proof;
 
 mod fatdb;
 mod fatdbmut;
@@ -57,6 +58,7 @@ pub use self::sectriedb::SecTrieDB;
 pub use self::fatdb::{FatDB, FatDBIterator};
 pub use self::fatdbmut::FatDBMut;
 pub use self::recorder::Recorder;
+pub use self::proof::{verify_proof, verify_secure_proof};
 
 /// Trie Errors.
 ///
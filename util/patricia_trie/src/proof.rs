@@ -0,0 +1,118 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Verification of merkle proofs recorded with `Recorder`.
+
+use aion_types::H256;
+use blake2b::blake2b;
+use db::{DBValue, HashStore, MemoryDB};
+
+use super::{Result, Trie, TrieDB};
+
+/// Check a merkle proof of `key` against the trie `root`. `proof` holds the trie nodes
+/// visited by a lookup of `key`, in any order.
+///
+/// Returns the value of `key`, or `None` if the proof shows the key is absent. Fails with
+/// `InvalidStateRoot` or `IncompleteDatabase` if a node on the path is missing.
+pub fn verify_proof<T: AsRef<[u8]>>(
+    root: &H256,
+    proof: &[T],
+    key: &[u8],
+) -> Result<Option<DBValue>>
+{
+    let mut db = MemoryDB::new();
+    for node in proof {
+        db.insert(node.as_ref());
+    }
+    let trie = TrieDB::new(&db, root)?;
+    trie.get(key)
+}
+
+/// Check a merkle proof of `key` in a secure trie, whose keys are hashed with blake2b.
+pub fn verify_secure_proof<T: AsRef<[u8]>>(
+    root: &H256,
+    proof: &[T],
+    key: &[u8],
+) -> Result<Option<DBValue>>
+{
+    verify_proof(root, proof, &blake2b(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aion_types::H256;
+    use db::MemoryDB;
+    use {Recorder, SecTrieDB, SecTrieDBMut, TrieError, TrieMut};
+
+    fn populated() -> (MemoryDB, H256) {
+        let mut db = MemoryDB::new();
+        let mut root = H256::default();
+        {
+            let mut t = SecTrieDBMut::new(&mut db, &mut root);
+            t.insert(b"dog", b"cat").unwrap();
+            t.insert(b"lunch", b"time").unwrap();
+            t.insert(b"notdog", b"notcat").unwrap();
+            t.insert(b"hotdog", b"hotcat").unwrap();
+        }
+        (db, root)
+    }
+
+    fn prove(db: &MemoryDB, root: &H256, key: &[u8]) -> Vec<Vec<u8>> {
+        let trie = SecTrieDB::new(db, root).unwrap();
+        let mut recorder = Recorder::new();
+        trie.get_with(key, &mut recorder).unwrap();
+        recorder.drain().into_iter().map(|r| r.data).collect()
+    }
+
+    #[test]
+    fn present_key() {
+        let (db, root) = populated();
+        let proof = prove(&db, &root, b"hotdog");
+        let value = verify_secure_proof(&root, &proof, b"hotdog").unwrap();
+        assert_eq!(value, Some(DBValue::from_slice(b"hotcat")));
+    }
+
+    #[test]
+    fn absent_key() {
+        let (db, root) = populated();
+        let proof = prove(&db, &root, b"cat");
+        assert_eq!(verify_secure_proof(&root, &proof, b"cat").unwrap(), None);
+    }
+
+    #[test]
+    fn incomplete_proof() {
+        let (db, root) = populated();
+        let proof = prove(&db, &root, b"lunch");
+        match verify_secure_proof(&root, &proof[1..], b"lunch") {
+            Err(e) => assert_eq!(*e, TrieError::InvalidStateRoot(root)),
+            Ok(v) => panic!("unexpected value {:?}", v),
+        }
+    }
+
+    #[test]
+    fn wrong_root() {
+        let (db, root) = populated();
+        let proof = prove(&db, &root, b"dog");
+        let other = blake2b(b"other root");
+        assert!(verify_secure_proof(&other, &proof, b"dog").is_err());
+    }
+}
@@ -0,0 +1,63 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Selection of the engine that runs FastVM bytecode.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Engine used by `FastVMFactory` to execute FVM bytecode.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum FastVMBackend {
+    /// LLVM based JIT from the native `fastvm` library, called over ffi.
+    Native,
+    /// Pure Rust interpreter, see `interpreter` module.
+    Interpreter,
+}
+
+impl Default for FastVMBackend {
+    fn default() -> Self { FastVMBackend::Native }
+}
+
+impl fmt::Display for FastVMBackend {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match *self {
+                FastVMBackend::Native => "native",
+                FastVMBackend::Interpreter => "interpreter",
+            }
+        )
+    }
+}
+
+impl FromStr for FastVMBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "native" | "jit" => Ok(FastVMBackend::Native),
+            "interpreter" | "rust" => Ok(FastVMBackend::Interpreter),
+            x => Err(format!("Invalid fastvm backend: {}", x)),
+        }
+    }
+}
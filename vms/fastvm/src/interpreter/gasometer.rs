@@ -0,0 +1,80 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Aion energy schedule. Mirrors `GasMeter.cpp` of the native library for the
+//! `EVM_AION` revisions, which is the only schedule a FastVM contract runs with.
+
+use super::instructions::*;
+
+/// SHA3 cost per 32 bytes word of input.
+pub const SHA3_WORD: u64 = 6;
+/// Memory copy cost per 32 bytes word (CALLDATACOPY, CODECOPY, ...).
+pub const COPY_WORD: u64 = 3;
+/// LOG cost per byte of data.
+pub const LOG_DATA: u64 = 20;
+/// SSTORE of a non zero value into an empty slot.
+pub const SSTORE_SET: u64 = 20000;
+/// Any other SSTORE.
+pub const SSTORE_RESET: u64 = 8000;
+/// CALL or CALLCODE carrying a non zero value.
+pub const CALL_VALUE_TRANSFER: u64 = 15000;
+/// Gas handed to the callee on top of the requested amount when value is transferred.
+pub const CALL_STIPEND: u64 = 2300;
+/// CALL with value to, or SELFDESTRUCT into, an account that does not exist.
+pub const CALL_NEW_ACCOUNT: u64 = 25000;
+
+/// Largest memory offset or size accepted before the access is priced out of reach.
+pub const MAX_MEMORY_INPUT: u128 = 1 << 33;
+
+/// Static part of the cost of `op`, charged before the instruction runs.
+/// Invalid instructions cost nothing; they abort the execution anyway.
+pub fn step_cost(op: u8) -> u64 {
+    match op {
+        STOP | RETURN | REVERT | SSTORE => 0,
+        BALANCE | EXTCODESIZE | EXTCODECOPY | SLOAD => 1000,
+        CALL | CALLCODE | DELEGATECALL | STATICCALL => 1000,
+        BLOCKHASH => 20,
+        SHA3 => 30,
+        JUMPDEST => 1,
+        LOG0...LOG4 => 500 + 500 * (op - LOG0) as u64,
+        CREATE => 200000,
+        SELFDESTRUCT => 5000,
+        ADD...SIGNEXTEND | LT...BYTE => 1,
+        ADDRESS | ORIGIN...CALLDATACOPY | CODESIZE | CODECOPY | GASPRICE => 1,
+        RETURNDATASIZE | RETURNDATACOPY => 1,
+        COINBASE...GASLIMIT => 1,
+        POP | MLOAD | MSTORE | MSTORE8 | JUMP | JUMPI | PC | MSIZE | GAS => 1,
+        PUSH1...PUSH32 | DUP1...DUP16 | SWAP1...SWAP16 | DUP17...DUP32 | SWAP17...SWAP32 => 1,
+        _ => 0,
+    }
+}
+
+/// Additional EXP cost: one per significant byte of the exponent.
+pub fn exp_cost(exponent: u128) -> u64 { ((128 - exponent.leading_zeros() as u64) + 7) / 8 }
+
+/// Number of 32 bytes words needed to hold `size` bytes.
+pub fn words(size: u64) -> u64 { (size + 31) / 32 }
+
+/// Total cost of a memory of `size` bytes, `size` being a multiple of 32.
+pub fn memory_cost(size: u64) -> u64 {
+    let words = size / 32;
+    words + words * words / 512
+}
@@ -0,0 +1,116 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Byte addressed, zero initialised memory of a single FVM frame.
+//! It always grows in 32 bytes steps, like the native implementation.
+
+use super::gasometer;
+use super::{word_from_bytes, word_to_bytes};
+
+#[derive(Debug, Default)]
+pub struct Memory {
+    data: Vec<u8>,
+}
+
+impl Memory {
+    pub fn new() -> Self { Memory::default() }
+
+    /// Current size in bytes.
+    pub fn size(&self) -> usize { self.data.len() }
+
+    /// Energy needed to make `[offset, offset + size)` addressable, or `None` when
+    /// the range is too large to ever be paid for. Does not resize.
+    pub fn expansion_cost(&self, offset: u128, size: u128) -> Option<u64> {
+        if size == 0 {
+            return Some(0);
+        }
+        if offset > gasometer::MAX_MEMORY_INPUT || size > gasometer::MAX_MEMORY_INPUT {
+            return None;
+        }
+        let required = ((offset + size) as u64 + 31) & !31;
+        let current = self.data.len() as u64;
+        if required <= current {
+            return Some(0);
+        }
+        Some(gasometer::memory_cost(required) - gasometer::memory_cost(current))
+    }
+
+    /// Grow to cover `[offset, offset + size)`. The range must have been priced
+    /// with `expansion_cost` first.
+    pub fn expand(&mut self, offset: u128, size: u128) {
+        if size == 0 {
+            return;
+        }
+        let required = (((offset + size) as u64 + 31) & !31) as usize;
+        if required > self.data.len() {
+            self.data.resize(required, 0);
+        }
+    }
+
+    pub fn read_word(&self, offset: usize) -> u128 {
+        word_from_bytes(&self.data[offset..offset + 16])
+    }
+
+    pub fn write_word(&mut self, offset: usize, value: u128) {
+        self.data[offset..offset + 16].copy_from_slice(&word_to_bytes(value));
+    }
+
+    pub fn write_byte(&mut self, offset: usize, value: u8) { self.data[offset] = value; }
+
+    pub fn read_slice(&self, offset: u128, size: u128) -> &[u8] {
+        if size == 0 {
+            return &[];
+        }
+        let offset = offset as usize;
+        &self.data[offset..offset + size as usize]
+    }
+
+    pub fn write_slice(&mut self, offset: u128, data: &[u8]) {
+        if data.is_empty() {
+            return;
+        }
+        let offset = offset as usize;
+        self.data[offset..offset + data.len()].copy_from_slice(data);
+    }
+
+    /// Copy `size` bytes of `source` starting at `source_offset` to `offset`,
+    /// padding with zeros past the end of `source`.
+    pub fn copy_padded(&mut self, offset: u128, source: &[u8], source_offset: u128, size: u128) {
+        if size == 0 {
+            return;
+        }
+        let offset = offset as usize;
+        let size = size as usize;
+        let available = if source_offset >= source.len() as u128 {
+            0
+        } else {
+            ::std::cmp::min(size, source.len() - source_offset as usize)
+        };
+        if available > 0 {
+            let start = source_offset as usize;
+            self.data[offset..offset + available]
+                .copy_from_slice(&source[start..start + available]);
+        }
+        for byte in &mut self.data[offset + available..offset + size] {
+            *byte = 0;
+        }
+    }
+}
@@ -0,0 +1,764 @@
+/*******************************************************************************
+ * Copyright (c) 2018-2019 Aion foundation.
+ *
+ *     This file is part of the aion network project.
+ *
+ *     The aion network project is free software: you can redistribute it
+ *     and/or modify it under the terms of the GNU General Public License
+ *     as published by the Free Software Foundation, either version 3 of
+ *     the License, or any later version.
+ *
+ *     The aion network project is distributed in the hope that it will
+ *     be useful, but WITHOUT ANY WARRANTY; without even the implied
+ *     warranty of MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.
+ *     See the GNU General Public License for more details.
+ *
+ *     You should have received a copy of the GNU General Public License
+ *     along with the aion network project source files.
+ *     If not, see <https://www.gnu.org/licenses/>.
+ *
+ ******************************************************************************/
+
+//! Pure Rust implementation of the FastVM.
+//!
+//! Runs FVM bytecode with the semantics of the native JIT: 128-bit stack words,
+//! 256-bit values (addresses, hashes) spread over two stack items with the high
+//! half on top, keccak256 for SHA3 and the Aion energy schedule. Like the JIT,
+//! every abnormal halt (bad instruction, bad jump, stack overflow or underflow,
+//! state change in static mode) ends as `OutOfGas` with no energy left.
+
+mod gasometer;
+mod instructions;
+mod memory;
+
+use std::cmp;
+use aion_types::{Address, H128, H256, U256};
+use tiny_keccak::keccak256;
+use basetypes::constants::MAX_CALL_DEPTH;
+use context::ExecutionContext;
+use ffi::EvmStatusCode;
+use types::{CallType, ExecStatus};
+use types::traits::Ext;
+use vm::Error;
+use self::instructions::*;
+use self::memory::Memory;
+
+/// Maximum number of items on the stack.
+const STACK_LIMIT: usize = 1024;
+
+/// Big-endian bytes of a stack word.
+pub fn word_to_bytes(word: u128) -> [u8; 16] {
+    let mut bytes = [0u8; 16];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = (word >> (8 * (15 - i))) as u8;
+    }
+    bytes
+}
+
+/// Stack word from big-endian bytes. Shorter input is treated as a smaller number.
+pub fn word_from_bytes(bytes: &[u8]) -> u128 {
+    bytes.iter().fold(0u128, |acc, b| (acc << 8) | *b as u128)
+}
+
+fn to_u256(word: u128) -> U256 { (U256::from((word >> 64) as u64) << 64) | U256::from(word as u64) }
+
+fn low_u128(value: U256) -> u128 {
+    ((value >> 64).low_u64() as u128) << 64 | value.low_u64() as u128
+}
+
+/// How a frame stopped without error.
+enum Halt {
+    Stop,
+    Return(Vec<u8>),
+    Revert(Vec<u8>),
+}
+
+/// Pure Rust FastVM. Interchangeable with `core::FastVM`, see `FastVMBackend`.
+#[derive(Debug, Clone, Default)]
+pub struct Interpreter;
+
+impl Interpreter {
+    pub fn new() -> Self { Interpreter }
+
+    /// Execute `code` in `ctx`. Returns the status code, the energy left and the output
+    /// data, exactly like `FastVM::run`.
+    pub fn run(
+        &mut self,
+        code: &[u8],
+        ctx: &ExecutionContext,
+        ext: &mut Ext,
+    ) -> (EvmStatusCode, i64, Vec<u8>)
+    {
+        let mut frame = Frame::new(code, ctx, ext);
+        let res = match frame.execute() {
+            Ok(Halt::Stop) => (EvmStatusCode::Success, frame.gas as i64, Vec::new()),
+            Ok(Halt::Return(data)) => (EvmStatusCode::Success, frame.gas as i64, data),
+            Ok(Halt::Revert(data)) => (EvmStatusCode::Revert, frame.gas as i64, data),
+            Err(err) => {
+                debug!(target: "vm", "fastvm interpreter: {} at pc {}", err, frame.pc);
+                (EvmStatusCode::OutOfGas, 0, Vec::new())
+            }
+        };
+        debug!(target: "vm", "fastvm interpreter status code = {:?}", res.0);
+        res
+    }
+}
+
+/// State of one execution of a piece of code.
+struct Frame<'a> {
+    code: &'a [u8],
+    ctx: &'a ExecutionContext,
+    ext: &'a mut Ext,
+    jump_destinations: Vec<bool>,
+    gas: u64,
+    pc: usize,
+    stack: Vec<u128>,
+    memory: Memory,
+    return_data: Vec<u8>,
+    /// DUP17-32 and SWAP17-32 are only available at the top level (revision AION_V1).
+    extended_stack: bool,
+    static_mode: bool,
+}
+
+impl<'a> Frame<'a> {
+    fn new(code: &'a [u8], ctx: &'a ExecutionContext, ext: &'a mut Ext) -> Self {
+        Frame {
+            code,
+            ctx,
+            ext,
+            jump_destinations: Frame::jump_destinations(code),
+            gas: ctx.nrg_limit,
+            pc: 0,
+            stack: Vec::with_capacity(STACK_LIMIT),
+            memory: Memory::new(),
+            return_data: Vec::new(),
+            extended_stack: ctx.depth == 0,
+            static_mode: ctx.flags == 1,
+        }
+    }
+
+    /// Positions of the JUMPDEST instructions, skipping PUSH data.
+    fn jump_destinations(code: &[u8]) -> Vec<bool> {
+        let mut destinations = vec![false; code.len()];
+        let mut pc = 0;
+        while pc < code.len() {
+            if code[pc] == JUMPDEST {
+                destinations[pc] = true;
+            }
+            pc += 1 + push_bytes(code[pc]);
+        }
+        destinations
+    }
+
+    fn execute(&mut self) -> Result<Halt, Error> {
+        while self.pc < self.code.len() {
+            let op = self.code[self.pc];
+            self.charge(gasometer::step_cost(op))?;
+            if let Some(halt) = self.step(op)? {
+                return Ok(halt);
+            }
+        }
+        Ok(Halt::Stop)
+    }
+
+    fn charge(&mut self, cost: u64) -> Result<(), Error> {
+        if cost > self.gas {
+            self.gas = 0;
+            return Err(Error::OutOfGas);
+        }
+        self.gas -= cost;
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Result<u128, Error> {
+        match self.stack.pop() {
+            Some(word) => Ok(word),
+            None => {
+                Err(Error::StackUnderflow {
+                    instruction: name(self.code[self.pc]),
+                    wanted: 1,
+                    on_stack: 0,
+                })
+            }
+        }
+    }
+
+    fn push(&mut self, word: u128) -> Result<(), Error> {
+        if self.stack.len() >= STACK_LIMIT {
+            return Err(Error::OutOfStack {
+                instruction: name(self.code[self.pc]),
+                wanted: 1,
+                limit: STACK_LIMIT,
+            });
+        }
+        self.stack.push(word);
+        Ok(())
+    }
+
+    /// Push a 32 bytes value as two words, high half on top.
+    fn push_h256(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.push(word_from_bytes(&bytes[16..32]))?;
+        self.push(word_from_bytes(&bytes[0..16]))
+    }
+
+    /// Pop a 32 bytes value stored as two words, high half on top.
+    fn pop_h256(&mut self) -> Result<H256, Error> {
+        let high = self.pop()?;
+        let low = self.pop()?;
+        let mut bytes = [0u8; 32];
+        bytes[0..16].copy_from_slice(&word_to_bytes(high));
+        bytes[16..32].copy_from_slice(&word_to_bytes(low));
+        Ok(bytes.into())
+    }
+
+    fn pop_address(&mut self) -> Result<Address, Error> { self.pop_h256() }
+
+    /// Charge for and grow the memory so that `[offset, offset + size)` is addressable.
+    fn require_memory(&mut self, offset: u128, size: u128) -> Result<(), Error> {
+        match self.memory.expansion_cost(offset, size) {
+            Some(cost) => self.charge(cost)?,
+            None => {
+                self.gas = 0;
+                return Err(Error::OutOfGas);
+            }
+        }
+        self.memory.expand(offset, size);
+        Ok(())
+    }
+
+    fn static_violation(&self, op: u8) -> Result<(), Error> {
+        if self.static_mode {
+            return Err(Error::BadInstruction {
+                instruction: op,
+            });
+        }
+        Ok(())
+    }
+
+    /// Run the instruction at `pc`. Returns `Some` when the frame halts.
+    fn step(&mut self, op: u8) -> Result<Option<Halt>, Error> {
+        let mut next_pc = self.pc + 1;
+        match op {
+            STOP => return Ok(Some(Halt::Stop)),
+            ADD => {
+                let (a, b) = (self.pop()?, self.pop()?);
+                self.push(a.wrapping_add(b))?;
+            }
+            MUL => {
+                let (a, b) = (self.pop()?, self.pop()?);
+                self.push(a.wrapping_mul(b))?;
+            }
+            SUB => {
+                let (a, b) = (self.pop()?, self.pop()?);
+                self.push(a.wrapping_sub(b))?;
+            }
+            DIV => {
+                let (a, b) = (self.pop()?, self.pop()?);
+                self.push(if b == 0 { 0 } else { a / b })?;
+            }
+            SDIV => {
+                let (a, b) = (self.pop()? as i128, self.pop()? as i128);
+                let res = match b {
+                    0 => 0,
+                    -1 => a.wrapping_neg(),
+                    _ => a / b,
+                };
+                self.push(res as u128)?;
+            }
+            MOD => {
+                let (a, b) = (self.pop()?, self.pop()?);
+                self.push(if b == 0 { 0 } else { a % b })?;
+            }
+            SMOD => {
+                let (a, b) = (self.pop()? as i128, self.pop()? as i128);
+                let res = match b {
+                    0 | -1 => 0,
+                    _ => a % b,
+                };
+                self.push(res as u128)?;
+            }
+            ADDMOD | MULMOD => {
+                let (a, b, m) = (self.pop()?, self.pop()?, self.pop()?);
+                let res = if m == 0 {
+                    0
+                } else if op == ADDMOD {
+                    low_u128((to_u256(a) + to_u256(b)) % to_u256(m))
+                } else {
+                    low_u128((to_u256(a) * to_u256(b)) % to_u256(m))
+                };
+                self.push(res)?;
+            }
+            EXP => {
+                let (base, exponent) = (self.pop()?, self.pop()?);
+                self.charge(gasometer::exp_cost(exponent))?;
+                let (mut base, mut exponent, mut res) = (base, exponent, 1u128);
+                while exponent != 0 {
+                    if exponent & 1 == 1 {
+                        res = res.wrapping_mul(base);
+                    }
+                    base = base.wrapping_mul(base);
+                    exponent >>= 1;
+                }
+                self.push(res)?;
+            }
+            SIGNEXTEND => {
+                let (index, word) = (self.pop()?, self.pop()?);
+                let res = if index <= 14 {
+                    let bit = index as u32 * 8 + 7;
+                    let mask = (1u128 << bit) - 1;
+                    if (word >> bit) & 1 == 1 {
+                        word | !mask
+                    } else {
+                        word & mask
+                    }
+                } else {
+                    word
+                };
+                self.push(res)?;
+            }
+            LT | GT | SLT | SGT | EQ => {
+                let (a, b) = (self.pop()?, self.pop()?);
+                let res = match op {
+                    LT => a < b,
+                    GT => a > b,
+                    SLT => (a as i128) < (b as i128),
+                    SGT => (a as i128) > (b as i128),
+                    _ => a == b,
+                };
+                self.push(res as u128)?;
+            }
+            ISZERO => {
+                let a = self.pop()?;
+                self.push((a == 0) as u128)?;
+            }
+            AND => {
+                let (a, b) = (self.pop()?, self.pop()?);
+                self.push(a & b)?;
+            }
+            OR => {
+                let (a, b) = (self.pop()?, self.pop()?);
+                self.push(a | b)?;
+            }
+            XOR => {
+                let (a, b) = (self.pop()?, self.pop()?);
+                self.push(a ^ b)?;
+            }
+            NOT => {
+                let a = self.pop()?;
+                self.push(!a)?;
+            }
+            BYTE => {
+                let (index, word) = (self.pop()?, self.pop()?);
+                let res = if index < 16 {
+                    (word >> (8 * (15 - index as u32))) & 0xff
+                } else {
+                    0
+                };
+                self.push(res)?;
+            }
+            SHA3 => {
+                let (offset, size) = (self.pop()?, self.pop()?);
+                self.require_memory(offset, size)?;
+                self.charge(gasometer::SHA3_WORD * gasometer::words(size as u64))?;
+                let hash = keccak256(self.memory.read_slice(offset, size));
+                self.push_h256(&hash)?;
+            }
+            ADDRESS => {
+                let address = self.ctx.address;
+                self.push_h256(&address)?;
+            }
+            BALANCE => {
+                let address = self.pop_address()?;
+                let balance = self.ext.balance(&address);
+                self.push(low_u128(balance))?;
+            }
+            ORIGIN => {
+                let origin = self.ctx.origin;
+                self.push_h256(&origin)?;
+            }
+            CALLER => {
+                let caller = self.ctx.caller;
+                self.push_h256(&caller)?;
+            }
+            CALLVALUE => {
+                let value = word_from_bytes(&self.ctx.call_value.data);
+                self.push(value)?;
+            }
+            CALLDATALOAD => {
+                let index = self.pop()?;
+                let data = &self.ctx.call_data;
+                let mut word = [0u8; 16];
+                if index < data.len() as u128 {
+                    let index = index as usize;
+                    let size = cmp::min(16, data.len() - index);
+                    word[..size].copy_from_slice(&data[index..index + size]);
+                }
+                self.push(word_from_bytes(&word))?;
+            }
+            CALLDATASIZE => {
+                let size = self.ctx.call_data.len();
+                self.push(size as u128)?;
+            }
+            CALLDATACOPY | CODECOPY => {
+                let (offset, source_offset, size) = (self.pop()?, self.pop()?, self.pop()?);
+                self.require_memory(offset, size)?;
+                self.charge(gasometer::COPY_WORD * gasometer::words(size as u64))?;
+                let source: &[u8] = match op {
+                    CALLDATACOPY => &self.ctx.call_data,
+                    _ => self.code,
+                };
+                self.memory
+                    .copy_padded(offset, source, source_offset, size);
+            }
+            CODESIZE => {
+                let size = self.code.len();
+                self.push(size as u128)?;
+            }
+            GASPRICE => {
+                let price = word_from_bytes(&self.ctx.nrg_price.data);
+                self.push(price)?;
+            }
+            EXTCODESIZE => {
+                let address = self.pop_address()?;
+                let size = self.ext.extcode(&address).len();
+                self.push(size as u128)?;
+            }
+            EXTCODECOPY => {
+                let address = self.pop_address()?;
+                let (offset, source_offset, size) = (self.pop()?, self.pop()?, self.pop()?);
+                self.require_memory(offset, size)?;
+                self.charge(gasometer::COPY_WORD * gasometer::words(size as u64))?;
+                let code = self.ext.extcode(&address);
+                self.memory
+                    .copy_padded(offset, &code, source_offset, size);
+            }
+            RETURNDATASIZE => {
+                let size = self.return_data.len();
+                self.push(size as u128)?;
+            }
+            RETURNDATACOPY => {
+                let (offset, source_offset, size) = (self.pop()?, self.pop()?, self.pop()?);
+                self.require_memory(offset, size)?;
+                match source_offset.checked_add(size) {
+                    Some(end) if end <= self.return_data.len() as u128 => {}
+                    _ => {
+                        self.gas = 0;
+                        return Err(Error::OutOfBounds);
+                    }
+                }
+                self.charge(gasometer::COPY_WORD * gasometer::words(size as u64))?;
+                let start = source_offset as usize;
+                self.memory
+                    .write_slice(offset, &self.return_data[start..start + size as usize]);
+            }
+            BLOCKHASH => {
+                let number = self.pop()?;
+                if number > i64::max_value() as u128 {
+                    self.push_h256(&[0u8; 32])?;
+                } else {
+                    let hash = self.ext.blockhash(&U256::from(number as u64));
+                    self.push_h256(&hash)?;
+                }
+            }
+            COINBASE => {
+                let coinbase = self.ctx.block_coinbase;
+                self.push_h256(&coinbase)?;
+            }
+            TIMESTAMP => {
+                let timestamp = self.ctx.block_timestamp as u64;
+                self.push(timestamp as u128)?;
+            }
+            NUMBER => {
+                let number = self.ctx.block_number;
+                self.push(number as u128)?;
+            }
+            DIFFICULTY => {
+                let difficulty = word_from_bytes(&self.ctx.block_difficulty.data);
+                self.push(difficulty)?;
+            }
+            GASLIMIT => {
+                let gas_limit = self.ctx.block_nrglimit;
+                self.push(gas_limit as u128)?;
+            }
+            POP => {
+                self.pop()?;
+            }
+            MLOAD => {
+                let offset = self.pop()?;
+                self.require_memory(offset, 16)?;
+                let word = self.memory.read_word(offset as usize);
+                self.push(word)?;
+            }
+            MSTORE => {
+                let (offset, word) = (self.pop()?, self.pop()?);
+                self.require_memory(offset, 16)?;
+                self.memory.write_word(offset as usize, word);
+            }
+            MSTORE8 => {
+                let (offset, word) = (self.pop()?, self.pop()?);
+                self.require_memory(offset, 1)?;
+                self.memory.write_byte(offset as usize, word as u8);
+            }
+            SLOAD => {
+                let key = self.pop()?;
+                let value = self.ext.storage_at(&H128::from(word_to_bytes(key)));
+                self.push(word_from_bytes(&value))?;
+            }
+            SSTORE => {
+                self.static_violation(op)?;
+                let (key, value) = (self.pop()?, self.pop()?);
+                let key = H128::from(word_to_bytes(key));
+                let current = self.ext.storage_at(&key);
+                let cost = if current.is_zero() && value != 0 {
+                    gasometer::SSTORE_SET
+                } else {
+                    gasometer::SSTORE_RESET
+                };
+                self.charge(cost)?;
+                self.ext.set_storage(key, H128::from(word_to_bytes(value)));
+            }
+            JUMP => {
+                let destination = self.pop()?;
+                next_pc = self.jump(destination)?;
+            }
+            JUMPI => {
+                let (destination, condition) = (self.pop()?, self.pop()?);
+                if condition != 0 {
+                    next_pc = self.jump(destination)?;
+                }
+            }
+            PC => {
+                let pc = self.pc;
+                self.push(pc as u128)?;
+            }
+            MSIZE => {
+                let size = self.memory.size();
+                self.push(size as u128)?;
+            }
+            GAS => {
+                let gas = self.gas;
+                self.push(gas as u128)?;
+            }
+            JUMPDEST => {}
+            PUSH1...PUSH32 => {
+                let size = push_bytes(op);
+                let mut bytes = [0u8; 32];
+                let start = self.pc + 1;
+                let end = cmp::min(start + size, self.code.len());
+                // missing trailing bytes read as zeros
+                bytes[32 - size..32 - size + (end - start)].copy_from_slice(&self.code[start..end]);
+                if size > 16 {
+                    self.push_h256(&bytes)?;
+                } else {
+                    self.push(word_from_bytes(&bytes[16..32]))?;
+                }
+                next_pc = start + size;
+            }
+            DUP1...DUP16 | DUP17...DUP32 => {
+                let position = match op {
+                    DUP1...DUP16 => (op - DUP1) as usize + 1,
+                    _ if self.extended_stack => (op - DUP17) as usize + 17,
+                    _ => {
+                        return Err(Error::BadInstruction {
+                            instruction: op,
+                        })
+                    }
+                };
+                if self.stack.len() < position {
+                    return Err(Error::StackUnderflow {
+                        instruction: name(op),
+                        wanted: position,
+                        on_stack: self.stack.len(),
+                    });
+                }
+                let word = self.stack[self.stack.len() - position];
+                self.push(word)?;
+            }
+            SWAP1...SWAP16 | SWAP17...SWAP32 => {
+                let position = match op {
+                    SWAP1...SWAP16 => (op - SWAP1) as usize + 1,
+                    _ if self.extended_stack => (op - SWAP17) as usize + 17,
+                    _ => {
+                        return Err(Error::BadInstruction {
+                            instruction: op,
+                        })
+                    }
+                };
+                if self.stack.len() <= position {
+                    return Err(Error::StackUnderflow {
+                        instruction: name(op),
+                        wanted: position + 1,
+                        on_stack: self.stack.len(),
+                    });
+                }
+                let top = self.stack.len() - 1;
+                self.stack.swap(top, top - position);
+            }
+            LOG0...LOG4 => {
+                self.static_violation(op)?;
+                let (offset, size) = (self.pop()?, self.pop()?);
+                self.require_memory(offset, size)?;
+                self.charge(gasometer::LOG_DATA * size as u64)?;
+                let mut topics = Vec::new();
+                for _ in 0..(op - LOG0) {
+                    topics.push(self.pop_h256()?);
+                }
+                let data = self.memory.read_slice(offset, size).to_vec();
+                self.ext.log(topics, &data);
+            }
+            CREATE => self.create()?,
+            CALL | CALLCODE | DELEGATECALL | STATICCALL => self.call(op)?,
+            RETURN | REVERT => {
+                let (offset, size) = (self.pop()?, self.pop()?);
+                self.require_memory(offset, size)?;
+                let data = self.memory.read_slice(offset, size).to_vec();
+                return Ok(Some(match op {
+                    RETURN => Halt::Return(data),
+                    _ => Halt::Revert(data),
+                }));
+            }
+            SELFDESTRUCT => {
+                self.static_violation(op)?;
+                let beneficiary = self.pop_address()?;
+                if !self.ext.exists(&beneficiary) && !self.ext.balance(&self.ctx.address).is_zero()
+                {
+                    self.charge(gasometer::CALL_NEW_ACCOUNT)?;
+                }
+                self.ext.suicide(&beneficiary);
+                return Ok(Some(Halt::Stop));
+            }
+            _ => {
+                return Err(Error::BadInstruction {
+                    instruction: op,
+                })
+            }
+        }
+        self.pc = next_pc;
+        Ok(None)
+    }
+
+    fn jump(&self, destination: u128) -> Result<usize, Error> {
+        if destination < self.code.len() as u128 && self.jump_destinations[destination as usize] {
+            Ok(destination as usize)
+        } else {
+            Err(Error::BadJumpDestination {
+                destination: cmp::min(destination, usize::max_value() as u128) as usize,
+            })
+        }
+    }
+
+    /// Whether a sub call or create moving `value` may start at all. A failed check
+    /// hands the energy back to the caller, as in the JIT.
+    fn can_transfer(&self, value: u128) -> bool {
+        value == 0 || self.ext.balance(&self.ctx.address) >= to_u256(value)
+    }
+
+    fn create(&mut self) -> Result<(), Error> {
+        self.static_violation(CREATE)?;
+        let (endowment, offset, size) = (self.pop()?, self.pop()?, self.pop()?);
+        self.require_memory(offset, size)?;
+
+        // all but one 64th of the remaining energy goes to the new contract
+        let kept = self.gas / 64;
+        let create_gas = self.gas - kept;
+        self.gas = kept;
+        self.return_data = Vec::new();
+
+        if !self.can_transfer(endowment) {
+            self.gas += create_gas;
+            return self.push_h256(&[0u8; 32]);
+        }
+        if self.ctx.depth + 1 >= MAX_CALL_DEPTH {
+            return self.push_h256(&[0u8; 32]);
+        }
+
+        let code = self.memory.read_slice(offset, size).to_vec();
+        let result = self
+            .ext
+            .create(&U256::from(create_gas), &to_u256(endowment), &code);
+        self.gas += result.gas_left.low_u64();
+        self.return_data = result.return_data.to_vec();
+        if result.status_code == ExecStatus::Success && self.return_data.len() >= 32 {
+            let address = self.return_data[..32].to_vec();
+            self.push_h256(&address)
+        } else {
+            self.push_h256(&[0u8; 32])
+        }
+    }
+
+    fn call(&mut self, op: u8) -> Result<(), Error> {
+        let requested_gas = self.pop()?;
+        let address = self.pop_address()?;
+        let value = match op {
+            CALL | CALLCODE => self.pop()?,
+            _ => 0,
+        };
+        let (in_offset, in_size) = (self.pop()?, self.pop()?);
+        let (out_offset, out_size) = (self.pop()?, self.pop()?);
+
+        self.require_memory(out_offset, out_size)?;
+        self.require_memory(in_offset, in_size)?;
+
+        if value != 0 {
+            if op == CALL && self.static_mode {
+                self.gas = 0;
+                return Err(Error::OutOfGas);
+            }
+            self.charge(gasometer::CALL_VALUE_TRANSFER)?;
+            if op == CALL && !self.ext.exists(&address) {
+                self.charge(gasometer::CALL_NEW_ACCOUNT)?;
+            }
+        }
+
+        // all but one 64th of the remaining energy may be given away
+        let max_gas = self.gas - self.gas / 64;
+        let call_gas = cmp::min(requested_gas, max_gas as u128) as u64;
+        self.charge(call_gas)?;
+        let call_gas = match value {
+            0 => call_gas,
+            _ => call_gas + gasometer::CALL_STIPEND,
+        };
+        self.return_data = Vec::new();
+
+        if op != DELEGATECALL && !self.can_transfer(value) {
+            self.gas += call_gas;
+            return self.push(0);
+        }
+        if self.ctx.depth + 1 >= MAX_CALL_DEPTH {
+            return self.push(0);
+        }
+
+        let (sender, receiver, value, call_type) = match op {
+            CALL => (self.ctx.address, address, value, CallType::Call),
+            STATICCALL => (self.ctx.address, address, value, CallType::StaticCall),
+            CALLCODE => (self.ctx.address, self.ctx.address, value, CallType::CallCode),
+            _ => {
+                (
+                    self.ctx.caller,
+                    self.ctx.address,
+                    word_from_bytes(&self.ctx.call_value.data),
+                    CallType::DelegateCall,
+                )
+            }
+        };
+        let static_flag = self.static_mode || op == STATICCALL;
+        let input = self.memory.read_slice(in_offset, in_size).to_vec();
+        let result = self.ext.call(
+            &U256::from(call_gas),
+            &sender,
+            &receiver,
+            Some(to_u256(value)),
+            &input,
+            &address,
+            call_type,
+            static_flag,
+        );
+
+        let output: &[u8] = &result.return_data;
+        let copied = cmp::min(out_size, output.len() as u128) as usize;
+        self.memory.write_slice(out_offset, &output[..copied]);
+        self.return_data = output.to_vec();
+        self.gas += result.gas_left.low_u64();
+        self.push((result.status_code == ExecStatus::Success) as u128)
+    }
+}
@@ -30,6 +30,7 @@ extern crate aion_types;
 extern crate log;
 extern crate patricia_trie as trie;
 extern crate vm_common as types;
+extern crate tiny_keccak;
 
 pub mod context;
 pub mod basetypes;
@@ -38,3 +39,5 @@ pub mod callback;
 pub mod vm;
 pub mod core;
 pub mod ffi;
+pub mod backend;
+pub mod interpreter;
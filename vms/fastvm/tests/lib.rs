@@ -35,9 +35,11 @@ use std::sync::Arc;
 use std::collections::HashMap;
 use std::convert::Into;
 use fastvm::core::FastVM;
+use fastvm::backend::FastVMBackend;
+use fastvm::interpreter::Interpreter;
 use fastvm::basetypes::{DataWord};
 use fastvm::context::{execution_kind, TransactionResult, ExecutionContext};
-use fastvm::ffi::EvmJit;
+use fastvm::ffi::{EvmJit, EvmStatusCode};
 use aion_types::{Address, H128, U256, H256};
 use vm_common::traits::Ext;
 use vm_common::{FvmExecutionResult, CallType, EnvInfo};
@@ -316,69 +318,104 @@ impl<'a> EvmJit<::libc::c_void> for TestEnv<'a> {
     }
 }
 
-#[test]
-fn fastvm_env() {
-    let context = FastVMTest::new();
-    let mut instance = FastVM::new();
-    let ext = TestEnv {
-        env_info: &EnvInfo::default(),
+fn test_env(env_info: &EnvInfo) -> TestEnv {
+    TestEnv {
+        env_info,
         accounts: HashMap::new(),
         _balance: HashMap::new(),
         storage: HashMap::new(),
         storage_dword: HashMap::new(),
         log_topics: Vec::new(),
         log_data: Vec::new(),
+    }
+}
+
+/// Run `code` on the given backend.
+fn execute(
+    backend: FastVMBackend,
+    code: &Vec<u8>,
+    context: FastVMTest,
+    ext: &mut TestEnv,
+) -> (EvmStatusCode, i64, Vec<u8>)
+{
+    let mut context: ExecutionContext = context.into();
+    match backend {
+        FastVMBackend::Native => {
+            let mut instance = FastVM::new();
+            let raw_env: *mut ::libc::c_void =
+                unsafe { ::std::mem::transmute(Box::new(&mut *ext as &mut Ext)) };
+            instance.init(raw_env);
+            println!("raw_ext = {:?}", raw_env);
+            instance.run(code, &mut context)
+        }
+        FastVMBackend::Interpreter => Interpreter::new().run(code, &context, ext),
+    }
+}
+
+/// Every test runs against both the native FastVM and the Rust interpreter.
+macro_rules! backend_tests {
+    ($($name:ident),*) => {
+        mod native {
+            $(
+                #[test]
+                fn $name() { super::$name(::fastvm::backend::FastVMBackend::Native); }
+            )*
+        }
+
+        mod interpreter {
+            $(
+                #[test]
+                fn $name() { super::$name(::fastvm::backend::FastVMBackend::Interpreter); }
+            )*
+        }
     };
-    let raw_env: *mut ::libc::c_void = unsafe { ::std::mem::transmute(Box::new(&ext)) };
-    instance.init(raw_env);
-    println!("raw_ext = {:?}", raw_env);
+}
+
+backend_tests!(
+    fastvm_env,
+    operation_underflow,
+    evm_storage,
+    evm_mstore,
+    evm_log,
+    blockhash,
+    sha3,
+    invalid_gas,
+    arithmetic,
+    bad_jump_destination,
+    push32_high_half_on_top,
+    revert_keeps_gas
+);
+
+fn fastvm_env(backend: FastVMBackend) {
+    let context = FastVMTest::new();
+    let env_info = EnvInfo::default();
+    let mut ext = test_env(&env_info);
 
     let code = vec![0x61, 0x01, 0x02];
-    let res = instance.run(&code, &mut context.into());
+    let res = execute(backend, &code, context, &mut ext);
     println!("TEST<fastvm_env>: res = {:?}", res);
+    assert_eq!(res.0, EvmStatusCode::Success);
 }
 
-#[test]
-fn operation_underflow() {
+fn operation_underflow(backend: FastVMBackend) {
     let context = FastVMTest::new();
-    let mut instance = FastVM::new();
-    let ext = TestEnv {
-        env_info: &EnvInfo::default(),
-        accounts: HashMap::new(),
-        _balance: HashMap::new(),
-        storage: HashMap::new(),
-        storage_dword: HashMap::new(),
-        log_topics: Vec::new(),
-        log_data: Vec::new(),
-    };
-    let raw_env: *mut ::libc::c_void = unsafe { ::std::mem::transmute(Box::new(&ext)) };
-    instance.init(raw_env);
-    println!("raw_ext = {:?}", raw_env);
+    let env_info = EnvInfo::default();
+    let mut ext = test_env(&env_info);
 
     let code = vec![0x06, 0x05, 0x06];
-    let res = instance.run(&code, &mut context.into());
+    let res = execute(backend, &code, context, &mut ext);
     println!("TEST<fastvm_env>: res = {:?}", res);
+    assert_eq!(res.0, EvmStatusCode::OutOfGas);
+    assert_eq!(res.1, 0);
 }
 
-#[test]
-fn evm_storage() {
+fn evm_storage(backend: FastVMBackend) {
     let context = FastVMTest::new();
-    let mut instance = FastVM::new();
-    let ext = TestEnv {
-        env_info: &EnvInfo::default(),
-        accounts: HashMap::new(),
-        _balance: HashMap::new(),
-        storage: HashMap::new(),
-        storage_dword: HashMap::new(),
-        log_topics: Vec::new(),
-        log_data: Vec::new(),
-    };
-    let raw_env: *mut ::libc::c_void = unsafe { ::std::mem::transmute(Box::new(&ext as &Ext)) };
-    instance.init(raw_env);
-    println!("raw_ext = {:?}", raw_env);
+    let env_info = EnvInfo::default();
+    let mut ext = test_env(&env_info);
 
     let code = vec![0x60, 0x01, 0x60, 0x02, 0x55];
-    let res = instance.run(&code, &mut context.into());
+    let res = execute(backend, &code, context, &mut ext);
     println!("TEST<fastvm_env>: res = {:?}", res);
     assert_eq!(
         ext.storage_at(&[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x02u8].into()),
@@ -386,27 +423,16 @@ fn evm_storage() {
     );
 }
 
-#[test]
-fn evm_mstore() {
+fn evm_mstore(backend: FastVMBackend) {
     let context = FastVMTest::new();
-    let mut instance = FastVM::new();
-    let ext = TestEnv {
-        env_info: &EnvInfo::default(),
-        accounts: HashMap::new(),
-        _balance: HashMap::new(),
-        storage: HashMap::new(),
-        storage_dword: HashMap::new(),
-        log_topics: Vec::new(),
-        log_data: Vec::new(),
-    };
-    let raw_env: *mut ::libc::c_void = unsafe { ::std::mem::transmute(Box::new(&ext as &Ext)) };
-    instance.init(raw_env);
+    let env_info = EnvInfo::default();
+    let mut ext = test_env(&env_info);
 
     // first mstore, then mload
     let code = vec![
         0x60, 0x0f, 0x60, 0x02, 0x52, 0x60, 0x02, 0x51, 0x60, 0x10, 0x60, 0x02, 0xf3,
     ];
-    let res = instance.run(&code, &mut context.clone().into());
+    let res = execute(backend, &code, context, &mut ext);
     println!("UT: evm_log, topics = {:?}", ext.log_topics);
     assert_eq!(
         res.2,
@@ -414,25 +440,14 @@ fn evm_mstore() {
     );
 }
 
-#[test]
-fn evm_log() {
+fn evm_log(backend: FastVMBackend) {
     let context = FastVMTest::new();
-    let mut instance = FastVM::new();
-    let mut ext = TestEnv {
-        env_info: &EnvInfo::default(),
-        accounts: HashMap::new(),
-        _balance: HashMap::new(),
-        storage: HashMap::new(),
-        storage_dword: HashMap::new(),
-        log_topics: Vec::new(),
-        log_data: Vec::new(),
-    };
-    let raw_env: *mut ::libc::c_void = unsafe { ::std::mem::transmute(Box::new(&ext as &Ext)) };
-    instance.init(raw_env);
+    let env_info = EnvInfo::default();
+    let mut ext = test_env(&env_info);
 
     // LOG0
     let code = vec![0x60, 0x01, 0x60, 0x02, 0xa0];
-    let _res = instance.run(&code, &mut context.clone().into());
+    let _res = execute(backend, &code, context.clone(), &mut ext);
     println!("UT: evm_log, topics = {:?}", ext.log_topics);
     assert_eq!(ext.log_topics.len(), 1);
     assert!(ext.log_topics[0].is_empty());
@@ -440,14 +455,12 @@ fn evm_log() {
     ext.log_topics.clear();
     ext.log_data.clear();
 
-    instance.init(raw_env);
     // LOG1
     // set M[0x02] = 0xaf
     let code = vec![
         0x60, 0xaf, 0x60, 0x02, 0x52, 0x60, 0x03, 0x60, 0x00, 0x60, 0x1, 0x60, 0x11, 0xa1,
     ];
-    // let code = vec![0x60, 0x01, 0x60, 0x02, 0xa0];
-    let _res = instance.run(&code, &mut context.clone().into());
+    let _res = execute(backend, &code, context.clone(), &mut ext);
 
     println!("topics = {:?}, data = {:?}", ext.log_topics, ext.log_data);
     assert_eq!(ext.log_topics.len(), 1);
@@ -460,27 +473,16 @@ fn evm_log() {
     assert_eq!(ext.log_data, [0xafu8]);
 }
 
-#[test]
-fn blockhash() {
+fn blockhash(backend: FastVMBackend) {
     let context = FastVMTest::new();
-    let mut instance = FastVM::new();
-    let ext = TestEnv {
-        env_info: &EnvInfo::default(),
-        accounts: HashMap::new(),
-        _balance: HashMap::new(),
-        storage: HashMap::new(),
-        storage_dword: HashMap::new(),
-        log_topics: Vec::new(),
-        log_data: Vec::new(),
-    };
-    let raw_env: *mut ::libc::c_void = unsafe { ::std::mem::transmute(Box::new(&ext as &Ext)) };
-    instance.init(raw_env);
+    let env_info = EnvInfo::default();
+    let mut ext = test_env(&env_info);
 
     // 0x40
     let code = vec![
         0x60, 0x01, 0x40, 0x60, 0x02, 0x52, 0x60, 0x12, 0x52, 0x60, 0x20, 0x60, 0x02, 0xf3,
     ];
-    let res = instance.run(&code, &mut context.clone().into());
+    let res = execute(backend, &code, context, &mut ext);
     assert_eq!(
         res.2,
         vec![
@@ -490,28 +492,17 @@ fn blockhash() {
     );
 }
 
-#[test]
-fn sha3() {
+fn sha3(backend: FastVMBackend) {
     let context = FastVMTest::new();
-    let mut instance = FastVM::new();
-    let ext = TestEnv {
-        env_info: &EnvInfo::default(),
-        accounts: HashMap::new(),
-        _balance: HashMap::new(),
-        storage: HashMap::new(),
-        storage_dword: HashMap::new(),
-        log_topics: Vec::new(),
-        log_data: Vec::new(),
-    };
-    let raw_env: *mut ::libc::c_void = unsafe { ::std::mem::transmute(Box::new(&ext as &Ext)) };
-    instance.init(raw_env);
+    let env_info = EnvInfo::default();
+    let mut ext = test_env(&env_info);
 
     // 0x20: compute sha3(0xff)
     let code = vec![
         0x60, 0xff, 0x60, 0x00, 0x52, 0x60, 0x10, 0x60, 0x00, 0x20, 0x60, 0x10, 0x52, 0x60, 0x20,
         0x52, 0x60, 0x20, 0x60, 0x10, 0xf3,
     ];
-    let res = instance.run(&code, &mut context.clone().into());
+    let res = execute(backend, &code, context, &mut ext);
     assert_eq!(
         res.2,
         vec![
@@ -523,27 +514,75 @@ fn sha3() {
 
 use rustc_hex::FromHex;
 
-#[test]
-fn invalid_gas() {
+fn invalid_gas(backend: FastVMBackend) {
     let mut context = FastVMTest::new();
     context.nrg_limit = 0; //9223372036854775808;
-    let mut instance = FastVM::new();
-    let ext = TestEnv {
-        env_info: &EnvInfo::default(),
-        accounts: HashMap::new(),
-        _balance: HashMap::new(),
-        storage: HashMap::new(),
-        storage_dword: HashMap::new(),
-        log_topics: Vec::new(),
-        log_data: Vec::new(),
-    };
-    let raw_env: *mut ::libc::c_void = unsafe { ::std::mem::transmute(Box::new(&ext as &Ext)) };
-    instance.init(raw_env);
+    let env_info = EnvInfo::default();
+    let mut ext = test_env(&env_info);
     //let code = "d0d1188a00000000000000000000000000000032".from_hex().unwrap();
     // let code = vec![0x60, 0x50];
     println!("{:?}-{:?}", i64::max_value(), i64::min_value());
     let code = "60506040523415600f5760006000fd5b5b5b600115601b576011565b5b6020565b603a80602d6000396000f30060506040526008565b60006000fd00a165627a7a72305820c39f9e61953f77cbe7316aee3ed72ba5914ee08019d883f16b87cff04a1c829d0029".from_hex().unwrap();
     println!("code = {:?}", code);
-    let res = instance.run(&code, &mut context.clone().into());
+    let res = execute(backend, &code, context, &mut ext);
     println!("{:?}", res);
+    assert_eq!(res.0, EvmStatusCode::OutOfGas);
+}
+
+fn arithmetic(backend: FastVMBackend) {
+    let context = FastVMTest::new();
+    let env_info = EnvInfo::default();
+    let mut ext = test_env(&env_info);
+
+    // M[0] = 7 - 3, return M[0..16]
+    let code = vec![
+        0x60, 0x03, 0x60, 0x07, 0x03, 0x60, 0x00, 0x52, 0x60, 0x10, 0x60, 0x00, 0xf3,
+    ];
+    let res = execute(backend, &code, context, &mut ext);
+    assert_eq!(res.0, EvmStatusCode::Success);
+    assert_eq!(
+        res.2,
+        vec![0u8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x04]
+    );
+}
+
+fn bad_jump_destination(backend: FastVMBackend) {
+    let context = FastVMTest::new();
+    let env_info = EnvInfo::default();
+    let mut ext = test_env(&env_info);
+
+    // jump into PUSH data
+    let code = vec![0x60, 0x5b, 0x60, 0x01, 0x56];
+    let res = execute(backend, &code, context, &mut ext);
+    assert_eq!(res.0, EvmStatusCode::OutOfGas);
+    assert_eq!(res.1, 0);
+}
+
+fn push32_high_half_on_top(backend: FastVMBackend) {
+    let context = FastVMTest::new();
+    let env_info = EnvInfo::default();
+    let mut ext = test_env(&env_info);
+
+    let value: Vec<u8> = (1..33).collect();
+    // PUSH32 value, M[0] = high half, M[16] = low half, return M[0..32]
+    let mut code = vec![0x7f];
+    code.extend_from_slice(&value);
+    code.extend_from_slice(&[
+        0x60, 0x00, 0x52, 0x60, 0x10, 0x52, 0x60, 0x20, 0x60, 0x00, 0xf3,
+    ]);
+    let res = execute(backend, &code, context, &mut ext);
+    assert_eq!(res.0, EvmStatusCode::Success);
+    assert_eq!(res.2, value);
+}
+
+fn revert_keeps_gas(backend: FastVMBackend) {
+    let context = FastVMTest::new();
+    let nrg_limit = context.nrg_limit as i64;
+    let env_info = EnvInfo::default();
+    let mut ext = test_env(&env_info);
+
+    let code = vec![0x60, 0x00, 0x60, 0x00, 0xfd];
+    let res = execute(backend, &code, context, &mut ext);
+    assert_eq!(res.0, EvmStatusCode::Revert);
+    assert_eq!(res.1, nrg_limit - 2);
 }
@@ -26,6 +26,8 @@ use std::cmp;
 use avm::AVM;
 use fastvm::ffi::EvmStatusCode;
 use fastvm::core::FastVM;
+use fastvm::backend::FastVMBackend;
+use fastvm::interpreter::Interpreter;
 use fastvm::basetypes::{constants::GAS_CODE_DEPOSIT, DataWord};
 use fastvm::context::{execution_kind, ExecutionContext, TransactionResult};
 use types::{AvmExecutionResult, FvmExecutionResult, ExecStatus, CallType, ReturnData, ActionParams, ActionValue};
@@ -36,17 +38,25 @@ use aion_types::{U128, U256, H256};
 #[derive(Clone)]
 pub struct FastVMFactory {
     instance: FastVM,
+    backend: FastVMBackend,
 }
 
 impl FastVMFactory {
     /// Create new instance of FastVM factory, with a size in bytes
     /// for caching jump destinations.
-    pub fn new() -> Self {
+    pub fn new() -> Self { FastVMFactory::with_backend(FastVMBackend::default()) }
+
+    /// Create new instance of FastVM factory executing code with the given backend.
+    pub fn with_backend(backend: FastVMBackend) -> Self {
         FastVMFactory {
             instance: FastVM::new(),
+            backend,
         }
     }
 
+    /// Backend used to execute code.
+    pub fn backend(&self) -> FastVMBackend { self.backend }
+
     pub fn exec(&mut self, params: ActionParams, ext: &mut Ext) -> FvmExecutionResult {
         let params = params.clone();
         assert!(
@@ -130,12 +140,17 @@ impl FastVMFactory {
             block_difficulty,
             tx_result,
         );
-        let inst = &mut self.instance;
-        let ext_ptr: *mut ::libc::c_void = unsafe { ::std::mem::transmute(Box::new(ext)) };
-        inst.init(ext_ptr);
-        let res = inst.run(code, &mut ctx);
+        let res = match self.backend {
+            FastVMBackend::Native => {
+                let inst = &mut self.instance;
+                let ext_ptr: *mut ::libc::c_void =
+                    unsafe { ::std::mem::transmute(Box::new(&mut *ext)) };
+                inst.init(ext_ptr);
+                inst.run(code, &mut ctx)
+            }
+            FastVMBackend::Interpreter => Interpreter::new().run(code, &ctx, ext),
+        };
 
-        let ext_post: &mut Box<Ext> = unsafe { ::std::mem::transmute(ext_ptr) };
         let mut status_code = res.0;
         let mut gas_left = U256::from(cmp::max(res.1, 0i64));
         let return_data = res.2;
@@ -152,7 +167,7 @@ impl FastVMFactory {
             && !return_data.is_empty()
         {
             if gas_left >= GAS_CODE_DEPOSIT || depth == 0 {
-                ext_post.save_code(return_data.clone());
+                ext.save_code(return_data.clone());
             } else {
                 gas_left = U256::from(0);
                 status_code = EvmStatusCode::Failure;
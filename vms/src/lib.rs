@@ -37,5 +37,6 @@ pub use factory::{FastVMFactory, AVMFactory};
 pub use vmtype::VMType;
 pub use fastvm::vm::{self, Error};
 pub use fastvm::basetypes::constants;
+pub use fastvm::backend::FastVMBackend;
 // export vm related types from vms module
 pub use types::*;